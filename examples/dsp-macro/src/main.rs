@@ -1,6 +1,5 @@
 use faust_state::DspHandle;
 use jack_utils::run_dsp_as_jack_client;
-use std::{thread, time::Duration};
 
 faust_macro::dsp!(
     declare flags       "-single"; // example for possible flags declaration use
@@ -29,7 +28,7 @@ faust_macro::dsp!(
 );
 
 fn main() {
-    let (dsp, mut state) = DspHandle::<volume_control::VolumeControl>::new();
+    let (dsp, state) = DspHandle::<volume_control::VolumeControl>::new();
     eprintln!("client name: {}", dsp.name());
     eprintln!("inputs: {}", dsp.num_inputs());
     eprintln!("outputs: {}", dsp.num_outputs());
@@ -38,9 +37,8 @@ fn main() {
 
     let mut volume = -70.0;
 
-    // Spawn a thread to do state changes.
-    // This could be a GUI thread or API server.
-    thread::spawn(move || loop {
+    // Run the DSP as JACK client and change the state while it runs.
+    run_dsp_as_jack_client(dsp, state, move |state| {
         // This loops the volume up and when on max sets it down to 0 again.
         // It also reports the current output level of the signal.
         eprintln!("volume: {} dB", state.get_by_path("volume").unwrap());
@@ -51,9 +49,5 @@ fn main() {
         }
         let _ = state.set_by_path("volume", volume);
         state.send();
-        thread::sleep(Duration::from_millis(200));
     });
-
-    // Run the DSP as JACK client.
-    run_dsp_as_jack_client(dsp);
 }
@@ -49,7 +49,13 @@ fn main() {
 
     // Create JACK client
     let (client, in_ports, mut out_ports) =
-        jack_utils::create_jack_client("jacktest", num_inputs, num_outputs);
+        match jack_utils::create_jack_client("jacktest", num_inputs, num_outputs) {
+            Ok(client) => client,
+            Err(err) => {
+                eprintln!("failed to create jack client: {err}");
+                return;
+            }
+        };
 
     // Init DSP with a given sample rate
     let sample_rate = client.sample_rate();
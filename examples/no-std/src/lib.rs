@@ -0,0 +1,72 @@
+//! The volume control of the `minimal` example on a microcontroller, without `std` and without an
+//! allocator.
+//!
+//! The firmware calls [`Audio::process`] from the interrupt of its audio peripheral and sets the
+//! volume through [`PARAMS`] from anywhere else. Build it for a Cortex-M4F with
+//!
+//! ```sh
+//! rustup target add thumbv7em-none-eabihf
+//! cargo build -p no-std --target thumbv7em-none-eabihf
+//! ```
+#![no_std]
+
+pub mod dsp;
+
+use dsp::{Volumecontrol, FAUST_ACTIVES, FAUST_PASSIVES};
+use faust_state::fixed::{AtomicParams, FixedDspHandle};
+use faust_types::{ParamIndex, Sample};
+
+pub const LEVEL: ParamIndex = ParamIndex(0);
+pub const VOLUME: ParamIndex = ParamIndex(1);
+
+/// Frames computed at once, longer buffers of the peripheral are computed in several blocks.
+pub const BLOCK_SIZE: usize = 32;
+const CHANNELS: usize = 2;
+
+pub static PARAMS: AtomicParams<{ FAUST_ACTIVES + FAUST_PASSIVES }> = AtomicParams::new();
+
+pub struct Audio {
+    handle: FixedDspHandle<'static, Volumecontrol, { FAUST_ACTIVES + FAUST_PASSIVES }>,
+    inputs: [[f32; BLOCK_SIZE]; CHANNELS],
+    outputs: [[f32; BLOCK_SIZE]; CHANNELS],
+}
+
+impl Audio {
+    #[must_use]
+    pub fn new(sample_rate: i32) -> Self {
+        let mut handle = FixedDspHandle::new(&PARAMS);
+        handle.init(sample_rate);
+        Self {
+            handle,
+            inputs: [[0.0; BLOCK_SIZE]; CHANNELS],
+            outputs: [[0.0; BLOCK_SIZE]; CHANNELS],
+        }
+    }
+
+    /// Computes interleaved stereo frames, e.g. the DMA buffers of an I2S peripheral.
+    pub fn process(&mut self, input: &[i16], output: &mut [i16]) {
+        for (input, output) in input
+            .chunks(BLOCK_SIZE * CHANNELS)
+            .zip(output.chunks_mut(BLOCK_SIZE * CHANNELS))
+        {
+            let frames = input.len().min(output.len()) / CHANNELS;
+            for (frame, samples) in input.chunks_exact(CHANNELS).take(frames).enumerate() {
+                for (channel, sample) in samples.iter().enumerate() {
+                    self.inputs[channel][frame] = sample.convert();
+                }
+            }
+            let [left, right] = &self.inputs;
+            let [out_left, out_right] = &mut self.outputs;
+            self.handle.update_and_compute(
+                frames as i32,
+                &[&left[..frames], &right[..frames]],
+                &mut [&mut out_left[..frames], &mut out_right[..frames]],
+            );
+            for (frame, samples) in output.chunks_exact_mut(CHANNELS).take(frames).enumerate() {
+                for (channel, sample) in samples.iter_mut().enumerate() {
+                    *sample = self.outputs[channel][frame].convert();
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,12 @@
-use crate::builder::FaustBuilder;
+use crate::{build_context::BuildContext, template};
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use std::path::{Path, PathBuf};
-use syn::parse_str;
+use syn::{parse_quote, parse_str, FnArg, ForeignItem, Item, ItemStruct, Pat, UseTree};
 
 pub enum Architecture {
     None,
-    Function(&'static dyn Fn(&FaustBuilder, &TokenStream) -> TokenStream),
+    Function(&'static dyn Fn(&BuildContext, &TokenStream) -> TokenStream),
     Object(Box<dyn ObjectInterface>),
     File(PathBuf),
 }
@@ -15,13 +15,13 @@ impl Architecture {
     #[cfg(feature = "faust-ui")]
     #[must_use]
     pub fn ui() -> Self {
-        Self::Function(&ui)
+        Pipeline::ui().into()
     }
 
     #[cfg(feature = "faust-ui")]
     #[must_use]
     pub fn mod_ui() -> Self {
-        Self::Function(&mod_ui)
+        Pipeline::mod_ui().into()
     }
 
     #[must_use]
@@ -29,6 +29,11 @@ impl Architecture {
         Self::File(path)
     }
 
+    #[must_use]
+    pub fn object(object: impl ObjectInterface + 'static) -> Self {
+        Self::Object(Box::new(object))
+    }
+
     #[must_use]
     pub(crate) fn get_file_path(&self) -> Option<&Path> {
         match self {
@@ -37,32 +42,27 @@ impl Architecture {
         }
     }
 
-    #[allow(clippy::option_if_let_else)]
-    pub(crate) fn apply(&self, builder: &FaustBuilder, dsp_code: &str) -> TokenStream {
+    pub(crate) fn apply(&self, context: &BuildContext, dsp_code: &str) -> TokenStream {
         match self {
             Self::None => {
                 //or would it be better to do really no architecture?
                 let ts =
                     parse_str::<TokenStream>(dsp_code).expect("Failed to parse string into tokens");
-                default(builder, &ts)
+                default(context, &ts)
             }
             Self::Function(architecture_function) => {
                 let ts =
                     parse_str::<TokenStream>(dsp_code).expect("Failed to parse string into tokens");
-                architecture_function(builder, &ts)
+                architecture_function(context, &ts)
             }
             Self::Object(architecture_interface) => {
                 let ts =
                     parse_str::<TokenStream>(dsp_code).expect("Failed to parse string into tokens");
-                architecture_interface.apply(builder, &ts)
+                architecture_interface.apply(context, &ts)
             }
             Self::File(_path_buf) => {
-                let dsp_code = if let Some(mn) = builder.get_module_name() {
-                    &dsp_code.replace("<<moduleName>>", mn)
-                } else {
-                    dsp_code
-                };
-                parse_str::<TokenStream>(dsp_code).expect("Failed to parse string into tokens")
+                let dsp_code = template::render(dsp_code, context);
+                parse_str::<TokenStream>(&dsp_code).expect("Failed to parse string into tokens")
             }
         }
     }
@@ -70,120 +70,412 @@ impl Architecture {
 
 impl Default for Architecture {
     fn default() -> Self {
-        Self::Function(&default)
+        Pipeline::default_wrapper().into()
     }
 }
+
+impl From<Pipeline> for Architecture {
+    fn from(pipeline: Pipeline) -> Self {
+        Self::object(pipeline)
+    }
+}
+
+/// A step that transforms the rust code generated by faust.
+///
+/// Objects can be combined with a [`Pipeline`] where every step receives the output of the previous one.
 pub trait ObjectInterface {
-    fn apply(&self, builder: &FaustBuilder, dsp_code: &TokenStream) -> TokenStream;
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream;
 }
 
-#[must_use]
-pub fn default(_builder: &FaustBuilder, dsp_code: &TokenStream) -> TokenStream {
-    quote! {
-        #![allow(clippy::all)]
-        #![allow(unused_parens)]
-        #![allow(non_snake_case)]
-        #![allow(non_camel_case_types)]
-        #![allow(dead_code)]
-        #![allow(unused_variables)]
-        #![allow(unused_mut)]
-        #![allow(non_upper_case_globals)]
-        use faust_types::*;
-        #dsp_code
+impl<F> ObjectInterface for F
+where
+    F: Fn(&BuildContext, &TokenStream) -> TokenStream,
+{
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        self(context, dsp_code)
     }
 }
 
-#[cfg(feature = "faust-ui")]
-#[must_use]
-fn ui(builder: &FaustBuilder, dsp_code: &TokenStream) -> TokenStream {
-    let struct_name = builder.get_struct_name();
-    let json_path = builder.get_json_path();
-    match std::fs::exists(&json_path) {
-        Ok(b) => {
-            assert!(b, "json file not found at path: {:?}", json_path);
+/// Applies a list of architecture objects one after another.
+///
+/// Steps that prepend code have to be ordered with care: inner attributes like the ones of
+/// [`AllowLints`] must end up in front of the [`Prelude`], so they are added after it.
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<Box<dyn ObjectInterface>>,
+}
+
+impl Pipeline {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The wrapper used by `Architecture::default()`: the prelude and the lint allow list.
+    #[must_use]
+    pub fn default_wrapper() -> Self {
+        Self::new()
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    /// The default wrapper extended with the generated UI code and its docs.
+    #[cfg(feature = "faust-ui")]
+    #[must_use]
+    pub fn ui() -> Self {
+        Self::new()
+            .with(Ui)
+            .with(Docs)
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    /// Like [`Pipeline::ui`] but wrapped into the module of the builder.
+    #[cfg(feature = "faust-ui")]
+    #[must_use]
+    pub fn mod_ui() -> Self {
+        Self::ui().with(Module::default())
+    }
+
+    /// Like [`Pipeline::ui`] with the nih-plug parameters of [`NihParams`].
+    #[cfg(feature = "faust-ui")]
+    #[must_use]
+    pub fn nih_plug() -> Self {
+        Self::new()
+            .with(Ui)
+            .with(NihParams)
+            .with(Docs)
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    /// The default wrapper extended with the entry point of an lv2 plugin, see [`crate::lv2`].
+    #[must_use]
+    pub fn lv2(uri: impl Into<String>) -> Self {
+        Self::new()
+            .with(crate::lv2::Lv2Glue::new(uri))
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    /// The default wrapper extended with the entry point of a CLAP plugin, see [`crate::clap`].
+    #[must_use]
+    pub fn clap(clap: crate::clap::Clap) -> Self {
+        Self::new()
+            .with(clap)
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    /// The default wrapper for `#![no_std]` crates, see [`NoStd`].
+    #[must_use]
+    pub fn no_std() -> Self {
+        Self::new()
+            .with(NoStd)
+            .with(Prelude::default())
+            .with(AllowLints::default())
+    }
+
+    #[must_use]
+    pub fn with(mut self, step: impl ObjectInterface + 'static) -> Self {
+        self.push(step);
+        self
+    }
+
+    pub fn push(&mut self, step: impl ObjectInterface + 'static) {
+        self.steps.push(Box::new(step));
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl ObjectInterface for Pipeline {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        self.steps
+            .iter()
+            .fold(dsp_code.clone(), |ts, step| step.apply(context, &ts))
+    }
+}
+
+/// Prepends `#![allow(...)]` for the lints triggered by the generated code.
+pub struct AllowLints(Vec<String>);
+
+impl AllowLints {
+    #[must_use]
+    pub fn new(lints: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(lints.into_iter().map(Into::into).collect())
+    }
+
+    #[must_use]
+    pub fn with_lint(mut self, lint: impl Into<String>) -> Self {
+        self.0.push(lint.into());
+        self
+    }
+}
+
+impl Default for AllowLints {
+    fn default() -> Self {
+        Self::new([
+            "clippy::all",
+            "unused_parens",
+            "non_snake_case",
+            "non_camel_case_types",
+            "dead_code",
+            "unused_variables",
+            "unused_mut",
+            "non_upper_case_globals",
+        ])
+    }
+}
+
+impl ObjectInterface for AllowLints {
+    fn apply(&self, _context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let lints = self.0.iter().map(|lint| {
+            parse_str::<syn::Path>(lint)
+                .unwrap_or_else(|err| panic!("invalid lint {}: {}", lint, err))
+        });
+        quote! {
+            #(#![allow(#lints)])*
+            #dsp_code
         }
-        Err(err) => core::panic!("json file not found at path: {:?}", err),
     }
-    let ui_code = FaustBuilder::generate_ui_from_json(&json_path, struct_name);
-    quote! {
-        #![allow(clippy::all)]
-        #![allow(unused_parens)]
-        #![allow(non_snake_case)]
-        #![allow(non_camel_case_types)]
-        #![allow(dead_code)]
-        #![allow(unused_variables)]
-        #![allow(unused_mut)]
-        #![allow(non_upper_case_globals)]
-        use faust_types::*;
-        #dsp_code
-        #ui_code
+}
+
+/// Prepends code to the generated code, by default `use faust_types::*;`.
+pub struct Prelude(TokenStream);
+
+impl Prelude {
+    #[must_use]
+    pub fn new(prelude: TokenStream) -> Self {
+        Self(prelude)
     }
 }
 
-#[cfg(feature = "faust-ui")]
-#[must_use]
-fn mod_ui(builder: &FaustBuilder, dsp_code: &TokenStream) -> TokenStream {
-    let module_name = builder
-        .get_module_name()
-        .as_ref()
-        .expect("module name needed y mod ui architecture function");
-    let struct_name = builder.get_struct_name();
-    let json_path = builder.get_json_path();
-    match std::fs::exists(&json_path) {
-        Ok(b) => {
-            assert!(b, "json file not found at path: {:?}", json_path);
+impl Default for Prelude {
+    fn default() -> Self {
+        Self(quote! { use faust_types::*; })
+    }
+}
+
+impl ObjectInterface for Prelude {
+    fn apply(&self, _context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let prelude = &self.0;
+        quote! {
+            #prelude
+            #dsp_code
+        }
+    }
+}
+
+/// Wraps the generated code into `pub mod`.
+///
+/// Without an explicit name the module name of the builder is used.
+#[derive(Default)]
+pub struct Module(Option<String>);
+
+impl Module {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(Some(name.into()))
+    }
+}
+
+impl ObjectInterface for Module {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let module_name = self
+            .0
+            .as_deref()
+            .or_else(|| context.module_name())
+            .expect("module name needed by module architecture object");
+        let module_name = format_ident!("{}", module_name);
+        quote! {
+            pub mod #module_name {
+                #dsp_code
+            }
+        }
+    }
+}
+
+/// Adds `#[derive(...)]` to the dsp struct.
+///
+/// The struct is looked up by the struct name of the builder, also inside of inline modules.
+pub struct ExtraDerives(Vec<String>);
+
+impl ExtraDerives {
+    #[must_use]
+    pub fn new(derives: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(derives.into_iter().map(Into::into).collect())
+    }
+}
+
+impl ObjectInterface for ExtraDerives {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let derives = self
+            .0
+            .iter()
+            .map(|derive| {
+                parse_str::<syn::Path>(derive)
+                    .unwrap_or_else(|err| panic!("invalid derive {}: {}", derive, err))
+            })
+            .collect::<Vec<_>>();
+        let mut file: syn::File =
+            syn::parse2(dsp_code.clone()).expect("Failed to parse Rust code in wrapper");
+        let item_struct = find_struct(&mut file.items, context.struct_name())
+            .unwrap_or_else(|| panic!("struct {} not found", context.struct_name()));
+        item_struct
+            .attrs
+            .push(parse_quote!(#[derive(#(#derives),*)]));
+        quote! { #file }
+    }
+}
+
+/// Finds the dsp struct, also inside of inline modules.
+fn find_struct<'a>(items: &'a mut [Item], struct_name: &str) -> Option<&'a mut ItemStruct> {
+    items.iter_mut().find_map(|item| match item {
+        Item::Struct(item_struct) if item_struct.ident == struct_name => Some(item_struct),
+        Item::Mod(item_mod) => item_mod
+            .content
+            .as_mut()
+            .and_then(|(_, items)| find_struct(items, struct_name)),
+        _ => None,
+    })
+}
+
+/// Makes the generated code usable in `#![no_std]` crates.
+///
+/// Faust declares functions like `remainderf` and `rintf` in an `ffi` module that links the C math
+/// library through `std::os::raw` types. They are replaced by the functions of the same name of
+/// `libm`, re-exported by the `libm` feature of `faust-types`. The other math functions resolve to
+/// `faust_types::FloatMath` through the [`Prelude`].
+pub struct NoStd;
+
+impl ObjectInterface for NoStd {
+    fn apply(&self, _context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let mut file: syn::File =
+            syn::parse2(dsp_code.clone()).expect("Failed to parse Rust code in wrapper");
+        for item in &mut file.items {
+            if let Item::Mod(item_mod) = item {
+                if item_mod.ident == "ffi" {
+                    if let Some((_, items)) = &mut item_mod.content {
+                        *items = items.drain(..).flat_map(no_std_ffi_item).collect();
+                    }
+                }
+            }
         }
-        Err(err) => core::panic!("json file not found at path: {:?}", err),
-    }
-    let ui_code = FaustBuilder::generate_ui_from_json(&json_path, struct_name);
-
-    let module_name = quote::format_ident!("{}", module_name);
-    quote! {
-        pub mod #module_name {
-            #![allow(clippy::all)]
-            #![allow(unused_parens)]
-            #![allow(non_snake_case)]
-            #![allow(non_camel_case_types)]
-            #![allow(dead_code)]
-            #![allow(unused_variables)]
-            #![allow(unused_mut)]
-            #![allow(non_upper_case_globals)]
-            use faust_types::*;
+        quote! { #file }
+    }
+}
+
+/// Replaces the imports of `std::os::raw` by `core::ffi` and the extern functions by wrappers of
+/// `libm`. The wrappers stay `unsafe`, as they are called from `unsafe` blocks.
+fn no_std_ffi_item(item: Item) -> Vec<Item> {
+    match item {
+        Item::Use(mut item_use) => {
+            if let UseTree::Path(std) = &item_use.tree {
+                if let UseTree::Path(os) = &*std.tree {
+                    if let UseTree::Path(raw) = &*os.tree {
+                        if std.ident == "std" && os.ident == "os" && raw.ident == "raw" {
+                            let imported = &raw.tree;
+                            item_use.tree = parse_quote!(core::ffi::#imported);
+                        }
+                    }
+                }
+            }
+            vec![Item::Use(item_use)]
+        }
+        Item::ForeignMod(foreign_mod) => foreign_mod
+            .items
+            .into_iter()
+            .map(|foreign_item| match foreign_item {
+                ForeignItem::Fn(foreign_fn) => {
+                    let mut sig = foreign_fn.sig;
+                    sig.unsafety = Some(parse_quote!(unsafe));
+                    let name = &sig.ident;
+                    let args = sig.inputs.iter().map(|input| match input {
+                        FnArg::Typed(arg) => match &*arg.pat {
+                            Pat::Ident(pat) => &pat.ident,
+                            pat => panic!("unsupported argument {} of {}", quote!(#pat), name),
+                        },
+                        FnArg::Receiver(_) => panic!("unsupported receiver of {}", name),
+                    });
+                    parse_quote! {
+                        pub #sig {
+                            faust_types::math::libm::#name(#(#args),*)
+                        }
+                    }
+                }
+                other => panic!("unsupported extern item {}", quote!(#other)),
+            })
+            .collect(),
+        item => vec![item],
+    }
+}
+
+/// Documents the dsp struct with the metadata, channels and parameters from the json or xml description.
+///
+/// The ui code generated by [`Ui`] is documented independently of this step.
+#[cfg(feature = "faust-ui")]
+pub struct Docs;
+
+#[cfg(feature = "faust-ui")]
+impl ObjectInterface for Docs {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let doc = faust_ui::docs::dsp_doc(&context.expect_ui_model());
+        let mut file: syn::File =
+            syn::parse2(dsp_code.clone()).expect("Failed to parse Rust code in wrapper");
+        let item_struct = find_struct(&mut file.items, context.struct_name())
+            .unwrap_or_else(|| panic!("struct {} not found", context.struct_name()));
+        item_struct.attrs.insert(0, parse_quote!(#[doc = #doc]));
+        quote! { #file }
+    }
+}
+
+/// Appends the ui code generated from the json or xml description of the build.
+#[cfg(feature = "faust-ui")]
+pub struct Ui;
+
+#[cfg(feature = "faust-ui")]
+impl ObjectInterface for Ui {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let ui_code = faust_ui::generate_ui_code_from_model(
+            &context.expect_ui_model(),
+            context.struct_name(),
+        );
+        quote! {
             #dsp_code
             #ui_code
         }
     }
 }
 
-// Architecture Object needs a usecase first
-// pub struct ArchitectureUI {}
-
-// impl ArchitectureInterface for ArchitectureUI {
-//     fn wrap(&self, builder: &FaustBuilder, dsp_code: TokenStream) -> TokenStream {
-//         let struct_name = builder.get_struct_name();
-//         let json_path = builder.get_json_path();
-//         match fs::exists(&json_path) {
-//             Ok(b) => {
-//                 assert!(b, "json file not found at path: {:?}", json_path);
-//             }
-//             Err(err) => panic!("json file not found at path: {:?}", err),
-//         }
-//         let ui_code =
-//             FaustBuilder::get_ui_from_json(&json_path, struct_name);
-//         let struct_name = format_ident!("{}", struct_name);
-//         quote! {
-//                 #![allow(clippy::all)]
-//                 #![allow(unused_parens)]
-//                 #![allow(non_snake_case)]
-//                 #![allow(non_camel_case_types)]
-//                 #![allow(dead_code)]
-//                 #![allow(unused_variables)]
-//                 #![allow(unused_mut)]
-//                 #![allow(non_upper_case_globals)]
-//                 use faust_types::*;
-//                 #dsp_code
-//                 #ui_code
-//             }
-//         }
-//     }
-// }
+/// Appends the `nih_params` module with the nih-plug parameters of the dsp, see
+/// [`faust_ui::nih_params`]. Needs the ui code of [`Ui`].
+#[cfg(feature = "faust-ui")]
+pub struct NihParams;
+
+#[cfg(feature = "faust-ui")]
+impl ObjectInterface for NihParams {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let params = faust_ui::nih_params::create(
+            &context.expect_ui_model(),
+            &format_ident!("{}", context.struct_name()),
+        );
+        quote! {
+            #dsp_code
+            #params
+        }
+    }
+}
+
+#[must_use]
+pub fn default(context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+    Pipeline::default_wrapper().apply(context, dsp_code)
+}
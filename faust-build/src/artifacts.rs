@@ -0,0 +1,180 @@
+//! Block diagrams, graphs and timing information generated next to the rust code.
+//!
+//! Faust writes these files into the output dir of the builder. [`FaustBuilder::build_with_artifacts`]
+//! collects them and copies them into [`ArtifactOptions::dir`] if it is set. Without it the paths point
+//! into the output dir, which is removed together with the builder when it is a temporary one.
+//!
+//! [`FaustBuilder::build_with_artifacts`]: crate::builder::FaustBuilder::build_with_artifacts
+
+#![allow(clippy::missing_errors_doc)]
+
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ArtifactOptions {
+    //   -svg       --svg                        print block-diagram to a svg file.
+    pub svg: bool,
+    //   -sd        --simplify-diagrams          try to further simplify diagrams before drawing.
+    pub simplify_diagrams: bool,
+    //   -f <n>     --fold <n>                   threshold to activate folding mode during block-diagram generation (default 25 elements).
+    pub fold: Option<String>,
+    //   -sn        --simple-names               use simple names (without arguments) during block-diagram generation.
+    pub simple_names: bool,
+    //   -sc        --scaled-svg                 automatic scalable SVG.
+    pub scaled_svg: bool,
+    //   -tg        --task-graph                 print the internal task graph in dot format.
+    pub task_graph: bool,
+    //   -sg        --signal-graph               print the internal signal graph in dot format.
+    pub signal_graph: bool,
+    //   -time      --compilation-time           display compilation phases timing information.
+    pub timing: bool,
+    /// Directory the generated files are copied to.
+    pub dir: Option<PathBuf>,
+}
+
+impl ArtifactOptions {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.svg || self.task_graph || self.signal_graph || self.timing
+    }
+
+    #[must_use]
+    pub fn to_command_args(&self) -> Vec<&OsStr> {
+        let mut r = Vec::<&OsStr>::new();
+        if self.svg {
+            r.push("-svg".as_ref());
+            if self.simplify_diagrams {
+                r.push("-sd".as_ref());
+            }
+            if let Some(fold) = &self.fold {
+                r.push("-f".as_ref());
+                r.push(fold.as_ref());
+            }
+            if self.simple_names {
+                r.push("-sn".as_ref());
+            }
+            if self.scaled_svg {
+                r.push("-sc".as_ref());
+            }
+        }
+        if self.task_graph {
+            r.push("-tg".as_ref());
+        }
+        if self.signal_graph {
+            r.push("-sg".as_ref());
+        }
+        if self.timing {
+            r.push("-time".as_ref());
+        }
+        r
+    }
+}
+
+/// Files and information generated by faust besides the rust code.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildArtifacts {
+    /// Directory with one svg file per block diagram.
+    pub svg_dir: Option<PathBuf>,
+    /// Top level block diagram, `process.svg` inside [`BuildArtifacts::svg_dir`].
+    pub block_diagram: Option<PathBuf>,
+    /// Task graph in dot format. Faust only writes it for vectorized code, e.g. with `-vec -tg`.
+    pub task_graph: Option<PathBuf>,
+    /// Signal graph in dot format.
+    pub signal_graph: Option<PathBuf>,
+    /// Compilation phases in the order they finished.
+    pub timing: Vec<Timing>,
+}
+
+/// Duration of one compilation phase as printed by `faust -time`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub phase: String,
+    pub duration: f64,
+}
+
+impl Timing {
+    /// Parses the `end <phase> (duration : <n>)` lines of the faust output.
+    #[must_use]
+    pub fn parse_all(output: &str) -> Vec<Self> {
+        output.lines().filter_map(Self::parse).collect()
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix("end ")?;
+        let (phase, duration) = rest.rsplit_once("(duration")?;
+        let duration = duration
+            .trim_start_matches([' ', ':'])
+            .trim_end_matches([' ', ')'])
+            .parse()
+            .ok()?;
+        Some(Self {
+            phase: phase.trim().to_owned(),
+            duration,
+        })
+    }
+}
+
+impl BuildArtifacts {
+    /// Looks up the files faust wrote for the dsp `name` into `output_dir` and copies them into
+    /// `options.dir`.
+    ///
+    /// `name` is the file name of the dsp without `.dsp`, `stdin` for code read from stdin. Faust
+    /// names the block diagrams `<name>-svg`, the task graph `<name>.dsp.dot` and the signal graph
+    /// `<name>.dsp-sig.dot`, so the files of other dsps in a shared output dir are left alone.
+    pub fn collect(
+        options: &ArtifactOptions,
+        output_dir: &Path,
+        name: &str,
+        stderr: &str,
+    ) -> io::Result<Self> {
+        let generated = |enabled: bool, file_name: String| {
+            let path = output_dir.join(file_name);
+            (enabled && path.exists()).then_some(path)
+        };
+        let mut artifacts = Self {
+            svg_dir: generated(options.svg, format!("{name}-svg")).filter(|path| path.is_dir()),
+            task_graph: generated(options.task_graph, format!("{name}.dsp.dot")),
+            signal_graph: generated(options.signal_graph, format!("{name}.dsp-sig.dot")),
+            ..Self::default()
+        };
+        if options.timing {
+            artifacts.timing = Timing::parse_all(stderr);
+        }
+        if let Some(dir) = &options.dir {
+            fs::create_dir_all(dir)?;
+            for path in IntoIterator::into_iter([
+                &mut artifacts.svg_dir,
+                &mut artifacts.task_graph,
+                &mut artifacts.signal_graph,
+            ])
+            .flatten()
+            {
+                *path = copy_into(path, dir)?;
+            }
+        }
+        artifacts.block_diagram = artifacts
+            .svg_dir
+            .as_ref()
+            .map(|svg_dir| svg_dir.join("process.svg"))
+            .filter(|path| path.exists());
+        Ok(artifacts)
+    }
+}
+
+fn copy_into(source: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let target = dir.join(source.file_name().unwrap_or_default());
+    if source.is_dir() {
+        fs::create_dir_all(&target)?;
+        for entry in fs::read_dir(source)? {
+            copy_into(&entry?.path(), &target)?;
+        }
+    } else {
+        fs::copy(source, &target)?;
+    }
+    Ok(target)
+}
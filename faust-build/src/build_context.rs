@@ -0,0 +1,129 @@
+use crate::{
+    builder::FaustBuilder, code_option::CodeOptionMap, compile_options::CompileOptions,
+    faust_version::FaustVersion,
+};
+use faust_json::FaustJson;
+use faust_xml::FaustXML;
+use std::path::Path;
+
+/// Everything an architecture needs to know about the current build.
+///
+/// The json and xml descriptions are parsed once by [`FaustBuilder::build`] when `write_json_file`
+/// or `write_xml_file` is enabled.
+pub struct BuildContext<'a> {
+    builder: &'a FaustBuilder,
+    json: Option<FaustJson>,
+    xml: Option<FaustXML>,
+}
+
+impl<'a> BuildContext<'a> {
+    #[must_use]
+    pub fn new(builder: &'a FaustBuilder, json: Option<FaustJson>) -> Self {
+        Self {
+            builder,
+            json,
+            xml: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_xml(mut self, xml: Option<FaustXML>) -> Self {
+        self.xml = xml;
+        self
+    }
+
+    #[must_use]
+    pub fn builder(&self) -> &'a FaustBuilder {
+        self.builder
+    }
+
+    #[must_use]
+    pub fn struct_name(&self) -> &'a str {
+        self.builder.get_struct_name()
+    }
+
+    #[must_use]
+    pub fn module_name(&self) -> Option<&'a str> {
+        self.builder.get_module_name().as_deref()
+    }
+
+    #[must_use]
+    pub fn dsp_path(&self) -> &'a Path {
+        self.builder.get_dsp_path()
+    }
+
+    #[must_use]
+    pub fn code_options(&self) -> &'a CodeOptionMap {
+        self.builder.get_code_options()
+    }
+
+    #[must_use]
+    pub fn compile_options(&self) -> &'a CompileOptions {
+        self.builder.get_compile_options()
+    }
+
+    /// Version of the faust compiler that generated the code.
+    #[must_use]
+    pub fn faust_version(&self) -> FaustVersion {
+        self.builder.faust_version()
+    }
+
+    #[must_use]
+    pub fn json(&self) -> Option<&FaustJson> {
+        self.json.as_ref()
+    }
+
+    /// Like [`BuildContext::json`] but panics if no json file was generated.
+    #[must_use]
+    pub fn expect_json(&self) -> &FaustJson {
+        self.json
+            .as_ref()
+            .expect("json description not available, enable it with FaustBuilder::write_json_file")
+    }
+
+    #[must_use]
+    pub fn xml(&self) -> Option<&FaustXML> {
+        self.xml.as_ref()
+    }
+
+    /// Description of the user interface, from the json description or else from the xml one.
+    ///
+    /// Panics if neither was generated or the xml layout is invalid.
+    #[cfg(feature = "faust-ui")]
+    #[must_use]
+    pub fn expect_ui_model(&self) -> faust_ui::model::UiModel {
+        use faust_ui::model::UiModel;
+        use std::convert::TryFrom;
+
+        if let Some(json) = &self.json {
+            return UiModel::from(json);
+        }
+        let xml = self.xml.as_ref().expect(
+            "json or xml description not available, enable one with FaustBuilder::write_json_file or FaustBuilder::write_xml_file",
+        );
+        UiModel::try_from(xml).unwrap_or_else(|err| panic!("invalid xml description: {}", err))
+    }
+
+    #[must_use]
+    pub fn inputs(&self) -> Option<usize> {
+        self.json.as_ref().map(|json| json.inputs)
+    }
+
+    #[must_use]
+    pub fn outputs(&self) -> Option<usize> {
+        self.json.as_ref().map(|json| json.outputs)
+    }
+
+    /// Global metadata declared in the dsp, in declaration order.
+    pub fn metadata(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.json
+            .iter()
+            .flat_map(|json| json.meta.iter())
+            .map(|meta| (meta.key.as_str(), meta.value.as_str()))
+    }
+
+    #[must_use]
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.metadata().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+}
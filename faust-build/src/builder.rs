@@ -3,24 +3,33 @@
 #![allow(clippy::missing_const_for_fn)]
 
 use crate::{
-    architecture::Architecture,
+    architecture::{Architecture, Pipeline},
+    artifacts::{ArtifactOptions, BuildArtifacts},
+    build_context::BuildContext,
+    clap::Clap,
     code_option::{CodeOption, CodeOptionDiscriminants, CodeOptionMap},
-    compile_options::CompileOptions,
+    compile_options::{CompileOptions, WarningPolicy},
+    config::FaustConfig,
     dsp_path::DspPath,
+    faust_version::{FaustVersion, FaustVersionRange},
+    lv2::{self, Lv2Options, Lv2Plugin},
+    output_dir::OutputDir,
     CodeOptionToCommandArgs,
 };
+use faust_json::ParseMode;
 use heck::{CamelCase, SnakeCase};
 use proc_macro2::TokenStream;
 use std::{
+    cell::{OnceCell, RefCell},
     env,
     fs::{self},
-    io::{BufWriter, Write},
+    io::{self, Write},
     panic,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
     str,
 };
-use tempfile::{NamedTempFile, TempPath};
+use tempfile::TempPath;
 
 pub struct FaustBuilder {
     faust_path: PathBuf,
@@ -28,16 +37,34 @@ pub struct FaustBuilder {
     module_name: Option<String>,
     out_path: Option<PathBuf>,
     compile_options: CompileOptions,
+    faust_version: OnceCell<FaustVersion>,
+    faust_version_range: FaustVersionRange,
+    /// Warnings of the last faust run, see [`WarningPolicy`].
+    warnings: RefCell<Vec<String>>,
 }
 
+/// Environment variable with the path of the faust binary, used instead of `faust` from `PATH`.
+pub const FAUST_PATH_ENV: &str = "FAUST_PATH";
+/// Environment variable with a list of library directories, separated like `PATH`.
+pub const FAUST_LIB_PATH_ENV: &str = "FAUST_LIB_PATH";
+
 impl Default for FaustBuilder {
+    /// Faust binary and library dirs are taken from `FAUST_PATH` and `FAUST_LIB_PATH` if they are set.
     fn default() -> Self {
         Self {
-            faust_path: "faust".into(),
+            faust_path: env::var_os(FAUST_PATH_ENV).map_or_else(|| "faust".into(), PathBuf::from),
             code_gen_options: CodeOptionMap::default(),
             module_name: None,
             out_path: None,
-            compile_options: CompileOptions::default(),
+            compile_options: CompileOptions {
+                import_dirs: env::var_os(FAUST_LIB_PATH_ENV)
+                    .map(|paths| env::split_paths(&paths).collect())
+                    .unwrap_or_default(),
+                ..CompileOptions::default()
+            },
+            faust_version: OnceCell::new(),
+            faust_version_range: FaustVersionRange::default(),
+            warnings: RefCell::new(Vec::new()),
         }
     }
 }
@@ -48,18 +75,107 @@ impl FaustBuilder {
         self.code_gen_options.get(key)
     }
 
+    /// Sets an option, replacing a conflicting one set before, e.g. `Double` by `Single`.
     pub fn set_code_option(&mut self, arg: CodeOption) -> Option<CodeOption> {
-        self.code_gen_options.insert(arg)
+        self.code_gen_options.set(arg)
     }
 
     pub fn set_faust_path(&mut self, faust_path: impl Into<PathBuf>) {
         self.faust_path = faust_path.into();
+        self.faust_version = OnceCell::new();
+    }
+
+    /// Oldest faust version the build accepts. Checked before faust is run.
+    pub fn set_min_faust_version(&mut self, version: FaustVersion) {
+        self.faust_version_range.min = Some(version);
+    }
+
+    /// Newest faust version the build accepts. Checked before faust is run.
+    pub fn set_max_faust_version(&mut self, version: FaustVersion) {
+        self.faust_version_range.max = Some(version);
+    }
+
+    #[must_use]
+    pub fn get_faust_version_range(&self) -> &FaustVersionRange {
+        &self.faust_version_range
+    }
+
+    /// Version of the faust compiler, queried once with `faust -v`.
+    pub fn try_faust_version(&self) -> Result<FaustVersion, String> {
+        if let Some(version) = self.faust_version.get() {
+            return Ok(*version);
+        }
+        let output = Command::new(&self.faust_path)
+            .arg("-v")
+            .output()
+            .map_err(|err| format!("failed to execute {}: {err}", self.faust_path.display()))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} -v failed with {}: {}",
+                self.faust_path.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let version = FaustVersion::from_version_output(&String::from_utf8_lossy(&output.stdout))?;
+        Ok(*self.faust_version.get_or_init(|| version))
+    }
+
+    /// Like [`FaustBuilder::try_faust_version`] but panics if the version cannot be determined.
+    #[must_use]
+    pub fn faust_version(&self) -> FaustVersion {
+        self.try_faust_version()
+            .unwrap_or_else(|err| panic!("cannot determine faust version: {}", err))
+    }
+
+    /// Checks the installed faust against the range set with `set_min_faust_version` and `set_max_faust_version`.
+    pub fn check_faust_version(&self) -> Result<FaustVersion, String> {
+        let version = self.try_faust_version()?;
+        self.faust_version_range.check(&version)?;
+        Ok(version)
     }
 
     pub fn set_out_path(&mut self, out_path: impl Into<PathBuf>) {
         self.out_path = Some(out_path.into());
     }
 
+    /// Directory for the json and xml description and other files generated by faust.
+    ///
+    /// Without an output dir faust writes them next to the dsp file. Code set with
+    /// [`FaustBuilder::set_dsp_code`] is given a temporary output dir instead.
+    pub fn set_output_dir(&mut self, output_dir: impl Into<PathBuf>) {
+        self.compile_options.output_dir = Some(OutputDir::Dir(output_dir.into()));
+        let stdin_code = self
+            .compile_options
+            .dsp_path
+            .as_ref()
+            .and_then(DspPath::code)
+            .map(str::to_owned);
+        if let Some(code) = stdin_code {
+            // files generated for stdin would clash in a shared directory
+            self.write_temp_dsp_file(&code);
+        }
+    }
+
+    /// Uses a temporary output dir that is private to this builder and removed when it is dropped.
+    pub fn set_temp_output_dir(&mut self) {
+        self.compile_options.output_dir = Some(OutputDir::temp());
+    }
+
+    /// Adds a directory to the library search path (`-I`).
+    ///
+    /// Directories from `FAUST_LIB_PATH` are searched first.
+    pub fn add_import_dir(&mut self, import_dir: impl Into<PathBuf>) {
+        self.compile_options.import_dirs.push(import_dir.into());
+    }
+
+    /// Adds a directory to the architecture file search path (`-A`).
+    pub fn add_architecture_dir(&mut self, architecture_dir: impl Into<PathBuf>) {
+        self.compile_options
+            .architecture_dirs
+            .push(architecture_dir.into());
+    }
+
     pub fn set_module_name(&mut self, module_name: impl Into<String>) {
         self.module_name = Some(module_name.into());
     }
@@ -75,6 +191,75 @@ impl FaustBuilder {
         self.compile_options.dsp_path = Some(DspPath::Temp(temp_path.into().into()));
     }
 
+    /// Uses `faust_code` as dsp.
+    ///
+    /// Unless an output dir is set, the builder gets a temporary one and faust reads the code from
+    /// stdin on unix. Otherwise it is written into the output dir like
+    /// [`FaustBuilder::write_temp_dsp_file`], because the files faust generates for stdin are all
+    /// named `stdin` and would clash in a shared directory.
+    pub fn set_dsp_code(&mut self, faust_code: &str) {
+        if cfg!(unix) && !matches!(self.compile_options.output_dir, Some(OutputDir::Dir(_))) {
+            self.compile_options
+                .output_dir
+                .get_or_insert_with(OutputDir::temp);
+            self.compile_options.dsp_path = Some(DspPath::Stdin(faust_code.into()));
+        } else {
+            self.write_temp_dsp_file(faust_code);
+        }
+    }
+
+    /// Aborts the compilation after `seconds`, faust uses 120 by default.
+    pub fn set_timeout(&mut self, seconds: u32) {
+        self.compile_options.timeout = Some(seconds.to_string());
+    }
+
+    pub fn set_warning_policy(&mut self, warnings: WarningPolicy) {
+        self.compile_options.warnings = warnings;
+    }
+
+    /// Compiles with `-wall`, so faust reports all warnings.
+    pub fn set_warn_all(&mut self, warn_all: bool) {
+        self.compile_options.warn_all = warn_all;
+    }
+
+    /// Warnings of the last faust run, empty with [`WarningPolicy::Ignore`].
+    #[must_use]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.borrow().clone()
+    }
+
+    /// Applies the `faust.toml` found in `CARGO_MANIFEST_DIR` or its parents, see [`crate::config`].
+    ///
+    /// Called by the `default_for_*` constructors after the dsp path is set. In a build script it
+    /// prints `cargo:rerun-if-changed=` lines for the config and the dsp file, as cargo then only
+    /// reruns the script for the listed files. Files imported by the dsp need their own lines.
+    pub fn apply_workspace_config(&mut self) {
+        match FaustConfig::discover() {
+            Ok(Some((path, config))) => {
+                if is_build_script() {
+                    println!("cargo:rerun-if-changed={}", path.display());
+                    if let Some(DspPath::File(dsp_path)) = &self.compile_options.dsp_path {
+                        println!("cargo:rerun-if-changed={}", dsp_path.display());
+                    }
+                }
+                config.apply(self);
+            }
+            Ok(None) => {}
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Block diagrams, graphs and timings to generate, see [`crate::artifacts`].
+    pub fn set_artifact_options(&mut self, artifacts: ArtifactOptions) {
+        self.compile_options.artifacts = artifacts;
+    }
+
+    /// Generates svg block diagrams and copies them into `dir`.
+    pub fn write_svg(&mut self, dir: impl Into<PathBuf>) {
+        self.compile_options.artifacts.svg = true;
+        self.compile_options.artifacts.dir = Some(dir.into());
+    }
+
     pub fn write_xml_file(&mut self) {
         self.compile_options.xml = true;
     }
@@ -90,6 +275,7 @@ impl FaustBuilder {
     ) -> Self {
         let mut b = Self::default();
         b.set_dsp_path(dsp_path);
+        b.apply_workspace_config();
         b.set_out_path(out_path);
         b.struct_name_from_dsp_name();
         b.write_json_file();
@@ -100,17 +286,42 @@ impl FaustBuilder {
     pub fn default_for_file(dsp_path: impl Into<PathBuf>, out_path: impl Into<PathBuf>) -> Self {
         let mut b = Self::default();
         b.set_dsp_path(dsp_path);
+        b.apply_workspace_config();
         b.set_out_path(out_path);
         b.struct_name_from_dsp_name();
         b
     }
 
+    /// Builder for a CLAP plugin, see [`crate::clap`].
+    pub fn default_for_clap(
+        dsp_path: impl Into<PathBuf>,
+        out_path: impl Into<PathBuf>,
+        clap: Clap,
+    ) -> Self {
+        let mut b = Self::default_for_file(dsp_path, out_path);
+        b.write_json_file();
+        b.set_architecture(Pipeline::clap(clap).into());
+        b
+    }
+
+    /// Builder for [`FaustBuilder::build_lv2`], with the json description enabled.
+    pub fn default_for_lv2(dsp_path: impl Into<PathBuf>) -> Self {
+        let mut b = Self::default();
+        b.set_dsp_path(dsp_path);
+        b.apply_workspace_config();
+        b.struct_name_from_dsp_name();
+        b.write_json_file();
+        b
+    }
+
     #[cfg(feature = "faust-ui")]
     #[must_use]
     pub fn default_for_include_macro(dsp_path: PathBuf, extra_flags: CodeOptionMap) -> Self {
         let mut builder = Self::default();
+        builder.set_temp_output_dir();
         builder.write_json_file();
         builder.set_dsp_path(dsp_path);
+        builder.apply_workspace_config();
         builder.struct_name_from_dsp_name();
         builder.module_name_from_dsp_file_path();
         builder.set_architecture(Architecture::mod_ui());
@@ -122,7 +333,8 @@ impl FaustBuilder {
     #[must_use]
     pub fn default_for_dsp_macro(faust_code: &str, extra_flags: CodeOptionMap) -> Self {
         let mut builder = Self::default();
-        builder.write_temp_dsp_file(faust_code);
+        builder.set_dsp_code(faust_code);
+        builder.apply_workspace_config();
         builder.write_json_file();
         builder.struct_name_from_dsp_name();
         builder.module_name_from_struct_name();
@@ -133,15 +345,50 @@ impl FaustBuilder {
 
     #[must_use]
     pub fn run_faust(&self) -> String {
-        let faust_result = Command::new(&self.faust_path)
+        self.execute_faust().0
+    }
+
+    /// Runs faust and returns stdout with the generated code and stderr.
+    fn execute_faust(&self) -> (String, String) {
+        if let Err(err) = self.code_gen_options.validate() {
+            panic!("invalid code options: {}", err);
+        }
+        if self.faust_version_range != FaustVersionRange::default() {
+            if let Err(err) = self.check_faust_version() {
+                panic!("{}", err);
+            }
+        }
+        let stdin_code = self
+            .compile_options
+            .dsp_path
+            .as_ref()
+            .and_then(DspPath::code);
+        let mut faust = Command::new(&self.faust_path)
             .args(self.compile_options.to_command_args())
             .args(CodeOptionToCommandArgs::to_command_args(
                 &self.code_gen_options,
             ))
-            .output()
+            .stdin(if stdin_code.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
             .expect("Failed to execute faust");
+        if let Some(code) = stdin_code {
+            // faust reads all of its input before it writes any output
+            let mut stdin = faust.stdin.take().expect("stdin is piped");
+            match stdin.write_all(code.as_bytes()) {
+                // faust exited early, its status and stderr tell why
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+                result => result.expect("failed to write dsp code to faust"),
+            }
+        }
+        let faust_result = faust.wait_with_output().expect("Failed to execute faust");
         let stderr =
-            str::from_utf8(&faust_result.stderr).expect("could not parse stderr from command");
+            String::from_utf8(faust_result.stderr).expect("could not parse stderr from command");
 
         assert!(
             faust_result.status.success(),
@@ -149,10 +396,23 @@ impl FaustBuilder {
             stderr
         );
 
+        let mut warnings = self.warnings.borrow_mut();
+        warnings.clear();
         if stderr.contains("WARNING") {
-            dbg!(stderr);
+            match self.compile_options.warnings {
+                WarningPolicy::Ignore => {}
+                WarningPolicy::Print => warnings.extend(
+                    stderr
+                        .lines()
+                        .filter(|line| !line.trim().is_empty())
+                        .map(str::to_owned),
+                ),
+                WarningPolicy::Deny => panic!("faust warnings are denied: {}", stderr),
+            }
         }
-        String::from_utf8(faust_result.stdout).expect("could not parse stdout from command")
+        let stdout =
+            String::from_utf8(faust_result.stdout).expect("could not parse stdout from command");
+        (stdout, stderr)
     }
 
     fn pretty(ts: TokenStream) -> String {
@@ -160,10 +420,136 @@ impl FaustBuilder {
         prettyplease::unparse(&st)
     }
 
+    /// Parses the json description generated by faust, if enabled.
+    #[must_use]
+    pub fn parse_json(&self) -> Option<faust_json::FaustJson> {
+        if !self.compile_options.json {
+            return None;
+        }
+        let json_path = self.find_generated_file("json");
+        let json_file = fs::File::open(&json_path).unwrap_or_else(|err| {
+            panic!(
+                "json file cannot be read at path {}: {}",
+                json_path.display(),
+                err
+            )
+        });
+        let json_reader = std::io::BufReader::new(json_file);
+        let faust_json = faust_json::FaustJson::from_reader(json_reader, ParseMode::Lenient)
+            .unwrap_or_else(|err| {
+            let version = self
+                .try_faust_version()
+                .map_or_else(|_| "unknown".to_owned(), |version| version.to_string());
+            panic!(
+                "json parsing error: {} (generated by faust {}, the json format may have changed, see FaustBuilder::set_max_faust_version)",
+                err, version
+            );
+        });
+        Some(faust_json)
+    }
+
+    /// Parses the xml description generated by faust, if enabled.
+    #[must_use]
+    pub fn parse_xml(&self) -> Option<faust_xml::FaustXML> {
+        if !self.compile_options.xml {
+            return None;
+        }
+        let xml_path = self.find_generated_file("xml");
+        let xml_file = fs::File::open(&xml_path).unwrap_or_else(|err| {
+            panic!(
+                "xml file cannot be read at path {}: {}",
+                xml_path.display(),
+                err
+            )
+        });
+        let xml_reader = std::io::BufReader::new(xml_file);
+        let faust_xml = quick_xml::de::from_reader(xml_reader).unwrap_or_else(|err| {
+            let version = self
+                .try_faust_version()
+                .map_or_else(|_| "unknown".to_owned(), |version| version.to_string());
+            panic!(
+                "xml parsing error: {} (generated by faust {}, the xml format may have changed, see FaustBuilder::set_max_faust_version)",
+                err, version
+            );
+        });
+        Some(faust_xml)
+    }
+
+    /// Builds the dsp and prints the faust warnings as `cargo:warning=` lines, for build scripts.
     #[allow(clippy::must_use_candidate)]
     pub fn build(&self) -> TokenStream {
+        let (ts, warnings) = self.build_with_warnings();
+        print_cargo_warnings(&warnings);
+        ts
+    }
+
+    /// Like [`FaustBuilder::build`] but returns the faust warnings instead of printing them, e.g.
+    /// for proc macros, whose output cargo does not read.
+    #[allow(clippy::must_use_candidate)]
+    pub fn build_with_warnings(&self) -> (TokenStream, Vec<String>) {
         let dsp_code = self.run_faust();
-        let ts = self.compile_options.architecture.apply(self, &dsp_code);
+        (self.wrap_dsp_code(&dsp_code), self.warnings())
+    }
+
+    /// Like [`FaustBuilder::build`] but also returns the diagrams, graphs and timings enabled with
+    /// [`FaustBuilder::set_artifact_options`].
+    #[allow(clippy::must_use_candidate)]
+    pub fn build_with_artifacts(&self) -> (TokenStream, BuildArtifacts) {
+        let (dsp_code, stderr) = self.execute_faust();
+        let ts = self.wrap_dsp_code(&dsp_code);
+        let output_dir = self.get_output_dir().unwrap_or_else(|| {
+            self.get_dsp_path()
+                .parent()
+                .expect("dsp_path has no parent directory")
+        });
+        let name = self
+            .get_dsp_path()
+            .file_stem()
+            .expect("dsp_path does not end with a filename")
+            .to_string_lossy();
+        let artifacts =
+            BuildArtifacts::collect(&self.compile_options.artifacts, output_dir, &name, &stderr)
+                .unwrap_or_else(|err| panic!("failed to collect build artifacts: {}", err));
+        print_cargo_warnings(&self.warnings());
+        (ts, artifacts)
+    }
+
+    /// Generates an lv2 plugin crate and its bundle, see [`crate::lv2`].
+    ///
+    /// The architecture of the builder is replaced by [`Pipeline::lv2`]. Panics if the json
+    /// description is not enabled, the dsp is built with `Double` or the plugin description is
+    /// invalid.
+    ///
+    /// [`Pipeline::lv2`]: crate::architecture::Pipeline::lv2
+    #[allow(clippy::must_use_candidate)]
+    pub fn build_lv2(&self, options: &Lv2Options) -> Lv2Plugin {
+        lv2::assert_single_precision(&self.code_gen_options);
+        let dsp_code = self.run_faust();
+        let json = self.parse_json().expect(
+            "json description needed for lv2 plugins, enable it with FaustBuilder::write_json_file",
+        );
+        let plugin = Lv2Plugin::from_json(&json, options);
+        if let Err(errors) = plugin.validate() {
+            panic!("invalid lv2 plugin: {}", errors.join(", "));
+        }
+        print_cargo_warnings(&self.warnings());
+        let context = BuildContext::new(self, Some(json));
+        let ts = Architecture::from(Pipeline::lv2(&options.uri)).apply(&context, &dsp_code);
+        plugin
+            .write_crate(options, &Self::pretty(ts))
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to write lv2 crate to {}: {}",
+                    options.crate_dir.display(),
+                    err
+                )
+            });
+        plugin
+    }
+
+    fn wrap_dsp_code(&self, dsp_code: &str) -> TokenStream {
+        let context = BuildContext::new(self, self.parse_json()).with_xml(self.parse_xml());
+        let ts = self.compile_options.architecture.apply(&context, dsp_code);
         let dsp_code = Self::pretty(ts.clone());
         if let Some(out_path) = &self.out_path {
             fs::write(out_path, &dsp_code).expect("failed to write to destination path");
@@ -171,20 +557,18 @@ impl FaustBuilder {
         ts
     }
 
+    /// Sets every option like [`FaustBuilder::set_code_option`].
     pub fn extend_code_options(&mut self, flags: impl IntoIterator<Item = CodeOption>) {
-        self.code_gen_options.extend(flags);
+        for flag in flags {
+            self.code_gen_options.set(flag);
+        }
     }
 
     pub fn struct_name_from_dsp_name(&mut self) {
-        let msg = "generated rust code could";
-        let path = self.get_dsp_path();
-        let faust_code = fs::read(path)
-            .unwrap_or_else(|_| panic!("{} not be read at path: {}", msg, path.to_string_lossy()));
-        let faust_code = String::from_utf8(faust_code)
-            .unwrap_or_else(|_| panic!("{} interpreted as String", msg));
-        let ts: proc_macro2::TokenStream = faust_code
+        let ts: proc_macro2::TokenStream = self
+            .dsp_code()
             .parse()
-            .unwrap_or_else(|_| panic!("{} parse as TokenStream", msg));
+            .unwrap_or_else(|_| panic!("dsp code could not be parsed as TokenStream"));
         let sn = get_name_token(ts);
         let sn = sn.to_camel_case();
         self.set_code_option(CodeOption::StructName(sn));
@@ -208,6 +592,11 @@ impl FaustBuilder {
         self.module_name = Some(struct_name.to_snake_case());
     }
 
+    #[must_use]
+    pub fn try_get_dsp_path(&self) -> Option<&Path> {
+        self.compile_options.dsp_path.as_deref()
+    }
+
     #[must_use]
     pub fn get_dsp_path(&self) -> &Path {
         let Some(path) = &self.compile_options.dsp_path else {
@@ -223,13 +612,31 @@ impl FaustBuilder {
     ) -> proc_macro2::TokenStream {
         let json_file = std::fs::File::open(json_path).expect("Failed to open json file");
         let json_reader = std::io::BufReader::new(json_file);
-        let faust_json: faust_json::FaustJson = serde_json::from_reader(json_reader)
+        let faust_json = faust_json::FaustJson::from_reader(json_reader, ParseMode::Lenient)
             .unwrap_or_else(|err| {
                 panic!("json parsing error: {}", err);
             });
         faust_ui::generate_ui_code(&faust_json, struct_name)
     }
 
+    #[cfg(feature = "faust-ui")]
+    pub fn generate_ui_from_xml(
+        xml_path: &Path,
+        struct_name: impl AsRef<str>,
+    ) -> proc_macro2::TokenStream {
+        use std::convert::TryFrom;
+
+        let xml_file = std::fs::File::open(xml_path).expect("Failed to open xml file");
+        let xml_reader = std::io::BufReader::new(xml_file);
+        let faust_xml: faust_xml::FaustXML =
+            quick_xml::de::from_reader(xml_reader).unwrap_or_else(|err| {
+                panic!("xml parsing error: {}", err);
+            });
+        let model = faust_ui::model::UiModel::try_from(&faust_xml)
+            .unwrap_or_else(|err| panic!("invalid xml description: {}", err));
+        faust_ui::generate_ui_code_from_model(&model, struct_name)
+    }
+
     #[must_use]
     pub fn get_struct_name(&self) -> &String {
         let msg = "No Struct Name defined";
@@ -247,31 +654,144 @@ impl FaustBuilder {
         &self.module_name
     }
 
+    #[must_use]
+    pub fn get_code_options(&self) -> &CodeOptionMap {
+        &self.code_gen_options
+    }
+
+    #[must_use]
+    pub fn get_compile_options(&self) -> &CompileOptions {
+        &self.compile_options
+    }
+
+    #[must_use]
+    pub fn get_output_dir(&self) -> Option<&Path> {
+        self.compile_options.output_dir.as_deref()
+    }
+
     #[must_use]
     pub fn get_json_path(&self) -> PathBuf {
-        let dsp_path = self.get_dsp_path();
-        let gen_json_fn = dsp_path.to_str().expect("dsp path is not utf8").to_owned() + ".json";
-        PathBuf::from(gen_json_fn)
+        self.generated_file_path("json")
     }
 
     #[must_use]
     pub fn xml_path_from_dsp_path(&self) -> PathBuf {
+        self.generated_file_path("xml")
+    }
+
+    /// Path of a description file written by faust, or where it is expected if it does not exist.
+    fn generated_file_path(&self, extension: &str) -> PathBuf {
+        let candidates = self.generated_file_candidates(extension);
+        candidates
+            .iter()
+            .find(|path| path.exists())
+            .unwrap_or(&candidates[0])
+            .clone()
+    }
+
+    /// Paths faust writes a description file to.
+    ///
+    /// Without an output dir faust puts it next to the dsp file. In the output dir it is named after
+    /// the dsp file with the `.dsp` extension, which older faust versions drop. Code read from stdin
+    /// gives `stdin.dsp.json`.
+    fn generated_file_candidates(&self, extension: &str) -> Vec<PathBuf> {
         let dsp_path = self.get_dsp_path();
-        let mut extension = dsp_path.extension().unwrap_or_default().to_owned();
-        extension.push(".xml");
-        dsp_path.with_extension(extension)
+        let file_name = dsp_path
+            .file_name()
+            .expect("dsp_path does not end with a filename");
+        let mut with_extension = file_name.to_owned();
+        with_extension.push(".");
+        with_extension.push(extension);
+        let Some(output_dir) = self.get_output_dir() else {
+            return vec![dsp_path.with_file_name(with_extension)];
+        };
+        let stem = Path::new(file_name)
+            .file_stem()
+            .expect("dsp_path does not end with a filename");
+        let named = |suffix: &str| {
+            let mut name = stem.to_owned();
+            name.push(suffix);
+            output_dir.join(name)
+        };
+        let mut candidates = vec![
+            named(&format!(".dsp.{extension}")),
+            output_dir.join(with_extension),
+            named(&format!(".{extension}")),
+        ];
+        candidates.dedup();
+        candidates
+    }
+
+    /// Like [`FaustBuilder::generated_file_path`], but panics if faust did not write the file.
+    fn find_generated_file(&self, extension: &str) -> PathBuf {
+        let candidates = self.generated_file_candidates(extension);
+        candidates
+            .iter()
+            .find(|path| path.exists())
+            .cloned()
+            .unwrap_or_else(|| {
+                let candidates = candidates
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+                panic!(
+                    "faust did not write the {} description, expected it at {}",
+                    extension,
+                    candidates.join(" or ")
+                )
+            })
+    }
+
+    /// The faust code of the dsp.
+    fn dsp_code(&self) -> String {
+        let dsp_path = self
+            .compile_options
+            .dsp_path
+            .as_ref()
+            .expect("DspPath is not set");
+        dsp_path.code().map_or_else(
+            || {
+                fs::read_to_string(&**dsp_path).unwrap_or_else(|err| {
+                    panic!("dsp file {} cannot be read: {}", dsp_path.display(), err)
+                })
+            },
+            str::to_owned,
+        )
     }
 
+    /// Writes `faust_code` into the output dir and uses it as dsp file.
+    ///
+    /// The file is named after the declared name of the dsp. In a shared output dir set with
+    /// [`FaustBuilder::set_output_dir`] the name gets a unique suffix, so builds of dsps with the same
+    /// name do not overwrite each other, and the file is removed when the builder is dropped.
+    /// Without an output dir the builder gets a temporary one.
     pub fn write_temp_dsp_file(&mut self, faust_code: &str) {
-        let temp_dsp = NamedTempFile::new().expect("failed creating temp dsp file");
-        let mut f = BufWriter::new(temp_dsp);
-        f.write_all(faust_code.as_bytes())
-            .expect("Unable to write to temp dsp file");
-        let temp_path = f
-            .into_inner()
-            .expect("temp dsp error on flush")
-            .into_temp_path();
-        self.set_dsp_temp_path(temp_path);
+        let name = faust_code
+            .parse()
+            .ok()
+            .and_then(|ts| get_declared_value("name", ts))
+            .map_or_else(|| "dsp".to_owned(), |name| name.to_snake_case());
+        match self
+            .compile_options
+            .output_dir
+            .get_or_insert_with(OutputDir::temp)
+        {
+            OutputDir::Temp(dir) => {
+                let dsp_path = dir.path().join(name).with_extension("dsp");
+                fs::write(&dsp_path, faust_code).expect("Unable to write dsp file to output dir");
+                self.set_dsp_path(dsp_path);
+            }
+            OutputDir::Dir(dir) => {
+                let mut file = tempfile::Builder::new()
+                    .prefix(&format!("{name}-"))
+                    .suffix(".dsp")
+                    .tempfile_in(dir)
+                    .expect("Unable to create dsp file in output dir");
+                file.write_all(faust_code.as_bytes())
+                    .expect("Unable to write dsp file to output dir");
+                self.set_dsp_temp_path(file.into_temp_path());
+            }
+        }
     }
 
     pub fn write_debug_dsp_file(&self, name: &str) {
@@ -281,7 +801,7 @@ impl FaustBuilder {
         .join("DEBUG_".to_owned() + name)
         .with_extension("dsp");
         if cfg!(debug_assertions) {
-            fs::copy(self.get_dsp_path(), &debug_dsp)
+            fs::write(&debug_dsp, self.dsp_code())
                 .expect("temp dsp file cannot be copied to target");
         } else {
             let _ignore_error = fs::remove_file(&debug_dsp);
@@ -316,6 +836,17 @@ impl FaustBuilder {
     }
 }
 
+/// Cargo sets `TARGET` and `OUT_DIR` for build scripts, macros only see `OUT_DIR`.
+fn is_build_script() -> bool {
+    env::var_os("TARGET").is_some() && env::var_os("OUT_DIR").is_some()
+}
+
+fn print_cargo_warnings(warnings: &[String]) {
+    for warning in warnings {
+        println!("cargo:warning={warning}");
+    }
+}
+
 fn strip_quotes(name: &proc_macro2::TokenTree) -> String {
     name.to_string()
         .strip_prefix('\"')
@@ -0,0 +1,139 @@
+//! CLAP plugins generated from a dsp.
+//!
+//! [`Clap`] implements `faust_clap::ClapDsp` for the generated dsp and exports its `clap_entry`,
+//! so the generated file only has to be included in a `cdylib` crate depending on `faust-clap`
+//! and `faust-types`. The library built from the crate is the plugin, renamed to `<name>.clap`.
+//!
+//! ```no_run
+//! use faust_build::{builder::FaustBuilder, clap::Clap};
+//!
+//! FaustBuilder::default_for_clap(
+//!     "dsp/volume.dsp",
+//!     "src/dsp.rs",
+//!     Clap::new("org.example.volume").with_vendor("example"),
+//! )
+//! .build();
+//! ```
+//!
+//! Parameters, audio ports and note ports are derived by `faust-clap` when the plugin is loaded.
+
+#![allow(clippy::module_name_repetitions)]
+
+use crate::{architecture::ObjectInterface, build_context::BuildContext};
+use faust_json::{FaustJson, LayoutItem};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Appends the `ClapDsp` implementation and the `clap_entry` of the plugin to the generated code.
+///
+/// The name of the plugin is the name of the dsp, vendor, version and description default to
+/// the `author`, `version` and `description` metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clap {
+    /// Reverse domain name identifying the plugin, e.g. `org.example.volume`.
+    pub id: String,
+    pub vendor: Option<String>,
+    pub url: String,
+    /// CLAP features, by default [`default_features`].
+    pub features: Option<Vec<String>>,
+}
+
+impl Clap {
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            vendor: None,
+            url: String::new(),
+            features: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_features(mut self, features: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.features = Some(features.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+/// `instrument` and `synthesizer` for dsps with a `gate` widget, else `audio-effect`, followed by
+/// `mono` or `stereo` for dsps with one or two outputs.
+#[must_use]
+pub fn default_features(json: &FaustJson) -> Vec<String> {
+    let mut features = if json.ui.iter().any(has_gate) {
+        vec!["instrument", "synthesizer"]
+    } else {
+        vec!["audio-effect"]
+    };
+    match json.outputs {
+        1 => features.push("mono"),
+        2 => features.push("stereo"),
+        _ => {}
+    }
+    features.into_iter().map(str::to_owned).collect()
+}
+
+fn has_gate(item: &LayoutItem) -> bool {
+    match item {
+        LayoutItem::TGroup { items, .. }
+        | LayoutItem::VGroup { items, .. }
+        | LayoutItem::HGroup { items, .. } => items.iter().any(has_gate),
+        LayoutItem::Button { label, .. }
+        | LayoutItem::CheckBox { label, .. }
+        | LayoutItem::VSlider { label, .. }
+        | LayoutItem::HSlider { label, .. }
+        | LayoutItem::NEntry { label, .. } => label == "gate",
+        _ => false,
+    }
+}
+
+impl ObjectInterface for Clap {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        let json = context.expect_json();
+        let struct_name = format_ident!("{}", context.struct_name());
+        let meta = |key: &str| context.meta(key).unwrap_or_default().to_owned();
+        let id = &self.id;
+        let name = &json.name;
+        let vendor = self.vendor.clone().unwrap_or_else(|| meta("author"));
+        let url = &self.url;
+        let version = meta("version");
+        let description = meta("description");
+        let features = self
+            .features
+            .clone()
+            .unwrap_or_else(|| default_features(json));
+        quote! {
+            #dsp_code
+
+            impl faust_clap::ClapDsp for #struct_name {
+                fn descriptor() -> &'static faust_clap::Descriptor {
+                    static DESCRIPTOR: faust_clap::Descriptor =
+                        faust_clap::Descriptor::new(faust_clap::PluginInfo {
+                            id: #id,
+                            name: #name,
+                            vendor: #vendor,
+                            url: #url,
+                            version: #version,
+                            description: #description,
+                            features: &[#(#features),*],
+                        });
+                    &DESCRIPTOR
+                }
+            }
+
+            faust_clap::export_clap!(#struct_name);
+        }
+    }
+}
@@ -1,13 +1,33 @@
 #![allow(clippy::module_name_repetitions)]
+#![allow(clippy::missing_errors_doc)]
 
 use crate::{CodeOptionToCommandArgs, CodeOptionsToCommandArgsRef};
 use std::{
+    borrow::Cow,
     collections::{hash_map::IntoValues, HashMap, HashSet},
     ffi::OsStr,
+    fmt::Display,
     iter::FromIterator,
+    str::FromStr,
 };
 use strum::{EnumDiscriminants, EnumIs, EnumString, IntoDiscriminant};
 
+/// Options of which faust accepts only one: the float precision and the memory manager.
+const EXCLUSIVE_GROUPS: [&[CodeOptionDiscriminants]; 2] = [
+    &[
+        CodeOptionDiscriminants::Single,
+        CodeOptionDiscriminants::Double,
+        CodeOptionDiscriminants::Quad,
+        CodeOptionDiscriminants::FixedPoint,
+    ],
+    &[
+        CodeOptionDiscriminants::MemoryManager,
+        CodeOptionDiscriminants::MemoryManager1,
+        CodeOptionDiscriminants::MemoryManager2,
+        CodeOptionDiscriminants::MemoryManager3,
+    ],
+];
+
 #[derive(Debug, Clone, Default)]
 pub struct CodeOptionMap(HashMap<CodeOptionDiscriminants, CodeOption>);
 impl CodeOptionMap {
@@ -15,18 +35,37 @@ impl CodeOptionMap {
         self.0.insert(CodeOption::discriminant(&value), value)
     }
 
+    /// Inserts `value` and removes the options it excludes, e.g. `Double` for `Single`, so that a
+    /// later option overrides an earlier one instead of conflicting with it.
+    pub fn set(&mut self, value: CodeOption) -> Option<CodeOption> {
+        let key = CodeOption::discriminant(&value);
+        let replaced = EXCLUSIVE_GROUPS
+            .iter()
+            .filter(|group| group.contains(&key))
+            .flat_map(|group| group.iter())
+            .filter(|other| **other != key)
+            .filter_map(|other| self.0.remove(other))
+            .last();
+        self.insert(value).or(replaced)
+    }
+
     #[must_use]
     pub fn get(&self, key: &CodeOptionDiscriminants) -> Option<&CodeOption> {
         self.0.get(key)
     }
 
+    #[must_use]
+    pub fn contains(&self, key: CodeOptionDiscriminants) -> bool {
+        self.0.contains_key(&key)
+    }
+
     #[must_use]
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
     #[must_use]
-    pub fn to_command_args_merge<'a>(&'a self, other_args: &'a Self) -> Vec<&'a OsStr> {
+    pub fn to_command_args_merge<'a>(&'a self, other_args: &'a Self) -> Vec<Cow<'a, OsStr>> {
         let keys: HashSet<&CodeOptionDiscriminants> =
             self.0.keys().chain(other_args.0.keys()).collect();
         let values = keys
@@ -40,6 +79,66 @@ impl CodeOptionMap {
 
         CodeOptionToCommandArgs::to_command_args(values)
     }
+
+    /// Checks for combinations of options that faust rejects when generating rust code.
+    pub fn validate(&self) -> Result<(), String> {
+        use CodeOptionDiscriminants as D;
+
+        let [precisions, memory_managers] = EXCLUSIVE_GROUPS.map(|group| {
+            group
+                .iter()
+                .copied()
+                .filter(|key| self.contains(*key))
+                .collect::<Vec<_>>()
+        });
+        if precisions.len() > 1 {
+            return Err(format!(
+                "only one float precision can be selected, got {precisions:?}"
+            ));
+        }
+        if memory_managers.len() > 1 {
+            return Err("only one memory manager option can be selected".to_owned());
+        }
+        for unsupported in [
+            D::Quad,
+            D::FixedPoint,
+            D::OpenMp,
+            D::ParLoop,
+            D::Scheduler,
+            D::OpenCl,
+            D::Cuda,
+        ] {
+            if self.contains(unsupported) {
+                return Err(format!(
+                    "{unsupported:?} is not supported by the rust backend"
+                ));
+            }
+        }
+        let requires = [
+            (D::FixedPointSize, D::FixedPoint),
+            (D::VecSize, D::Vectorize),
+            (D::LoopVariant, D::Vectorize),
+            (D::DeepFirstScheduling, D::Vectorize),
+            (D::GroupTasks, D::Vectorize),
+            (D::FunTasks, D::Vectorize),
+        ];
+        for (option, required) in requires {
+            if self.contains(option) && !self.contains(required) {
+                return Err(format!("{option:?} requires {required:?}"));
+            }
+        }
+        let excludes = [
+            (D::Vectorize, D::Scalar),
+            (D::Vectorize, D::OneSample),
+            (D::Vectorize, D::InPlace),
+        ];
+        for (a, b) in excludes {
+            if self.contains(a) && self.contains(b) {
+                return Err(format!("{a:?} cannot be combined with {b:?}"));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Extend<CodeOption> for CodeOptionMap {
@@ -70,7 +169,7 @@ impl IntoIterator for CodeOptionMap {
 }
 
 impl<'a> CodeOptionToCommandArgs<'a> for &'a CodeOptionMap {
-    fn to_command_args(self) -> Vec<&'a OsStr> {
+    fn to_command_args(self) -> Vec<Cow<'a, OsStr>> {
         CodeOptionToCommandArgs::to_command_args(self.0.values())
     }
 }
@@ -86,57 +185,95 @@ pub enum CodeOption {
     //   -double     --double-precision-floats   use double precision floats for internal computations.
     Double,
     //   -quad       --quad-precision-floats     use quad precision floats for internal computations.
+    Quad,
     //   -fx         --fixed-point               use fixed-point for internal computations.
+    FixedPoint,
     //   -fx-size    --fixed-point-size          fixed-point number total size in bits (-1 is used to generate a unique fixpoint_t type).
+    FixedPointSize(i32),
     //   -es 1|0     --enable-semantics 1|0      use enable semantics when 1 (default), and simple multiplication otherwise.
+    EnableSemantics(bool),
     //   -lcc        --local-causality-check     check causality also at local level.
+    LocalCausalityCheck,
     //   -light      --light-mode                do not generate the entire DSP API.
+    Light,
     //   -clang      --clang                     when compiled with clang/clang++, adds specific #pragma for auto-vectorization.
     //   -nvi        --no-virtual                when compiled with the C++ backend, does not add the 'virtual' keyword.
     //   -fp         --full-parentheses          always add parentheses around binops.
+    FullParentheses,
     //   -cir        --check-integer-range       check float to integer range conversion.
+    CheckIntegerRange,
     //   -exp10      --generate-exp10            pow(10,x) replaced by possibly faster exp10(x).
+    GenerateExp10,
     //   -os         --one-sample                generate one sample computation.
     OneSample,
     //   -ec         --external-control          separated 'control' and 'compute' functions.
     ExternalControl,
     //   -it         --inline-table              inline rdtable/rwtable code in the main class.
+    InlineTable,
     //   -cm         --compute-mix               mix in outputs buffers.
     ComputeMix,
     //   -ct         --check-table               check rtable/rwtable index range and generate safe access code [0/1: 1 by default].
+    CheckTable(bool),
     //   -cn <name>  --class-name <name>         specify the name of the dsp class to be used instead of mydsp.
     StructName(String),
     //   -scn <name> --super-class-name <name>   specify the name of the super class to be used instead of dsp.
+    SuperClassName(String),
     //   -pn <name>  --process-name <name>       specify the name of the dsp entry-point instead of process.
     ProcessName(String),
     //   -mcd <n>    --max-copy-delay <n>        use a copy delay up to max delay <n> and a dense delay above (ocpp only) or a ring buffer (defaut 16 samples).
+    MaxCopyDelay(u32),
     //   -mdd <n>    --max-dense-delay <n>       use a dense delay up to max delay <n> (if enough density) and a ring buffer delay above (ocpp only, default 1024).
+    MaxDenseDelay(u32),
     //   -mdy <n>    --min-density <n>           minimal density (100*number of delays/max delay) to use a dense delays (ocpp only, default 33).
+    MinDensity(u32),
     //   -dlt <n>    --delay-line-threshold <n>  use a mask-based ring buffer delays up to max delay <n> and a select based ring buffers above (default INT_MAX samples).
+    DelayLineThreshold(u32),
     //   -mem        --memory-manager            allocations done using a custom memory manager.
+    MemoryManager,
     //   -mem1       --memory-manager1           allocations done using a custom memory manager, using the iControl/fControl and iZone/fZone model.
+    MemoryManager1,
     //   -mem2       --memory-manager2           use iControl/fControl, iZone/fZone model and no explicit memory manager.
+    MemoryManager2,
     //   -mem3       --memory-manager3           use iControl/fControl, iZone/fZone model and no explicit memory manager with access as function parameters.
+    MemoryManager3,
     //   -ftz <n>    --flush-to-zero <n>         code added to recursive signals [0:no (default), 1:fabs based, 2:mask based (fastest)].
+    FlushToZero(FlushToZeroMode),
     //   -rui        --range-ui                  whether to generate code to constraint vslider/hslider/nentry values in [min..max] range.
+    RangeUi,
     //   -fui        --freeze-ui                 whether to freeze vslider/hslider/nentry to a given value (init value by default).
+    FreezeUi,
     //   -inj <f>    --inject <f>                inject source file <f> into architecture file instead of compiling a dsp file.
+    Inject(String),
     //   -scal       --scalar                    generate non-vectorized code (default).
+    Scalar,
     //   -inpl       --in-place                  generates code working when input and output buffers are the same (scalar mode only).
     InPlace,
     //   -vec        --vectorize                 generate easier to vectorize code.
+    Vectorize,
     //   -vs <n>     --vec-size <n>              size of the vector (default 32 samples).
+    VecSize(u32),
     //   -lv <n>     --loop-variant <n>          [0:fastest, fixed vector size and a remaining loop (default), 1:simple, variable vector size, 2:fixed, fixed vector size].
+    LoopVariant(LoopVariantMode),
     //   -omp        --openmp                    generate OpenMP pragmas, activates --vectorize option.
+    OpenMp,
     //   -pl         --par-loop                  generate parallel loops in --openmp mode.
+    ParLoop,
     //   -sch        --scheduler                 generate tasks and use a Work Stealing scheduler, activates --vectorize option.
+    Scheduler,
     //   -ocl        --opencl                    generate tasks with OpenCL (experimental).
+    OpenCl,
     //   -cuda       --cuda                      generate tasks with CUDA (experimental).
+    Cuda,
     //   -dfs        --deep-first-scheduling     schedule vector loops in deep first order.
+    DeepFirstScheduling,
     //   -g          --group-tasks               group single-threaded sequential tasks together when -omp or -sch is used.
+    GroupTasks,
     //   -fun        --fun-tasks                 separate tasks code as separated functions (in -vec, -sch, or -omp mode).
+    FunTasks,
     //   -fm <file>  --fast-math <file>          use optimized versions of mathematical functions implemented in <file>, use 'faust/dsp/fastmath.cpp' when file is 'def', assume functions are defined in the architecture file when file is 'arch'.
+    FastMath(String),
     //   -mapp       --math-approximation        simpler/faster versions of 'floor/ceil/fmod/remainder' functions.
+    MathApproximation,
     //   -noreprc    --no-reprc                  (Rust only) Don't force dsp struct layout to follow C ABI.
     NoReprC,
     //   -ns <name>  --namespace <name>          generate C++ or D code in a namespace <name>.
@@ -145,7 +282,9 @@ pub enum CodeOption {
     //   -vhdl-components <file> --vhdl-components <file>    path to a file describing custom components for the VHDL backend.
     //   -fpga-mem <n>  --fpga-mem <n>           FPGA block ram max size, used in -mem1/-mem2 mode.
     //   -wi <n>     --widening-iterations <n>   number of iterations before widening in signal bounding.
+    WideningIterations(u32),
     //   -ni <n>     --narrowing-iterations <n>  number of iterations before stopping narrowing in signal bounding.
+    NarrowingIterations(u32),
 
     // Block diagram options:
     // ---------------------------------------
@@ -175,7 +314,9 @@ pub enum CodeOption {
     //   -sg         --signal-graph              print the internal signal graph in dot format.
     //   -norm       --normalized-form           print signals in normalized form and exit.
     //   -me         --math-exceptions           check / for 0 as denominator and remainder, fmod, sqrt, log10, log, acos, asin functions domain.
+    MathExceptions,
     //   -sts        --strict-select             generate strict code for 'selectX' even for stateless branches (both are computed).
+    StrictSelect,
     //   -wall       --warning-all               print all warnings.
     // DebugWarnings,
     //   -t <sec>    --timeout <sec>             abort compilation after <sec> seconds (default 120).
@@ -194,205 +335,254 @@ pub enum CodeOption {
     // ---------------------------------------
     // faust -a jack-gtk.cpp -o myfx.cpp myfx.dsp
 }
+
+/// Argument of `-ftz`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushToZeroMode {
+    #[default]
+    No = 0,
+    FabsBased = 1,
+    MaskBased = 2,
+}
+
+impl FromStr for FlushToZeroMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" | "No" => Ok(Self::No),
+            "1" | "FabsBased" => Ok(Self::FabsBased),
+            "2" | "MaskBased" => Ok(Self::MaskBased),
+            _ => Err(format!(
+                "invalid flush to zero mode {s}, expected 0, 1 or 2"
+            )),
+        }
+    }
+}
+
+/// Argument of `-lv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopVariantMode {
+    #[default]
+    Fastest = 0,
+    Simple = 1,
+    Fixed = 2,
+}
+
+impl FromStr for LoopVariantMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" | "Fastest" => Ok(Self::Fastest),
+            "1" | "Simple" => Ok(Self::Simple),
+            "2" | "Fixed" => Ok(Self::Fixed),
+            _ => Err(format!("invalid loop variant {s}, expected 0, 1 or 2")),
+        }
+    }
+}
+
+fn parse_arg<T>(
+    key: &str,
+    str_iter: &mut impl Iterator<Item = impl AsRef<str>>,
+) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let arg = str_iter
+        .next()
+        .ok_or_else(|| format!("Missing Argument after {key}"))?;
+    arg.as_ref()
+        .parse()
+        .map_err(|err| format!("Invalid Argument after {key}: {err}"))
+}
+
+fn parse_bool_arg(
+    key: &str,
+    str_iter: &mut impl Iterator<Item = impl AsRef<str>>,
+) -> Result<bool, String> {
+    match parse_arg::<String>(key, str_iter)?.as_str() {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        arg => Err(format!(
+            "Invalid Argument after {key}: {arg}, expected 0 or 1"
+        )),
+    }
+}
+
 impl CodeOption {
     pub fn arg_map_from_str_iter(
         iteratable: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> CodeOptionMap {
+        Self::try_arg_map_from_str_iter(iteratable).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    pub fn try_arg_map_from_str_iter(
+        iteratable: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<CodeOptionMap, String> {
         let mut r = CodeOptionMap::new();
         let mut str_iter = iteratable.into_iter();
         while let Some(key) = str_iter.next() {
-            let fa = Self::from_str_iter(key.as_ref(), &mut str_iter);
+            let fa = Self::try_from_str_iter(key.as_ref(), &mut str_iter)?;
             r.insert(fa);
         }
-        r
+        Ok(r)
+    }
+
+    /// Creates an option from its variant name and arguments, e.g. `("VecSize", ["64"])`.
+    ///
+    /// This is what the array of the `include!` macro is parsed with.
+    pub fn from_name_and_args(
+        name: &str,
+        args: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<Self, String> {
+        let variant = Self::from_str(name).map_err(|_| format!("unknown code option {name}"))?;
+        let args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        let expected = variant.to_command_args().len() - 1;
+        if args.len() != expected {
+            return Err(format!(
+                "{name} expects {expected} argument(s), got {}",
+                args.len()
+            ));
+        }
+        let key = variant.to_command_args()[0].to_string_lossy().into_owned();
+        Self::try_from_str_iter(&key, &mut args.iter())
+    }
+
+    pub fn from_str_iter(key: &str, str_iter: &mut impl Iterator<Item = impl AsRef<str>>) -> Self {
+        Self::try_from_str_iter(key, str_iter).unwrap_or_else(|err| panic!("{}", err))
     }
 
     #[allow(clippy::too_many_lines)]
     #[allow(clippy::match_same_arms)]
-    pub fn from_str_iter(key: &str, str_iter: &mut impl Iterator<Item = impl AsRef<str>>) -> Self {
+    pub fn try_from_str_iter(
+        key: &str,
+        str_iter: &mut impl Iterator<Item = impl AsRef<str>>,
+    ) -> Result<Self, String> {
         let impl_msg = format!("FaustArg Key not implemented {key}");
+        let backend_msg = format!("FaustArg Key not supported by the rust backend {key}");
         let unknown_msg = format!("FaustArg Key not found {key}");
+        let artifact_msg = format!(
+            "FaustArg Key {key} writes artifacts, enable it with FaustBuilder::set_artifact_options"
+        );
 
         let fa = match key {
             // Code generation options:
             // ---------------------------------------
-            //" => panic!("{}", impl_msg),//'lang' should be c, cpp (default), cmajor, codebox, csharp, dlang, fir, interp, java, jax, jsfx, julia, llvm, ocpp, rust, vhdl or wast/wasm.
+            //" => return Err(impl_msg),//'lang' should be c, cpp (default), cmajor, codebox, csharp, dlang, fir, interp, java, jax, jsfx, julia, llvm, ocpp, rust, vhdl or wast/wasm.
             "-single" | "--single-precision-floats" => Self::Single, //use single precision floats for internal computations (default).
 
             "-double" | "--double-precision-floats" => Self::Double, //use double precision floats for internal computations.
 
-            "-quad" | "--quad-precision-floats" => panic!("{}", impl_msg), //use quad precision floats for internal computations.
-            "-fx" | "--fixed-point" => panic!("{}", impl_msg), //use fixed-point for internal computations.
-            "-fx-size" | "--fixed-point-size" => panic!("{}", impl_msg), //fixed-point number total size in bits (-1 is used to generate a unique fixpoint_t type).
-            "-es 1|0" | "--enable-semantics 1|0" => panic!("{}", impl_msg), //use enable semantics when 1 (default), and simple multiplication otherwise.
-            "-lcc" | "--local-causality-check" => panic!("{}", impl_msg), //check causality also at local level.
-            "-light" | "--light-mode" => panic!("{}", impl_msg), //do not generate the entire DSP API.
-            "-clang" | "--clang" => panic!("{}", impl_msg), //when compiled with clang/clang++, adds specific #pragma for auto-vectorization.
-            "-nvi" | "--no-virtual" => panic!("{}", impl_msg), //when compiled with the C++ backend, does not add the 'virtual' keyword.
-            "-fp" | "--full-parentheses" => panic!("{}", impl_msg), //always add parentheses around binops.
-            "-cir" | "--check-integer-range" => panic!("{}", impl_msg), //check float to integer range conversion.
-            "-exp10" | "--generate-exp10" => panic!("{}", impl_msg), //pow(10,x) replaced by possibly faster exp10(x).
-            "-os" | "--one-sample" => Self::OneSample, //generate one sample computation.
+            "-quad" | "--quad-precision-floats" => Self::Quad, //use quad precision floats for internal computations.
+            "-fx" | "--fixed-point" => Self::FixedPoint, //use fixed-point for internal computations.
+            "-fx-size" | "--fixed-point-size" => Self::FixedPointSize(parse_arg(key, str_iter)?), //fixed-point number total size in bits (-1 is used to generate a unique fixpoint_t type).
+            "-es" | "--enable-semantics" => Self::EnableSemantics(parse_bool_arg(key, str_iter)?), //use enable semantics when 1 (default), and simple multiplication otherwise.
+            "-lcc" | "--local-causality-check" => Self::LocalCausalityCheck, //check causality also at local level.
+            "-light" | "--light-mode" => Self::Light, //do not generate the entire DSP API.
+            "-clang" | "--clang" => return Err(backend_msg), //when compiled with clang/clang++, adds specific #pragma for auto-vectorization.
+            "-nvi" | "--no-virtual" => return Err(backend_msg), //when compiled with the C++ backend, does not add the 'virtual' keyword.
+            "-fp" | "--full-parentheses" => Self::FullParentheses, //always add parentheses around binops.
+            "-cir" | "--check-integer-range" => Self::CheckIntegerRange, //check float to integer range conversion.
+            "-exp10" | "--generate-exp10" => Self::GenerateExp10, //pow(10,x) replaced by possibly faster exp10(x).
+            "-os" | "--one-sample" => Self::OneSample,            //generate one sample computation.
 
             "-ec" | "--external-control" => Self::ExternalControl, //separated 'control' and 'compute' functions.
 
-            "-it" | "--inline-table" => panic!("{}", impl_msg), //inline rdtable/rwtable code in the main class.
-            "-cm" | "--compute-mix" => Self::ComputeMix,        //mix in outputs buffers.
+            "-it" | "--inline-table" => Self::InlineTable, //inline rdtable/rwtable code in the main class.
+            "-cm" | "--compute-mix" => Self::ComputeMix,   //mix in outputs buffers.
 
-            "-ct" | "--check-table" => panic!("{}", impl_msg), //check rtable/rwtable index range and generate safe access code [0/1: 1 by default].
-            "-cn" | "--class-name" => Self::StructName(
-                str_iter
-                    .next()
-                    .unwrap_or_else(|| panic!("Missing Argument after {}", key))
-                    .as_ref()
-                    .into(),
-            ), //specify the name of the dsp class to be used instead of mydsp.
+            "-ct" | "--check-table" => Self::CheckTable(parse_bool_arg(key, str_iter)?), //check rtable/rwtable index range and generate safe access code [0/1: 1 by default].
+            "-cn" | "--class-name" => Self::StructName(parse_arg(key, str_iter)?), //specify the name of the dsp class to be used instead of mydsp.
 
-            "-scn" | " --super-class-name" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //specify the name of the super class to be used instead of dsp.
-            "-pn" | "--process-name" => Self::ProcessName(
-                str_iter
-                    .next()
-                    .unwrap_or_else(|| panic!("Missing Argument after {}", key))
-                    .as_ref()
-                    .into(),
-            ), //specify the name of the dsp entry-point instead of process.
-
-            "-mcd" | "--max-copy-delay" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //use a copy delay up to max delay <n> and a dense delay above (ocpp only) or a ring buffer (defaut 16 samples).
-            "-mdd" | "--max-dense-delay" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //use a dense delay up to max delay <n> (if enough density) and a ring buffer delay above (ocpp only, default 1024).
-            "-mdy" | "--min-density" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //minimal density (100*number of delays/max delay) to use a dense delays (ocpp only, default 33).
-            "-dlt" | "--delay-line-threshold" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
+            "-scn" | "--super-class-name" => Self::SuperClassName(parse_arg(key, str_iter)?), //specify the name of the super class to be used instead of dsp.
+            "-pn" | "--process-name" => Self::ProcessName(parse_arg(key, str_iter)?), //specify the name of the dsp entry-point instead of process.
+
+            "-mcd" | "--max-copy-delay" => Self::MaxCopyDelay(parse_arg(key, str_iter)?), //use a copy delay up to max delay <n> and a dense delay above (ocpp only) or a ring buffer (defaut 16 samples).
+            "-mdd" | "--max-dense-delay" => Self::MaxDenseDelay(parse_arg(key, str_iter)?), //use a dense delay up to max delay <n> (if enough density) and a ring buffer delay above (ocpp only, default 1024).
+            "-mdy" | "--min-density" => Self::MinDensity(parse_arg(key, str_iter)?), //minimal density (100*number of delays/max delay) to use a dense delays (ocpp only, default 33).
+            "-dlt" | "--delay-line-threshold" => {
+                Self::DelayLineThreshold(parse_arg(key, str_iter)?)
             } //use a mask-based ring buffer delays up to max delay <n> and a select based ring buffers above (default INT_MAX samples).
-            "-mem" | "--memory-manager" => panic!("{}", impl_msg), //allocations done using a custom memory manager.
-            "-mem1" | "--memory-manager1" => panic!("{}", impl_msg), //allocations done using a custom memory manager, using the iControl/fControl and iZone/fZone model.
-            "-mem2" | "--memory-manager2" => panic!("{}", impl_msg), //use iControl/fControl, iZone/fZone model and no explicit memory manager.
-            "-mem3" | "--memory-manager3" => panic!("{}", impl_msg), //use iControl/fControl, iZone/fZone model and no explicit memory manager with access as function parameters.
-            "-ftz" | "--flush-to-zero" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //code added to recursive signals [0:no (default), 1:fabs based, 2:mask based (fastest)].
-            "-rui" | "--range-ui" => panic!("{}", impl_msg), //whether to generate code to constraint vslider/hslider/nentry values in [min..max] range.
-            "-fui" | "--freeze-ui" => panic!("{}", impl_msg), //whether to freeze vslider/hslider/nentry to a given value (init value by default).
-            "-inj" | "--inject" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //inject source file <f> into architecture file instead of compiling a dsp file.
-            "-scal" | "--scalar" => panic!("{}", impl_msg), //generate non-vectorized code (default).
+            "-mem" | "--memory-manager" => Self::MemoryManager, //allocations done using a custom memory manager.
+            "-mem1" | "--memory-manager1" => Self::MemoryManager1, //allocations done using a custom memory manager, using the iControl/fControl and iZone/fZone model.
+            "-mem2" | "--memory-manager2" => Self::MemoryManager2, //use iControl/fControl, iZone/fZone model and no explicit memory manager.
+            "-mem3" | "--memory-manager3" => Self::MemoryManager3, //use iControl/fControl, iZone/fZone model and no explicit memory manager with access as function parameters.
+            "-ftz" | "--flush-to-zero" => Self::FlushToZero(parse_arg(key, str_iter)?), //code added to recursive signals [0:no (default), 1:fabs based, 2:mask based (fastest)].
+            "-rui" | "--range-ui" => Self::RangeUi, //whether to generate code to constraint vslider/hslider/nentry values in [min..max] range.
+            "-fui" | "--freeze-ui" => Self::FreezeUi, //whether to freeze vslider/hslider/nentry to a given value (init value by default).
+            "-inj" | "--inject" => Self::Inject(parse_arg(key, str_iter)?), //inject source file <f> into architecture file instead of compiling a dsp file.
+            "-scal" | "--scalar" => Self::Scalar, //generate non-vectorized code (default).
             "-inpl" | "--in-place" => Self::InPlace, //generates code working when input and output buffers are the same (scalar mode only).
 
-            "-vec" | "--vectorize" => panic!("{}", impl_msg), //generate easier to vectorize code.
-            "-vs" | "--vec-size" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //size of the vector (default 32 samples).
-            "-lv" | "--loop-variant" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //[0:fastest, fixed vector size and a remaining loop (default), 1:simple, variable vector size, 2:fixed, fixed vector size].
-            "-omp" | "--openmp" => panic!("{}", impl_msg), //generate OpenMP pragmas, activates --vectorize option.
-            "-pl" | "--par-loop" => panic!("{}", impl_msg), //generate parallel loops in --openmp mode.
-            "-sch" | "--scheduler" => panic!("{}", impl_msg), //generate tasks and use a Work Stealing scheduler, activates --vectorize option.
-            "-ocl" | "--opencl" => panic!("{}", impl_msg), //generate tasks with OpenCL (experimental).
-            "-cuda" | "--cuda" => panic!("{}", impl_msg), //generate tasks with CUDA (experimental).
-            "-dfs" | "--deep-first-scheduling" => panic!("{}", impl_msg), //schedule vector loops in deep first order.
-            "-g" | "--group-tasks" => panic!("{}", impl_msg), //group single-threaded sequential tasks together when -omp or -sch is used.
-            "-fun" | "--fun-tasks" => panic!("{}", impl_msg), //separate tasks code as separated functions (in -vec, -sch, or -omp mode).
-            "-fm" | "--fast-math" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //use optimized versions of mathematical functions implemented in <file>, use 'faust/dsp/fastmath.cpp' when file is 'def', assume functions are defined in the architecture file when file is 'arch'.
-            "-mapp" | "--math-approximation" => panic!("{}", impl_msg), //simpler/faster versions of 'floor/ceil/fmod/remainder' functions.
+            "-vec" | "--vectorize" => Self::Vectorize, //generate easier to vectorize code.
+            "-vs" | "--vec-size" => Self::VecSize(parse_arg(key, str_iter)?), //size of the vector (default 32 samples).
+            "-lv" | "--loop-variant" => Self::LoopVariant(parse_arg(key, str_iter)?), //[0:fastest, fixed vector size and a remaining loop (default), 1:simple, variable vector size, 2:fixed, fixed vector size].
+            "-omp" | "--openmp" => Self::OpenMp, //generate OpenMP pragmas, activates --vectorize option.
+            "-pl" | "--par-loop" => Self::ParLoop, //generate parallel loops in --openmp mode.
+            "-sch" | "--scheduler" => Self::Scheduler, //generate tasks and use a Work Stealing scheduler, activates --vectorize option.
+            "-ocl" | "--opencl" => Self::OpenCl,       //generate tasks with OpenCL (experimental).
+            "-cuda" | "--cuda" => Self::Cuda,          //generate tasks with CUDA (experimental).
+            "-dfs" | "--deep-first-scheduling" => Self::DeepFirstScheduling, //schedule vector loops in deep first order.
+            "-g" | "--group-tasks" => Self::GroupTasks, //group single-threaded sequential tasks together when -omp or -sch is used.
+            "-fun" | "--fun-tasks" => Self::FunTasks, //separate tasks code as separated functions (in -vec, -sch, or -omp mode).
+            "-fm" | "--fast-math" => Self::FastMath(parse_arg(key, str_iter)?), //use optimized versions of mathematical functions implemented in <file>, use 'faust/dsp/fastmath.cpp' when file is 'def', assume functions are defined in the architecture file when file is 'arch'.
+            "-mapp" | "--math-approximation" => Self::MathApproximation, //simpler/faster versions of 'floor/ceil/fmod/remainder' functions.
             "-noreprc" | "--no-reprc" => Self::NoReprC, //(Rust only) Don't force dsp struct layout to follow C ABI.
 
-            "-ns" | "--namespace" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //generate C++ or D code in a namespace <name>.
-            "-vhdl-trace" | "--vhdl-trace" => panic!("{}", impl_msg), //activate trace.
-            "-vhdl-float" | "--vhdl-float" => panic!("{}", impl_msg), //uses IEEE-754 format for samples instead of fixed point.
-            "-vhdl-components <file> --vhdl-components" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //path to a file describing custom components for the VHDL backend.
-            "-fpga-mem" | "--fpga-mem" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //FPGA block ram max size, used in -mem1/-mem2 mode.
-            "-wi" | "--widening-iterations" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //number of iterations before widening in signal bounding.
-            "-ni" | "--narrowing-iterations" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
+            "-ns" | "--namespace" => return Err(backend_msg), //generate C++ or D code in a namespace <name>.
+            "-vhdl-trace" | "--vhdl-trace" => return Err(backend_msg), //activate trace.
+            "-vhdl-float" | "--vhdl-float" => return Err(backend_msg), //uses IEEE-754 format for samples instead of fixed point.
+            "-vhdl-components" | "--vhdl-components" => return Err(backend_msg), //path to a file describing custom components for the VHDL backend.
+            "-fpga-mem" | "--fpga-mem" => return Err(backend_msg), //FPGA block ram max size, used in -mem1/-mem2 mode.
+            "-wi" | "--widening-iterations" => Self::WideningIterations(parse_arg(key, str_iter)?), //number of iterations before widening in signal bounding.
+            "-ni" | "--narrowing-iterations" => {
+                Self::NarrowingIterations(parse_arg(key, str_iter)?)
             } //number of iterations before stopping narrowing in signal bounding.
 
             // Block diagram options:
             // ---------------------------------------
-            "-ps" | "--postscript" => panic!("{}", impl_msg), //print block-diagram to a postscript file.
-            "-svg" | "--svg" => panic!("{}", impl_msg),       //print block-diagram to a svg file.
-            "-sd" | "--simplify-diagrams" => panic!("{}", impl_msg), //try to further simplify diagrams before drawing.
-            "-drf" | "--draw-route-frame" => panic!("{}", impl_msg), //draw route frames instead of simple cables.
-            "-f" | "--fold" =>
-            /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
-            {
-                panic!("{}", impl_msg)
-            } //threshold to activate folding mode during block-diagram generation (default 25 elements).
+            "-ps" | "--postscript" => return Err(impl_msg), //print block-diagram to a postscript file.
+            "-svg" | "--svg" => return Err(artifact_msg),   //print block-diagram to a svg file.
+            "-sd" | "--simplify-diagrams" => return Err(artifact_msg), //try to further simplify diagrams before drawing.
+            "-drf" | "--draw-route-frame" => return Err(impl_msg), //draw route frames instead of simple cables.
+            "-f" | "--fold" => return Err(artifact_msg), //threshold to activate folding mode during block-diagram generation (default 25 elements).
             "-fc" | "--fold-complexity" =>
             /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
             {
-                panic!("{}", impl_msg)
+                return Err(impl_msg)
             } //complexity threshold to fold an expression in folding mode (default 2).
             "-mns" | "--max-name-size" =>
             /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
             {
-                panic!("{}", impl_msg)
+                return Err(impl_msg)
             } //threshold during block-diagram generation (default 40 char).
-            "-sn" | "--simple-names" => panic!("{}", impl_msg), //use simple names (without arguments) during block-diagram generation.
-            "-blur" | "--shadow-blur" => panic!("{}", impl_msg), //add a shadow blur to SVG boxes.
-            "-sc" | "--scaled-svg" => panic!("{}", impl_msg),   //automatic scalable SVG.
+            "-sn" | "--simple-names" => return Err(artifact_msg), //use simple names (without arguments) during block-diagram generation.
+            "-blur" | "--shadow-blur" => return Err(impl_msg),    //add a shadow blur to SVG boxes.
+            "-sc" | "--scaled-svg" => return Err(artifact_msg),   //automatic scalable SVG.
 
             // Math doc options:
             // ---------------------------------------
-            "-mdoc" | "--mathdoc" => panic!("{}", impl_msg), //print math documentation of the Faust program in LaTeX format in a -mdoc folder.
+            "-mdoc" | "--mathdoc" => return Err(impl_msg), //print math documentation of the Faust program in LaTeX format in a -mdoc folder.
             "-mdlang" | "--mathdoc-lang" =>
             /*(str_iter.next().unwrap_or_else(||panic!("Missing Argument after {}", key)).into())*/
             {
-                panic!("{}", impl_msg)
+                return Err(impl_msg)
             } //if translation file exists (<l> = en, fr, ...).
-            "-stripmdoc" | "--strip-mdoc-tags" => panic!("{}", impl_msg), //strip mdoc tags when printing Faust -mdoc listings.
+            "-stripmdoc" | "--strip-mdoc-tags" => return Err(impl_msg), //strip mdoc tags when printing Faust -mdoc listings.
+
+            // Debug options:
+            // ---------------------------------------
+            "-me" | "--math-exceptions" => Self::MathExceptions, //check / for 0 as denominator and remainder, fmod, sqrt, log10, log, acos, asin functions domain.
+            "-sts" | "--strict-select" => Self::StrictSelect, //generate strict code for 'selectX' even for stateless branches (both are computed).
 
-            _ => panic!("{}", unknown_msg),
+            _ => return Err(unknown_msg),
         };
-        fa
+        Ok(fa)
     }
 }
 
@@ -404,20 +594,70 @@ impl PartialEq for CodeOption {
     }
 }
 
+fn flag(flag: &str) -> Cow<'_, OsStr> {
+    Cow::Borrowed(flag.as_ref())
+}
+
+fn flag_with_arg<'a>(name: &'a str, arg: &impl ToString) -> Vec<Cow<'a, OsStr>> {
+    vec![flag(name), Cow::Owned(arg.to_string().into())]
+}
+
 impl<'a> CodeOptionsToCommandArgsRef<'a> for CodeOption {
-    fn to_command_args(&'a self) -> Vec<&'a OsStr> {
+    fn to_command_args(&'a self) -> Vec<Cow<'a, OsStr>> {
         match self {
-            Self::Single => vec!["-single".as_ref() as &OsStr],
-            Self::Double => vec!["-double".as_ref() as &OsStr],
-            Self::OneSample => vec!["-os".as_ref() as &OsStr],
-            Self::ExternalControl => vec!["-ec".as_ref() as &OsStr],
-            Self::ComputeMix => vec!["-cm".as_ref() as &OsStr],
-            Self::StructName(name) => vec!["-cn".as_ref() as &OsStr, name.as_ref()],
+            Self::Single => vec![flag("-single")],
+            Self::Double => vec![flag("-double")],
+            Self::Quad => vec![flag("-quad")],
+            Self::FixedPoint => vec![flag("-fx")],
+            Self::FixedPointSize(size) => flag_with_arg("-fx-size", size),
+            Self::EnableSemantics(enable) => flag_with_arg("-es", &u8::from(*enable)),
+            Self::LocalCausalityCheck => vec![flag("-lcc")],
+            Self::Light => vec![flag("-light")],
+            Self::FullParentheses => vec![flag("-fp")],
+            Self::CheckIntegerRange => vec![flag("-cir")],
+            Self::GenerateExp10 => vec![flag("-exp10")],
+            Self::OneSample => vec![flag("-os")],
+            Self::ExternalControl => vec![flag("-ec")],
+            Self::InlineTable => vec![flag("-it")],
+            Self::ComputeMix => vec![flag("-cm")],
+            Self::CheckTable(check) => flag_with_arg("-ct", &u8::from(*check)),
+            Self::StructName(name) => vec![flag("-cn"), flag(name)],
+            Self::SuperClassName(name) => vec![flag("-scn"), flag(name)],
             Self::ProcessName(name) => {
-                vec!["-pn".as_ref() as &OsStr, name.as_ref()]
+                vec![flag("-pn"), flag(name)]
             }
-            Self::InPlace => vec!["-inpl".as_ref() as &OsStr],
-            Self::NoReprC => vec!["-noreprc".as_ref() as &OsStr],
+            Self::MaxCopyDelay(n) => flag_with_arg("-mcd", n),
+            Self::MaxDenseDelay(n) => flag_with_arg("-mdd", n),
+            Self::MinDensity(n) => flag_with_arg("-mdy", n),
+            Self::DelayLineThreshold(n) => flag_with_arg("-dlt", n),
+            Self::MemoryManager => vec![flag("-mem")],
+            Self::MemoryManager1 => vec![flag("-mem1")],
+            Self::MemoryManager2 => vec![flag("-mem2")],
+            Self::MemoryManager3 => vec![flag("-mem3")],
+            Self::FlushToZero(mode) => flag_with_arg("-ftz", &(*mode as u8)),
+            Self::RangeUi => vec![flag("-rui")],
+            Self::FreezeUi => vec![flag("-fui")],
+            Self::Inject(file) => vec![flag("-inj"), flag(file)],
+            Self::Scalar => vec![flag("-scal")],
+            Self::InPlace => vec![flag("-inpl")],
+            Self::Vectorize => vec![flag("-vec")],
+            Self::VecSize(n) => flag_with_arg("-vs", n),
+            Self::LoopVariant(mode) => flag_with_arg("-lv", &(*mode as u8)),
+            Self::OpenMp => vec![flag("-omp")],
+            Self::ParLoop => vec![flag("-pl")],
+            Self::Scheduler => vec![flag("-sch")],
+            Self::OpenCl => vec![flag("-ocl")],
+            Self::Cuda => vec![flag("-cuda")],
+            Self::DeepFirstScheduling => vec![flag("-dfs")],
+            Self::GroupTasks => vec![flag("-g")],
+            Self::FunTasks => vec![flag("-fun")],
+            Self::FastMath(file) => vec![flag("-fm"), flag(file)],
+            Self::MathApproximation => vec![flag("-mapp")],
+            Self::NoReprC => vec![flag("-noreprc")],
+            Self::WideningIterations(n) => flag_with_arg("-wi", n),
+            Self::NarrowingIterations(n) => flag_with_arg("-ni", n),
+            Self::MathExceptions => vec![flag("-me")],
+            Self::StrictSelect => vec![flag("-sts")],
         }
     }
 }
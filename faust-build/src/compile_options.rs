@@ -1,7 +1,25 @@
-use crate::{architecture::Architecture, dsp_path::DspPath};
+use crate::{
+    architecture::Architecture, artifacts::ArtifactOptions, dsp_path::DspPath,
+    output_dir::OutputDir,
+};
 use core::panic;
+use serde::Deserialize;
 use std::{ffi::OsStr, path::PathBuf};
 
+/// What to do with warnings reported by faust. Faust reports more of them with `-wall`, see
+/// `FaustBuilder::set_warn_all`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WarningPolicy {
+    Ignore,
+    /// Report the warnings: `FaustBuilder::build` prints them as `cargo:warning=` lines for build
+    /// scripts, the macros show them as compiler warnings.
+    #[default]
+    Print,
+    /// Fail on any warning.
+    Deny,
+}
+
 // the best thing would be a serde_command_args serializer
 pub struct CompileOptions {
     // Input options:
@@ -10,8 +28,9 @@ pub struct CompileOptions {
     pub architecture: Architecture,
     //   -i        --inline-architecture-files   inline architecture files.
     //   -A <dir>  --architecture-dir <dir>      add the directory <dir> to the architecture search path.
+    pub architecture_dirs: Vec<PathBuf>,
     //   -I <dir>  --import-dir <dir>            add the directory <dir> to the libraries search path.
-    pub import_dir: Option<PathBuf>,
+    pub import_dirs: Vec<PathBuf>,
     //   -L <file> --library <file>              link with the LLVM module <file>.
     // Output options:
     // ---------------------------------------
@@ -23,14 +42,17 @@ pub struct CompileOptions {
     //   -json                                   generate a JSON description file.
     pub json: bool,
     //   -O <dir>  --output-dir <dir>            specify the relative directory of the generated output code and of additional generated files (SVG, XML...).
-    // ExtraOutputDir(PathBuf),
+    pub output_dir: Option<OutputDir>,
+    // Block diagram and debug options that write files, see `crate::artifacts`.
+    pub artifacts: ArtifactOptions,
     // Code generation options:
     // ---------------------------------------
     //   -lang <lang> --language                 select output language,
     pub lang: String,
     // ..
+    pub warnings: WarningPolicy,
     //   -wall       --warning-all               print all warnings.
-    pub debug_warnings: bool,
+    pub warn_all: bool,
     //   -t <sec>    --timeout <sec>             abort compilation after <sec> seconds (default 120).
     pub timeout: Option<String>,
     pub dsp_path: Option<DspPath>,
@@ -40,11 +62,15 @@ impl Default for CompileOptions {
     fn default() -> Self {
         Self {
             architecture: Architecture::None,
-            import_dir: None,
+            architecture_dirs: Vec::new(),
+            import_dirs: Vec::new(),
             xml: false,
             json: false,
+            output_dir: None,
+            artifacts: ArtifactOptions::default(),
             lang: "rust".to_owned(),
-            debug_warnings: true,
+            warnings: WarningPolicy::default(),
+            warn_all: false,
             timeout: None,
             dsp_path: None,
         }
@@ -59,7 +85,11 @@ impl CompileOptions {
             r.push("-a".as_ref());
             r.push(arch_file.as_ref());
         }
-        if let Some(import_dir) = &self.import_dir {
+        for architecture_dir in &self.architecture_dirs {
+            r.push("-A".as_ref());
+            r.push(architecture_dir.as_ref());
+        }
+        for import_dir in &self.import_dirs {
             r.push("-I".as_ref());
             r.push(import_dir.as_ref());
         }
@@ -69,10 +99,15 @@ impl CompileOptions {
         if self.json {
             r.push("-json".as_ref());
         }
+        if let Some(output_dir) = &self.output_dir {
+            r.push("-O".as_ref());
+            r.push(output_dir.as_ref());
+        }
+        r.extend(self.artifacts.to_command_args());
         r.push("-lang".as_ref());
         r.push(self.lang.as_ref());
 
-        if self.debug_warnings {
+        if self.warn_all {
             r.push("-wall".as_ref());
         }
         // 120 is default
@@ -0,0 +1,182 @@
+//! Workspace configuration shared by build scripts and macros.
+//!
+//! `FaustBuilder::default_for_*` and both macros look for a `faust.toml` in `CARGO_MANIFEST_DIR`
+//! and its parent directories. Relative paths are resolved against the directory of the file.
+//! Build scripts are rerun when it changes.
+//!
+//! ```toml
+//! faust_path = "tools/faust"
+//! min_faust_version = "2.70.3"
+//!
+//! [defaults]
+//! flags = ["-double", "-ftz", "2"]
+//! import_dirs = ["dsp/lib"]
+//! warnings = "deny"
+//! warn_all = true
+//!
+//! [files."crates/synth/dsp/osc.dsp"]
+//! flags = ["-vec", "-vs", "32"]
+//! warnings = "ignore"
+//! ```
+//!
+//! The `[defaults]` section applies to every build, a `[files."path"]` section only to the given dsp file.
+//! Code from the `dsp!` macro has no file, so only the defaults apply to it.
+//! `FAUST_PATH` takes precedence over `faust_path`. Options set explicitly in the code or in the
+//! macro invocation take precedence over the config.
+
+#![allow(clippy::missing_errors_doc)]
+
+use crate::{
+    builder::{FaustBuilder, FAUST_PATH_ENV},
+    code_option::{CodeOption, CodeOptionMap},
+    compile_options::WarningPolicy,
+    faust_version::FaustVersion,
+};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+pub const CONFIG_FILE_NAME: &str = "faust.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FaustConfig {
+    /// Directory containing the config file.
+    #[serde(skip)]
+    pub dir: PathBuf,
+    pub faust_path: Option<PathBuf>,
+    pub min_faust_version: Option<FaustVersion>,
+    pub max_faust_version: Option<FaustVersion>,
+    pub defaults: BuildSettings,
+    pub files: BTreeMap<PathBuf, BuildSettings>,
+}
+
+/// Settings of the `[defaults]` and `[files."path"]` sections.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildSettings {
+    /// Faust command line flags, parsed into [`CodeOption`]s.
+    pub flags: Vec<String>,
+    pub import_dirs: Vec<PathBuf>,
+    pub architecture_dirs: Vec<PathBuf>,
+    pub warnings: Option<WarningPolicy>,
+    /// Compile with `-wall`.
+    pub warn_all: Option<bool>,
+    pub json: Option<bool>,
+    pub xml: Option<bool>,
+    pub timeout: Option<u32>,
+}
+
+impl BuildSettings {
+    pub fn code_options(&self) -> Result<CodeOptionMap, String> {
+        CodeOption::try_arg_map_from_str_iter(&self.flags)
+    }
+
+    fn apply(&self, builder: &mut FaustBuilder, dir: &Path) {
+        builder.extend_code_options(
+            self.code_options()
+                .unwrap_or_else(|err| panic!("invalid flags in {}: {}", CONFIG_FILE_NAME, err)),
+        );
+        for import_dir in &self.import_dirs {
+            builder.add_import_dir(dir.join(import_dir));
+        }
+        for architecture_dir in &self.architecture_dirs {
+            builder.add_architecture_dir(dir.join(architecture_dir));
+        }
+        if let Some(warnings) = self.warnings {
+            builder.set_warning_policy(warnings);
+        }
+        if let Some(warn_all) = self.warn_all {
+            builder.set_warn_all(warn_all);
+        }
+        if self.json == Some(true) {
+            builder.write_json_file();
+        }
+        if self.xml == Some(true) {
+            builder.write_xml_file();
+        }
+        if let Some(timeout) = self.timeout {
+            builder.set_timeout(timeout);
+        }
+    }
+}
+
+impl FaustConfig {
+    /// Reads and validates a config file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+        let mut config: Self = toml::from_str(&content)
+            .map_err(|err| format!("cannot parse {}: {err}", path.display()))?;
+        for settings in std::iter::once(&config.defaults).chain(config.files.values()) {
+            settings
+                .code_options()
+                .and_then(|options| options.validate())
+                .map_err(|err| format!("invalid flags in {}: {err}", path.display()))?;
+        }
+        config.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    /// Finds the closest `faust.toml` in `start` or one of its parents.
+    #[must_use]
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the closest `faust.toml` of the crate that is being compiled, if there is one, and
+    /// returns it with its path.
+    pub fn discover() -> Result<Option<(PathBuf, Self)>, String> {
+        env::var_os("CARGO_MANIFEST_DIR")
+            .and_then(|dir| Self::find(Path::new(&dir)))
+            .map(|path| Self::load(&path).map(|config| (path, config)))
+            .transpose()
+    }
+
+    /// Settings of the `[files."path"]` section matching `dsp_path`.
+    #[must_use]
+    pub fn file_settings(&self, dsp_path: &Path) -> Option<&BuildSettings> {
+        let dsp_path = normalize(dsp_path);
+        self.files
+            .iter()
+            .find(|(path, _)| normalize(&self.dir.join(path)) == dsp_path)
+            .map(|(_, settings)| settings)
+    }
+
+    /// Applies the defaults and the settings for the dsp file of `builder`, if it has one.
+    pub fn apply(&self, builder: &mut FaustBuilder) {
+        if env::var_os(FAUST_PATH_ENV).is_none() {
+            if let Some(faust_path) = &self.faust_path {
+                // a bare name is looked up in PATH
+                if faust_path.components().count() > 1 {
+                    builder.set_faust_path(self.dir.join(faust_path));
+                } else {
+                    builder.set_faust_path(faust_path);
+                }
+            }
+        }
+        if let Some(version) = self.min_faust_version {
+            builder.set_min_faust_version(version);
+        }
+        if let Some(version) = self.max_faust_version {
+            builder.set_max_faust_version(version);
+        }
+        self.defaults.apply(builder, &self.dir);
+        if let Some(settings) = builder
+            .try_get_dsp_path()
+            .and_then(|dsp_path| self.file_settings(dsp_path))
+        {
+            settings.apply(builder, &self.dir);
+        }
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -5,10 +5,26 @@ use std::{
 };
 use tempfile::TempPath;
 
+/// Path faust reads the code of [`DspPath::Stdin`] from.
+pub const STDIN_PATH: &str = "/dev/stdin";
+
 #[derive(Debug, Clone)]
 pub enum DspPath {
     File(PathBuf),
     Temp(Rc<TempPath>),
+    /// Faust code written to the stdin of faust, which reads it from [`STDIN_PATH`].
+    Stdin(Rc<str>),
+}
+
+impl DspPath {
+    /// The faust code of [`DspPath::Stdin`].
+    #[must_use]
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            Self::Stdin(code) => Some(code),
+            Self::File(_) | Self::Temp(_) => None,
+        }
+    }
 }
 
 impl Deref for DspPath {
@@ -18,6 +34,7 @@ impl Deref for DspPath {
         match self {
             Self::File(path_buf) => path_buf,
             Self::Temp(rc) => rc,
+            Self::Stdin(_) => Path::new(STDIN_PATH),
         }
     }
 }
@@ -27,6 +44,7 @@ impl PartialEq for DspPath {
         match (self, other) {
             (Self::File(l0), Self::File(r0)) => l0 == r0,
             (Self::Temp(_l0), Self::Temp(_r0)) => false,
+            (Self::Stdin(l0), Self::Stdin(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -0,0 +1,98 @@
+#![allow(clippy::missing_errors_doc)]
+
+use serde::Deserialize;
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// Version of the faust compiler as reported by `faust -v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FaustVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl FaustVersion {
+    #[must_use]
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Extracts the version from the output of `faust -v`, e.g. `FAUST Version 2.76.0`.
+    pub fn from_version_output(output: &str) -> Result<Self, String> {
+        output
+            .split_whitespace()
+            .skip_while(|word| !word.eq_ignore_ascii_case("version"))
+            .nth(1)
+            .ok_or_else(|| format!("no version found in faust output: {output}"))?
+            .parse()
+    }
+}
+
+/// Parses `major.minor.patch`. Suffixes like `-dev` are ignored, a missing patch is read as `0`.
+impl FromStr for FaustVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers = s
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default();
+        let mut parts = numbers.split('.').map(str::parse::<u32>);
+        let mut next = || {
+            parts
+                .next()
+                .transpose()
+                .map_err(|err| format!("invalid faust version {s}: {err}"))
+        };
+        let major = next()?.ok_or_else(|| format!("invalid faust version {s}"))?;
+        let minor = next()?.ok_or_else(|| format!("invalid faust version {s}"))?;
+        let patch = next()?.unwrap_or_default();
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
+impl TryFrom<String> for FaustVersion {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for FaustVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Range of faust versions a build accepts, both ends inclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaustVersionRange {
+    pub min: Option<FaustVersion>,
+    pub max: Option<FaustVersion>,
+}
+
+impl FaustVersionRange {
+    #[must_use]
+    pub fn contains(&self, version: &FaustVersion) -> bool {
+        self.min.is_none_or(|min| min <= *version) && self.max.is_none_or(|max| *version <= max)
+    }
+
+    pub fn check(&self, version: &FaustVersion) -> Result<(), String> {
+        if self.contains(version) {
+            return Ok(());
+        }
+        let bound =
+            |bound: Option<FaustVersion>| bound.map_or_else(|| "any".to_owned(), |v| v.to_string());
+        Err(format!(
+            "faust {version} is not supported, expected a version between {} and {}",
+            bound(self.min),
+            bound(self.max)
+        ))
+    }
+}
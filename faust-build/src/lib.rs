@@ -10,25 +10,33 @@
 #![allow(clippy::missing_panics_doc)]
 #![allow(clippy::missing_const_for_fn)]
 
-use std::ffi::OsStr;
+use std::{borrow::Cow, ffi::OsStr};
 
 pub mod architecture;
+pub mod artifacts;
+pub mod build_context;
 pub mod builder;
+pub mod clap;
 pub mod code_option;
 pub mod compile_options;
+pub mod config;
 pub mod dsp_path;
+pub mod faust_version;
+pub mod lv2;
 #[cfg(feature = "faust-ui")]
 pub mod macro_lib;
+pub mod output_dir;
+pub mod template;
 
 /// Trait to transform a Vector o`FaustArgs`gs into a Vector`OsStr`sStr references.
 ///
 /// `FaustArgs` cannot simply be translated into an &`OsStr` because one enum variant might produce two command arguments
 pub trait CodeOptionToCommandArgs<'a> {
-    fn to_command_args(self) -> Vec<&'a OsStr>;
+    fn to_command_args(self) -> Vec<Cow<'a, OsStr>>;
 }
 
 pub trait CodeOptionsToCommandArgsRef<'a> {
-    fn to_command_args(&'a self) -> Vec<&'a OsStr>;
+    fn to_command_args(&'a self) -> Vec<Cow<'a, OsStr>>;
 }
 
 impl<'a, T, V> CodeOptionToCommandArgs<'a> for T
@@ -36,7 +44,7 @@ where
     T: IntoIterator<Item = &'a V>,
     V: CodeOptionsToCommandArgsRef<'a> + 'a,
 {
-    fn to_command_args(self) -> Vec<&'a OsStr> {
+    fn to_command_args(self) -> Vec<Cow<'a, OsStr>> {
         let i = self.into_iter();
         let m = i.flat_map(CodeOptionsToCommandArgsRef::to_command_args);
         m.collect()
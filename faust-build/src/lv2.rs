@@ -0,0 +1,897 @@
+//! LV2 plugin crates generated from a dsp.
+//!
+//! [`FaustBuilder::build_lv2`] writes a `cdylib` crate into [`Lv2Options::crate_dir`]: the code
+//! generated by faust wrapped with [`Lv2Glue`], and a `<lib>.lv2` bundle with the `manifest.ttl` and
+//! the plugin description. The ports are derived from the json description: audio inputs, audio
+//! outputs and one control port per widget, in the order faust builds the user interface. Active
+//! widgets become control inputs, bargraphs control outputs.
+//!
+//! The bundle is complete once the library built from the crate is copied next to the ttl files.
+//! Only single precision dsps are supported, lv2 ports are always `f32`. Dsps built with
+//! [`CodeOption::Double`] are rejected.
+//!
+//! [`CodeOption::Double`]: crate::code_option::CodeOption::Double
+//!
+//! [`FaustBuilder::build_lv2`]: crate::builder::FaustBuilder::build_lv2
+
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::module_name_repetitions)]
+
+use crate::{
+    architecture::ObjectInterface,
+    build_context::BuildContext,
+    code_option::{CodeOptionDiscriminants, CodeOptionMap},
+};
+use faust_json::{FaustJson, LayoutItem, Meta};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use std::{
+    collections::HashSet,
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lv2Options {
+    /// Uri identifying the plugin, e.g. `https://example.org/plugins/volume`.
+    pub uri: String,
+    /// Directory the crate is written to, created if it does not exist.
+    pub crate_dir: PathBuf,
+    /// Name of the crate, by default the name of `crate_dir`.
+    pub crate_name: String,
+    /// Toml value of the `faust-types` dependency of the crate, e.g. `{ path = "../faust-types" }`.
+    /// By default the version of `faust-types` this crate was built with.
+    pub faust_types: String,
+}
+
+impl Lv2Options {
+    #[must_use]
+    pub fn new(uri: impl Into<String>, crate_dir: impl Into<PathBuf>) -> Self {
+        let crate_dir = crate_dir.into();
+        let crate_name = crate_dir.file_name().map_or_else(
+            || "faust-lv2".to_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Self {
+            uri: uri.into(),
+            crate_dir,
+            crate_name,
+            faust_types: format!("\"{}\"", faust_types::VERSION),
+        }
+    }
+
+    #[must_use]
+    pub fn with_crate_name(mut self, crate_name: impl Into<String>) -> Self {
+        self.crate_name = crate_name.into();
+        self
+    }
+
+    #[must_use]
+    pub fn with_faust_types(mut self, dependency: impl Into<String>) -> Self {
+        self.faust_types = dependency.into();
+        self
+    }
+
+    /// Name of the library built from the crate.
+    #[must_use]
+    pub fn lib_name(&self) -> String {
+        self.crate_name.replace('-', "_")
+    }
+
+    /// File name of the library on the current platform, e.g. `libvolume.so`.
+    #[must_use]
+    pub fn binary_name(&self) -> String {
+        format!("{}{}{}", DLL_PREFIX, self.lib_name(), DLL_SUFFIX)
+    }
+
+    #[must_use]
+    pub fn bundle_dir(&self) -> PathBuf {
+        self.crate_dir.join(format!("{}.lv2", self.lib_name()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lv2PortKind {
+    AudioInput,
+    AudioOutput,
+    ControlInput,
+    ControlOutput,
+}
+
+impl Lv2PortKind {
+    #[must_use]
+    pub const fn is_control(self) -> bool {
+        matches!(self, Self::ControlInput | Self::ControlOutput)
+    }
+
+    #[must_use]
+    pub const fn is_input(self) -> bool {
+        matches!(self, Self::AudioInput | Self::ControlInput)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lv2Port {
+    pub kind: Lv2PortKind,
+    pub index: usize,
+    pub symbol: String,
+    pub name: String,
+    /// Default of control inputs, the init value of the widget.
+    pub default: Option<f32>,
+    pub minimum: Option<f32>,
+    pub maximum: Option<f32>,
+    /// Unit from the `[unit:..]` metadata of the widget.
+    pub unit: Option<String>,
+    /// Port properties like `lv2:toggled` or `pprops:logarithmic`.
+    pub properties: Vec<&'static str>,
+    /// Tooltip of the widget.
+    pub comment: Option<String>,
+}
+
+impl Lv2Port {
+    fn audio(kind: Lv2PortKind, index: usize, symbol: String, name: String) -> Self {
+        Self {
+            kind,
+            index,
+            symbol,
+            name,
+            default: None,
+            minimum: None,
+            maximum: None,
+            unit: None,
+            properties: Vec::new(),
+            comment: None,
+        }
+    }
+}
+
+/// Description of the plugin, rendered into the ttl files of the bundle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lv2Plugin {
+    pub uri: String,
+    pub name: String,
+    /// File name of the library, relative to the bundle.
+    pub binary: String,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub ports: Vec<Lv2Port>,
+}
+
+impl Lv2Plugin {
+    /// Builds the ports from the json description.
+    ///
+    /// Audio ports are named `in0`, `out0`, .. unless the dsp declares `input0`, `output0`, ..
+    /// metadata. Control ports use the shortname of their widget as symbol.
+    #[must_use]
+    pub fn from_json(json: &FaustJson, options: &Lv2Options) -> Self {
+        let meta = |key: &str| {
+            json.meta
+                .iter()
+                .find(|meta| meta.key == key)
+                .map(|meta| meta.value.clone())
+        };
+        let mut symbols = Symbols::default();
+        let mut ports = Vec::new();
+        for (kind, key, default, count) in [
+            (Lv2PortKind::AudioInput, "input", "in", json.inputs),
+            (Lv2PortKind::AudioOutput, "output", "out", json.outputs),
+        ] {
+            for i in 0..count {
+                let name = meta(&format!("{key}{i}")).unwrap_or_else(|| format!("{default}{i}"));
+                let symbol = symbols.unique(&name);
+                ports.push(Lv2Port::audio(kind, ports.len(), symbol, name));
+            }
+        }
+        for item in &json.ui {
+            control_ports(item, &mut symbols, &mut ports);
+        }
+        Self {
+            uri: options.uri.clone(),
+            name: json.name.clone(),
+            binary: options.binary_name(),
+            author: meta("author"),
+            license: meta("license"),
+            version: meta("version"),
+            description: meta("description"),
+            ports,
+        }
+    }
+
+    /// File name of the plugin description in the bundle.
+    #[must_use]
+    pub fn ttl_file_name(&self) -> String {
+        format!("{}.ttl", symbol(&self.name))
+    }
+
+    pub fn control_ports(&self) -> impl Iterator<Item = &Lv2Port> {
+        self.ports.iter().filter(|port| port.kind.is_control())
+    }
+
+    /// Checks the description like `lv2lint` would, returns all problems found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if !is_absolute_uri(&self.uri) {
+            errors.push(format!("plugin uri {:?} is not an absolute uri", self.uri));
+        }
+        if self.name.trim().is_empty() {
+            errors.push("plugin has no name".to_owned());
+        }
+        let mut symbols = HashSet::new();
+        for (i, port) in self.ports.iter().enumerate() {
+            let port_name = format!("port {} ({:?})", i, port.symbol);
+            if port.index != i {
+                errors.push(format!("{} has index {}", port_name, port.index));
+            }
+            if !is_symbol(&port.symbol) {
+                errors.push(format!("{port_name} has an invalid symbol"));
+            }
+            if !symbols.insert(port.symbol.as_str()) {
+                errors.push(format!("{port_name} has a duplicate symbol"));
+            }
+            if port.name.trim().is_empty() {
+                errors.push(format!("{port_name} has no name"));
+            }
+            if !port.kind.is_control() {
+                continue;
+            }
+            let values = [port.default, port.minimum, port.maximum];
+            if values.iter().flatten().any(|value| !value.is_finite()) {
+                errors.push(format!("{port_name} has a value that is not finite"));
+                continue;
+            }
+            let (Some(min), Some(max)) = (port.minimum, port.maximum) else {
+                errors.push(format!("{port_name} has no range"));
+                continue;
+            };
+            if min >= max {
+                errors.push(format!("{port_name} has minimum {min} >= maximum {max}"));
+            }
+            match port.default {
+                Some(default) if default < min || default > max => errors.push(format!(
+                    "{port_name} has default {default} outside of {min}..{max}"
+                )),
+                None if port.kind == Lv2PortKind::ControlInput => {
+                    errors.push(format!("{port_name} has no default"));
+                }
+                _ => {}
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The `manifest.ttl` of the bundle, pointing to the binary and the plugin description.
+    #[must_use]
+    pub fn manifest_ttl(&self) -> String {
+        format!(
+            "@prefix lv2: <http://lv2plug.in/ns/lv2core#> .\n\
+             @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n\
+             \n\
+             <{}>\n    a lv2:Plugin ;\n    lv2:binary <{}> ;\n    rdfs:seeAlso <{}> .\n",
+            self.uri,
+            self.binary,
+            self.ttl_file_name()
+        )
+    }
+
+    /// The plugin description with all ports.
+    #[must_use]
+    pub fn plugin_ttl(&self) -> String {
+        let mut ttl = String::from(
+            "@prefix doap: <http://usefulinc.com/ns/doap#> .\n\
+             @prefix foaf: <http://xmlns.com/foaf/0.1/> .\n\
+             @prefix lv2: <http://lv2plug.in/ns/lv2core#> .\n\
+             @prefix pprops: <http://lv2plug.in/ns/ext/port-props#> .\n\
+             @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .\n\
+             @prefix units: <http://lv2plug.in/ns/extensions/units#> .\n\n",
+        );
+        writeln!(ttl, "<{}>\n    a lv2:Plugin ;", self.uri).expect("cannot fail");
+        let mut line = |line: String| writeln!(ttl, "    {line} ;").expect("cannot fail");
+        line(format!("doap:name {}", literal(&self.name)));
+        if let Some(author) = &self.author {
+            line(format!("doap:maintainer [ foaf:name {} ]", literal(author)));
+        }
+        if let Some(license) = &self.license {
+            line(format!("doap:license {}", literal(license)));
+        }
+        if let Some(description) = &self.description {
+            line(format!("rdfs:comment {}", literal(description)));
+        }
+        if let Some(version) = &self.version {
+            let mut parts = version
+                .split('.')
+                .skip(1)
+                .map(|part| part.parse::<u32>().unwrap_or(0));
+            line(format!("lv2:minorVersion {}", parts.next().unwrap_or(0)));
+            line(format!("lv2:microVersion {}", parts.next().unwrap_or(0)));
+        }
+        line("lv2:optionalFeature lv2:hardRTCapable".to_owned());
+        // the dsp reads its inputs while writing its outputs
+        line("lv2:requiredFeature lv2:inPlaceBroken".to_owned());
+        let ports = self.ports.iter().map(port_ttl).collect::<Vec<_>>();
+        if ports.is_empty() {
+            // replace the separator of the last statement
+            ttl.truncate(ttl.len() - 2);
+        } else {
+            write!(ttl, "    lv2:port {}", ports.join(" , ")).expect("cannot fail");
+        }
+        ttl.push_str(" .\n");
+        ttl
+    }
+
+    /// Writes the crate with the given rust code and the bundle with the ttl files.
+    ///
+    /// Returns the path of the bundle.
+    pub fn write_crate(&self, options: &Lv2Options, code: &str) -> io::Result<PathBuf> {
+        let src_dir = options.crate_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+        fs::write(options.crate_dir.join("Cargo.toml"), cargo_toml(options))?;
+        fs::write(src_dir.join("lib.rs"), code)?;
+        self.write_bundle(&options.bundle_dir())?;
+        Ok(options.bundle_dir())
+    }
+
+    /// Writes `manifest.ttl` and the plugin description into the bundle dir.
+    pub fn write_bundle(&self, bundle_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(bundle_dir)?;
+        fs::write(bundle_dir.join("manifest.ttl"), self.manifest_ttl())?;
+        fs::write(bundle_dir.join(self.ttl_file_name()), self.plugin_ttl())
+    }
+}
+
+fn cargo_toml(options: &Lv2Options) -> String {
+    format!(
+        "[package]\n\
+         name = \"{}\"\n\
+         version = \"0.1.0\"\n\
+         edition = \"2018\"\n\
+         \n\
+         [lib]\n\
+         crate-type = [\"cdylib\"]\n\
+         \n\
+         [dependencies]\n\
+         faust-types = {}\n\
+         \n\
+         # generated crates are not part of an enclosing workspace\n\
+         [workspace]\n",
+        options.crate_name, options.faust_types
+    )
+}
+
+#[derive(Default)]
+struct Symbols(HashSet<String>);
+
+impl Symbols {
+    fn unique(&mut self, name: &str) -> String {
+        let base = symbol(name);
+        let mut symbol = base.clone();
+        let mut n = 1;
+        while self.0.contains(&symbol) {
+            symbol = format!("{base}_{n}");
+            n += 1;
+        }
+        self.0.insert(symbol.clone());
+        symbol
+    }
+}
+
+/// Turns a name into a valid lv2 symbol, `[_a-zA-Z][_a-zA-Z0-9]*`.
+fn symbol(name: &str) -> String {
+    let mut symbol = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if !symbol.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        symbol.insert(0, '_');
+    }
+    symbol
+}
+
+fn is_symbol(symbol: &str) -> bool {
+    symbol.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_absolute_uri(uri: &str) -> bool {
+    uri.split_once(':').is_some_and(|(scheme, rest)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            && !rest.is_empty()
+            && !uri.contains(|c: char| c.is_whitespace() || "<>\"{}|\\^`".contains(c))
+    })
+}
+
+fn literal(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Formats a value as a turtle decimal or double, `0` would be an integer.
+fn number(value: f32) -> String {
+    format!("{value:?}")
+}
+
+#[allow(clippy::too_many_lines)]
+fn control_ports(item: &LayoutItem, symbols: &mut Symbols, ports: &mut Vec<Lv2Port>) {
+    let (kind, label, shortname, meta, default, range, properties) = match item {
+        LayoutItem::TGroup { items, .. }
+        | LayoutItem::VGroup { items, .. }
+        | LayoutItem::HGroup { items, .. } => {
+            for item in items {
+                control_ports(item, symbols, ports);
+            }
+            return;
+        }
+        LayoutItem::VSlider {
+            label,
+            shortname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        }
+        | LayoutItem::HSlider {
+            label,
+            shortname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        } => (
+            Lv2PortKind::ControlInput,
+            label,
+            shortname,
+            meta,
+            Some(*init),
+            (*min, *max),
+            number_properties(*min, *max, *step, meta),
+        ),
+        LayoutItem::NEntry {
+            label,
+            shortname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        } => (
+            Lv2PortKind::ControlInput,
+            label,
+            shortname,
+            meta,
+            Some(init.unwrap_or(*min)),
+            (*min, *max),
+            number_properties(*min, *max, *step, meta),
+        ),
+        LayoutItem::Button {
+            label,
+            shortname,
+            init,
+            meta,
+            ..
+        } => (
+            Lv2PortKind::ControlInput,
+            label,
+            shortname,
+            meta,
+            Some(init.unwrap_or_default()),
+            (0.0, 1.0),
+            vec!["lv2:toggled", "pprops:trigger"],
+        ),
+        LayoutItem::CheckBox {
+            label,
+            shortname,
+            init,
+            meta,
+            ..
+        } => (
+            Lv2PortKind::ControlInput,
+            label,
+            shortname,
+            meta,
+            Some(init.unwrap_or_default()),
+            (0.0, 1.0),
+            vec!["lv2:toggled"],
+        ),
+        LayoutItem::VBarGraph {
+            label,
+            shortname,
+            min,
+            max,
+            meta,
+            ..
+        }
+        | LayoutItem::HBarGraph {
+            label,
+            shortname,
+            min,
+            max,
+            meta,
+            ..
+        } => (
+            Lv2PortKind::ControlOutput,
+            label,
+            shortname,
+            meta,
+            None,
+            (*min, *max),
+            number_properties(*min, *max, 0.0, meta),
+        ),
+        // soundfiles have no port, they are skipped by the glue as well
+        LayoutItem::Soundfile { .. } => return,
+    };
+    let widget_meta = |key: &str| {
+        meta.iter()
+            .find(|meta| meta.key == key)
+            .map(|meta| meta.value.clone())
+    };
+    let symbol = symbols.unique(if shortname.is_empty() {
+        label
+    } else {
+        shortname
+    });
+    ports.push(Lv2Port {
+        kind,
+        index: ports.len(),
+        symbol,
+        name: label.clone(),
+        default,
+        minimum: Some(range.0),
+        maximum: Some(range.1),
+        unit: widget_meta("unit"),
+        properties,
+        comment: widget_meta("tooltip"),
+    });
+}
+
+fn number_properties(min: f32, max: f32, step: f32, meta: &[Meta]) -> Vec<&'static str> {
+    let mut properties = Vec::new();
+    let integral = |value: f32| value.fract() == 0.0;
+    if step != 0.0 && integral(step) && integral(min) && integral(max) {
+        properties.push("lv2:integer");
+    }
+    if meta
+        .iter()
+        .any(|meta| meta.key == "scale" && (meta.value == "log" || meta.value == "exp"))
+    {
+        properties.push("pprops:logarithmic");
+    }
+    properties
+}
+
+fn port_ttl(port: &Lv2Port) -> String {
+    let direction = if port.kind.is_input() {
+        "lv2:InputPort"
+    } else {
+        "lv2:OutputPort"
+    };
+    let typ = if port.kind.is_control() {
+        "lv2:ControlPort"
+    } else {
+        "lv2:AudioPort"
+    };
+    let mut lines = vec![
+        format!("a {} , {}", direction, typ),
+        format!("lv2:index {}", port.index),
+        format!("lv2:symbol {}", literal(&port.symbol)),
+        format!("lv2:name {}", literal(&port.name)),
+    ];
+    for (key, value) in [
+        ("default", port.default),
+        ("minimum", port.minimum),
+        ("maximum", port.maximum),
+    ] {
+        if let Some(value) = value {
+            lines.push(format!("lv2:{} {}", key, number(value)));
+        }
+    }
+    if let Some(unit) = &port.unit {
+        lines.push(format!("units:unit {}", unit_ttl(unit)));
+    }
+    for property in &port.properties {
+        lines.push(format!("lv2:portProperty {property}"));
+    }
+    if let Some(comment) = &port.comment {
+        lines.push(format!("rdfs:comment {}", literal(comment)));
+    }
+    format!("[\n        {}\n    ]", lines.join(" ;\n        "))
+}
+
+/// Maps faust units to the lv2 units extension, other units are described inline.
+fn unit_ttl(unit: &str) -> String {
+    let known = match unit {
+        "dB" | "db" => "db",
+        "Hz" | "hz" => "hz",
+        "kHz" | "khz" => "khz",
+        "MHz" => "mhz",
+        "ms" => "ms",
+        "s" | "sec" => "s",
+        "min" => "min",
+        "%" => "pc",
+        "cent" | "cents" => "cent",
+        "semitone" | "semitones" | "st" => "semitone12TET",
+        "bpm" | "BPM" => "bpm",
+        "deg" | "°" => "degree",
+        "oct" => "oct",
+        "m" => "m",
+        "cm" => "cm",
+        "mm" => "mm",
+        _ => {
+            return format!(
+                "[ a units:Unit ; rdfs:label {0} ; units:symbol {0} ; units:render {1} ]",
+                literal(unit),
+                literal(&format!("%f {unit}"))
+            )
+        }
+    };
+    format!("units:{known}")
+}
+
+/// Appends the `lv2_descriptor` entry point of the plugin to the generated code.
+///
+/// The ports of the plugin are connected in the order of [`Lv2Plugin::from_json`]: audio inputs,
+/// audio outputs and then the parameters in the order of `build_user_interface`.
+pub struct Lv2Glue(String);
+
+impl Lv2Glue {
+    #[must_use]
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self(uri.into())
+    }
+}
+
+impl ObjectInterface for Lv2Glue {
+    fn apply(&self, context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+        assert_single_precision(context.code_options());
+        let glue = glue(
+            context.struct_name(),
+            &self.0,
+            context.expect_json().inputs,
+            context.expect_json().outputs,
+        );
+        quote! {
+            #dsp_code
+            #glue
+        }
+    }
+}
+
+/// Lv2 ports are `f32`, the glue passes them to the dsp without conversion.
+pub(crate) fn assert_single_precision(code_options: &CodeOptionMap) {
+    assert!(
+        !code_options.contains(CodeOptionDiscriminants::Double),
+        "lv2 plugins only support single precision dsps, remove the Double option"
+    );
+}
+
+#[allow(clippy::too_many_lines)]
+fn glue(struct_name: &str, uri: &str, inputs: usize, outputs: usize) -> TokenStream {
+    let struct_name = format_ident!("{}", struct_name);
+    let uri = Literal::byte_string(format!("{uri}\0").as_bytes());
+    quote! {
+        /// lv2 entry point and plugin instance.
+        pub mod lv2 {
+            use super::#struct_name as Dsp;
+            use faust_types::{FaustDsp, ParamIndex, UI};
+            use std::{
+                os::raw::{c_char, c_void},
+                ptr, slice,
+            };
+
+            const INPUTS: usize = #inputs;
+            const OUTPUTS: usize = #outputs;
+
+            /// `LV2_Descriptor` of `lv2/core/lv2.h`.
+            #[repr(C)]
+            pub struct Descriptor {
+                uri: *const c_char,
+                instantiate: unsafe extern "C" fn(
+                    *const Descriptor,
+                    f64,
+                    *const c_char,
+                    *const *const c_void,
+                ) -> *mut c_void,
+                connect_port: unsafe extern "C" fn(*mut c_void, u32, *mut c_void),
+                activate: Option<unsafe extern "C" fn(*mut c_void)>,
+                run: unsafe extern "C" fn(*mut c_void, u32),
+                deactivate: Option<unsafe extern "C" fn(*mut c_void)>,
+                cleanup: unsafe extern "C" fn(*mut c_void),
+                extension_data: unsafe extern "C" fn(*const c_char) -> *const c_void,
+            }
+
+            // only contains a pointer to a static string
+            unsafe impl Sync for Descriptor {}
+
+            static DESCRIPTOR: Descriptor = Descriptor {
+                uri: #uri.as_ptr().cast(),
+                instantiate,
+                connect_port,
+                activate: Some(activate),
+                run,
+                deactivate: None,
+                cleanup,
+                extension_data,
+            };
+
+            #[no_mangle]
+            pub extern "C" fn lv2_descriptor(index: u32) -> *const Descriptor {
+                if index == 0 {
+                    &DESCRIPTOR
+                } else {
+                    ptr::null()
+                }
+            }
+
+            struct Control {
+                param: ParamIndex,
+                input: bool,
+            }
+
+            /// Collects the parameters in the order of the control ports.
+            #[derive(Default)]
+            struct Controls(Vec<Control>);
+
+            impl Controls {
+                fn push(&mut self, param: ParamIndex, input: bool) {
+                    self.0.push(Control { param, input });
+                }
+            }
+
+            impl UI<f32> for Controls {
+                fn open_tab_box(&mut self, _label: &str) {}
+                fn open_horizontal_box(&mut self, _label: &str) {}
+                fn open_vertical_box(&mut self, _label: &str) {}
+                fn close_box(&mut self) {}
+                fn add_button(&mut self, _label: &str, param: ParamIndex) {
+                    self.push(param, true);
+                }
+                fn add_check_button(&mut self, _label: &str, param: ParamIndex) {
+                    self.push(param, true);
+                }
+                fn add_vertical_slider(
+                    &mut self,
+                    _label: &str,
+                    param: ParamIndex,
+                    _init: f32,
+                    _min: f32,
+                    _max: f32,
+                    _step: f32,
+                ) {
+                    self.push(param, true);
+                }
+                fn add_horizontal_slider(
+                    &mut self,
+                    _label: &str,
+                    param: ParamIndex,
+                    _init: f32,
+                    _min: f32,
+                    _max: f32,
+                    _step: f32,
+                ) {
+                    self.push(param, true);
+                }
+                fn add_num_entry(
+                    &mut self,
+                    _label: &str,
+                    param: ParamIndex,
+                    _init: f32,
+                    _min: f32,
+                    _max: f32,
+                    _step: f32,
+                ) {
+                    self.push(param, true);
+                }
+                fn add_horizontal_bargraph(
+                    &mut self,
+                    _label: &str,
+                    param: ParamIndex,
+                    _min: f32,
+                    _max: f32,
+                ) {
+                    self.push(param, false);
+                }
+                fn add_vertical_bargraph(
+                    &mut self,
+                    _label: &str,
+                    param: ParamIndex,
+                    _min: f32,
+                    _max: f32,
+                ) {
+                    self.push(param, false);
+                }
+                fn declare(&mut self, _param: Option<ParamIndex>, _key: &str, _value: &str) {}
+            }
+
+            struct Plugin {
+                dsp: Box<Dsp>,
+                controls: Vec<Control>,
+                /// Last value passed to the dsp, `NaN` until the first run.
+                values: Vec<f32>,
+                ports: Vec<*mut f32>,
+            }
+
+            unsafe extern "C" fn instantiate(
+                _descriptor: *const Descriptor,
+                sample_rate: f64,
+                _bundle_path: *const c_char,
+                _features: *const *const c_void,
+            ) -> *mut c_void {
+                let mut dsp = Box::new(Dsp::new());
+                dsp.init(sample_rate as i32);
+                let mut controls = Controls::default();
+                dsp.build_user_interface(&mut controls);
+                let plugin = Plugin {
+                    dsp,
+                    values: vec![f32::NAN; controls.0.len()],
+                    ports: vec![ptr::null_mut(); INPUTS + OUTPUTS + controls.0.len()],
+                    controls: controls.0,
+                };
+                Box::into_raw(Box::new(plugin)).cast()
+            }
+
+            unsafe extern "C" fn connect_port(instance: *mut c_void, port: u32, data: *mut c_void) {
+                let plugin = &mut *instance.cast::<Plugin>();
+                if let Some(port) = plugin.ports.get_mut(port as usize) {
+                    *port = data.cast();
+                }
+            }
+
+            unsafe extern "C" fn activate(instance: *mut c_void) {
+                let plugin = &mut *instance.cast::<Plugin>();
+                plugin.dsp.instance_clear();
+            }
+
+            unsafe extern "C" fn run(instance: *mut c_void, sample_count: u32) {
+                let plugin = &mut *instance.cast::<Plugin>();
+                let (audio, controls) = plugin.ports.split_at(INPUTS + OUTPUTS);
+                for ((control, port), value) in plugin
+                    .controls
+                    .iter()
+                    .zip(controls)
+                    .zip(&mut plugin.values)
+                {
+                    if control.input && !port.is_null() && **port != *value {
+                        *value = **port;
+                        plugin.dsp.set_param(control.param, *value);
+                    }
+                }
+                if sample_count > 0 && audio.iter().all(|port| !port.is_null()) {
+                    let count = sample_count as usize;
+                    let inputs: [&[f32]; INPUTS] =
+                        std::array::from_fn(|i| slice::from_raw_parts(audio[i], count));
+                    let mut outputs: [&mut [f32]; OUTPUTS] =
+                        std::array::from_fn(|i| slice::from_raw_parts_mut(audio[INPUTS + i], count));
+                    FaustDsp::compute(&mut *plugin.dsp, sample_count as i32, &inputs, &mut outputs);
+                }
+                for (control, port) in plugin.controls.iter().zip(controls) {
+                    if !control.input && !port.is_null() {
+                        **port = plugin.dsp.get_param(control.param).unwrap_or_default();
+                    }
+                }
+            }
+
+            unsafe extern "C" fn cleanup(instance: *mut c_void) {
+                drop(Box::from_raw(instance.cast::<Plugin>()));
+            }
+
+            unsafe extern "C" fn extension_data(_uri: *const c_char) -> *const c_void {
+                ptr::null()
+            }
+        }
+    }
+}
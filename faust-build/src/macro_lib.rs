@@ -9,11 +9,19 @@
 
 use crate::{
     builder::{get_declared_value, FaustBuilder},
-    code_option::CodeOption,
+    code_option::{CodeOption, CodeOptionMap},
 };
 use heck::SnakeCase;
-use std::{env, iter::FromIterator, path::PathBuf, str::FromStr};
-use syn::{parse::Parse, Error, Expr, ExprArray, ExprPath, LitStr, Token};
+use quote::quote;
+use std::{
+    env,
+    iter::FromIterator,
+    path::{Path, PathBuf},
+};
+use syn::{
+    parse::Parse, Error, Expr, ExprArray, ExprCall, ExprLit, ExprPath, ExprUnary, Lit, LitStr,
+    Token, UnOp,
+};
 
 fn get_flags_token(ts: proc_macro2::TokenStream) -> Vec<String> {
     get_declared_value("flags", ts).map_or_else(std::vec::Vec::new, |s| {
@@ -23,39 +31,171 @@ fn get_flags_token(ts: proc_macro2::TokenStream) -> Vec<String> {
     })
 }
 
+/// Search directories of a macro, relative paths are resolved against the crate root.
+#[derive(Debug, Default)]
+pub struct SearchDirs {
+    pub import_dirs: Vec<String>,
+    pub architecture_dirs: Vec<String>,
+}
+
+impl SearchDirs {
+    /// Removes `-I <dir>` and `-A <dir>` from the flags declared in a `dsp!` macro.
+    fn split_from_flags(flags: Vec<String>) -> (Vec<String>, Self) {
+        let mut dirs = Self::default();
+        let mut rest = Vec::new();
+        let mut flags = flags.into_iter();
+        while let Some(flag) = flags.next() {
+            let list = match flag.as_str() {
+                "-I" | "--import-dir" => &mut dirs.import_dirs,
+                "-A" | "--architecture-dir" => &mut dirs.architecture_dirs,
+                _ => {
+                    rest.push(flag);
+                    continue;
+                }
+            };
+            list.push(
+                flags
+                    .next()
+                    .unwrap_or_else(|| panic!("{} expects a directory", flag)),
+            );
+        }
+        (rest, dirs)
+    }
+
+    fn apply(&self, builder: &mut FaustBuilder, crate_dir: &Path) {
+        for dir in &self.import_dirs {
+            builder.add_import_dir(crate_dir.join(dir));
+        }
+        for dir in &self.architecture_dirs {
+            builder.add_architecture_dir(crate_dir.join(dir));
+        }
+    }
+}
+
+/// Shows faust warnings as a compiler warning at the macro invocation.
+///
+/// Proc macros cannot print `cargo:warning=` lines and `proc_macro::Diagnostic` is unstable, but the
+/// note of a deprecated item is shown wherever it is used.
+fn warnings_to_tokens(warnings: &[String]) -> proc_macro2::TokenStream {
+    if warnings.is_empty() {
+        return proc_macro2::TokenStream::new();
+    }
+    let note = format!("faust reported warnings:\n{}", warnings.join("\n"));
+    quote! {
+        const _: () = {
+            #[deprecated(note = #note)]
+            struct FaustWarnings;
+            let _ = FaustWarnings;
+        };
+    }
+}
+
+fn crate_dir() -> PathBuf {
+    env::var_os("CARGO_MANIFEST_DIR")
+        .expect("environment variable CARGO_MANIFEST_DIR is not set")
+        .into()
+}
+
 pub struct FileMacroArgs {
     pub dsp_path: LitStr,
     pub flags: Vec<CodeOption>,
+    pub search_dirs: SearchDirs,
+}
+
+enum ArrayElement {
+    Flag(CodeOption),
+    ImportDir(String),
+    ArchitectureDir(String),
 }
 
 impl FileMacroArgs {
-    fn parse_enums(input_expr: ExprArray) -> syn::Result<Vec<CodeOption>> {
-        let elems = input_expr.elems;
-        elems
-            .iter()
-            .map(|expr| {
-                let Expr::Path(ExprPath { path, .. }) = expr else {
-                    return Result::Err(Error::new_spanned(
-                        expr,
+    fn parse_arg(expr: &Expr) -> syn::Result<String> {
+        match expr {
+            Expr::Lit(ExprLit { lit, .. }) => match lit {
+                Lit::Str(s) => Ok(s.value()),
+                Lit::Int(i) => Ok(i.base10_digits().to_owned()),
+                Lit::Float(f) => Ok(f.base10_digits().to_owned()),
+                Lit::Bool(b) => Ok(b.value.to_string()),
+                _ => Err(Error::new_spanned(lit, "Unsupported CodeOption argument")),
+            },
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr,
+                ..
+            }) => Ok(format!("-{}", Self::parse_arg(expr)?)),
+            Expr::Path(ExprPath { path, .. }) => path
+                .get_ident()
+                .map(std::string::ToString::to_string)
+                .ok_or_else(|| Error::new_spanned(path, "Unsupported CodeOption argument")),
+            _ => Err(Error::new_spanned(expr, "Unsupported CodeOption argument")),
+        }
+    }
+
+    fn parse_element(expr: &Expr) -> syn::Result<ArrayElement> {
+        let (path, args) = match expr {
+            Expr::Path(ExprPath { path, .. }) => (path, Vec::new()),
+            Expr::Call(ExprCall { func, args, .. }) => {
+                let Expr::Path(ExprPath { path, .. }) = func.as_ref() else {
+                    return Err(Error::new_spanned(
+                        func,
                         "Can not parse Array Element as Enum Variant",
                     ));
                 };
+                let args = args
+                    .iter()
+                    .map(Self::parse_arg)
+                    .collect::<syn::Result<Vec<_>>>()?;
+                (path, args)
+            }
+            _ => {
+                return Err(Error::new_spanned(
+                    expr,
+                    "Can not parse Array Element as Enum Variant",
+                ))
+            }
+        };
+        let Some(name) = path.get_ident().map(std::string::ToString::to_string) else {
+            return Err(Error::new_spanned(
+                path,
+                "Can not parse Array Element as CodeGenerationOption Enum Variant",
+            ));
+        };
+        match (name.as_str(), args.as_slice()) {
+            ("ImportDir", [dir]) => return Ok(ArrayElement::ImportDir(dir.clone())),
+            ("ArchitectureDir", [dir]) => return Ok(ArrayElement::ArchitectureDir(dir.clone())),
+            ("ImportDir" | "ArchitectureDir", _) => {
+                return Err(Error::new_spanned(
+                    expr,
+                    format!("{name} expects a directory"),
+                ))
+            }
+            _ => {}
+        }
+        CodeOption::from_name_and_args(&name, args).map(ArrayElement::Flag).map_err(|err| {
+            Error::new_spanned(
+                expr,
+                format!("Can not parse Array Element as CodeGenerationOption Enum Variant {name}: {err}"),
+            )
+        })
+    }
 
-                let Some(name) = path.get_ident().map(std::string::ToString::to_string) else {
-                    return Result::Err(Error::new_spanned(
-                        path,
-                        "Can not parse Array Element as CodeGenerationOption Enum Variant",
-                    ));
-                };
-                let Ok(fa) = CodeOption::from_str(&name) else {
-                    return Result::Err(Error::new_spanned(
-                        path,
-                        format!("Can not parse Array Element as CodeGenerationOption Enum Variant {name}"),
-                    ));
-                };
-                Ok(fa)
-            })
-            .collect()
+    fn parse_elements(input_expr: &ExprArray) -> syn::Result<(Vec<CodeOption>, SearchDirs)> {
+        let mut flags = Vec::new();
+        let mut search_dirs = SearchDirs::default();
+        for element in &input_expr.elems {
+            match Self::parse_element(element)? {
+                ArrayElement::Flag(flag) => flags.push(flag),
+                ArrayElement::ImportDir(dir) => search_dirs.import_dirs.push(dir),
+                ArrayElement::ArchitectureDir(dir) => search_dirs.architecture_dirs.push(dir),
+            }
+        }
+        flags
+            .iter()
+            .cloned()
+            .collect::<CodeOptionMap>()
+            .validate()
+            .map_err(|err| Error::new_spanned(input_expr, err))?;
+        Ok((flags, search_dirs))
     }
 }
 
@@ -66,11 +206,16 @@ impl Parse for FileMacroArgs {
             Ok(Self {
                 dsp_path,
                 flags: Vec::new(),
+                search_dirs: SearchDirs::default(),
             })
         } else {
             let _comma: Token![,] = input.parse()?;
-            let flags = Self::parse_enums(input.parse()?)?;
-            Ok(Self { dsp_path, flags })
+            let (flags, search_dirs) = Self::parse_elements(&input.parse()?)?;
+            Ok(Self {
+                dsp_path,
+                flags,
+                search_dirs,
+            })
         }
     }
 }
@@ -78,22 +223,24 @@ impl Parse for FileMacroArgs {
 #[cfg(feature = "faust-ui")]
 #[must_use]
 pub fn build_faust_file_from_macro(args: FileMacroArgs) -> proc_macro2::TokenStream {
-    use crate::code_option::CodeOptionMap;
-
-    let source_file =
-        env::var("CARGO_MANIFEST_DIR").expect("environment variable CARGO_MANIFEST_DIR is not set");
-    let folder: PathBuf = source_file.into();
+    let folder = crate_dir();
     let flags = CodeOptionMap::from_iter(args.flags);
     let relative_dsp_path: PathBuf = args.dsp_path.value().into();
     let dsp_path = folder.join(&relative_dsp_path);
     assert!(
         dsp_path.exists(),
-        "dsp file does not exist at: {:?}",
-        dsp_path
+        "dsp file does not exist at: {}",
+        dsp_path.display()
     );
 
-    let builder = FaustBuilder::default_for_include_macro(dsp_path, flags);
-    builder.build()
+    let mut builder = FaustBuilder::default_for_include_macro(dsp_path, flags);
+    args.search_dirs.apply(&mut builder, &folder);
+    let (dsp_code, warnings) = builder.build_with_warnings();
+    let warnings = warnings_to_tokens(&warnings);
+    quote! {
+        #dsp_code
+        #warnings
+    }
 }
 
 #[cfg(feature = "faust-ui")]
@@ -101,13 +248,21 @@ pub fn build_faust_file_from_macro(args: FileMacroArgs) -> proc_macro2::TokenStr
 pub fn build_dsp_code_from_macro(input: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
     let faust_code = format!("{input}").replace(';', ";\n");
 
-    let flags = get_flags_token(input.clone());
+    let (flags, search_dirs) = SearchDirs::split_from_flags(get_flags_token(input.clone()));
     let flags = CodeOption::arg_map_from_str_iter(flags.iter());
 
-    let builder = FaustBuilder::default_for_dsp_macro(&faust_code, flags);
+    let mut builder = FaustBuilder::default_for_dsp_macro(&faust_code, flags);
+    // the dsp code is compiled from a temp dir, so crate-local libraries need the crate root
+    let crate_dir = crate_dir();
+    builder.add_import_dir(&crate_dir);
+    search_dirs.apply(&mut builder, &crate_dir);
 
     builder.write_debug_dsp_file(&builder.get_struct_name().to_snake_case());
-    let dsp_code = builder.build();
+    let (dsp_code, warnings) = builder.build_with_warnings();
     builder.write_debug_json_file(&builder.get_struct_name().to_snake_case());
-    dsp_code
+    let warnings = warnings_to_tokens(&warnings);
+    quote! {
+        #dsp_code
+        #warnings
+    }
 }
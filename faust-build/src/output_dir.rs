@@ -0,0 +1,37 @@
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+use tempfile::TempDir;
+
+/// Directory passed to faust with `-O` for json, xml and other generated files.
+#[derive(Debug, Clone)]
+pub enum OutputDir {
+    Dir(PathBuf),
+    /// Private to one build and removed together with the last clone.
+    Temp(Rc<TempDir>),
+}
+
+impl OutputDir {
+    #[must_use]
+    pub fn temp() -> Self {
+        Self::Temp(Rc::new(
+            tempfile::Builder::new()
+                .prefix("faust-build")
+                .tempdir()
+                .expect("failed creating temp output dir"),
+        ))
+    }
+}
+
+impl Deref for OutputDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Dir(path_buf) => path_buf,
+            Self::Temp(rc) => rc.path(),
+        }
+    }
+}
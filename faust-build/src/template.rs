@@ -0,0 +1,248 @@
+//! Placeholders for architecture files.
+//!
+//! Faust itself fills `<<includeIntrinsic>>` and `<<includeclass>>` when it is called with `-a`.
+//! All other placeholders are left untouched by faust and are rendered afterwards from the [`BuildContext`]:
+//!
+//! * `<<moduleName>>`, `<<structName>>`, `<<dspName>>`
+//! * `<<inputs>>`, `<<outputs>>`
+//! * `<<paramTable>>`: one tuple `(address, label, type, init, min, max, step),` per widget
+//! * `<<meta:key>>`: the value of a global metadata declaration, e.g. `<<meta:author>>`
+//!
+//! Sections can be rendered conditionally with `<<if:name>> ... <<else>> ... <<endif>>`
+//! and `<<ifnot:name>> ... <<endif>>`. A condition holds if the placeholder `name` renders
+//! to something other than an empty string or `0`. Conditions inside a section that is not
+//! rendered are not evaluated.
+//!
+//! Placeholders that need the json description (everything besides the module and struct name)
+//! panic if `FaustBuilder::write_json_file` was not enabled. Unknown placeholders are left untouched,
+//! so code like `1<<N>>2` passes through, but conditions on an unknown name panic.
+
+use crate::build_context::BuildContext;
+use faust_json::{FaustJson, LayoutItem};
+use std::fmt::Write;
+
+struct Section {
+    parent_active: bool,
+    condition: bool,
+    in_else: bool,
+}
+
+impl Section {
+    fn is_active(&self) -> bool {
+        self.parent_active && (self.condition != self.in_else)
+    }
+}
+
+fn is_active(sections: &[Section]) -> bool {
+    sections.last().is_none_or(Section::is_active)
+}
+
+fn is_placeholder_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '.' | '/' | '-'))
+}
+
+/// Renders all placeholders and conditional sections of `template`.
+#[must_use]
+pub fn render(template: &str, context: &BuildContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut sections: Vec<Section> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("<<") {
+        let (text, tail) = rest.split_at(start);
+        if is_active(&sections) {
+            out.push_str(text);
+        }
+        let tail = &tail[2..];
+        let Some(name) = tail
+            .find(">>")
+            .map(|end| &tail[..end])
+            .filter(|name| is_placeholder_name(name))
+        else {
+            // not a placeholder, e.g. a shift operator
+            if is_active(&sections) {
+                out.push_str("<<");
+            }
+            rest = tail;
+            continue;
+        };
+        rest = &tail[name.len() + 2..];
+
+        if let Some(condition) = name.strip_prefix("if:") {
+            let parent_active = is_active(&sections);
+            sections.push(Section {
+                parent_active,
+                condition: parent_active && is_true(&condition_value(condition, context)),
+                in_else: false,
+            });
+        } else if let Some(condition) = name.strip_prefix("ifnot:") {
+            let parent_active = is_active(&sections);
+            sections.push(Section {
+                parent_active,
+                condition: parent_active && !is_true(&condition_value(condition, context)),
+                in_else: false,
+            });
+        } else if name == "else" {
+            let section = sections
+                .last_mut()
+                .expect("<<else>> without matching <<if:..>> in architecture file");
+            assert!(!section.in_else, "duplicate <<else>> in architecture file");
+            section.in_else = true;
+        } else if name == "endif" {
+            sections
+                .pop()
+                .expect("<<endif>> without matching <<if:..>> in architecture file");
+        } else if is_active(&sections) {
+            if let Some(value) = value(name, context) {
+                out.push_str(&value);
+            } else {
+                // unknown placeholders are kept, e.g. `1<<N>>2`
+                write!(out, "<<{name}>>").expect("cannot fail");
+            }
+        }
+    }
+    assert!(
+        sections.is_empty(),
+        "<<if:..>> without matching <<endif>> in architecture file"
+    );
+    out.push_str(rest);
+    out
+}
+
+fn is_true(value: &str) -> bool {
+    !value.is_empty() && value != "0"
+}
+
+fn condition_value(name: &str, context: &BuildContext) -> String {
+    value(name, context)
+        .unwrap_or_else(|| panic!("unknown placeholder <<{}>> in architecture file", name))
+}
+
+fn value(name: &str, context: &BuildContext) -> Option<String> {
+    let json = || {
+        context.json().unwrap_or_else(|| {
+            panic!(
+                "placeholder <<{}>> needs the json description, enable it with FaustBuilder::write_json_file",
+                name
+            )
+        })
+    };
+    let value = match name {
+        "moduleName" => context.module_name().unwrap_or_default().to_owned(),
+        "structName" => context.struct_name().to_owned(),
+        "dspName" => json().name.clone(),
+        "inputs" => json().inputs.to_string(),
+        "outputs" => json().outputs.to_string(),
+        "paramTable" => param_table(json()),
+        _ => {
+            let key = name.strip_prefix("meta:")?;
+            json()
+                .meta
+                .iter()
+                .find(|meta| meta.key == key)
+                .map(|meta| meta.value.clone())
+                .unwrap_or_default()
+        }
+    };
+    Some(value)
+}
+
+fn param_table(json: &FaustJson) -> String {
+    let mut table = String::new();
+    for item in &json.ui {
+        write_params(&mut table, item);
+    }
+    table
+}
+
+#[allow(clippy::many_single_char_names)]
+fn write_params(table: &mut String, item: &LayoutItem) {
+    let mut row = |typ: &str, address: &str, label: &str, values: [f32; 4]| {
+        let [init, min, max, step] = values;
+        writeln!(
+            table,
+            "({address:?}, {label:?}, {typ:?}, {init:?}, {min:?}, {max:?}, {step:?}),"
+        )
+        .expect("cannot fail");
+    };
+    match item {
+        LayoutItem::TGroup { items, .. }
+        | LayoutItem::VGroup { items, .. }
+        | LayoutItem::HGroup { items, .. } => {
+            for item in items {
+                write_params(table, item);
+            }
+        }
+        LayoutItem::VSlider {
+            label,
+            address,
+            init,
+            min,
+            max,
+            step,
+            ..
+        } => row("vslider", address, label, [*init, *min, *max, *step]),
+        LayoutItem::HSlider {
+            label,
+            address,
+            init,
+            min,
+            max,
+            step,
+            ..
+        } => row("hslider", address, label, [*init, *min, *max, *step]),
+        LayoutItem::NEntry {
+            label,
+            address,
+            init,
+            min,
+            max,
+            step,
+            ..
+        } => row(
+            "nentry",
+            address,
+            label,
+            [init.unwrap_or(*min), *min, *max, *step],
+        ),
+        LayoutItem::Button {
+            label,
+            address,
+            init,
+            ..
+        } => row(
+            "button",
+            address,
+            label,
+            [init.unwrap_or_default(), 0.0, 1.0, 1.0],
+        ),
+        LayoutItem::CheckBox {
+            label,
+            address,
+            init,
+            ..
+        } => row(
+            "checkbox",
+            address,
+            label,
+            [init.unwrap_or_default(), 0.0, 1.0, 1.0],
+        ),
+        LayoutItem::VBarGraph {
+            label,
+            address,
+            min,
+            max,
+            ..
+        } => row("vbargraph", address, label, [*min, *min, *max, 0.0]),
+        LayoutItem::HBarGraph {
+            label,
+            address,
+            min,
+            max,
+            ..
+        } => row("hbargraph", address, label, [*min, *min, *max, 0.0]),
+        LayoutItem::Soundfile { .. } => {}
+    }
+}
@@ -0,0 +1,123 @@
+use faust_build::{
+    architecture::{AllowLints, ExtraDerives, Module, ObjectInterface, Pipeline, Prelude},
+    build_context::BuildContext,
+    builder::FaustBuilder,
+    code_option::CodeOption,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+
+fn builder() -> FaustBuilder {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Volume".to_owned()));
+    builder.set_module_name("dsp");
+    builder
+}
+
+fn render(pipeline: &Pipeline, builder: &FaustBuilder) -> String {
+    let context = BuildContext::new(builder, None);
+    let dsp_code = "pub struct Volume { fRec0: [f32; 2] }"
+        .parse()
+        .expect("invalid dsp code");
+    let code = pipeline.apply(&context, &dsp_code);
+    prettyplease::unparse(&syn::parse2(code).expect("invalid architecture output"))
+}
+
+/// Appends a function named after the step, to see in which order the steps ran.
+fn step(name: &'static str) -> impl ObjectInterface {
+    move |_: &BuildContext, dsp_code: &TokenStream| {
+        let name = quote::format_ident!("{}", name);
+        quote! {
+            #dsp_code
+            fn #name() {}
+        }
+    }
+}
+
+#[test]
+fn steps_run_in_order() {
+    let mut pipeline = Pipeline::new();
+    assert!(pipeline.is_empty());
+    assert_eq!(
+        render(&pipeline, &builder()),
+        "pub struct Volume {\n    fRec0: [f32; 2],\n}\n"
+    );
+
+    pipeline.push(step("first"));
+    let pipeline = pipeline.with(step("second")).with(step("third"));
+    assert_eq!(pipeline.len(), 3);
+    let code = render(&pipeline, &builder());
+    let first = code.find("fn first()").expect("first step missing");
+    let second = code.find("fn second()").expect("second step missing");
+    let third = code.find("fn third()").expect("third step missing");
+    assert!(first < second && second < third, "{}", code);
+}
+
+#[test]
+fn default_wrapper() {
+    let code = render(&Pipeline::default_wrapper(), &builder());
+    assert!(
+        code.starts_with("#![allow(clippy::all)]\n#![allow(unused_parens)]\n"),
+        "{}",
+        code
+    );
+    let prelude = code.find("use faust_types::*;").expect("prelude missing");
+    assert!(code.rfind("#![allow").expect("lints missing") < prelude);
+    assert!(prelude < code.find("pub struct Volume").expect("dsp missing"));
+}
+
+#[test]
+fn custom_prelude_and_lints() {
+    let pipeline = Pipeline::new()
+        .with(Prelude::new(quote! { use crate::types::*; }))
+        .with(AllowLints::new(["dead_code"]).with_lint("clippy::pedantic"));
+    assert_eq!(
+        render(&pipeline, &builder()),
+        "#![allow(dead_code)]\n#![allow(clippy::pedantic)]\nuse crate::types::*;\n\
+         pub struct Volume {\n    fRec0: [f32; 2],\n}\n"
+    );
+}
+
+#[test]
+fn module_from_builder_or_name() {
+    let code = render(&Pipeline::new().with(Module::default()), &builder());
+    assert!(
+        code.starts_with("pub mod dsp {\n    pub struct Volume"),
+        "{}",
+        code
+    );
+    let code = render(&Pipeline::new().with(Module::new("volume")), &builder());
+    assert!(code.starts_with("pub mod volume {\n"), "{}", code);
+}
+
+#[test]
+#[should_panic(expected = "module name needed by module architecture object")]
+fn module_without_name() {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Volume".to_owned()));
+    let _ = render(&Pipeline::new().with(Module::default()), &builder);
+}
+
+#[test]
+fn extra_derives_inside_module() {
+    let pipeline = Pipeline::new()
+        .with(Module::default())
+        .with(ExtraDerives::new(["Debug", "Clone"]));
+    let code = render(&pipeline, &builder());
+    assert!(
+        code.contains("pub mod dsp {\n    #[derive(Debug, Clone)]\n    pub struct Volume {"),
+        "{}",
+        code
+    );
+}
+
+#[test]
+#[should_panic(expected = "struct Other not found")]
+fn extra_derives_without_struct() {
+    let mut builder = builder();
+    builder.set_code_option(CodeOption::StructName("Other".to_owned()));
+    let _ = render(
+        &Pipeline::new().with(ExtraDerives::new(["Debug"])),
+        &builder,
+    );
+}
@@ -0,0 +1,107 @@
+use faust_build::artifacts::{ArtifactOptions, BuildArtifacts, Timing};
+use std::fs;
+
+#[test]
+fn parse_timing() {
+    let stderr = "start compile\n  start evaluation\n  end evaluation (duration : 0.0125)\nWARNING : something\nend compile (duration : 0.5)\n";
+    assert_eq!(
+        Timing::parse_all(stderr),
+        [
+            Timing {
+                phase: "evaluation".to_owned(),
+                duration: 0.0125
+            },
+            Timing {
+                phase: "compile".to_owned(),
+                duration: 0.5
+            }
+        ]
+    );
+}
+
+#[test]
+fn collect_and_copy() {
+    let output_dir = tempfile::tempdir().expect("failed creating temp dir");
+    let svg_dir = output_dir.path().join("volume-svg");
+    fs::create_dir(&svg_dir).expect("failed creating svg dir");
+    fs::write(svg_dir.join("process.svg"), "<svg/>").expect("failed writing svg");
+    fs::write(output_dir.path().join("volume.dsp.dot"), "digraph {}").expect("failed writing dot");
+    fs::write(output_dir.path().join("volume.dsp-sig.dot"), "digraph {}")
+        .expect("failed writing dot");
+    fs::write(output_dir.path().join("volume.dsp.json"), "{}").expect("failed writing json");
+
+    let target = tempfile::tempdir().expect("failed creating temp dir");
+    let options = ArtifactOptions {
+        svg: true,
+        task_graph: true,
+        signal_graph: true,
+        timing: true,
+        dir: Some(target.path().join("docs")),
+        ..ArtifactOptions::default()
+    };
+    let artifacts = BuildArtifacts::collect(
+        &options,
+        output_dir.path(),
+        "volume",
+        "end all (duration : 1)",
+    )
+    .expect("failed collecting artifacts");
+    let docs = target.path().join("docs");
+    assert_eq!(artifacts.svg_dir, Some(docs.join("volume-svg")));
+    assert_eq!(
+        artifacts.block_diagram,
+        Some(docs.join("volume-svg").join("process.svg"))
+    );
+    assert_eq!(artifacts.task_graph, Some(docs.join("volume.dsp.dot")));
+    assert_eq!(
+        artifacts.signal_graph,
+        Some(docs.join("volume.dsp-sig.dot"))
+    );
+    assert_eq!(artifacts.timing.len(), 1);
+    assert!(docs.join("volume-svg").join("process.svg").exists());
+    assert!(!docs.join("volume.dsp.json").exists());
+}
+
+#[test]
+fn only_files_of_the_dsp() {
+    let output_dir = tempfile::tempdir().expect("failed creating temp dir");
+    for file_name in [
+        "other.dsp.dot",
+        "other.dsp-sig.dot",
+        "volume.dot",
+        "volume-sig.dot",
+    ] {
+        fs::write(output_dir.path().join(file_name), "digraph {}").expect("failed writing dot");
+    }
+    fs::create_dir(output_dir.path().join("other-svg")).expect("failed creating svg dir");
+    let options = ArtifactOptions {
+        svg: true,
+        task_graph: true,
+        signal_graph: true,
+        ..ArtifactOptions::default()
+    };
+    let artifacts = BuildArtifacts::collect(&options, output_dir.path(), "volume", "")
+        .expect("failed collecting artifacts");
+    assert_eq!(artifacts, BuildArtifacts::default());
+
+    fs::write(output_dir.path().join("volume.dsp.dot"), "digraph {}").expect("failed writing dot");
+    let artifacts = BuildArtifacts::collect(&options, output_dir.path(), "volume", "")
+        .expect("failed collecting artifacts");
+    assert_eq!(
+        artifacts.task_graph,
+        Some(output_dir.path().join("volume.dsp.dot"))
+    );
+    assert_eq!(artifacts.signal_graph, None);
+}
+
+#[test]
+fn command_args() {
+    let options = ArtifactOptions {
+        svg: true,
+        fold: Some("10".to_owned()),
+        signal_graph: true,
+        ..ArtifactOptions::default()
+    };
+    assert_eq!(options.to_command_args(), ["-svg", "-f", "10", "-sg"]);
+    assert!(ArtifactOptions::default().to_command_args().is_empty());
+}
@@ -0,0 +1,136 @@
+use faust_build::{
+    architecture::{AllowLints, ObjectInterface, Pipeline},
+    build_context::BuildContext,
+    builder::FaustBuilder,
+    code_option::CodeOption,
+};
+use faust_json::{FaustJson, ParseMode};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::path::Path;
+
+/// The output of faust 2.77.3 for `examples/jack-ui/dsp/volume.dsp`.
+const VOLUME: &str = include_str!("fixtures/volume.rs");
+
+fn builder() -> FaustBuilder {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Volume".to_owned()));
+    builder.set_module_name("volume");
+    builder
+}
+
+fn json() -> FaustJson {
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples/jack-ui/dsp/volume.dsp.json");
+    let json = std::fs::read_to_string(path).expect("failed reading volume.dsp.json");
+    FaustJson::parse(&json, ParseMode::Strict).expect("invalid volume.dsp.json")
+}
+
+fn apply(object: &impl ObjectInterface, context: &BuildContext) -> syn::File {
+    let dsp_code = VOLUME.parse().expect("invalid fixture");
+    syn::parse2(object.apply(context, &dsp_code)).expect("invalid architecture output")
+}
+
+#[test]
+fn context_from_json() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, Some(json()));
+    assert_eq!(context.struct_name(), "Volume");
+    assert_eq!(context.module_name(), Some("volume"));
+    assert_eq!(context.expect_json().name, "volume");
+    assert_eq!((context.inputs(), context.outputs()), (Some(2), Some(2)));
+    assert_eq!(context.meta("author"), Some("Franz Heinzmann"));
+    assert_eq!(context.meta("options"), Some("[osc:on]"));
+    assert_eq!(context.meta("missing"), None);
+    let keys = context.metadata().map(|(key, _)| key).collect::<Vec<_>>();
+    assert_eq!(keys.first(), Some(&"author"));
+    assert_eq!(keys.last(), Some(&"version"));
+    assert!(context.xml().is_none());
+}
+
+#[test]
+fn context_without_json() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    assert!(context.json().is_none());
+    assert_eq!((context.inputs(), context.outputs()), (None, None));
+    assert_eq!(context.metadata().count(), 0);
+}
+
+#[test]
+#[should_panic(expected = "json description not available")]
+fn expect_json_without_json() {
+    let builder = builder();
+    let _ = BuildContext::new(&builder, None).expect_json();
+}
+
+#[test]
+fn allow_lints_on_generated_code() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    let file = apply(&AllowLints::default(), &context);
+    let lints = file
+        .attrs
+        .iter()
+        .map(|attr| {
+            assert!(matches!(attr.style, syn::AttrStyle::Inner(_)));
+            assert!(attr.path().is_ident("allow"));
+            attr.parse_args::<syn::Path>()
+                .map(|path| quote!(#path).to_string().replace(' ', ""))
+                .expect("invalid lint")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lints.len(), 8);
+    assert_eq!(lints[0], "clippy::all");
+    assert!(lints.contains(&"non_snake_case".to_owned()));
+    // the generated items are kept as they are
+    let items = &file.items;
+    assert_eq!(
+        quote!(#(#items)*).to_string(),
+        VOLUME
+            .parse::<TokenStream>()
+            .expect("invalid fixture")
+            .to_string()
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid lint not a lint")]
+fn invalid_lint() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    let _ = apply(&AllowLints::new(["not a lint"]), &context);
+}
+
+/// A custom architecture that describes the dsp with the context only.
+fn descriptor(context: &BuildContext, dsp_code: &TokenStream) -> TokenStream {
+    let struct_name = format_ident!("{}", context.struct_name());
+    let name = &context.expect_json().name;
+    let (inputs, outputs) = (context.inputs(), context.outputs());
+    let author = context.meta("author").unwrap_or_default();
+    quote! {
+        #dsp_code
+        impl #struct_name {
+            pub const DESCRIPTOR: (&'static str, usize, usize, &'static str) =
+                (#name, #inputs, #outputs, #author);
+        }
+    }
+}
+
+#[test]
+fn custom_architecture_from_context() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, Some(json()));
+    let pipeline = Pipeline::default_wrapper().with(descriptor);
+    let code = prettyplease::unparse(&apply(&pipeline, &context));
+    assert!(code.starts_with("#![allow(clippy::all)]"), "{}", code);
+    assert!(code.contains("impl FaustDsp for Volume {"), "{}", code);
+    assert!(
+        code.contains(
+            "pub const DESCRIPTOR: (&'static str, usize, usize, &'static str) = (\n        \
+             \"volume\",\n        2usize,\n        2usize,\n        \"Franz Heinzmann\",\n    );"
+        ),
+        "{}",
+        code
+    );
+}
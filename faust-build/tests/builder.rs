@@ -0,0 +1,233 @@
+#![cfg(unix)]
+
+use faust_build::{
+    artifacts::ArtifactOptions, builder::FaustBuilder, code_option::CodeOption,
+    compile_options::WarningPolicy,
+};
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// Stands in for faust: writes a json description named after the input into the `-O` dir and
+/// keeps the code read from stdin in `received.dsp`.
+const FAKE_FAUST: &str = r#"#!/bin/sh
+out=.
+input=
+while [ $# -gt 0 ]; do
+  case "$1" in
+    -O) out="$2"; shift ;;
+    *.dsp|/dev/stdin) input="$1" ;;
+  esac
+  shift
+done
+name=$(basename "$input" .dsp)
+if [ "$input" = /dev/stdin ]; then cat > "$out/received.dsp"; fi
+sleep 0.05
+printf '{"name": "%s", "filename": "%s.dsp", "version": "2.77.3", "inputs": 0, "outputs": 0, "meta": [], "ui": []}' "$name" "$name" > "$out/$name.dsp.json"
+echo "// $name"
+"#;
+
+fn fake_faust(dir: &Path, script: &str) -> PathBuf {
+    let path = dir.join("faust");
+    fs::write(&path, script).expect("failed writing script");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("failed chmod");
+    path
+}
+
+fn dsp_code(name: &str) -> String {
+    format!("declare name \"{name}\";\nprocess = _;\n")
+}
+
+#[test]
+fn dsp_code_from_stdin() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(fake_faust(dir.path(), FAKE_FAUST));
+    builder.set_dsp_code(&dsp_code("volume"));
+    builder.write_json_file();
+    assert_eq!(builder.run_faust(), "// stdin\n");
+    let output_dir = builder.get_output_dir().expect("no output dir");
+    assert_eq!(
+        fs::read_to_string(output_dir.join("received.dsp")).expect("no code on stdin"),
+        dsp_code("volume")
+    );
+    let json = builder.parse_json().expect("json not enabled");
+    assert_eq!(json.name, "stdin");
+    builder.struct_name_from_dsp_name();
+    assert_eq!(builder.get_struct_name(), "Volume");
+}
+
+#[test]
+fn parallel_builds_into_shared_dir() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let faust = fake_faust(dir.path(), FAKE_FAUST);
+    let shared = dir.path().join("out");
+    fs::create_dir(&shared).expect("failed creating output dir");
+    let builds = (0..8)
+        .map(|i| {
+            let (faust, shared, dsp_dir) = (faust.clone(), shared.clone(), dir.path().to_owned());
+            thread::spawn(move || {
+                let name = format!("dsp{i}");
+                let mut builder = FaustBuilder::default();
+                builder.set_faust_path(faust);
+                builder.set_output_dir(&shared);
+                builder.write_json_file();
+                if i % 2 == 0 {
+                    let dsp_path = dsp_dir.join(&name).with_extension("dsp");
+                    fs::write(&dsp_path, dsp_code(&name)).expect("failed writing dsp");
+                    builder.set_dsp_path(dsp_path);
+                } else {
+                    // written into the shared dir, stdin would give every build the same files,
+                    // and all of them declare the same name
+                    builder.set_dsp_code(&dsp_code("volume"));
+                }
+                let code = builder.run_faust();
+                let json = builder.parse_json().expect("json not enabled");
+                (i, code, json.name)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut names = Vec::new();
+    for build in builds {
+        let (i, code, json_name) = build.join().expect("build failed");
+        assert_eq!(code, format!("// {json_name}\n"));
+        if i % 2 == 0 {
+            assert_eq!(json_name, format!("dsp{i}"));
+        } else {
+            assert!(json_name.starts_with("volume-"), "{}", json_name);
+            names.push(json_name);
+        }
+    }
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), 4);
+    assert!(!shared.join("received.dsp").exists());
+    let dsp_files = fs::read_dir(&shared)
+        .expect("failed reading output dir")
+        .map(|entry| entry.expect("failed reading entry").path())
+        .filter(|path| path.extension() == Some("dsp".as_ref()))
+        .count();
+    assert_eq!(dsp_files, 0);
+}
+
+#[test]
+fn file_builds_write_next_to_the_dsp() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let dsp_path = dir.path().join("volume.dsp");
+    fs::write(&dsp_path, dsp_code("volume")).expect("failed writing dsp");
+    let mut builder = FaustBuilder::default_for_file(&dsp_path, dir.path().join("dsp.rs"));
+    builder.set_faust_path(fake_faust(dir.path(), FAKE_FAUST));
+    builder.write_json_file();
+    assert!(builder.get_output_dir().is_none());
+    assert!(!builder
+        .get_compile_options()
+        .to_command_args()
+        .contains(&"-O".as_ref()));
+    assert_eq!(builder.get_json_path(), dir.path().join("volume.dsp.json"));
+}
+
+#[test]
+fn warnings_are_returned() {
+    // reports a warning, and the arguments it was called with
+    let script = r#"#!/bin/sh
+echo "WARNING : $*" >&2
+echo 'pub struct Volume;'
+"#;
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(fake_faust(dir.path(), script));
+    builder.set_dsp_code(&dsp_code("volume"));
+    let _ = builder.run_faust();
+    let warnings = builder.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("WARNING : "));
+    assert!(!warnings[0].contains("-wall"));
+
+    builder.set_warn_all(true);
+    let _ = builder.run_faust();
+    assert!(builder.warnings()[0].contains("-wall"));
+
+    builder.set_warning_policy(WarningPolicy::Ignore);
+    let _ = builder.run_faust();
+    assert!(builder.warnings().is_empty());
+}
+
+#[test]
+fn temp_files_are_removed() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let faust = fake_faust(dir.path(), FAKE_FAUST);
+    let output_dirs = [false, true].map(|write_file| {
+        let mut builder = FaustBuilder::default();
+        builder.set_faust_path(&faust);
+        builder.write_json_file();
+        if write_file {
+            builder.write_temp_dsp_file(&dsp_code("volume"));
+            assert!(builder.get_dsp_path().exists());
+        } else {
+            builder.set_dsp_code(&dsp_code("volume"));
+        }
+        let _ = builder.run_faust();
+        assert!(builder.get_json_path().exists());
+        builder.get_output_dir().expect("no output dir").to_owned()
+    });
+    for output_dir in output_dirs {
+        assert!(
+            !output_dir.exists(),
+            "{} was not removed",
+            output_dir.display()
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "faust did not write the json description")]
+fn missing_json_description() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(fake_faust(dir.path(), "#!/bin/sh\n"));
+    builder.set_dsp_code(&dsp_code("volume"));
+    builder.write_json_file();
+    let _ = builder.run_faust();
+    let _ = builder.parse_json();
+}
+
+#[test]
+fn task_graph_of_the_dsp() {
+    // writes the task graph like faust with -O, and only for vectorized code
+    let script = r#"#!/bin/sh
+while [ $# -gt 0 ]; do
+  case "$1" in
+    -O) out="$2"; shift ;;
+    -vec) vec=1 ;;
+    -tg) graph=1 ;;
+    *.dsp) input="$1" ;;
+  esac
+  shift
+done
+if [ -n "$vec" ] && [ -n "$graph" ]; then
+  echo 'digraph {}' > "$out/$(basename "$input" .dsp).dsp.dot"
+fi
+echo 'pub struct Volume;'
+"#;
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let shared = dir.path().join("out");
+    fs::create_dir(&shared).expect("failed creating output dir");
+    fs::write(shared.join("other.dsp.dot"), "digraph {}").expect("failed writing dot");
+    let dsp_path = dir.path().join("volume.dsp");
+    fs::write(&dsp_path, dsp_code("volume")).expect("failed writing dsp");
+
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(fake_faust(dir.path(), script));
+    builder.set_dsp_path(dsp_path);
+    builder.set_output_dir(&shared);
+    builder.set_code_option(CodeOption::Vectorize);
+    builder.set_artifact_options(ArtifactOptions {
+        task_graph: true,
+        ..ArtifactOptions::default()
+    });
+    let (_, artifacts) = builder.build_with_artifacts();
+    assert_eq!(artifacts.task_graph, Some(shared.join("volume.dsp.dot")));
+}
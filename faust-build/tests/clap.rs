@@ -0,0 +1,77 @@
+use faust_build::{
+    architecture::{ObjectInterface, Pipeline},
+    build_context::BuildContext,
+    builder::FaustBuilder,
+    clap::{default_features, Clap},
+    code_option::CodeOption,
+};
+use faust_json::{FaustJson, ParseMode};
+
+fn json(outputs: usize, widget: &str) -> FaustJson {
+    let json = format!(
+        r#"{{
+            "name": "organ",
+            "filename": "organ.dsp",
+            "version": "2.76.0",
+            "inputs": 0,
+            "outputs": {outputs},
+            "meta": [{{ "author": "Jane" }}, {{ "version": "0.3" }}],
+            "ui": [{{
+                "type": "vgroup",
+                "label": "organ",
+                "items": [{{
+                    "type": "button",
+                    "label": "{widget}",
+                    "shortname": "{widget}",
+                    "address": "/organ/{widget}"
+                }}]
+            }}]
+        }}"#
+    );
+    FaustJson::parse(&json, ParseMode::Strict).expect("invalid test json")
+}
+
+#[test]
+fn features_from_json() {
+    assert_eq!(
+        default_features(&json(2, "gate")),
+        ["instrument", "synthesizer", "stereo"]
+    );
+    assert_eq!(
+        default_features(&json(1, "bypass")),
+        ["audio-effect", "mono"]
+    );
+    assert_eq!(default_features(&json(4, "bypass")), ["audio-effect"]);
+}
+
+#[test]
+fn clap_glue() {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Organ".to_owned()));
+    let context = BuildContext::new(&builder, Some(json(2, "gate")));
+    let dsp_code = "pub struct Organ;".parse().expect("invalid dsp code");
+    let render = |clap: Clap| {
+        let code = Pipeline::clap(clap).apply(&context, &dsp_code);
+        prettyplease::unparse(&syn::parse2(code).expect("invalid glue code"))
+    };
+
+    let code = render(Clap::new("org.example.organ"));
+    assert!(code.starts_with("#![allow(clippy::all)]"));
+    assert!(code.contains("impl faust_clap::ClapDsp for Organ {"));
+    assert!(code.contains("id: \"org.example.organ\","));
+    assert!(code.contains("name: \"organ\","));
+    assert!(code.contains("vendor: \"Jane\","));
+    assert!(code.contains("version: \"0.3\","));
+    assert!(code.contains("features: &[\"instrument\", \"synthesizer\", \"stereo\"],"));
+    assert!(code.contains("faust_clap::export_clap!(Organ);"));
+
+    let code = render(
+        Clap::new("org.example.organ")
+            .with_vendor("example")
+            .with_url("https://example.org")
+            .with_features(["instrument"]),
+    );
+    assert!(code.contains("vendor: \"example\","));
+    assert!(code.contains("url: \"https://example.org\","));
+    assert!(code.contains("features: &[\"instrument\"],"));
+}
@@ -0,0 +1,90 @@
+use faust_build::{
+    code_option::{CodeOption, CodeOptionDiscriminants, CodeOptionMap, FlushToZeroMode},
+    CodeOptionToCommandArgs,
+};
+
+fn command_line(options: &CodeOptionMap) -> Vec<String> {
+    let mut args = options
+        .to_command_args()
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    args.sort();
+    args
+}
+
+#[test]
+fn parse_flags_with_arguments() {
+    let options = CodeOption::arg_map_from_str_iter([
+        "-double", "-vec", "-vs", "64", "-ftz", "2", "-es", "0",
+    ]);
+    assert!(matches!(
+        options.get(&CodeOptionDiscriminants::VecSize),
+        Some(CodeOption::VecSize(64))
+    ));
+    let Some(CodeOption::FlushToZero(mode)) = options.get(&CodeOptionDiscriminants::FlushToZero)
+    else {
+        panic!("missing -ftz")
+    };
+    assert_eq!(*mode, FlushToZeroMode::MaskBased);
+    assert_eq!(
+        command_line(&options),
+        ["-double", "-es", "-ftz", "-vec", "-vs", "0", "2", "64"]
+    );
+    assert!(options.validate().is_ok());
+}
+
+#[test]
+fn from_name_and_args() {
+    let option = CodeOption::from_name_and_args("DelayLineThreshold", ["128"]).unwrap();
+    assert!(matches!(option, CodeOption::DelayLineThreshold(128)));
+    let option = CodeOption::from_name_and_args("LoopVariant", ["Simple"]).unwrap();
+    assert!(matches!(option, CodeOption::LoopVariant(_)));
+    assert!(CodeOption::from_name_and_args("MathApproximation", Vec::<String>::new()).is_ok());
+    assert!(CodeOption::from_name_and_args("VecSize", Vec::<String>::new()).is_err());
+    assert!(CodeOption::from_name_and_args("FlushToZero", ["3"]).is_err());
+    assert!(CodeOption::from_name_and_args("NoSuchOption", ["3"]).is_err());
+}
+
+#[test]
+fn validate_combinations() {
+    let invalid = [
+        vec!["-single", "-double"],
+        vec!["-vs", "32"],
+        vec!["-vec", "-inpl"],
+        vec!["-vec", "-os"],
+        vec!["-omp"],
+        vec!["-quad"],
+        vec!["-mem", "-mem2"],
+    ];
+    for args in invalid {
+        assert!(
+            CodeOption::arg_map_from_str_iter(&args).validate().is_err(),
+            "{:?} should be rejected",
+            args
+        );
+    }
+    let valid = CodeOption::arg_map_from_str_iter(["-vec", "-lv", "1", "-mapp", "-rui", "-me"]);
+    assert!(valid.validate().is_ok());
+}
+
+#[test]
+#[should_panic(expected = "not supported by the rust backend")]
+fn backend_specific_flags() {
+    let _ = CodeOption::arg_map_from_str_iter(["-nvi"]);
+}
+
+#[test]
+fn unsupported_flags() {
+    for flag in ["-quad", "-fx", "-omp", "-pl", "-sch", "-ocl", "-cuda"] {
+        let options = CodeOption::arg_map_from_str_iter([flag]);
+        let err = options.validate().unwrap_err();
+        assert!(err.contains("not supported by the rust backend"), "{}", err);
+    }
+    let options = CodeOption::arg_map_from_str_iter(["-fx", "-fx-size", "32"]);
+    assert!(matches!(
+        options.get(&CodeOptionDiscriminants::FixedPointSize),
+        Some(CodeOption::FixedPointSize(32))
+    ));
+    assert!(options.validate().is_err());
+}
@@ -0,0 +1,150 @@
+use faust_build::{
+    builder::FaustBuilder,
+    code_option::{CodeOption, CodeOptionDiscriminants},
+    compile_options::WarningPolicy,
+    config::FaustConfig,
+    faust_version::FaustVersion,
+};
+use std::{env, fs, process::Command};
+
+const CONFIG: &str = r#"
+min_faust_version = "2.70.3"
+
+[defaults]
+flags = ["-double", "-ftz", "2"]
+import_dirs = ["lib"]
+warnings = "deny"
+
+[files."synth/dsp/osc.dsp"]
+flags = ["-vec", "-vs", "32"]
+architecture_dirs = ["arch"]
+warnings = "ignore"
+"#;
+
+#[test]
+fn discover_and_apply() {
+    let workspace = tempfile::tempdir().expect("failed creating temp dir");
+    let dsp_dir = workspace.path().join("synth/dsp");
+    fs::create_dir_all(&dsp_dir).expect("failed creating dsp dir");
+    fs::write(workspace.path().join("faust.toml"), CONFIG).expect("failed writing config");
+
+    let config_path = FaustConfig::find(&dsp_dir).expect("config not found");
+    let config = FaustConfig::load(&config_path).expect("invalid config");
+    assert_eq!(config.min_faust_version, Some(FaustVersion::new(2, 70, 3)));
+
+    let mut builder = FaustBuilder::default();
+    builder.set_dsp_path(dsp_dir.join("osc.dsp"));
+    config.apply(&mut builder);
+    assert!(matches!(
+        builder.get_code_option(&CodeOptionDiscriminants::VecSize),
+        Some(CodeOption::VecSize(32))
+    ));
+    assert!(builder
+        .get_code_options()
+        .contains(CodeOptionDiscriminants::Double));
+    let compile_options = builder.get_compile_options();
+    assert!(compile_options
+        .import_dirs
+        .ends_with(&[workspace.path().join("lib")]));
+    assert_eq!(
+        compile_options.architecture_dirs,
+        [workspace.path().join("arch")]
+    );
+    assert_eq!(compile_options.warnings, WarningPolicy::Ignore);
+
+    let mut builder = FaustBuilder::default();
+    builder.set_dsp_path(dsp_dir.join("other.dsp"));
+    config.apply(&mut builder);
+    assert!(!builder
+        .get_code_options()
+        .contains(CodeOptionDiscriminants::Vectorize));
+    assert_eq!(builder.get_compile_options().warnings, WarningPolicy::Deny);
+}
+
+#[test]
+fn invalid_config() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("faust.toml");
+    fs::write(&path, "[defaults]\nflag = [\"-double\"]\n").expect("failed writing config");
+    assert!(FaustConfig::load(&path).is_err());
+    fs::write(&path, "[defaults]\nflags = [\"-vs\", \"32\"]\n").expect("failed writing config");
+    assert!(FaustConfig::load(&path).is_err());
+    fs::write(&path, "min_faust_version = \"latest\"\n").expect("failed writing config");
+    assert!(FaustConfig::load(&path).is_err());
+}
+
+#[test]
+fn explicit_options_replace_conflicting_config_options() {
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let path = dir.path().join("faust.toml");
+    fs::write(&path, "[defaults]\nflags = [\"-double\", \"-mem1\"]\n")
+        .expect("failed writing config");
+    let config = FaustConfig::load(&path).expect("invalid config");
+
+    let mut builder = FaustBuilder::default();
+    config.apply(&mut builder);
+    assert!(matches!(
+        builder.set_code_option(CodeOption::Single),
+        Some(CodeOption::Double)
+    ));
+    builder.extend_code_options([CodeOption::MemoryManager3]);
+    let options = builder.get_code_options();
+    assert!(options.contains(CodeOptionDiscriminants::Single));
+    assert!(!options.contains(CodeOptionDiscriminants::Double));
+    assert!(options.contains(CodeOptionDiscriminants::MemoryManager3));
+    assert!(!options.contains(CodeOptionDiscriminants::MemoryManager1));
+    assert!(options.validate().is_ok());
+}
+
+/// Set for the child process of `build_scripts_rerun_when_the_config_changes`.
+const CHILD_ENV: &str = "FAUST_BUILD_CONFIG_TEST_DSP";
+
+/// The lines go to stdout, so the builder runs in a child process that looks like a build script.
+#[test]
+fn build_scripts_rerun_when_the_config_changes() {
+    if let Some(dsp_path) = env::var_os(CHILD_ENV) {
+        let mut builder = FaustBuilder::default();
+        builder.set_dsp_path(dsp_path);
+        builder.apply_workspace_config();
+        return;
+    }
+    let workspace = tempfile::tempdir().expect("failed creating temp dir");
+    let crate_dir = workspace.path().join("synth");
+    fs::create_dir_all(&crate_dir).expect("failed creating crate dir");
+    let config_path = workspace.path().join("faust.toml");
+    fs::write(&config_path, CONFIG).expect("failed writing config");
+    let dsp_path = crate_dir.join("osc.dsp");
+
+    let run = |build_script: bool| {
+        let mut command = Command::new(env::current_exe().expect("no test binary"));
+        command
+            .args([
+                "build_scripts_rerun_when_the_config_changes",
+                "--exact",
+                "--nocapture",
+            ])
+            .env(CHILD_ENV, &dsp_path)
+            .env("CARGO_MANIFEST_DIR", &crate_dir)
+            .env("OUT_DIR", workspace.path())
+            .env_remove("TARGET");
+        if build_script {
+            command.env("TARGET", "x86_64-unknown-linux-gnu");
+        }
+        let output = command.output().expect("failed running test binary");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).expect("invalid stdout")
+    };
+    let stdout = run(true);
+    for path in [&config_path, &dsp_path] {
+        let line = format!("cargo:rerun-if-changed={}", path.display());
+        // libtest prints the test name in front of the first line
+        assert!(
+            stdout.lines().any(|l| l.ends_with(&line)),
+            "{} missing in {}",
+            line,
+            stdout
+        );
+    }
+    // macros must not print to stdout
+    assert!(!run(false).contains("cargo:"));
+}
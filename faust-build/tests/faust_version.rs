@@ -0,0 +1,73 @@
+use faust_build::{
+    builder::FaustBuilder,
+    faust_version::{FaustVersion, FaustVersionRange},
+};
+
+#[test]
+fn parse_version_output() {
+    let output = "FAUST Version 2.76.0\nEmbedded backends: \n   DSP to C\n   DSP to Rust\n";
+    assert_eq!(
+        FaustVersion::from_version_output(output),
+        Ok(FaustVersion::new(2, 76, 0))
+    );
+    assert_eq!("2.81.2-dev".parse(), Ok(FaustVersion::new(2, 81, 2)));
+    assert_eq!("2.5".parse(), Ok(FaustVersion::new(2, 5, 0)));
+    assert!("two".parse::<FaustVersion>().is_err());
+    assert!(FaustVersion::from_version_output("faust: command not found").is_err());
+}
+
+#[test]
+fn version_range() {
+    let range = FaustVersionRange {
+        min: Some(FaustVersion::new(2, 70, 3)),
+        max: Some(FaustVersion::new(2, 79, 0)),
+    };
+    assert!(range.contains(&FaustVersion::new(2, 70, 3)));
+    assert!(range.contains(&FaustVersion::new(2, 79, 0)));
+    assert!(!range.contains(&FaustVersion::new(2, 9, 0)));
+    assert!(!range.contains(&FaustVersion::new(3, 0, 0)));
+    assert!(FaustVersionRange::default().contains(&FaustVersion::new(0, 0, 1)));
+    let err = range.check(&FaustVersion::new(2, 60, 0)).unwrap_err();
+    assert!(err.contains("faust 2.60.0 is not supported"), "{}", err);
+}
+
+#[cfg(unix)]
+#[test]
+fn query_installed_version() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let fake_faust = dir.path().join("faust");
+    fs::write(&fake_faust, "#!/bin/sh\necho 'FAUST Version 2.76.0'\n")
+        .expect("failed writing script");
+    fs::set_permissions(&fake_faust, fs::Permissions::from_mode(0o755)).expect("failed chmod");
+
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(&fake_faust);
+    assert_eq!(builder.faust_version(), FaustVersion::new(2, 76, 0));
+    builder.set_min_faust_version(FaustVersion::new(2, 70, 0));
+    assert!(builder.check_faust_version().is_ok());
+    builder.set_max_faust_version(FaustVersion::new(2, 75, 0));
+    assert!(builder.check_faust_version().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_faust_reports_stderr() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let fake_faust = dir.path().join("faust");
+    fs::write(
+        &fake_faust,
+        "#!/bin/sh\necho 'FAUST Version 2.76.0'\necho 'libfaust.so: cannot open' >&2\nexit 127\n",
+    )
+    .expect("failed writing script");
+    fs::set_permissions(&fake_faust, fs::Permissions::from_mode(0o755)).expect("failed chmod");
+
+    let mut builder = FaustBuilder::default();
+    builder.set_faust_path(&fake_faust);
+    let err = builder.try_faust_version().unwrap_err();
+    assert!(err.contains("libfaust.so: cannot open"), "{}", err);
+    assert!(err.contains("127"), "{}", err);
+}
@@ -0,0 +1,284 @@
+use faust_build::{
+    architecture::{ObjectInterface, Pipeline},
+    build_context::BuildContext,
+    builder::FaustBuilder,
+    code_option::CodeOption,
+    lv2::{Lv2Options, Lv2Plugin, Lv2PortKind},
+};
+use faust_json::{FaustJson, ParseMode};
+use std::{env, fs, path::Path, process::Command};
+
+const JSON: &str = r##"
+{
+	"name": "volumecontrol",
+	"filename": "volume.dsp",
+	"version": "2.76.0",
+	"compile_options": "-lang rust -ct 1 -es 1 -mcd 16 -mdd 1024 -mdy 33 -single -ftz 0",
+	"size": 48,
+	"inputs": 2,
+	"outputs": 1,
+	"meta": [
+		{ "author": "Franz Heinzmann" },
+		{ "license": "BSD" },
+		{ "version": "1.2.3" },
+		{ "input1": "side chain" }
+	],
+	"ui": [
+		{
+			"type": "vgroup",
+			"label": "volumecontrol",
+			"items": [
+				{
+					"type": "vbargraph",
+					"label": "level",
+					"shortname": "level",
+					"address": "/volumecontrol/level",
+					"varname": "fVbargraph0",
+					"min": -60,
+					"max": 5
+				},
+				{
+					"type": "vslider",
+					"label": "volume",
+					"shortname": "volume",
+					"address": "/volumecontrol/volume",
+					"varname": "fVslider0",
+					"meta": [
+						{ "unit": "dB" },
+						{ "tooltip": "output \"gain\"" }
+					],
+					"init": 0,
+					"min": -70,
+					"max": 4,
+					"step": 0.1
+				},
+				{
+					"type": "nentry",
+					"label": "mode",
+					"shortname": "mode",
+					"address": "/volumecontrol/mode",
+					"varname": "fEntry0",
+					"meta": [
+						{ "unit": "steps" }
+					],
+					"init": 1,
+					"min": 0,
+					"max": 3,
+					"step": 1
+				},
+				{
+					"type": "soundfile",
+					"label": "sample",
+					"url": "{'sample.wav'}",
+					"address": "/volumecontrol/sample",
+					"varname": "fSoundfile0"
+				},
+				{
+					"type": "checkbox",
+					"label": "mute",
+					"shortname": "volume",
+					"address": "/volumecontrol/mute",
+					"varname": "fCheckbox0"
+				}
+			]
+		}
+	]
+}"##;
+
+fn options() -> Lv2Options {
+    Lv2Options::new("https://example.org/plugins/volume", "volume-lv2")
+}
+
+fn plugin() -> Lv2Plugin {
+    let json = FaustJson::parse(JSON, ParseMode::Strict).expect("invalid test json");
+    Lv2Plugin::from_json(&json, &options())
+}
+
+#[test]
+fn ports_from_json() {
+    let plugin = plugin();
+    let ports = plugin
+        .ports
+        .iter()
+        .map(|port| (port.index, port.kind, port.symbol.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ports,
+        [
+            (0, Lv2PortKind::AudioInput, "in0"),
+            (1, Lv2PortKind::AudioInput, "side_chain"),
+            (2, Lv2PortKind::AudioOutput, "out0"),
+            (3, Lv2PortKind::ControlOutput, "level"),
+            (4, Lv2PortKind::ControlInput, "volume"),
+            (5, Lv2PortKind::ControlInput, "mode"),
+            (6, Lv2PortKind::ControlInput, "volume_1"),
+        ]
+    );
+    let volume = &plugin.ports[4];
+    assert_eq!(
+        (volume.default, volume.minimum, volume.maximum),
+        (Some(0.0), Some(-70.0), Some(4.0))
+    );
+    assert_eq!(volume.unit.as_deref(), Some("dB"));
+    assert_eq!(plugin.ports[3].default, None);
+    assert_eq!(plugin.ports[5].properties, ["lv2:integer"]);
+    assert_eq!(plugin.ports[6].properties, ["lv2:toggled"]);
+    assert_eq!(plugin.binary, options().binary_name());
+    assert_eq!(plugin.control_ports().count(), 4);
+    assert_eq!(plugin.validate(), Ok(()));
+}
+
+#[test]
+fn render_ttl() {
+    let plugin = plugin();
+    let manifest = plugin.manifest_ttl();
+    assert!(manifest.contains("<https://example.org/plugins/volume>\n    a lv2:Plugin ;"));
+    assert!(manifest.contains(&format!("lv2:binary <{}> ;", options().binary_name())));
+    assert!(manifest.contains("rdfs:seeAlso <volumecontrol.ttl> ."));
+
+    let ttl = plugin.plugin_ttl();
+    assert!(ttl.contains("\n<https://example.org/plugins/volume>\n    a lv2:Plugin ;"));
+    assert!(ttl.contains("doap:name \"volumecontrol\" ;"));
+    assert!(ttl.contains("doap:maintainer [ foaf:name \"Franz Heinzmann\" ] ;"));
+    assert!(ttl.contains("lv2:minorVersion 2 ;\n    lv2:microVersion 3 ;"));
+    assert!(ttl.contains(
+        "a lv2:InputPort , lv2:ControlPort ;\n        lv2:index 4 ;\n        lv2:symbol \"volume\" ;\n        lv2:name \"volume\" ;\n        lv2:default 0.0 ;\n        lv2:minimum -70.0 ;\n        lv2:maximum 4.0 ;\n        units:unit units:db ;\n        rdfs:comment \"output \\\"gain\\\"\""
+    ));
+    assert!(ttl.contains("a lv2:OutputPort , lv2:ControlPort ;\n        lv2:index 3 ;"));
+    assert!(ttl.contains("units:unit [ a units:Unit ; rdfs:label \"steps\" ;"));
+    assert!(ttl.contains("lv2:portProperty lv2:toggled"));
+    assert!(ttl.ends_with("\n    ] .\n"));
+    assert_eq!(ttl.matches("lv2:index").count(), plugin.ports.len());
+}
+
+#[test]
+fn validate_reports_problems() {
+    let mut plugin = plugin();
+    plugin.uri = "volume".to_owned();
+    plugin.ports[1].symbol = "in0".to_owned();
+    plugin.ports[2].symbol = "0out".to_owned();
+    plugin.ports[4].default = Some(10.0);
+    plugin.ports[5].minimum = Some(3.0);
+    plugin.ports[5].default = Some(3.0);
+    plugin.ports[6].index = 7;
+    let errors = plugin.validate().expect_err("invalid plugin passed");
+    assert_eq!(
+        errors,
+        [
+            "plugin uri \"volume\" is not an absolute uri",
+            "port 1 (\"in0\") has a duplicate symbol",
+            "port 2 (\"0out\") has an invalid symbol",
+            "port 4 (\"volume\") has default 10 outside of -70..4",
+            "port 5 (\"mode\") has minimum 3 >= maximum 3",
+            "port 6 (\"volume_1\") has index 7",
+        ]
+    );
+}
+
+#[test]
+fn write_crate_and_glue() {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Volume".to_owned()));
+    let json = FaustJson::parse(JSON, ParseMode::Strict).expect("invalid test json");
+    let context = BuildContext::new(&builder, Some(json));
+    let dsp_code = "pub struct Volume;".parse().expect("invalid dsp code");
+    let code = Pipeline::lv2("https://example.org/plugins/volume").apply(&context, &dsp_code);
+    let code = prettyplease::unparse(&syn::parse2(code).expect("invalid glue code"));
+    assert!(code.starts_with("#![allow(clippy::all)]"));
+    assert!(code.contains("use super::Volume as Dsp;"));
+    assert!(code.contains("const INPUTS: usize = 2usize;"));
+    assert!(code.contains("b\"https://example.org/plugins/volume\\0\""));
+    assert!(code.contains("pub extern \"C\" fn lv2_descriptor(index: u32)"));
+
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let options = Lv2Options::new(
+        "https://example.org/plugins/volume",
+        dir.path().join("volume-lv2"),
+    )
+    .with_faust_types("{ path = \"../faust-types\" }");
+    let bundle = plugin()
+        .write_crate(&options, &code)
+        .expect("failed writing crate");
+    assert_eq!(bundle, dir.path().join("volume-lv2/volume_lv2.lv2"));
+    let cargo_toml =
+        fs::read_to_string(options.crate_dir.join("Cargo.toml")).expect("no Cargo.toml");
+    assert!(cargo_toml.contains("name = \"volume-lv2\""));
+    assert!(cargo_toml.contains("crate-type = [\"cdylib\"]"));
+    assert!(cargo_toml.contains("faust-types = { path = \"../faust-types\" }"));
+    assert_eq!(
+        fs::read_to_string(options.crate_dir.join("src/lib.rs")).expect("no lib.rs"),
+        code
+    );
+    assert!(bundle.join("manifest.ttl").is_file());
+    assert!(bundle.join("volumecontrol.ttl").is_file());
+}
+
+#[test]
+fn faust_types_version() {
+    assert_eq!(
+        options().faust_types,
+        format!("\"{}\"", faust_types::VERSION)
+    );
+}
+
+#[test]
+#[should_panic(expected = "only support single precision")]
+fn reject_double() {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::Double);
+    builder.build_lv2(&options());
+}
+
+/// Builds the plugin crate of `examples/jack-ui/dsp/volume.dsp`. Instead of faust a script prints
+/// the output of faust 2.77.3 for the dsp, checked in as `fixtures/volume.rs`, and copies its json
+/// description.
+#[cfg(unix)]
+#[test]
+fn build_generated_crate() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let examples = manifest_dir.join("../examples/jack-ui/dsp");
+    let dir = tempfile::tempdir().expect("failed creating temp dir");
+    let fake_faust = dir.path().join("faust");
+    let script = format!(
+        "#!/bin/sh\n\
+         while [ $# -gt 0 ]; do\n\
+         \x20 if [ \"$1\" = -O ]; then out=\"$2\"; fi\n\
+         \x20 shift\n\
+         done\n\
+         cp '{}' \"$out/volume.dsp.json\"\n\
+         cat '{}'\n",
+        examples.join("volume.dsp.json").display(),
+        manifest_dir.join("tests/fixtures/volume.rs").display()
+    );
+    fs::write(&fake_faust, script).expect("failed writing script");
+    fs::set_permissions(&fake_faust, fs::Permissions::from_mode(0o755)).expect("failed chmod");
+
+    let mut builder =
+        FaustBuilder::default_for_file(examples.join("volume.dsp"), dir.path().join("unused.rs"));
+    builder.set_faust_path(&fake_faust);
+    builder.write_json_file();
+    let options = Lv2Options::new(
+        "https://example.org/plugins/volume",
+        dir.path().join("volume"),
+    )
+    .with_faust_types(format!(
+        "{{ path = \"{}\" }}",
+        manifest_dir.join("../faust-types").display()
+    ));
+    let plugin = builder.build_lv2(&options);
+    assert_eq!(plugin.ports.len(), 6);
+
+    let status = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(["build", "--offline", "--quiet"])
+        .current_dir(&options.crate_dir)
+        .env("CARGO_TARGET_DIR", manifest_dir.join("../target/lv2-test"))
+        .status()
+        .expect("failed running cargo");
+    assert!(status.success(), "generated lv2 crate does not build");
+    let library = manifest_dir
+        .join("../target/lv2-test/debug")
+        .join(options.binary_name());
+    assert!(library.is_file(), "{} missing", library.display());
+}
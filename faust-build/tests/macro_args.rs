@@ -0,0 +1,29 @@
+#![cfg(feature = "faust-ui")]
+
+use faust_build::{code_option::CodeOption, macro_lib::FileMacroArgs};
+
+#[test]
+fn search_dirs_in_include_array() {
+    let args: FileMacroArgs = syn::parse_str(
+        r#""dsp/volume.dsp", [Double, ImportDir("dsp/lib"), VecSize(32), Vectorize, ArchitectureDir("arch")]"#,
+    )
+    .expect("valid macro arguments");
+    assert_eq!(args.dsp_path.value(), "dsp/volume.dsp");
+    assert!(matches!(
+        args.flags.as_slice(),
+        [
+            CodeOption::Double,
+            CodeOption::VecSize(32),
+            CodeOption::Vectorize
+        ]
+    ));
+    assert_eq!(args.search_dirs.import_dirs, ["dsp/lib"]);
+    assert_eq!(args.search_dirs.architecture_dirs, ["arch"]);
+}
+
+#[test]
+fn invalid_include_array() {
+    assert!(syn::parse_str::<FileMacroArgs>(r#""a.dsp", [ImportDir]"#).is_err());
+    assert!(syn::parse_str::<FileMacroArgs>(r#""a.dsp", [VecSize(32)]"#).is_err());
+    assert!(syn::parse_str::<FileMacroArgs>(r#""a.dsp", [NoSuchOption]"#).is_err());
+}
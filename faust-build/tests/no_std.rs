@@ -0,0 +1,42 @@
+use faust_build::{
+    architecture::{ObjectInterface, Pipeline},
+    build_context::BuildContext,
+    builder::FaustBuilder,
+};
+
+/// The math functions faust declares in front of the dsp struct.
+const DSP_CODE: &str = r#"
+mod ffi {
+    use std::os::raw::c_float;
+    #[cfg_attr(not(target_os = "windows"), link(name = "m"))]
+    extern "C" {
+        pub fn remainderf(from: c_float, to: c_float) -> c_float;
+        pub fn rintf(val: c_float) -> c_float;
+    }
+}
+fn remainder_f32(from: f32, to: f32) -> f32 {
+    unsafe { ffi::remainderf(from, to) }
+}
+pub struct Volume;
+"#;
+
+#[test]
+fn ffi_uses_libm() {
+    let builder = FaustBuilder::default();
+    let context = BuildContext::new(&builder, None);
+    let dsp_code = DSP_CODE.parse().expect("invalid dsp code");
+    let code = Pipeline::no_std().apply(&context, &dsp_code);
+    let code = prettyplease::unparse(&syn::parse2(code).expect("invalid no_std code"));
+    let contains = |expected: &str| assert!(code.contains(expected), "{}\n\n{}", expected, code);
+    contains("use faust_types::*;");
+    contains("use core::ffi::c_float;");
+    contains(
+        "pub unsafe fn remainderf(from: c_float, to: c_float) -> c_float {\n        \
+         faust_types::math::libm::remainderf(from, to)\n    }",
+    );
+    contains("pub unsafe fn rintf(val: c_float) -> c_float {\n        faust_types::math::libm::rintf(val)\n    }");
+    contains("unsafe { ffi::remainderf(from, to) }");
+    assert!(!code.contains("std::"), "{}", code);
+    assert!(!code.contains("extern"), "{}", code);
+    assert!(!code.contains("link"), "{}", code);
+}
@@ -0,0 +1,131 @@
+use faust_build::{
+    build_context::BuildContext, builder::FaustBuilder, code_option::CodeOption, template,
+};
+use faust_json::{FaustJson, ParseMode};
+
+const JSON: &str = r##"
+{
+	"name": "volumecontrol",
+	"filename": "volume.dsp",
+	"version": "2.76.0",
+	"compile_options": "-lang rust -ct 1 -es 1 -mcd 16 -mdd 1024 -mdy 33 -single -ftz 0",
+	"size": 48,
+	"inputs": 2,
+	"outputs": 1,
+	"meta": [
+		{ "author": "Franz Heinzmann" },
+		{ "license": "BSD" }
+	],
+	"ui": [
+		{
+			"type": "vgroup",
+			"label": "volumecontrol",
+			"items": [
+				{
+					"type": "vbargraph",
+					"label": "level",
+					"shortname": "level",
+					"address": "/volumecontrol/level",
+					"varname": "fVbargraph0",
+					"min": -60,
+					"max": 5
+				},
+				{
+					"type": "vslider",
+					"label": "volume",
+					"shortname": "volume",
+					"address": "/volumecontrol/volume",
+					"varname": "fVslider0",
+					"init": 0,
+					"min": -70,
+					"max": 4,
+					"step": 0.1
+				}
+			]
+		}
+	]
+}"##;
+
+fn builder() -> FaustBuilder {
+    let mut builder = FaustBuilder::default();
+    builder.set_code_option(CodeOption::StructName("Volume".to_owned()));
+    builder.set_module_name("volume");
+    builder
+}
+
+fn json() -> FaustJson {
+    FaustJson::parse(JSON, ParseMode::Strict).expect("invalid test json")
+}
+
+#[test]
+fn placeholders() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, Some(json()));
+    let rendered = template::render(
+        "mod <<moduleName>> { struct <<structName>>; } // <<dspName>> <<inputs>>x<<outputs>> by <<meta:author>><<meta:missing>>",
+        &context,
+    );
+    assert_eq!(
+        rendered,
+        "mod volume { struct Volume; } // volumecontrol 2x1 by Franz Heinzmann"
+    );
+}
+
+#[test]
+fn param_table() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, Some(json()));
+    let rendered = template::render("<<paramTable>>", &context);
+    assert_eq!(
+        rendered,
+        "(\"/volumecontrol/level\", \"level\", \"vbargraph\", -60.0, -60.0, 5.0, 0.0),\n\
+         (\"/volumecontrol/volume\", \"volume\", \"vslider\", 0.0, -70.0, 4.0, 0.1),\n"
+    );
+}
+
+#[test]
+fn conditional_sections() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, Some(json()));
+    let rendered = template::render(
+        "<<if:meta:license>>l<<if:meta:missing>>m<<else>>n<<endif>><<endif>><<ifnot:inputs>>i<<else>>o<<endif>>",
+        &context,
+    );
+    assert_eq!(rendered, "lno");
+}
+
+#[test]
+fn conditions_in_skipped_sections_are_not_evaluated() {
+    let builder = builder();
+    // without json, evaluating <<if:inputs>> or <<ifnot:meta:license>> would panic
+    let context = BuildContext::new(&builder, None);
+    let rendered = template::render(
+        "<<ifnot:structName>><<if:inputs>>i<<endif>><<else>>s<<ifnot:moduleName>><<ifnot:meta:license>>l<<endif>><<endif>><<endif>>",
+        &context,
+    );
+    assert_eq!(rendered, "s");
+}
+
+#[test]
+fn shift_operators_are_kept() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    let rendered = template::render("let x = 1 << 24 >> 2; <<structName>>", &context);
+    assert_eq!(rendered, "let x = 1 << 24 >> 2; Volume");
+}
+
+#[test]
+fn unknown_placeholders_are_kept() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    let rendered = template::render("let x = 1<<N>>2; <<nope>> <<structName>>", &context);
+    assert_eq!(rendered, "let x = 1<<N>>2; <<nope>> Volume");
+}
+
+#[test]
+#[should_panic(expected = "unknown placeholder <<nope>>")]
+fn unknown_condition() {
+    let builder = builder();
+    let context = BuildContext::new(&builder, None);
+    let _ = template::render("<<if:nope>>x<<endif>>", &context);
+}
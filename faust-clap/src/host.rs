@@ -0,0 +1,688 @@
+//! A headless CLAP host to test plugins without a DAW.
+//!
+//! [`HeadlessHost::load`] loads a built `.clap` library, [`HeadlessHost::from_entry`] uses a
+//! `clap_entry` of the current process, e.g. [`crate::entry`] in tests. Both drive the plugin
+//! through its C interface only.
+//!
+//! Needs the `host` feature, usually enabled in `dev-dependencies` of a plugin crate.
+//!
+//! ```no_run
+//! use faust_clap::host::{HeadlessHost, HostEvent};
+//!
+//! let mut host = HeadlessHost::load("target/release/libvolume.so", None)?;
+//! let volume = host.params().iter().find(|param| param.name == "volume").map(|param| param.id);
+//! host.activate(48000.0, 512)?;
+//! let events = volume.map(|id| HostEvent::Param { time: 0, id, value: -6.0 });
+//! let outputs = host.process(&[&[0.5; 512], &[0.5; 512]], 512, events.as_slice())?;
+//! # Ok::<(), String>(())
+//! ```
+
+use clap_sys::{
+    audio_buffer::clap_audio_buffer,
+    entry::clap_plugin_entry,
+    events::{
+        clap_event_header, clap_event_midi, clap_event_note, clap_event_param_value,
+        clap_input_events, clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI,
+        CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_VALUE,
+    },
+    ext::{
+        audio_ports::{clap_audio_port_info, clap_plugin_audio_ports, CLAP_EXT_AUDIO_PORTS},
+        note_ports::{clap_note_port_info, clap_plugin_note_ports, CLAP_EXT_NOTE_PORTS},
+        params::{clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS},
+        state::{clap_plugin_state, CLAP_EXT_STATE},
+    },
+    factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID},
+    host::clap_host,
+    plugin::clap_plugin,
+    process::{clap_process, CLAP_PROCESS_ERROR},
+    stream::{clap_istream, clap_ostream},
+    version::{clap_version_is_compatible, CLAP_VERSION},
+};
+use libloading::Library;
+use std::{
+    ffi::{c_void, CStr, CString, OsStr},
+    mem,
+    os::raw::c_char,
+    ptr, slice,
+};
+
+/// Events passed to [`HeadlessHost::process`], `time` is the frame in the processed block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostEvent {
+    Param { time: u32, id: u32, value: f64 },
+    NoteOn { time: u32, key: i16, velocity: f64 },
+    NoteOff { time: u32, key: i16 },
+    Midi { time: u32, data: [u8; 3] },
+}
+
+impl HostEvent {
+    fn to_raw(self) -> RawEvent {
+        let header = |time, type_, size| clap_event_header {
+            size: size as u32,
+            time,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_,
+            flags: 0,
+        };
+        let note = |header, key, velocity| RawEvent {
+            note: clap_event_note {
+                header,
+                note_id: -1,
+                port_index: 0,
+                channel: 0,
+                key,
+                velocity,
+            },
+        };
+        match self {
+            Self::Param { time, id, value } => RawEvent {
+                param: clap_event_param_value {
+                    header: header(
+                        time,
+                        CLAP_EVENT_PARAM_VALUE,
+                        mem::size_of::<clap_event_param_value>(),
+                    ),
+                    param_id: id,
+                    cookie: ptr::null_mut(),
+                    note_id: -1,
+                    port_index: -1,
+                    channel: -1,
+                    key: -1,
+                    value,
+                },
+            },
+            Self::NoteOn {
+                time,
+                key,
+                velocity,
+            } => note(header(time, CLAP_EVENT_NOTE_ON, NOTE_SIZE), key, velocity),
+            Self::NoteOff { time, key } => {
+                note(header(time, CLAP_EVENT_NOTE_OFF, NOTE_SIZE), key, 0.0)
+            }
+            Self::Midi { time, data } => RawEvent {
+                midi: clap_event_midi {
+                    header: header(time, CLAP_EVENT_MIDI, mem::size_of::<clap_event_midi>()),
+                    port_index: 0,
+                    data,
+                },
+            },
+        }
+    }
+}
+
+const NOTE_SIZE: usize = mem::size_of::<clap_event_note>();
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union RawEvent {
+    header: clap_event_header,
+    param: clap_event_param_value,
+    note: clap_event_note,
+    midi: clap_event_midi,
+}
+
+/// A parameter as reported by the plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostParam {
+    pub id: u32,
+    pub name: String,
+    pub module: String,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    /// `CLAP_PARAM_*` flags.
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioPort {
+    pub id: u32,
+    pub channels: u32,
+    pub flags: u32,
+}
+
+/// A plugin instance driven from the current thread, which is main and audio thread at once.
+pub struct HeadlessHost {
+    entry: *const clap_plugin_entry,
+    plugin: *const clap_plugin,
+    host: Box<clap_host>,
+    active: bool,
+    max_frames: usize,
+    // unloaded after the plugin is destroyed
+    _library: Option<Library>,
+}
+
+impl HeadlessHost {
+    /// Loads the library at `path` and creates the plugin with `plugin_id`, or the first plugin.
+    pub fn load(path: impl AsRef<OsStr>, plugin_id: Option<&str>) -> Result<Self, String> {
+        let path = path.as_ref();
+        unsafe {
+            let library = Library::new(path).map_err(|err| err.to_string())?;
+            let entry = *library
+                .get::<*const clap_plugin_entry>(b"clap_entry\0")
+                .map_err(|err| err.to_string())?;
+            Self::create(entry, &path.to_string_lossy(), plugin_id, Some(library))
+        }
+    }
+
+    /// Creates the plugin with `plugin_id`, or the first plugin, from an entry of this process.
+    pub fn from_entry(
+        entry: &'static clap_plugin_entry,
+        plugin_id: Option<&str>,
+    ) -> Result<Self, String> {
+        unsafe { Self::create(entry, "", plugin_id, None) }
+    }
+
+    unsafe fn create(
+        entry: *const clap_plugin_entry,
+        path: &str,
+        plugin_id: Option<&str>,
+        library: Option<Library>,
+    ) -> Result<Self, String> {
+        let entry_ref = entry.as_ref().ok_or("clap_entry is null")?;
+        if !clap_version_is_compatible(entry_ref.clap_version) {
+            return Err(format!(
+                "incompatible clap version {}.{}",
+                entry_ref.clap_version.major, entry_ref.clap_version.minor
+            ));
+        }
+        let path = CString::new(path).map_err(|err| err.to_string())?;
+        let init = entry_ref.init.ok_or("clap_entry has no init")?;
+        if !init(path.as_ptr()) {
+            return Err("clap_entry init failed".to_owned());
+        }
+        let mut host = Self {
+            entry,
+            plugin: ptr::null(),
+            host: Box::new(clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ptr::null_mut(),
+                name: b"faust-clap headless host\0".as_ptr().cast(),
+                vendor: b"faust-clap\0".as_ptr().cast(),
+                url: b"\0".as_ptr().cast(),
+                version: b"0.1.0\0".as_ptr().cast(),
+                get_extension: Some(host_get_extension),
+                request_restart: Some(host_request),
+                request_process: Some(host_request),
+                request_callback: Some(host_request),
+            }),
+            active: false,
+            max_frames: 0,
+            _library: library,
+        };
+        let factory = entry_ref
+            .get_factory
+            .map(|get_factory| get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()))
+            .filter(|factory| !factory.is_null())
+            .ok_or("plugin has no plugin factory")?
+            .cast::<clap_plugin_factory>();
+        let factory_ref = &*factory;
+        let count = factory_ref
+            .get_plugin_count
+            .map_or(0, |count| count(factory));
+        let id = (0..count)
+            .filter_map(|index| {
+                let descriptor = factory_ref.get_plugin_descriptor?(factory, index);
+                descriptor.as_ref().map(|descriptor| descriptor.id)
+            })
+            .find(|id| plugin_id.is_none_or(|plugin_id| c_str(*id) == plugin_id))
+            .ok_or_else(|| format!("plugin {plugin_id:?} not found"))?;
+        let create_plugin = factory_ref
+            .create_plugin
+            .ok_or("factory has no create_plugin")?;
+        host.plugin = create_plugin(factory, &raw const *host.host, id);
+        let plugin = host.plugin.as_ref().ok_or("failed to create the plugin")?;
+        if !plugin.init.is_some_and(|init| init(host.plugin)) {
+            return Err("plugin init failed".to_owned());
+        }
+        Ok(host)
+    }
+
+    fn plugin(&self) -> &clap_plugin {
+        unsafe { &*self.plugin }
+    }
+
+    unsafe fn extension<T>(&self, id: &CStr) -> Option<&T> {
+        let get_extension = self.plugin().get_extension?;
+        get_extension(self.plugin, id.as_ptr()).cast::<T>().as_ref()
+    }
+
+    #[must_use]
+    pub fn plugin_id(&self) -> String {
+        unsafe { c_str((*self.plugin().desc).id) }
+    }
+
+    #[must_use]
+    pub fn plugin_name(&self) -> String {
+        unsafe { c_str((*self.plugin().desc).name) }
+    }
+
+    #[must_use]
+    pub fn features(&self) -> Vec<String> {
+        let mut features = Vec::new();
+        unsafe {
+            let mut feature = (*self.plugin().desc).features;
+            while !feature.is_null() && !(*feature).is_null() {
+                features.push(c_str(*feature));
+                feature = feature.add(1);
+            }
+        }
+        features
+    }
+
+    #[must_use]
+    pub fn audio_ports(&self, is_input: bool) -> Vec<AudioPort> {
+        unsafe {
+            let Some(ports) = self.extension::<clap_plugin_audio_ports>(CLAP_EXT_AUDIO_PORTS)
+            else {
+                return Vec::new();
+            };
+            let (Some(count), Some(get)) = (ports.count, ports.get) else {
+                return Vec::new();
+            };
+            (0..count(self.plugin, is_input))
+                .filter_map(|index| {
+                    let mut info: clap_audio_port_info = mem::zeroed();
+                    get(self.plugin, index, is_input, &raw mut info).then_some(AudioPort {
+                        id: info.id,
+                        channels: info.channel_count,
+                        flags: info.flags,
+                    })
+                })
+                .collect()
+        }
+    }
+
+    /// Names of the note ports.
+    #[must_use]
+    pub fn note_ports(&self, is_input: bool) -> Vec<String> {
+        unsafe {
+            let Some(ports) = self.extension::<clap_plugin_note_ports>(CLAP_EXT_NOTE_PORTS) else {
+                return Vec::new();
+            };
+            let (Some(count), Some(get)) = (ports.count, ports.get) else {
+                return Vec::new();
+            };
+            (0..count(self.plugin, is_input))
+                .filter_map(|index| {
+                    let mut info: clap_note_port_info = mem::zeroed();
+                    get(self.plugin, index, is_input, &raw mut info)
+                        .then(|| c_str(info.name.as_ptr()))
+                })
+                .collect()
+        }
+    }
+
+    #[must_use]
+    pub fn params(&self) -> Vec<HostParam> {
+        unsafe {
+            let Some(params) = self.extension::<clap_plugin_params>(CLAP_EXT_PARAMS) else {
+                return Vec::new();
+            };
+            let (Some(count), Some(get_info)) = (params.count, params.get_info) else {
+                return Vec::new();
+            };
+            (0..count(self.plugin))
+                .filter_map(|index| {
+                    let mut info: clap_param_info = mem::zeroed();
+                    get_info(self.plugin, index, &raw mut info).then(|| HostParam {
+                        id: info.id,
+                        name: c_str(info.name.as_ptr()),
+                        module: c_str(info.module.as_ptr()),
+                        min: info.min_value,
+                        max: info.max_value,
+                        default: info.default_value,
+                        flags: info.flags,
+                    })
+                })
+                .collect()
+        }
+    }
+
+    #[must_use]
+    pub fn param_value(&self, id: u32) -> Option<f64> {
+        unsafe {
+            let get_value = self
+                .extension::<clap_plugin_params>(CLAP_EXT_PARAMS)?
+                .get_value?;
+            let mut value = 0.0;
+            get_value(self.plugin, id, &raw mut value).then_some(value)
+        }
+    }
+
+    #[must_use]
+    pub fn value_to_text(&self, id: u32, value: f64) -> Option<String> {
+        unsafe {
+            let value_to_text = self
+                .extension::<clap_plugin_params>(CLAP_EXT_PARAMS)?
+                .value_to_text?;
+            let mut buffer = [0 as c_char; 256];
+            value_to_text(
+                self.plugin,
+                id,
+                value,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            )
+            .then(|| c_str(buffer.as_ptr()))
+        }
+    }
+
+    #[must_use]
+    pub fn text_to_value(&self, id: u32, text: &str) -> Option<f64> {
+        unsafe {
+            let text_to_value = self
+                .extension::<clap_plugin_params>(CLAP_EXT_PARAMS)?
+                .text_to_value?;
+            let text = CString::new(text).ok()?;
+            let mut value = 0.0;
+            text_to_value(self.plugin, id, text.as_ptr(), &raw mut value).then_some(value)
+        }
+    }
+
+    /// Passes parameter events to an inactive plugin.
+    pub fn flush(&mut self, events: &[HostEvent]) -> Result<(), String> {
+        unsafe {
+            let flush = self
+                .extension::<clap_plugin_params>(CLAP_EXT_PARAMS)
+                .and_then(|params| params.flush)
+                .ok_or("plugin has no params flush")?;
+            let events = events
+                .iter()
+                .map(|event| event.to_raw())
+                .collect::<Vec<_>>();
+            let events = events.as_slice();
+            let in_events = input_events(&events);
+            flush(self.plugin, &raw const in_events, &raw const OUTPUT_EVENTS);
+        }
+        Ok(())
+    }
+
+    /// Activates the plugin and starts processing.
+    pub fn activate(&mut self, sample_rate: f64, max_frames: u32) -> Result<(), String> {
+        if self.active {
+            return Err("plugin is already active".to_owned());
+        }
+        unsafe {
+            let plugin = &*self.plugin;
+            let activate = plugin.activate.ok_or("plugin has no activate")?;
+            if !activate(self.plugin, sample_rate, 1, max_frames) {
+                return Err("plugin activation failed".to_owned());
+            }
+            self.active = true;
+            self.max_frames = max_frames as usize;
+            if !plugin
+                .start_processing
+                .is_none_or(|start| start(self.plugin))
+            {
+                return Err("plugin failed to start processing".to_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops processing and deactivates the plugin.
+    pub fn deactivate(&mut self) {
+        if !self.active {
+            return;
+        }
+        unsafe {
+            let plugin = self.plugin();
+            if let Some(stop) = plugin.stop_processing {
+                stop(self.plugin);
+            }
+            if let Some(deactivate) = plugin.deactivate {
+                deactivate(self.plugin);
+            }
+        }
+        self.active = false;
+    }
+
+    /// Processes `frames` frames of the main input port and returns the main output port.
+    ///
+    /// Missing input channels are silent, `events` must be sorted by time.
+    pub fn process(
+        &mut self,
+        inputs: &[&[f32]],
+        frames: usize,
+        events: &[HostEvent],
+    ) -> Result<Vec<Vec<f32>>, String> {
+        self.process_buffers(inputs, frames, events)
+    }
+
+    /// Like [`HeadlessHost::process`], with 64 bit buffers as offered to ports that support them.
+    pub fn process_f64(
+        &mut self,
+        inputs: &[&[f64]],
+        frames: usize,
+        events: &[HostEvent],
+    ) -> Result<Vec<Vec<f64>>, String> {
+        self.process_buffers(inputs, frames, events)
+    }
+
+    fn process_buffers<S: HostSample>(
+        &mut self,
+        inputs: &[&[S]],
+        frames: usize,
+        events: &[HostEvent],
+    ) -> Result<Vec<Vec<S>>, String> {
+        if !self.active {
+            return Err("plugin is not active".to_owned());
+        }
+        if frames > self.max_frames {
+            return Err(format!(
+                "{} frames exceed the maximum of {}",
+                frames, self.max_frames
+            ));
+        }
+        let input_channels = self
+            .audio_ports(true)
+            .first()
+            .map_or(0, |port| port.channels);
+        let output_channels = self
+            .audio_ports(false)
+            .first()
+            .map_or(0, |port| port.channels);
+        let mut input_data = (0..input_channels as usize)
+            .map(|channel| {
+                let mut data = vec![S::default(); frames];
+                if let Some(input) = inputs.get(channel) {
+                    let len = input.len().min(frames);
+                    data[..len].copy_from_slice(&input[..len]);
+                }
+                data
+            })
+            .collect::<Vec<_>>();
+        let mut output_data = vec![vec![S::default(); frames]; output_channels as usize];
+        let mut input_pointers = input_data
+            .iter_mut()
+            .map(std::vec::Vec::as_mut_ptr)
+            .collect::<Vec<_>>();
+        let mut output_pointers = output_data
+            .iter_mut()
+            .map(std::vec::Vec::as_mut_ptr)
+            .collect::<Vec<_>>();
+        let input_buffer = S::buffer(&mut input_pointers);
+        let mut output_buffer = S::buffer(&mut output_pointers);
+        let events = events
+            .iter()
+            .map(|event| event.to_raw())
+            .collect::<Vec<_>>();
+        let events = events.as_slice();
+        let in_events = input_events(&events);
+        let process = clap_process {
+            steady_time: -1,
+            frames_count: frames as u32,
+            transport: ptr::null(),
+            audio_inputs: &raw const input_buffer,
+            audio_outputs: &raw mut output_buffer,
+            audio_inputs_count: u32::from(input_channels > 0),
+            audio_outputs_count: u32::from(output_channels > 0),
+            in_events: &raw const in_events,
+            out_events: &raw const OUTPUT_EVENTS,
+        };
+        let status = unsafe {
+            let process_fn = self.plugin().process.ok_or("plugin has no process")?;
+            process_fn(self.plugin, &raw const process)
+        };
+        if status == CLAP_PROCESS_ERROR {
+            return Err("plugin failed to process".to_owned());
+        }
+        Ok(output_data)
+    }
+
+    pub fn save_state(&self) -> Result<Vec<u8>, String> {
+        unsafe {
+            let save = self
+                .extension::<clap_plugin_state>(CLAP_EXT_STATE)
+                .and_then(|state| state.save)
+                .ok_or("plugin has no state extension")?;
+            let mut data = Vec::<u8>::new();
+            let stream = clap_ostream {
+                ctx: (&raw mut data).cast(),
+                write: Some(stream_write),
+            };
+            if save(self.plugin, &raw const stream) {
+                Ok(data)
+            } else {
+                Err("plugin failed to save its state".to_owned())
+            }
+        }
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        unsafe {
+            let load = self
+                .extension::<clap_plugin_state>(CLAP_EXT_STATE)
+                .and_then(|state| state.load)
+                .ok_or("plugin has no state extension")?;
+            let mut reader = data;
+            let stream = clap_istream {
+                ctx: (&raw mut reader).cast(),
+                read: Some(stream_read),
+            };
+            if load(self.plugin, &raw const stream) {
+                Ok(())
+            } else {
+                Err("plugin failed to load the state".to_owned())
+            }
+        }
+    }
+}
+
+impl Drop for HeadlessHost {
+    fn drop(&mut self) {
+        self.deactivate();
+        unsafe {
+            if let Some(destroy) = self.plugin.as_ref().and_then(|plugin| plugin.destroy) {
+                destroy(self.plugin);
+            }
+            if let Some(deinit) = (*self.entry).deinit {
+                deinit();
+            }
+        }
+    }
+}
+
+/// Sample types of the buffers passed to [`clap_plugin::process`].
+trait HostSample: Copy + Default {
+    fn buffer(pointers: &mut [*mut Self]) -> clap_audio_buffer;
+}
+
+impl HostSample for f32 {
+    fn buffer(pointers: &mut [*mut Self]) -> clap_audio_buffer {
+        clap_audio_buffer {
+            data32: pointers.as_mut_ptr(),
+            data64: ptr::null_mut(),
+            channel_count: pointers.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        }
+    }
+}
+
+impl HostSample for f64 {
+    fn buffer(pointers: &mut [*mut Self]) -> clap_audio_buffer {
+        clap_audio_buffer {
+            data32: ptr::null_mut(),
+            data64: pointers.as_mut_ptr(),
+            channel_count: pointers.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        }
+    }
+}
+
+unsafe fn c_str(value: *const c_char) -> String {
+    if value.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(value).to_string_lossy().into_owned()
+    }
+}
+
+fn input_events(events: &&[RawEvent]) -> clap_input_events {
+    clap_input_events {
+        ctx: ptr::from_ref(events).cast_mut().cast(),
+        size: Some(input_events_size),
+        get: Some(input_events_get),
+    }
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events: &[RawEvent] = *(*list).ctx.cast::<&[RawEvent]>();
+    events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+    list: *const clap_input_events,
+    index: u32,
+) -> *const clap_event_header {
+    let events: &[RawEvent] = *(*list).ctx.cast::<&[RawEvent]>();
+    events
+        .get(index as usize)
+        .map_or(ptr::null(), |event| &raw const event.header)
+}
+
+unsafe extern "C" fn output_events_try_push(
+    _list: *const clap_output_events,
+    _event: *const clap_event_header,
+) -> bool {
+    true
+}
+
+static OUTPUT_EVENTS: clap_output_events = clap_output_events {
+    ctx: ptr::null_mut(),
+    try_push: Some(output_events_try_push),
+};
+
+unsafe extern "C" fn host_get_extension(
+    _host: *const clap_host,
+    _extension_id: *const c_char,
+) -> *const c_void {
+    ptr::null()
+}
+
+unsafe extern "C" fn host_request(_host: *const clap_host) {}
+
+unsafe extern "C" fn stream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    let data = &mut *(*stream).ctx.cast::<Vec<u8>>();
+    data.extend_from_slice(slice::from_raw_parts(buffer.cast::<u8>(), size as usize));
+    size as i64
+}
+
+unsafe extern "C" fn stream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    let reader = &mut *(*stream).ctx.cast::<&[u8]>();
+    let count = reader.len().min(size as usize);
+    ptr::copy_nonoverlapping(reader.as_ptr(), buffer.cast::<u8>(), count);
+    *reader = &reader[count..];
+    count as i64
+}
@@ -0,0 +1,291 @@
+#![warn(
+    clippy::all,
+    clippy::pedantic,
+    clippy::nursery,
+    unused_crate_dependencies,
+    clippy::unwrap_used
+)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_const_for_fn)]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_sign_loss)]
+#![allow(clippy::cast_possible_wrap)]
+
+//! Expose a [`FaustDsp`] as a CLAP plugin.
+//!
+//! A dsp implements [`ClapDsp`] with the [`PluginInfo`] of the plugin, and [`export_clap!`] adds the
+//! `clap_entry` symbol to a `cdylib` crate. The `Clap` architecture object of `faust-build` generates
+//! both from the json description of a dsp.
+//!
+//! ```
+//! # use faust_types::*;
+//! # struct Volume;
+//! # impl FaustDsp for Volume {
+//! #     type T = f32;
+//! #     fn new() -> Self { Self }
+//! #     fn metadata(&self, _: &mut dyn Meta) {}
+//! #     fn get_sample_rate(&self) -> i32 { 44100 }
+//! #     fn get_num_inputs(&self) -> i32 { 2 }
+//! #     fn get_num_outputs(&self) -> i32 { 2 }
+//! #     fn class_init(_: i32) {}
+//! #     fn instance_reset_params(&mut self) {}
+//! #     fn instance_clear(&mut self) {}
+//! #     fn instance_constants(&mut self, _: i32) {}
+//! #     fn instance_init(&mut self, _: i32) {}
+//! #     fn init(&mut self, _: i32) {}
+//! #     fn build_user_interface(&self, _: &mut dyn UI<f32>) {}
+//! #     fn build_user_interface_static(_: &mut dyn UI<f32>) {}
+//! #     fn get_param(&self, _: ParamIndex) -> Option<f32> { None }
+//! #     fn set_param(&mut self, _: ParamIndex, _: f32) {}
+//! #     fn compute(&mut self, _: i32, _: &[&[f32]], _: &mut [&mut [f32]]) {}
+//! # }
+//! use faust_clap::{ClapDsp, Descriptor, PluginInfo};
+//!
+//! impl ClapDsp for Volume {
+//!     fn descriptor() -> &'static Descriptor {
+//!         static DESCRIPTOR: Descriptor = Descriptor::new(PluginInfo {
+//!             id: "org.example.volume",
+//!             name: "Volume",
+//!             vendor: "example",
+//!             url: "",
+//!             version: "1.0.0",
+//!             description: "",
+//!             features: &["audio-effect", "stereo"],
+//!         });
+//!         &DESCRIPTOR
+//!     }
+//! }
+//!
+//! faust_clap::export_clap!(Volume);
+//! ```
+//!
+//! Parameters are derived from the user interface of the dsp with ids that stay stable when the
+//! dsp changes, see [`params`]. Dsps with `freq`, `gate` and `gain` widgets get a note port and
+//! are played monophonically. The state of a plugin contains the values of its parameters.
+
+#[cfg(feature = "host")]
+pub mod host;
+pub mod params;
+mod plugin;
+
+pub use clap_sys as sys;
+
+use clap_sys::{
+    entry::clap_plugin_entry,
+    factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID},
+    host::clap_host,
+    plugin::{clap_plugin, clap_plugin_descriptor},
+    version::CLAP_VERSION,
+};
+use faust_types::{FaustDsp, Sample};
+use params::{NoteParams, Param};
+use std::{
+    collections::HashMap,
+    ffi::{c_void, CStr, CString},
+    marker::PhantomData,
+    os::raw::c_char,
+    ptr,
+    sync::OnceLock,
+};
+
+/// Description of a plugin, shown by hosts in their plugin browser.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginInfo {
+    /// Reverse domain name like `org.example.volume`.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub vendor: &'static str,
+    pub url: &'static str,
+    pub version: &'static str,
+    pub description: &'static str,
+    /// CLAP features like `audio-effect`, `instrument` or `stereo`.
+    pub features: &'static [&'static str],
+}
+
+/// [`PluginInfo`] together with the data derived from it and the dsp.
+///
+/// Lives in a `static`, the CLAP structures are built on first use.
+pub struct Descriptor {
+    info: PluginInfo,
+    raw: OnceLock<RawDescriptor>,
+    layout: OnceLock<Layout>,
+}
+
+impl Descriptor {
+    #[must_use]
+    pub const fn new(info: PluginInfo) -> Self {
+        Self {
+            info,
+            raw: OnceLock::new(),
+            layout: OnceLock::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn info(&self) -> &PluginInfo {
+        &self.info
+    }
+
+    fn raw(&self) -> &clap_plugin_descriptor {
+        &self
+            .raw
+            .get_or_init(|| RawDescriptor::new(&self.info))
+            .descriptor
+    }
+
+    fn layout<D: ClapDsp>(&self) -> &Layout {
+        self.layout.get_or_init(|| {
+            let params = params::params::<D>();
+            let ids = params
+                .iter()
+                .enumerate()
+                .map(|(i, param)| (param.id, i))
+                .collect();
+            Layout {
+                notes: NoteParams::find(&params),
+                params,
+                ids,
+            }
+        })
+    }
+}
+
+/// C strings of a [`PluginInfo`] and the descriptor pointing to them.
+struct RawDescriptor {
+    descriptor: clap_plugin_descriptor,
+    _strings: Vec<CString>,
+    _features: Vec<*const c_char>,
+}
+
+// the pointers only point into the owned strings
+unsafe impl Send for RawDescriptor {}
+unsafe impl Sync for RawDescriptor {}
+
+impl RawDescriptor {
+    fn new(info: &PluginInfo) -> Self {
+        let c_string =
+            |value: &str| CString::new(value.replace('\0', "")).expect("nul bytes are removed");
+        let strings = [
+            info.id,
+            info.name,
+            info.vendor,
+            info.url,
+            info.version,
+            info.description,
+        ]
+        .iter()
+        .chain(info.features)
+        .map(|value| c_string(value))
+        .collect::<Vec<_>>();
+        let features = strings[6..]
+            .iter()
+            .map(|feature| feature.as_ptr())
+            .chain([ptr::null()])
+            .collect::<Vec<_>>();
+        let descriptor = clap_plugin_descriptor {
+            clap_version: CLAP_VERSION,
+            id: strings[0].as_ptr(),
+            name: strings[1].as_ptr(),
+            vendor: strings[2].as_ptr(),
+            url: strings[3].as_ptr(),
+            manual_url: strings[3].as_ptr(),
+            support_url: strings[3].as_ptr(),
+            version: strings[4].as_ptr(),
+            description: strings[5].as_ptr(),
+            features: features.as_ptr(),
+        };
+        Self {
+            descriptor,
+            _strings: strings,
+            _features: features,
+        }
+    }
+}
+
+/// Parameters of the dsp, shared by all instances of the plugin.
+struct Layout {
+    params: Vec<Param>,
+    /// Positions in `params` by id.
+    ids: HashMap<u32, usize>,
+    notes: Option<NoteParams>,
+}
+
+/// A dsp that can be exported as CLAP plugin with [`export_clap!`].
+pub trait ClapDsp: FaustDsp<T: Sample + Default> + Send + Sized + 'static {
+    fn descriptor() -> &'static Descriptor;
+}
+
+/// Adds the `clap_entry` symbol for a [`ClapDsp`] to the crate.
+#[macro_export]
+macro_rules! export_clap {
+    ($dsp:ty) => {
+        #[allow(non_upper_case_globals)]
+        #[no_mangle]
+        pub static clap_entry: $crate::sys::entry::clap_plugin_entry = $crate::entry::<$dsp>();
+    };
+}
+
+/// The `clap_entry` of a plugin library with the single plugin `D`.
+#[must_use]
+pub const fn entry<D: ClapDsp>() -> clap_plugin_entry {
+    clap_plugin_entry {
+        clap_version: CLAP_VERSION,
+        init: Some(entry_init),
+        deinit: Some(entry_deinit),
+        get_factory: Some(get_factory::<D>),
+    }
+}
+
+unsafe extern "C" fn entry_init(_plugin_path: *const c_char) -> bool {
+    true
+}
+
+unsafe extern "C" fn entry_deinit() {}
+
+unsafe extern "C" fn get_factory<D: ClapDsp>(factory_id: *const c_char) -> *const c_void {
+    if !factory_id.is_null() && CStr::from_ptr(factory_id) == CLAP_PLUGIN_FACTORY_ID {
+        let factory: &'static clap_plugin_factory = &Factory::<D>::FACTORY;
+        ptr::from_ref(factory).cast()
+    } else {
+        ptr::null()
+    }
+}
+
+struct Factory<D>(PhantomData<D>);
+
+impl<D: ClapDsp> Factory<D> {
+    const FACTORY: clap_plugin_factory = clap_plugin_factory {
+        get_plugin_count: Some(Self::plugin_count),
+        get_plugin_descriptor: Some(Self::plugin_descriptor),
+        create_plugin: Some(Self::create_plugin),
+    };
+
+    unsafe extern "C" fn plugin_count(_factory: *const clap_plugin_factory) -> u32 {
+        1
+    }
+
+    unsafe extern "C" fn plugin_descriptor(
+        _factory: *const clap_plugin_factory,
+        index: u32,
+    ) -> *const clap_plugin_descriptor {
+        if index == 0 {
+            D::descriptor().raw()
+        } else {
+            ptr::null()
+        }
+    }
+
+    unsafe extern "C" fn create_plugin(
+        _factory: *const clap_plugin_factory,
+        host: *const clap_host,
+        plugin_id: *const c_char,
+    ) -> *const clap_plugin {
+        let descriptor = D::descriptor();
+        if plugin_id.is_null()
+            || CStr::from_ptr(plugin_id).to_bytes() != descriptor.info.id.as_bytes()
+        {
+            return ptr::null();
+        }
+        plugin::Instance::<D>::create(host, descriptor.raw(), descriptor.layout::<D>())
+    }
+}
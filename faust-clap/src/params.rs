@@ -0,0 +1,198 @@
+//! Parameters of a dsp as seen by a CLAP host.
+//!
+//! The parameters are collected from `build_user_interface_static` with `faust_types::widgets`.
+//! Their ids are a hash of the address of the widget, so they stay the same when widgets are
+//! added, removed or reordered and automation and saved states of a host keep working.
+
+use faust_types::{
+    widgets::{widgets, WidgetKind},
+    FaustDsp, ParamIndex, Sample,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Button,
+    CheckBox,
+    Slider,
+    NumEntry,
+    /// Bargraphs, read-only for the host.
+    BarGraph,
+}
+
+impl From<WidgetKind> for ParamKind {
+    fn from(kind: WidgetKind) -> Self {
+        match kind {
+            WidgetKind::Button => Self::Button,
+            WidgetKind::CheckButton => Self::CheckBox,
+            WidgetKind::VerticalSlider | WidgetKind::HorizontalSlider => Self::Slider,
+            WidgetKind::NumEntry => Self::NumEntry,
+            WidgetKind::HorizontalBargraph | WidgetKind::VerticalBargraph => Self::BarGraph,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    /// Stable id, see [`stable_id`].
+    pub id: u32,
+    pub index: ParamIndex,
+    pub kind: ParamKind,
+    pub label: String,
+    /// Address like `/synth/filter/cutoff`, built from the labels of the boxes and the widget.
+    pub address: String,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    /// Step of sliders and num entries, `0` for bargraphs.
+    pub step: f64,
+    /// Metadata declared for the widget, e.g. `unit` or `hidden`.
+    pub meta: Vec<(String, String)>,
+}
+
+impl Param {
+    #[must_use]
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[must_use]
+    pub fn is_output(&self) -> bool {
+        self.kind == ParamKind::BarGraph
+    }
+
+    /// Buttons, checkboxes and widgets with an integer range and step.
+    #[must_use]
+    pub fn is_stepped(&self) -> bool {
+        let integral = |value: f64| value.fract() == 0.0;
+        match self.kind {
+            ParamKind::Button | ParamKind::CheckBox => true,
+            ParamKind::Slider | ParamKind::NumEntry => {
+                self.step != 0.0 && integral(self.step) && integral(self.min) && integral(self.max)
+            }
+            ParamKind::BarGraph => false,
+        }
+    }
+
+    /// Group of the widget: the labels of its boxes without the outermost one.
+    #[must_use]
+    pub fn module(&self) -> &str {
+        let path = self.address.trim_start_matches('/');
+        let path = path.split_once('/').map_or("", |(_, path)| path);
+        path.rsplit_once('/').map_or("", |(module, _)| module)
+    }
+
+    /// Formats `value` with the precision of the step and the unit of the widget.
+    #[must_use]
+    pub fn format(&self, value: f64) -> String {
+        if matches!(self.kind, ParamKind::Button | ParamKind::CheckBox) {
+            return if value >= 0.5 { "on" } else { "off" }.to_owned();
+        }
+        let precision = if self.step > 0.0 && self.step < 1.0 {
+            (-self.step.log10().floor()) as usize
+        } else if self.is_stepped() {
+            0
+        } else {
+            2
+        };
+        self.meta("unit").map_or_else(
+            || format!("{value:.precision$}"),
+            |unit| format!("{value:.precision$} {unit}"),
+        )
+    }
+
+    /// Parses the text of [`Param::format`], the unit is optional.
+    #[must_use]
+    pub fn parse(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        match text {
+            "on" => return Some(1.0),
+            "off" => return Some(0.0),
+            _ => {}
+        }
+        let number = self
+            .meta("unit")
+            .and_then(|unit| text.strip_suffix(unit))
+            .unwrap_or(text);
+        number
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|value| value.max(self.min).min(self.max))
+    }
+}
+
+/// Parameters following the `freq`/`gate`/`gain` convention of faust instruments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteParams {
+    /// Positions in the parameter list.
+    pub freq: Option<usize>,
+    pub gate: usize,
+    pub gain: Option<usize>,
+}
+
+impl NoteParams {
+    /// Finds the parameters by their labels, a `gate` is required.
+    #[must_use]
+    pub fn find(params: &[Param]) -> Option<Self> {
+        let find = |label: &str| {
+            params
+                .iter()
+                .position(|param| !param.is_output() && param.label == label)
+        };
+        Some(Self {
+            freq: find("freq"),
+            gate: find("gate")?,
+            gain: find("gain"),
+        })
+    }
+
+    #[must_use]
+    pub fn contains(&self, position: usize) -> bool {
+        self.gate == position || self.freq == Some(position) || self.gain == Some(position)
+    }
+}
+
+/// Id of a parameter: the 32 bit FNV-1a hash of its address.
+///
+/// [`params`] resolves collisions by incrementing the id of the later parameter.
+#[must_use]
+pub fn stable_id(address: &str) -> u32 {
+    let hash = address.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    // u32::MAX is CLAP_INVALID_ID
+    hash.min(u32::MAX - 1)
+}
+
+/// The parameters of `D` in the order of its user interface.
+#[must_use]
+pub fn params<D>() -> Vec<Param>
+where
+    D: FaustDsp,
+    D::T: Sample,
+{
+    let mut params: Vec<Param> = Vec::new();
+    for widget in widgets::<D>() {
+        let address = widget.address();
+        let mut id = stable_id(&address);
+        while params.iter().any(|param| param.id == id) {
+            id = (id + 1) % (u32::MAX - 1);
+        }
+        params.push(Param {
+            id,
+            index: widget.index,
+            kind: ParamKind::from(widget.kind),
+            label: widget.label,
+            address,
+            min: widget.min,
+            max: widget.max,
+            default: widget.init,
+            step: widget.step,
+            meta: widget.meta,
+        });
+    }
+    params
+}
@@ -0,0 +1,698 @@
+//! Plugin instances and the CLAP extensions they implement.
+//!
+//! Parameter values are kept in atomics shared between the main thread and the audio thread.
+//! The audio thread applies changed values to the dsp at the start of every process call and
+//! parameter events sample accurately, splitting the block at their time.
+
+use crate::{params::NoteParams, ClapDsp, Layout};
+use clap_sys::{
+    audio_buffer::clap_audio_buffer,
+    events::{
+        clap_event_header, clap_event_midi, clap_event_note, clap_event_param_value,
+        clap_input_events, clap_output_events, CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI,
+        CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_VALUE,
+    },
+    ext::{
+        audio_ports::{
+            clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN,
+            CLAP_AUDIO_PORT_SUPPORTS_64BITS, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_MONO,
+            CLAP_PORT_STEREO,
+        },
+        note_ports::{
+            clap_note_port_info, clap_plugin_note_ports, CLAP_EXT_NOTE_PORTS,
+            CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI,
+        },
+        params::{
+            clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS, CLAP_PARAM_IS_AUTOMATABLE,
+            CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_READONLY, CLAP_PARAM_IS_STEPPED,
+        },
+        state::{clap_plugin_state, CLAP_EXT_STATE},
+    },
+    host::clap_host,
+    id::CLAP_INVALID_ID,
+    plugin::{clap_plugin, clap_plugin_descriptor},
+    process::{clap_process, clap_process_status, CLAP_PROCESS_CONTINUE, CLAP_PROCESS_ERROR},
+    stream::{clap_istream, clap_ostream},
+};
+use faust_types::{AudioBuffers, Sample};
+use std::{
+    cell::UnsafeCell,
+    convert::TryFrom,
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    ptr, slice,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Magic bytes and version in front of saved states.
+const STATE_HEADER: &[u8; 8] = b"FCLP\x01\0\0\0";
+
+pub struct Instance<D: ClapDsp> {
+    raw: clap_plugin,
+    layout: &'static Layout,
+    /// Channel counts of the dsp, read once so main-thread callbacks never touch it.
+    num_inputs: u32,
+    num_outputs: u32,
+    /// Current values, written by the host on the main thread and by the dsp for outputs.
+    values: Vec<AtomicU64>,
+    /// Only touched by the audio thread or while the plugin is not active.
+    audio: UnsafeCell<Audio<D>>,
+}
+
+struct Audio<D: ClapDsp> {
+    dsp: Box<D>,
+    buffers: AudioBuffers<D::T>,
+    /// Values last passed to the dsp.
+    applied: Vec<f64>,
+    /// Key of the playing note.
+    note: Option<i16>,
+}
+
+impl<D: ClapDsp> Instance<D> {
+    pub(crate) fn create(
+        _host: *const clap_host,
+        descriptor: &'static clap_plugin_descriptor,
+        layout: &'static Layout,
+    ) -> *const clap_plugin {
+        let dsp = Box::new(D::new());
+        let num_inputs = u32::try_from(dsp.get_num_inputs()).unwrap_or(0);
+        let num_outputs = u32::try_from(dsp.get_num_outputs()).unwrap_or(0);
+        let audio = Audio {
+            buffers: AudioBuffers::for_dsp(&*dsp, 0),
+            dsp,
+            applied: vec![f64::NAN; layout.params.len()],
+            note: None,
+        };
+        let instance = Box::new(Self {
+            raw: clap_plugin {
+                desc: descriptor,
+                plugin_data: ptr::null_mut(),
+                init: Some(Self::init),
+                destroy: Some(Self::destroy),
+                activate: Some(Self::activate),
+                deactivate: Some(Self::deactivate),
+                start_processing: Some(Self::start_processing),
+                stop_processing: Some(Self::stop_processing),
+                reset: Some(Self::reset),
+                process: Some(Self::process),
+                get_extension: Some(Self::get_extension),
+                on_main_thread: Some(Self::on_main_thread),
+            },
+            values: layout
+                .params
+                .iter()
+                .map(|param| AtomicU64::new(param.default.to_bits()))
+                .collect(),
+            layout,
+            num_inputs,
+            num_outputs,
+            audio: UnsafeCell::new(audio),
+        });
+        let instance = Box::into_raw(instance);
+        unsafe {
+            (*instance).raw.plugin_data = instance.cast();
+            &raw const (*instance).raw
+        }
+    }
+
+    unsafe fn from_raw<'a>(plugin: *const clap_plugin) -> &'a Self {
+        &*(*plugin).plugin_data.cast::<Self>()
+    }
+
+    /// # Safety
+    /// Only on the audio thread or while the plugin is not processing.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn audio(&self) -> &mut Audio<D> {
+        &mut *self.audio.get()
+    }
+
+    fn value(&self, position: usize) -> f64 {
+        f64::from_bits(self.values[position].load(Ordering::Relaxed))
+    }
+
+    fn set_value(&self, position: usize, value: f64) {
+        self.values[position].store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    fn position(&self, id: u32) -> Option<usize> {
+        self.layout.ids.get(&id).copied()
+    }
+
+    unsafe extern "C" fn init(_plugin: *const clap_plugin) -> bool {
+        true
+    }
+
+    unsafe extern "C" fn destroy(plugin: *const clap_plugin) {
+        drop(Box::from_raw((*plugin).plugin_data.cast::<Self>()));
+    }
+
+    unsafe extern "C" fn activate(
+        plugin: *const clap_plugin,
+        sample_rate: f64,
+        _min_frames_count: u32,
+        max_frames_count: u32,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let audio = instance.audio();
+        audio.dsp.init(sample_rate as i32);
+        audio.buffers = AudioBuffers::for_dsp(&*audio.dsp, max_frames_count as usize);
+        // init resets the parameters of the dsp
+        audio.applied.iter_mut().for_each(|value| *value = f64::NAN);
+        audio.note = None;
+        true
+    }
+
+    unsafe extern "C" fn deactivate(_plugin: *const clap_plugin) {}
+
+    unsafe extern "C" fn start_processing(_plugin: *const clap_plugin) -> bool {
+        true
+    }
+
+    unsafe extern "C" fn stop_processing(_plugin: *const clap_plugin) {}
+
+    unsafe extern "C" fn reset(plugin: *const clap_plugin) {
+        let audio = Self::from_raw(plugin).audio();
+        audio.dsp.instance_clear();
+        audio.note = None;
+    }
+
+    unsafe extern "C" fn on_main_thread(_plugin: *const clap_plugin) {}
+
+    unsafe extern "C" fn process(
+        plugin: *const clap_plugin,
+        process: *const clap_process,
+    ) -> clap_process_status {
+        if process.is_null() {
+            return CLAP_PROCESS_ERROR;
+        }
+        let instance = Self::from_raw(plugin);
+        let audio = instance.audio();
+        let process = &*process;
+        for position in 0..instance.layout.params.len() {
+            instance.apply(audio, position);
+        }
+
+        let frames = process.frames_count as usize;
+        let mut start = 0;
+        for event in input_events(process.in_events) {
+            let time = ((*event).time as usize).min(frames);
+            if time > start {
+                Self::render(audio, process, start..time);
+                start = time;
+            }
+            instance.handle_event(audio, event);
+        }
+        Self::render(audio, process, start..frames);
+
+        for (position, param) in instance.layout.params.iter().enumerate() {
+            if param.is_output() {
+                let value = audio.dsp.get_param(param.index).map_or(0.0, Sample::to_f64);
+                instance.set_value(position, value);
+            }
+        }
+        CLAP_PROCESS_CONTINUE
+    }
+
+    /// Passes the current value of a parameter to the dsp if it changed.
+    fn apply(&self, audio: &mut Audio<D>, position: usize) {
+        let param = &self.layout.params[position];
+        let value = self.value(position);
+        if !param.is_output() && value.to_bits() != audio.applied[position].to_bits() {
+            audio.dsp.set_param(param.index, D::T::from_f64(value));
+            audio.applied[position] = value;
+        }
+    }
+
+    fn set_and_apply(&self, audio: &mut Audio<D>, position: usize, value: f64) {
+        self.set_value(position, value);
+        self.apply(audio, position);
+    }
+
+    // hosts align events for their types
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe fn handle_event(&self, audio: &mut Audio<D>, event: *const clap_event_header) {
+        if (*event).space_id != CLAP_CORE_EVENT_SPACE_ID {
+            return;
+        }
+        match (*event).type_ {
+            CLAP_EVENT_PARAM_VALUE => {
+                let event = &*event.cast::<clap_event_param_value>();
+                if let Some(position) = self.position(event.param_id) {
+                    let param = &self.layout.params[position];
+                    let value = event.value.max(param.min).min(param.max);
+                    self.set_and_apply(audio, position, value);
+                }
+            }
+            CLAP_EVENT_NOTE_ON => {
+                let event = &*event.cast::<clap_event_note>();
+                self.note_on(audio, event.key, event.velocity);
+            }
+            CLAP_EVENT_NOTE_OFF => {
+                let event = &*event.cast::<clap_event_note>();
+                self.note_off(audio, event.key);
+            }
+            CLAP_EVENT_MIDI => {
+                let event = &*event.cast::<clap_event_midi>();
+                let [status, key, velocity] = event.data;
+                let key = i16::from(key);
+                match (status & 0xf0, velocity) {
+                    (0x90, 0) | (0x80, _) => self.note_off(audio, key),
+                    (0x90, _) => self.note_on(audio, key, f64::from(velocity) / 127.0),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Notes are played monophonically, a new note takes over the gate of the playing one.
+    fn note_on(&self, audio: &mut Audio<D>, key: i16, velocity: f64) {
+        let Some(NoteParams { freq, gate, gain }) = self.layout.notes else {
+            return;
+        };
+        if let Some(freq) = freq {
+            let hz = 440.0 * ((f64::from(key) - 69.0) / 12.0).exp2();
+            self.set_and_apply(audio, freq, hz);
+        }
+        if let Some(gain) = gain {
+            self.set_and_apply(audio, gain, velocity);
+        }
+        self.set_and_apply(audio, gate, 1.0);
+        audio.note = Some(key);
+    }
+
+    fn note_off(&self, audio: &mut Audio<D>, key: i16) {
+        let Some(notes) = self.layout.notes else {
+            return;
+        };
+        // -1 is a wildcard for all keys
+        if key == -1 || audio.note == Some(key) {
+            self.set_and_apply(audio, notes.gate, 0.0);
+            audio.note = None;
+        }
+    }
+
+    /// Computes the frames of `range`, in blocks of at most the maximal frame count.
+    unsafe fn render(audio: &mut Audio<D>, process: &clap_process, range: std::ops::Range<usize>) {
+        let inputs = HostChannels::new(process.audio_inputs, process.audio_inputs_count);
+        let outputs = HostChannels::new(process.audio_outputs, process.audio_outputs_count);
+        let max_frames = audio.buffers.max_frames().max(1);
+        let mut start = range.start;
+        while start < range.end {
+            let frames = (range.end - start).min(max_frames);
+            for channel in 0..audio.buffers.inputs() {
+                let input = &mut audio.buffers.input_mut(channel)[..frames];
+                match inputs {
+                    HostChannels::F32(host) => read_channel(host, channel, start, input),
+                    HostChannels::F64(host) => read_channel(host, channel, start, input),
+                }
+            }
+            audio.buffers.compute(&mut *audio.dsp, frames);
+            for channel in 0..audio.buffers.outputs() {
+                let output = &audio.buffers.output(channel)[..frames];
+                match outputs {
+                    HostChannels::F32(host) => write_channel(host, channel, start, output),
+                    HostChannels::F64(host) => write_channel(host, channel, start, output),
+                }
+            }
+            start += frames;
+        }
+    }
+
+    unsafe extern "C" fn get_extension(
+        _plugin: *const clap_plugin,
+        id: *const c_char,
+    ) -> *const c_void {
+        if id.is_null() {
+            return ptr::null();
+        }
+        let id = CStr::from_ptr(id);
+        let extension: *const c_void = if id == CLAP_EXT_PARAMS {
+            ptr::from_ref(&Self::PARAMS).cast()
+        } else if id == CLAP_EXT_STATE {
+            ptr::from_ref(&Self::STATE).cast()
+        } else if id == CLAP_EXT_AUDIO_PORTS {
+            ptr::from_ref(&Self::AUDIO_PORTS).cast()
+        } else if id == CLAP_EXT_NOTE_PORTS {
+            ptr::from_ref(&Self::NOTE_PORTS).cast()
+        } else {
+            ptr::null()
+        };
+        extension
+    }
+
+    const PARAMS: clap_plugin_params = clap_plugin_params {
+        count: Some(Self::params_count),
+        get_info: Some(Self::params_get_info),
+        get_value: Some(Self::params_get_value),
+        value_to_text: Some(Self::params_value_to_text),
+        text_to_value: Some(Self::params_text_to_value),
+        flush: Some(Self::params_flush),
+    };
+
+    unsafe extern "C" fn params_count(plugin: *const clap_plugin) -> u32 {
+        Self::from_raw(plugin).layout.params.len() as u32
+    }
+
+    unsafe extern "C" fn params_get_info(
+        plugin: *const clap_plugin,
+        index: u32,
+        info: *mut clap_param_info,
+    ) -> bool {
+        let layout = Self::from_raw(plugin).layout;
+        let Some(param) = layout.params.get(index as usize) else {
+            return false;
+        };
+        let mut flags = if param.is_output() {
+            CLAP_PARAM_IS_READONLY
+        } else {
+            CLAP_PARAM_IS_AUTOMATABLE
+        };
+        if param.is_stepped() {
+            flags |= CLAP_PARAM_IS_STEPPED;
+        }
+        let is_note = layout
+            .notes
+            .is_some_and(|notes| notes.contains(index as usize));
+        if is_note || param.meta("hidden").is_some_and(|hidden| hidden != "0") {
+            flags |= CLAP_PARAM_IS_HIDDEN;
+        }
+        let info = &mut *info;
+        info.id = param.id;
+        info.flags = flags;
+        info.cookie = ptr::null_mut();
+        write_c_string(&mut info.name, &param.label);
+        write_c_string(&mut info.module, param.module());
+        info.min_value = param.min;
+        info.max_value = param.max;
+        info.default_value = param.default;
+        true
+    }
+
+    unsafe extern "C" fn params_get_value(
+        plugin: *const clap_plugin,
+        param_id: u32,
+        value: *mut f64,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let Some(position) = instance.position(param_id) else {
+            return false;
+        };
+        *value = instance.value(position);
+        true
+    }
+
+    unsafe extern "C" fn params_value_to_text(
+        plugin: *const clap_plugin,
+        param_id: u32,
+        value: f64,
+        buffer: *mut c_char,
+        capacity: u32,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let Some(position) = instance.position(param_id) else {
+            return false;
+        };
+        if buffer.is_null() || capacity == 0 {
+            return false;
+        }
+        let buffer = slice::from_raw_parts_mut(buffer, capacity as usize);
+        write_c_string(buffer, &instance.layout.params[position].format(value));
+        true
+    }
+
+    unsafe extern "C" fn params_text_to_value(
+        plugin: *const clap_plugin,
+        param_id: u32,
+        text: *const c_char,
+        value: *mut f64,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let Some(position) = instance.position(param_id) else {
+            return false;
+        };
+        if text.is_null() {
+            return false;
+        }
+        let text = CStr::from_ptr(text).to_string_lossy();
+        instance.layout.params[position]
+            .parse(&text)
+            .map(|parsed| *value = parsed)
+            .is_some()
+    }
+
+    /// Called instead of process while the plugin does not process, the values are applied by
+    /// the next process call.
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe extern "C" fn params_flush(
+        plugin: *const clap_plugin,
+        in_events: *const clap_input_events,
+        _out_events: *const clap_output_events,
+    ) {
+        let instance = Self::from_raw(plugin);
+        for event in input_events(in_events) {
+            if (*event).space_id == CLAP_CORE_EVENT_SPACE_ID
+                && (*event).type_ == CLAP_EVENT_PARAM_VALUE
+            {
+                let event = &*event.cast::<clap_event_param_value>();
+                if let Some(position) = instance.position(event.param_id) {
+                    let param = &instance.layout.params[position];
+                    instance.set_value(position, event.value.max(param.min).min(param.max));
+                }
+            }
+        }
+    }
+
+    const STATE: clap_plugin_state = clap_plugin_state {
+        save: Some(Self::state_save),
+        load: Some(Self::state_load),
+    };
+
+    /// Writes the header and the id and value of every input parameter, in little endian.
+    unsafe extern "C" fn state_save(
+        plugin: *const clap_plugin,
+        stream: *const clap_ostream,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let mut data = STATE_HEADER.to_vec();
+        for (position, param) in instance.layout.params.iter().enumerate() {
+            if !param.is_output() {
+                data.extend_from_slice(&param.id.to_le_bytes());
+                data.extend_from_slice(&instance.value(position).to_le_bytes());
+            }
+        }
+        write_stream(stream, &data)
+    }
+
+    /// Restores the values of the parameters in the state, ids unknown to the dsp are skipped.
+    unsafe extern "C" fn state_load(
+        plugin: *const clap_plugin,
+        stream: *const clap_istream,
+    ) -> bool {
+        let instance = Self::from_raw(plugin);
+        let Some(data) = read_stream(stream) else {
+            return false;
+        };
+        let Some(values) = data.strip_prefix(&STATE_HEADER[..]) else {
+            return false;
+        };
+        if values.len() % 12 != 0 {
+            return false;
+        }
+        for value in values.chunks_exact(12) {
+            let (id, value) = value.split_at(4);
+            let id = u32::from_le_bytes(<[u8; 4]>::try_from(id).expect("4 bytes"));
+            let value = f64::from_le_bytes(<[u8; 8]>::try_from(value).expect("8 bytes"));
+            if let Some(position) = instance.position(id) {
+                let param = &instance.layout.params[position];
+                if !param.is_output() && value.is_finite() {
+                    instance.set_value(position, value.max(param.min).min(param.max));
+                }
+            }
+        }
+        true
+    }
+
+    const AUDIO_PORTS: clap_plugin_audio_ports = clap_plugin_audio_ports {
+        count: Some(Self::audio_ports_count),
+        get: Some(Self::audio_ports_get),
+    };
+
+    unsafe fn channels(plugin: *const clap_plugin, is_input: bool) -> u32 {
+        let instance = Self::from_raw(plugin);
+        if is_input {
+            instance.num_inputs
+        } else {
+            instance.num_outputs
+        }
+    }
+
+    /// One main port per direction, if the dsp has channels in that direction.
+    unsafe extern "C" fn audio_ports_count(plugin: *const clap_plugin, is_input: bool) -> u32 {
+        u32::from(Self::channels(plugin, is_input) > 0)
+    }
+
+    unsafe extern "C" fn audio_ports_get(
+        plugin: *const clap_plugin,
+        index: u32,
+        is_input: bool,
+        info: *mut clap_audio_port_info,
+    ) -> bool {
+        let channels = Self::channels(plugin, is_input);
+        if index != 0 || channels == 0 {
+            return false;
+        }
+        let info = &mut *info;
+        info.id = 0;
+        write_c_string(&mut info.name, if is_input { "Input" } else { "Output" });
+        // the samples are converted to the type of the dsp
+        info.flags = CLAP_AUDIO_PORT_IS_MAIN | CLAP_AUDIO_PORT_SUPPORTS_64BITS;
+        info.channel_count = channels;
+        info.port_type = match channels {
+            1 => CLAP_PORT_MONO.as_ptr(),
+            2 => CLAP_PORT_STEREO.as_ptr(),
+            _ => ptr::null(),
+        };
+        info.in_place_pair = CLAP_INVALID_ID;
+        true
+    }
+
+    const NOTE_PORTS: clap_plugin_note_ports = clap_plugin_note_ports {
+        count: Some(Self::note_ports_count),
+        get: Some(Self::note_ports_get),
+    };
+
+    /// A note input if the dsp follows the `freq`/`gate`/`gain` convention.
+    unsafe extern "C" fn note_ports_count(plugin: *const clap_plugin, is_input: bool) -> u32 {
+        u32::from(is_input && Self::from_raw(plugin).layout.notes.is_some())
+    }
+
+    unsafe extern "C" fn note_ports_get(
+        plugin: *const clap_plugin,
+        index: u32,
+        is_input: bool,
+        info: *mut clap_note_port_info,
+    ) -> bool {
+        if index != 0 || Self::note_ports_count(plugin, is_input) == 0 {
+            return false;
+        }
+        let info = &mut *info;
+        info.id = 0;
+        info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
+        info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
+        write_c_string(&mut info.name, "Notes");
+        true
+    }
+}
+
+unsafe fn input_events(
+    events: *const clap_input_events,
+) -> impl Iterator<Item = *const clap_event_header> {
+    let (size, get) = match events.as_ref() {
+        Some(clap_input_events {
+            size: Some(size),
+            get: Some(get),
+            ..
+        }) => (size(events), Some(*get)),
+        _ => (0, None),
+    };
+    (0..size)
+        .filter_map(move |index| get.map(|get| get(events, index)))
+        .filter(|event| !event.is_null())
+}
+
+/// The channels of the first port of a direction, in the sample format the host provides.
+#[derive(Clone, Copy)]
+enum HostChannels<'a> {
+    F32(&'a [*mut f32]),
+    F64(&'a [*mut f64]),
+}
+
+impl HostChannels<'_> {
+    unsafe fn new(buffers: *const clap_audio_buffer, count: u32) -> Self {
+        match buffers.as_ref() {
+            Some(buffer) if count > 0 && !buffer.data32.is_null() => Self::F32(
+                slice::from_raw_parts(buffer.data32, buffer.channel_count as usize),
+            ),
+            Some(buffer) if count > 0 && !buffer.data64.is_null() => Self::F64(
+                slice::from_raw_parts(buffer.data64, buffer.channel_count as usize),
+            ),
+            _ => Self::F32(&[]),
+        }
+    }
+}
+
+/// Converts the host samples of `channel` from `start` on into `input`, missing channels are silent.
+unsafe fn read_channel<S: Sample, T: Sample + Default>(
+    host: &[*mut S],
+    channel: usize,
+    start: usize,
+    input: &mut [T],
+) {
+    match host.get(channel).filter(|host| !host.is_null()) {
+        Some(host) => {
+            let host = slice::from_raw_parts(host.add(start), input.len());
+            for (sample, host) in input.iter_mut().zip(host) {
+                *sample = host.convert();
+            }
+        }
+        None => input.fill(T::default()),
+    }
+}
+
+/// Converts `output` into the host samples of `channel` from `start` on.
+unsafe fn write_channel<S: Sample, T: Sample>(
+    host: &[*mut S],
+    channel: usize,
+    start: usize,
+    output: &[T],
+) {
+    if let Some(host) = host.get(channel).filter(|host| !host.is_null()) {
+        let host = slice::from_raw_parts_mut(host.add(start), output.len());
+        for (host, sample) in host.iter_mut().zip(output) {
+            *host = sample.convert();
+        }
+    }
+}
+
+fn write_c_string(buffer: &mut [c_char], value: &str) {
+    let Some(capacity) = buffer.len().checked_sub(1) else {
+        return;
+    };
+    let mut len = value.len().min(capacity);
+    while !value.is_char_boundary(len) {
+        len -= 1;
+    }
+    for (target, byte) in buffer.iter_mut().zip(&value.as_bytes()[..len]) {
+        *target = *byte as c_char;
+    }
+    buffer[len] = 0;
+}
+
+unsafe fn write_stream(stream: *const clap_ostream, mut data: &[u8]) -> bool {
+    let Some(write) = stream.as_ref().and_then(|stream| stream.write) else {
+        return false;
+    };
+    while !data.is_empty() {
+        let written = write(stream, data.as_ptr().cast(), data.len() as u64);
+        if written <= 0 {
+            return false;
+        }
+        data = &data[(written as usize).min(data.len())..];
+    }
+    true
+}
+
+unsafe fn read_stream(stream: *const clap_istream) -> Option<Vec<u8>> {
+    let read = stream.as_ref().and_then(|stream| stream.read)?;
+    let mut data = Vec::new();
+    let mut buffer = [0_u8; 1024];
+    loop {
+        let count = read(stream, buffer.as_mut_ptr().cast(), buffer.len() as u64);
+        match count {
+            0 => return Some(data),
+            count if count < 0 => return None,
+            count => data.extend_from_slice(&buffer[..(count as usize).min(buffer.len())]),
+        }
+    }
+}
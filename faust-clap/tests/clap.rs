@@ -0,0 +1,311 @@
+#![cfg(feature = "host")]
+
+use faust_clap::{
+    host::{AudioPort, HeadlessHost, HostEvent},
+    params::{params, stable_id, NoteParams, ParamKind},
+    sys::{
+        entry::clap_plugin_entry,
+        ext::{
+            audio_ports::CLAP_AUDIO_PORT_SUPPORTS_64BITS,
+            params::{
+                CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_HIDDEN, CLAP_PARAM_IS_READONLY,
+                CLAP_PARAM_IS_STEPPED,
+            },
+        },
+    },
+    ClapDsp, Descriptor, PluginInfo,
+};
+use faust_types::*;
+
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+const FREQ: ParamIndex = ParamIndex(0);
+const GATE: ParamIndex = ParamIndex(1);
+const GAIN: ParamIndex = ParamIndex(2);
+const VOLUME: ParamIndex = ParamIndex(3);
+const LEVEL: ParamIndex = ParamIndex(4);
+
+/// The first output is the input scaled by `volume` plus `gain` while `gate` is set, the second
+/// output is `freq`. `level` shows the last sample of the first output.
+struct Synth;
+
+impl Spec for Synth {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 2;
+    const META: &'static [(&'static str, &'static str)] = &[("name", "synth")];
+    const UI: &'static [Ui] = &[
+        Ui::VBox("synth"),
+        Ui::HSlider("freq", 440.0, 20.0, 20000.0, 0.01),
+        Ui::Button("gate"),
+        Ui::HSlider("gain", 0.5, 0.0, 1.0, 0.01),
+        Ui::HBox("fx"),
+        Ui::Declare("unit", "dB"),
+        Ui::HSlider("volume", 0.0, -60.0, 12.0, 0.1),
+        Ui::Close,
+        Ui::VBargraph("level", -10.0, 10.0),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let param = |param: ParamIndex| dsp.params[param.0 as usize];
+        let scale = 10_f32.powf(param(VOLUME) / 20.0);
+        let offset = param(GATE) * param(GAIN);
+        let freq = param(FREQ);
+        let (first, second) = outputs.split_at_mut(1);
+        for (output, input) in first[0][..count].iter_mut().zip(inputs[0]) {
+            *output = input * scale + offset;
+            dsp.params[LEVEL.0 as usize] = *output;
+        }
+        second[0][..count].fill(freq);
+    }
+}
+
+impl ClapDsp for TestDsp<Synth> {
+    fn descriptor() -> &'static Descriptor {
+        static DESCRIPTOR: Descriptor = Descriptor::new(PluginInfo {
+            id: "org.example.synth",
+            name: "Synth",
+            vendor: "example",
+            url: "https://example.org",
+            version: "1.0.0",
+            description: "test synth",
+            features: &["instrument", "synthesizer", "stereo"],
+        });
+        &DESCRIPTOR
+    }
+}
+
+static ENTRY: clap_plugin_entry = faust_clap::entry::<TestDsp<Synth>>();
+
+fn host() -> HeadlessHost {
+    HeadlessHost::from_entry(&ENTRY, Some("org.example.synth")).unwrap()
+}
+
+fn param_id(host: &HeadlessHost, name: &str) -> u32 {
+    host.params()
+        .into_iter()
+        .find(|param| param.name == name)
+        .unwrap()
+        .id
+}
+
+fn assert_close(actual: &[f32], expected: &[f32]) {
+    assert_eq!(actual.len(), expected.len());
+    for (i, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "frame {}: {} != {}",
+            i,
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn params_from_ui() {
+    let params = params::<TestDsp<Synth>>();
+    let addresses = params
+        .iter()
+        .map(|param| param.address.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        addresses,
+        [
+            "/synth/freq",
+            "/synth/gate",
+            "/synth/gain",
+            "/synth/fx/volume",
+            "/synth/level"
+        ]
+    );
+    for param in &params {
+        assert_eq!(param.id, stable_id(&param.address));
+    }
+    assert_eq!(params[1].kind, ParamKind::Button);
+    assert_eq!(params[3].meta("unit"), Some("dB"));
+    assert_eq!(params[3].module(), "fx");
+    assert!(params[4].is_output());
+    assert_eq!(
+        NoteParams::find(&params),
+        Some(NoteParams {
+            freq: Some(0),
+            gate: 1,
+            gain: Some(2)
+        })
+    );
+    assert_ne!(stable_id("/a"), stable_id("/b"));
+    assert!(HeadlessHost::from_entry(&ENTRY, Some("org.example.other")).is_err());
+}
+
+#[test]
+fn descriptor_params_and_ports() {
+    let host = host();
+    assert_eq!(host.plugin_id(), "org.example.synth");
+    assert_eq!(host.plugin_name(), "Synth");
+    assert_eq!(host.features(), ["instrument", "synthesizer", "stereo"]);
+
+    let params = host.params();
+    assert_eq!(params.len(), 5);
+    let volume = &params[3];
+    assert_eq!(volume.id, stable_id("/synth/fx/volume"));
+    assert_eq!(volume.module, "fx");
+    assert_eq!((volume.min, volume.max, volume.default), (-60.0, 12.0, 0.0));
+    assert_eq!(volume.flags, CLAP_PARAM_IS_AUTOMATABLE);
+    assert_eq!(
+        params[1].flags,
+        CLAP_PARAM_IS_AUTOMATABLE | CLAP_PARAM_IS_STEPPED | CLAP_PARAM_IS_HIDDEN
+    );
+    assert_eq!(params[4].flags, CLAP_PARAM_IS_READONLY);
+
+    assert_eq!(
+        host.audio_ports(true)
+            .iter()
+            .map(|port| port.channels)
+            .collect::<Vec<_>>(),
+        [1]
+    );
+    assert!(matches!(
+        host.audio_ports(false)[..],
+        [AudioPort { channels: 2, .. }]
+    ));
+    assert_eq!(host.note_ports(true).len(), 1);
+    assert!(host.note_ports(false).is_empty());
+}
+
+#[test]
+fn param_events_are_sample_accurate() {
+    let mut host = host();
+    let volume = param_id(&host, "volume");
+    let level = param_id(&host, "level");
+    assert!(host.process(&[&[1.0; 8]], 8, &[]).is_err());
+    host.activate(48000.0, 4).unwrap();
+    assert!(host.process(&[&[1.0; 8]], 8, &[]).is_err());
+    host.deactivate();
+    // Blocks are split at events and at the maximal frame count of the dsp buffers.
+    host.activate(48000.0, 8).unwrap();
+
+    let events = [HostEvent::Param {
+        time: 3,
+        id: volume,
+        value: 20.0 * 2_f64.log10(),
+    }];
+    let outputs = host.process(&[&[1.0; 8]], 8, &events).unwrap();
+    assert_close(&outputs[0], &[1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 2.0]);
+    assert_close(&outputs[1], &[440.0; 8]);
+    assert!((host.param_value(level).unwrap() - 2.0).abs() < 1e-3);
+
+    // Values are clamped to the range of the parameter.
+    let events = [HostEvent::Param {
+        time: 0,
+        id: volume,
+        value: 100.0,
+    }];
+    host.process(&[&[1.0; 8]], 8, &events).unwrap();
+    assert_eq!(host.param_value(volume), Some(12.0));
+}
+
+#[test]
+fn f64_buffers() {
+    let mut host = host();
+    assert!(host
+        .audio_ports(false)
+        .iter()
+        .all(|port| port.flags & CLAP_AUDIO_PORT_SUPPORTS_64BITS != 0));
+    host.activate(48000.0, 8).unwrap();
+    let events = [HostEvent::Param {
+        time: 2,
+        id: param_id(&host, "volume"),
+        value: 20.0 * 2_f64.log10(),
+    }];
+    let outputs = host.process_f64(&[&[0.5; 4]], 4, &events).unwrap();
+    let outputs = outputs
+        .iter()
+        .map(|output| {
+            output
+                .iter()
+                .map(|sample| *sample as f32)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_close(&outputs[0], &[0.5, 0.5, 1.0, 1.0]);
+    assert_close(&outputs[1], &[440.0; 4]);
+}
+
+#[test]
+fn notes_set_freq_gate_and_gain() {
+    let mut host = host();
+    host.activate(48000.0, 8).unwrap();
+    let events = [
+        HostEvent::NoteOn {
+            time: 2,
+            key: 81,
+            velocity: 0.25,
+        },
+        HostEvent::NoteOff { time: 4, key: 60 },
+        HostEvent::NoteOff { time: 6, key: 81 },
+    ];
+    let outputs = host.process(&[], 8, &events).unwrap();
+    assert_close(&outputs[0], &[0.0, 0.0, 0.25, 0.25, 0.25, 0.25, 0.0, 0.0]);
+    assert_close(&outputs[1][..2], &[440.0; 2]);
+    assert_close(&outputs[1][2..], &[880.0; 6]);
+
+    let events = [
+        HostEvent::Midi {
+            time: 0,
+            data: [0x90, 57, 127],
+        },
+        HostEvent::Midi {
+            time: 4,
+            data: [0x90, 57, 0],
+        },
+    ];
+    let outputs = host.process(&[], 8, &events).unwrap();
+    assert_close(&outputs[0], &[1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_close(&outputs[1], &[220.0; 8]);
+}
+
+#[test]
+fn state_roundtrip() {
+    let mut host = host();
+    let volume = param_id(&host, "volume");
+    host.flush(&[HostEvent::Param {
+        time: 0,
+        id: volume,
+        value: -12.0,
+    }])
+    .unwrap();
+    let state = host.save_state().unwrap();
+
+    let mut other = self::host();
+    assert_eq!(other.param_value(volume), Some(0.0));
+    other.load_state(&state).unwrap();
+    assert_eq!(other.param_value(volume), Some(-12.0));
+    assert!(other.load_state(b"invalid").is_err());
+
+    other.activate(48000.0, 8).unwrap();
+    let outputs = other.process(&[&[1.0; 8]], 8, &[]).unwrap();
+    assert_close(&outputs[0], &[10_f32.powf(-0.6); 8]);
+}
+
+#[test]
+fn values_as_text() {
+    let host = host();
+    let volume = param_id(&host, "volume");
+    let gate = param_id(&host, "gate");
+    assert_eq!(host.value_to_text(volume, -6.0).as_deref(), Some("-6.0 dB"));
+    assert_eq!(host.text_to_value(volume, "3 dB"), Some(3.0));
+    assert_eq!(host.text_to_value(volume, " -100"), Some(-60.0));
+    assert_eq!(host.text_to_value(volume, "loud"), None);
+    assert_eq!(host.value_to_text(gate, 1.0).as_deref(), Some("on"));
+    assert_eq!(host.value_to_text(0, 1.0), None);
+}
@@ -0,0 +1,102 @@
+//! JACK as an [`AudioBackend`].
+
+use crate::{midi::MidiMap, AutoConnect, JackBuilder, JackClient};
+use faust_state::{
+    backend::{AudioBackend, BackendConfig},
+    DspHandle, StateHandle,
+};
+use faust_types::FaustDsp;
+use std::collections::HashMap;
+
+/// Runs the dsp as a JACK client and connects it to the physical ports selected by the channel
+/// maps of the [`BackendConfig`].
+///
+/// The sample rate and block size are those of the server while the client runs.
+pub struct JackBackend<T> {
+    config: BackendConfig,
+    name: Option<String>,
+    meta: HashMap<String, String>,
+    midi_map: MidiMap,
+    client: Option<JackClient<T>>,
+}
+
+impl<T> JackBackend<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    #[must_use]
+    pub fn new(config: BackendConfig) -> Self {
+        Self {
+            config,
+            name: None,
+            meta: HashMap::new(),
+            midi_map: MidiMap::default(),
+            client: None,
+        }
+    }
+
+    /// Names the ports and enables MIDI input like [`JackBuilder::new`].
+    #[must_use]
+    pub fn with_state(mut self, state: &StateHandle) -> Self {
+        self.meta.clone_from(state.meta());
+        self.midi_map = MidiMap::from_params(state.params());
+        self
+    }
+
+    /// Uses `name` instead of the name of the dsp for the client.
+    #[must_use]
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// The running client.
+    #[must_use]
+    pub fn client(&self) -> Option<&JackClient<T>> {
+        self.client.as_ref()
+    }
+}
+
+impl<T> AudioBackend<T> for JackBackend<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    fn start(&mut self, dsp: DspHandle<T>) -> Result<(), String> {
+        if self.client.is_some() {
+            return Err("backend is already running".into());
+        }
+        let mut builder = JackBuilder::from_dsp(dsp)
+            .with_meta(&self.meta, self.midi_map.clone())
+            .connect_inputs(AutoConnect::Mapped(self.config.inputs.clone()))
+            .connect_outputs(AutoConnect::Mapped(self.config.outputs.clone()));
+        if let Some(name) = &self.name {
+            builder = builder.name(name);
+        }
+        let client = builder.activate().map_err(|err| err.to_string())?;
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<DspHandle<T>, String> {
+        let client = self.client.take().ok_or("backend is not running")?;
+        client.deactivate().map_err(|err| err.to_string())
+    }
+
+    fn is_running(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.client
+            .as_ref()
+            .map_or(self.config.sample_rate, JackClient::sample_rate)
+    }
+
+    fn block_size(&self) -> usize {
+        self.client
+            .as_ref()
+            .map_or(self.config.block_size, |client| {
+                client.buffer_size() as usize
+            })
+    }
+}
@@ -0,0 +1,507 @@
+#![warn(
+    clippy::all,
+    clippy::pedantic,
+    clippy::nursery,
+    unused_crate_dependencies,
+    clippy::unwrap_used
+)]
+#![allow(clippy::missing_errors_doc)]
+#![allow(clippy::missing_const_for_fn)]
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_possible_wrap)]
+
+//! Run a [`DspHandle`] as a JACK client.
+//!
+//! ```no_run
+//! # fn run<D: faust_types::FaustDsp<T = f32> + Send + 'static>() -> Result<(), jack::Error> {
+//! use faust_jack::{AutoConnect, JackBuilder};
+//! use faust_state::DspHandle;
+//!
+//! let (dsp, mut state) = DspHandle::<D>::new();
+//! let client = JackBuilder::new(dsp, &state)
+//!     .midi(true)
+//!     .connect_outputs(AutoConnect::Physical)
+//!     .on_xrun(|| eprintln!("xrun"))
+//!     .activate()?;
+//! // Change parameters through `state` while the client runs.
+//! state.update();
+//! let _dsp = client.deactivate()?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod backend;
+pub mod midi;
+
+use faust_state::{backend::ChannelMap, DspHandle, StateHandle};
+use faust_types::{AudioBuffers, FaustDsp};
+use jack::{
+    AsyncClient, AudioIn, AudioOut, Client, ClientOptions, ClientStatus, Control, Frames, MidiIn,
+    NotificationHandler, Port, PortFlags, PortSpec, ProcessHandler, ProcessScope,
+};
+use midi::MidiMap;
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Which ports of other clients the ports of the dsp are connected to after activation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum AutoConnect {
+    /// Leave the ports unconnected.
+    #[default]
+    None,
+    /// Connect to the physical ports of the server, e.g. `system:capture_1` to the first input.
+    /// The MIDI input is connected to every physical MIDI source.
+    Physical,
+    /// Connect to the physical ports selected by the map, e.g. `Channels(vec![None, Some(0)])`
+    /// connects the second channel to the first physical port.
+    Mapped(ChannelMap),
+    /// Connect to the ports with these full names, in the order of the channels.
+    Ports(Vec<String>),
+}
+
+type XrunCallback = Box<dyn Fn() + Send + Sync>;
+type ShutdownCallback = Box<dyn Fn(ClientStatus, &str) + Send + Sync>;
+
+/// Options of a JACK client for a dsp, consumed by [`JackBuilder::activate`].
+pub struct JackBuilder<T> {
+    dsp: DspHandle<T>,
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    midi: bool,
+    midi_map: MidiMap,
+    connect_inputs: AutoConnect,
+    connect_outputs: AutoConnect,
+    connect_midi: AutoConnect,
+    start_server: bool,
+    on_xrun: Option<XrunCallback>,
+    on_shutdown: Option<ShutdownCallback>,
+}
+
+impl<T> JackBuilder<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    /// The client is named after the dsp and its ports after [`port_names`] of the metadata in
+    /// `state`.
+    #[must_use]
+    pub fn new(dsp: DspHandle<T>, state: &StateHandle) -> Self {
+        Self::from_dsp(dsp).with_meta(state.meta(), MidiMap::from_params(state.params()))
+    }
+
+    /// Without the [`StateHandle`] the ports are numbered and there is no MIDI input.
+    #[must_use]
+    pub fn from_dsp(dsp: DspHandle<T>) -> Self {
+        let meta = HashMap::new();
+        Self {
+            name: dsp.name().to_string(),
+            inputs: port_names(&meta, "input", "in", dsp.num_inputs()),
+            outputs: port_names(&meta, "output", "out", dsp.num_outputs()),
+            dsp,
+            midi: false,
+            midi_map: MidiMap::default(),
+            connect_inputs: AutoConnect::None,
+            connect_outputs: AutoConnect::None,
+            connect_midi: AutoConnect::None,
+            start_server: false,
+            on_xrun: None,
+            on_shutdown: None,
+        }
+    }
+
+    pub(crate) fn with_meta(mut self, meta: &HashMap<String, String>, midi_map: MidiMap) -> Self {
+        self.inputs = port_names(meta, "input", "in", self.dsp.num_inputs());
+        self.outputs = port_names(meta, "output", "out", self.dsp.num_outputs());
+        self.midi = !midi_map.is_empty() || self.dsp.plays_notes();
+        self.midi_map = midi_map;
+        self
+    }
+
+    #[must_use]
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Whether to register a `midi_in` port that sets the parameters with `[midi:...]` metadata
+    /// and plays the notes of an instrument, see [`midi`]. Enabled by [`JackBuilder::new`] if the
+    /// dsp declares any or plays notes.
+    #[must_use]
+    pub fn midi(mut self, enabled: bool) -> Self {
+        self.midi = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn connect_inputs(mut self, connect: AutoConnect) -> Self {
+        self.connect_inputs = connect;
+        self
+    }
+
+    #[must_use]
+    pub fn connect_outputs(mut self, connect: AutoConnect) -> Self {
+        self.connect_outputs = connect;
+        self
+    }
+
+    #[must_use]
+    pub fn connect_midi(mut self, connect: AutoConnect) -> Self {
+        self.connect_midi = connect;
+        self
+    }
+
+    /// Whether to start a JACK server if none is running. Disabled by default.
+    #[must_use]
+    pub fn start_server(mut self, start: bool) -> Self {
+        self.start_server = start;
+        self
+    }
+
+    /// Called from the notification thread of JACK after every xrun.
+    #[must_use]
+    pub fn on_xrun(mut self, callback: impl Fn() + Send + Sync + 'static) -> Self {
+        self.on_xrun = Some(Box::new(callback));
+        self
+    }
+
+    /// Called when the server shuts the client down. This runs like a signal handler, so the
+    /// callback must only use async-signal-safe functions, e.g. store to an atomic.
+    #[must_use]
+    pub fn on_shutdown(
+        mut self,
+        callback: impl Fn(ClientStatus, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_shutdown = Some(Box::new(callback));
+        self
+    }
+
+    /// Opens the client, initializes the dsp with the sample rate of the server, starts
+    /// processing and makes the connections. Returns without blocking.
+    pub fn activate(self) -> Result<JackClient<T>, jack::Error> {
+        let Self {
+            mut dsp,
+            name,
+            inputs,
+            outputs,
+            midi,
+            midi_map,
+            connect_inputs,
+            connect_outputs,
+            connect_midi,
+            start_server,
+            on_xrun,
+            on_shutdown,
+        } = self;
+        let options = if start_server {
+            ClientOptions::empty()
+        } else {
+            ClientOptions::NO_START_SERVER
+        };
+        let (client, _status) = Client::new(&name, options)?;
+
+        let in_ports = inputs
+            .iter()
+            .map(|name| client.register_port(name, AudioIn::default()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let out_ports = outputs
+            .iter()
+            .map(|name| client.register_port(name, AudioOut::default()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let midi_port = if midi {
+            Some((
+                client.register_port("midi_in", MidiIn::default())?,
+                midi_map,
+            ))
+        } else {
+            None
+        };
+        let ports = Ports {
+            inputs: in_ports.iter().map(Port::name).collect::<Result<_, _>>()?,
+            outputs: out_ports.iter().map(Port::name).collect::<Result<_, _>>()?,
+            midi: midi_port
+                .as_ref()
+                .map(|(port, _)| port.name())
+                .transpose()?,
+        };
+
+        dsp.init(client.sample_rate() as i32);
+        let buffers = buffers(&dsp, client.buffer_size() as usize);
+        let processor = Processor {
+            dsp,
+            buffers,
+            inputs: in_ports,
+            outputs: out_ports,
+            midi: midi_port,
+        };
+        let status = Arc::new(Status::default());
+        let notifications = Notifications {
+            status: status.clone(),
+            on_xrun,
+            on_shutdown,
+        };
+        let client = AsyncClient::new(client, notifications, processor)?;
+        let jack_client = JackClient {
+            client,
+            ports,
+            status,
+        };
+
+        jack_client.auto_connect(
+            &connect_inputs,
+            &jack_client.ports.inputs,
+            true,
+            AudioIn::default().jack_port_type(),
+        )?;
+        jack_client.auto_connect(
+            &connect_outputs,
+            &jack_client.ports.outputs,
+            false,
+            AudioOut::default().jack_port_type(),
+        )?;
+        if let Some(midi) = &jack_client.ports.midi {
+            jack_client.auto_connect(
+                &connect_midi,
+                std::slice::from_ref(midi),
+                true,
+                MidiIn::default().jack_port_type(),
+            )?;
+        }
+        Ok(jack_client)
+    }
+}
+
+/// Port names from the metadata of a dsp, e.g. `declare input0 "left";`, numbered like `in0`,
+/// `in1` where nothing is declared.
+#[must_use]
+pub fn port_names<S: BuildHasher>(
+    meta: &HashMap<String, String, S>,
+    key: &str,
+    default: &str,
+    count: usize,
+) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            meta.get(&format!("{key}{i}"))
+                .cloned()
+                .unwrap_or_else(|| format!("{default}{i}"))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+struct Ports {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    midi: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Status {
+    xruns: AtomicUsize,
+    shut_down: AtomicBool,
+}
+
+/// A running JACK client. Dropping it closes the client, [`JackClient::deactivate`] returns the
+/// dsp.
+pub struct JackClient<T> {
+    client: AsyncClient<Notifications, Processor<T>>,
+    ports: Ports,
+    status: Arc<Status>,
+}
+
+impl<T> JackClient<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    #[must_use]
+    pub fn client(&self) -> &Client {
+        self.client.as_client()
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.client().name()
+    }
+
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.client().sample_rate()
+    }
+
+    #[must_use]
+    pub fn buffer_size(&self) -> Frames {
+        self.client().buffer_size()
+    }
+
+    /// Full names of the audio input ports, e.g. `volume:in0`.
+    #[must_use]
+    pub fn input_ports(&self) -> &[String] {
+        &self.ports.inputs
+    }
+
+    #[must_use]
+    pub fn output_ports(&self) -> &[String] {
+        &self.ports.outputs
+    }
+
+    #[must_use]
+    pub fn midi_port(&self) -> Option<&str> {
+        self.ports.midi.as_deref()
+    }
+
+    /// Number of xruns since activation.
+    #[must_use]
+    pub fn xruns(&self) -> usize {
+        self.status.xruns.load(Ordering::Relaxed)
+    }
+
+    /// Whether the server has shut the client down.
+    #[must_use]
+    pub fn is_shut_down(&self) -> bool {
+        self.status.shut_down.load(Ordering::Relaxed)
+    }
+
+    /// Connects two ports by their full names.
+    pub fn connect(&self, source: &str, destination: &str) -> Result<(), jack::Error> {
+        self.client().connect_ports_by_name(source, destination)
+    }
+
+    /// Stops processing, closes the client and returns the dsp.
+    pub fn deactivate(self) -> Result<DspHandle<T>, jack::Error> {
+        let (_client, _notifications, processor) = self.client.deactivate()?;
+        Ok(processor.dsp)
+    }
+
+    fn auto_connect(
+        &self,
+        connect: &AutoConnect,
+        ports: &[String],
+        input: bool,
+        port_type: &str,
+    ) -> Result<(), jack::Error> {
+        // Inputs of the dsp are connected to physical outputs and vice versa.
+        let physical = || {
+            let direction = if input {
+                PortFlags::IS_OUTPUT
+            } else {
+                PortFlags::IS_INPUT
+            };
+            self.client()
+                .ports(None, Some(port_type), PortFlags::IS_PHYSICAL | direction)
+        };
+        let is_midi = port_type == MidiIn::default().jack_port_type();
+        let pairs: Vec<(&String, String)> = match connect {
+            AutoConnect::None => Vec::new(),
+            AutoConnect::Physical if is_midi => ports
+                .iter()
+                .flat_map(|port| physical().into_iter().map(move |other| (port, other)))
+                .collect(),
+            AutoConnect::Physical => ports.iter().zip(physical()).collect(),
+            AutoConnect::Mapped(map) => {
+                let physical = physical();
+                ports
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(channel, port)| {
+                        let other = physical.get(map.device_channel(channel)?)?;
+                        Some((port, other.clone()))
+                    })
+                    .collect()
+            }
+            AutoConnect::Ports(others) => ports.iter().zip(others.iter().cloned()).collect(),
+        };
+        for (port, other) in &pairs {
+            if input {
+                self.connect(other, port)?;
+            } else {
+                self.connect(port, other)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Notifications {
+    status: Arc<Status>,
+    on_xrun: Option<XrunCallback>,
+    on_shutdown: Option<ShutdownCallback>,
+}
+
+impl NotificationHandler for Notifications {
+    unsafe fn shutdown(&mut self, status: ClientStatus, reason: &str) {
+        self.status.shut_down.store(true, Ordering::Relaxed);
+        if let Some(callback) = &self.on_shutdown {
+            callback(status, reason);
+        }
+    }
+
+    fn xrun(&mut self, _: &Client) -> Control {
+        self.status.xruns.fetch_add(1, Ordering::Relaxed);
+        if let Some(callback) = &self.on_xrun {
+            callback();
+        }
+        Control::Continue
+    }
+}
+
+fn buffers<T>(dsp: &DspHandle<T>, frames: usize) -> AudioBuffers<f32>
+where
+    T: FaustDsp<T = f32> + 'static,
+{
+    AudioBuffers::new(dsp.num_inputs(), dsp.num_outputs(), frames)
+}
+
+struct Processor<T> {
+    dsp: DspHandle<T>,
+    buffers: AudioBuffers<f32>,
+    inputs: Vec<Port<AudioIn>>,
+    outputs: Vec<Port<AudioOut>>,
+    midi: Option<(Port<MidiIn>, MidiMap)>,
+}
+
+impl<T> ProcessHandler for Processor<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    fn process(&mut self, _: &Client, ps: &ProcessScope) -> Control {
+        let Self {
+            dsp,
+            buffers,
+            inputs,
+            outputs,
+            midi,
+        } = self;
+        if let Some((port, map)) = midi {
+            for event in port.iter(ps) {
+                map.handle(event.bytes, |param, value| dsp.set_param(param, value));
+                if let Some(note) = midi::note_event(event.bytes) {
+                    dsp.handle_note(note);
+                }
+            }
+        }
+        let len = (ps.n_frames() as usize).min(buffers.max_frames());
+        for (channel, port) in inputs.iter().enumerate() {
+            buffers.input_mut(channel)[..len].copy_from_slice(&port.as_slice(ps)[..len]);
+        }
+        buffers.process(len, |count, inputs, outputs| {
+            dsp.update_and_compute(count, inputs, outputs);
+        });
+        for (channel, port) in outputs.iter_mut().enumerate() {
+            port.as_mut_slice(ps)[..len].copy_from_slice(&buffers.output(channel)[..len]);
+        }
+        Control::Continue
+    }
+
+    fn buffer_size(&mut self, _: &Client, size: Frames) -> Control {
+        if size as usize > self.buffers.max_frames() {
+            self.buffers = buffers(&self.dsp, size as usize);
+        }
+        Control::Continue
+    }
+}
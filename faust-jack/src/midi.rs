@@ -0,0 +1,161 @@
+//! Routing of MIDI messages to parameters declared with `[midi:...]` metadata.
+//!
+//! Supported declarations, with an optional MIDI channel from 1 to 16 after the number:
+//!
+//! * `[midi:ctrl n]`: value of control change `n`
+//! * `[midi:keyon n]`: velocity of note on messages for note `n`
+//! * `[midi:keyoff n]`: velocity of note off messages for note `n`
+//! * `[midi:pitchwheel]`: position of the pitch wheel
+//!
+//! Instruments created with [`faust_state::DspHandle::from_instrument`] also play the notes, see
+//! [`note_event`].
+
+use faust_state::{poly::NoteEvent, Node, WidgetType};
+use faust_types::ParamIndex;
+use std::collections::HashMap;
+
+/// The MIDI message a parameter follows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MidiControl {
+    Ctrl(u8),
+    KeyOn(u8),
+    KeyOff(u8),
+    PitchWheel,
+}
+
+impl MidiControl {
+    /// Parses the value of a `midi` metadata declaration, e.g. `ctrl 7` or `keyon 60 2`.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<(Self, Option<u8>)> {
+        let mut words = value.split_whitespace();
+        let control = match words.next()? {
+            "pitchwheel" => Self::PitchWheel,
+            kind => {
+                let n = words.next()?.parse::<u8>().ok().filter(|n| *n < 128)?;
+                match kind {
+                    "ctrl" => Self::Ctrl(n),
+                    "keyon" => Self::KeyOn(n),
+                    "keyoff" => Self::KeyOff(n),
+                    _ => return None,
+                }
+            }
+        };
+        let channel = match words.next() {
+            Some(word) => Some(word.parse::<u8>().ok().filter(|c| (1..=16).contains(c))?),
+            None => None,
+        };
+        Some((control, channel))
+    }
+}
+
+/// A parameter that follows a MIDI message.
+#[derive(Clone, Debug)]
+pub struct MidiBinding {
+    pub param: ParamIndex,
+    pub control: MidiControl,
+    /// Channel from 1 to 16, or any channel.
+    pub channel: Option<u8>,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl MidiBinding {
+    /// Scales a MIDI value between `0` and `max_value` to the range of the parameter.
+    fn scale(&self, value: u16, max_value: u16) -> f32 {
+        self.min + (self.max - self.min) * f32::from(value) / f32::from(max_value)
+    }
+}
+
+/// The MIDI bindings of all parameters of a dsp.
+#[derive(Clone, Debug, Default)]
+pub struct MidiMap {
+    bindings: Vec<MidiBinding>,
+}
+
+impl MidiMap {
+    /// Collects the bindings declared for the input widgets in `params`, as returned by
+    /// [`faust_state::StateHandle::params`]. Declarations that cannot be parsed are ignored.
+    #[must_use]
+    pub fn from_params(params: &HashMap<i32, Node>) -> Self {
+        let mut bindings = Vec::new();
+        for (idx, node) in params {
+            let (min, max) = match node.widget_type() {
+                WidgetType::Button | WidgetType::Toggle => (0.0, 1.0),
+                WidgetType::VerticalSlider(input)
+                | WidgetType::HorizontalSlider(input)
+                | WidgetType::NumEntry(input) => (*input.range.start(), *input.range.end()),
+                _ => continue,
+            };
+            for [key, value] in node.metadata() {
+                if key != "midi" {
+                    continue;
+                }
+                if let Some((control, channel)) = MidiControl::parse(value) {
+                    bindings.push(MidiBinding {
+                        param: ParamIndex(*idx),
+                        control,
+                        channel,
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+        bindings.sort_by_key(|binding| binding.param.0);
+        Self { bindings }
+    }
+
+    #[must_use]
+    pub fn bindings(&self) -> &[MidiBinding] {
+        &self.bindings
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Calls `set_param` for every parameter bound to the raw MIDI `message`. Does not allocate.
+    pub fn handle(&self, message: &[u8], mut set_param: impl FnMut(ParamIndex, f32)) {
+        let [status, data1, data2, ..] = *message else {
+            return;
+        };
+        let channel = (status & 0x0f) + 1;
+        let (kind, number, value, max_value) = match status & 0xf0 {
+            // Note on with velocity 0 is a note off.
+            0x90 if data2 == 0 => (0x80, data1, 0, 127),
+            0x80 | 0x90 | 0xb0 => (status & 0xf0, data1, u16::from(data2), 127),
+            0xe0 => (0xe0, 0, u16::from(data2) << 7 | u16::from(data1), 16383),
+            _ => return,
+        };
+        for binding in &self.bindings {
+            let matches = match binding.control {
+                MidiControl::Ctrl(n) => kind == 0xb0 && n == number,
+                MidiControl::KeyOn(n) => kind == 0x90 && n == number,
+                MidiControl::KeyOff(n) => kind == 0x80 && n == number,
+                MidiControl::PitchWheel => kind == 0xe0,
+            };
+            if matches && binding.channel.is_none_or(|c| c == channel) {
+                set_param(binding.param, binding.scale(value, max_value));
+            }
+        }
+    }
+}
+
+/// The note of a raw MIDI note on, note off or all notes off message on any channel.
+#[must_use]
+pub fn note_event(message: &[u8]) -> Option<NoteEvent> {
+    let [status, data1, data2, ..] = *message else {
+        return None;
+    };
+    match status & 0xf0 {
+        0x90 => Some(NoteEvent::On {
+            note: data1,
+            velocity: data2,
+        }),
+        0x80 => Some(NoteEvent::Off { note: data1 }),
+        // Control change 123
+        0xb0 if data1 == 123 => Some(NoteEvent::AllOff),
+        _ => None,
+    }
+}
@@ -0,0 +1,232 @@
+//! The tests marked with `#[ignore]` need a running JACK server, e.g. `jackd -d dummy`, and run
+//! with `cargo test -p faust-jack -- --ignored --test-threads 1`.
+
+use faust_jack::{
+    backend::JackBackend,
+    midi::{note_event, MidiControl, MidiMap},
+    port_names, AutoConnect, JackBuilder,
+};
+use faust_state::{
+    backend::{AudioBackend, BackendConfig, ChannelMap},
+    poly::NoteEvent,
+    DspHandle,
+};
+use faust_types::*;
+use std::{collections::HashMap, thread, time::Duration};
+
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+const VOLUME: ParamIndex = ParamIndex(0);
+const CYCLES: ParamIndex = ParamIndex(1);
+
+/// Multiplies its inputs with `volume` and counts its compute calls in `cycles`.
+struct Gain;
+
+impl Spec for Gain {
+    const INPUTS: usize = 2;
+    const OUTPUTS: usize = 2;
+    const META: &'static [(&'static str, &'static str)] = &[
+        ("name", "gain"),
+        ("input0", "left_in"),
+        ("output1", "right_out"),
+    ];
+    const UI: &'static [Ui] = &[
+        Ui::VBox("gain"),
+        Ui::Declare("midi", "ctrl 7 2"),
+        Ui::Declare("midi", "pitchwheel"),
+        Ui::HSlider("volume", 1.0, -1.0, 1.0, 0.01),
+        Ui::Declare("midi", "ctrl 8"),
+        Ui::VBargraph("cycles", 0.0, 1e9),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let volume = dsp.params[VOLUME.0 as usize];
+        for (output, input) in outputs.iter_mut().zip(inputs) {
+            for (output, input) in output[..count].iter_mut().zip(*input) {
+                *output = input * volume;
+            }
+        }
+        dsp.params[CYCLES.0 as usize] += 1.0;
+    }
+}
+
+#[test]
+fn parse_midi_metadata() {
+    assert_eq!(
+        MidiControl::parse("ctrl 7"),
+        Some((MidiControl::Ctrl(7), None))
+    );
+    assert_eq!(
+        MidiControl::parse(" keyon 60  16 "),
+        Some((MidiControl::KeyOn(60), Some(16)))
+    );
+    assert_eq!(
+        MidiControl::parse("pitchwheel"),
+        Some((MidiControl::PitchWheel, None))
+    );
+    for invalid in [
+        "ctrl",
+        "ctrl 128",
+        "keyoff 1 0",
+        "keyoff 1 17",
+        "chan 1",
+        "",
+    ] {
+        assert_eq!(MidiControl::parse(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn midi_messages_set_parameters() {
+    let (_dsp, state) = DspHandle::<TestDsp<Gain>>::new();
+    let map = MidiMap::from_params(state.params());
+    // The bargraph cannot be set.
+    assert_eq!(map.bindings().len(), 2);
+    let handle = |message: &[u8]| {
+        let mut values = Vec::new();
+        map.handle(message, |param, value| values.push((param.0, value)));
+        values
+    };
+    // Control change 7 on channel 2
+    assert_eq!(handle(&[0xb1, 7, 127]), [(VOLUME.0, 1.0)]);
+    assert_eq!(handle(&[0xb1, 7, 0]), [(VOLUME.0, -1.0)]);
+    assert!(handle(&[0xb0, 7, 127]).is_empty());
+    assert!(handle(&[0xb1, 8, 127]).is_empty());
+    // Pitch wheel on any channel
+    assert_eq!(handle(&[0xe5, 0x7f, 0x7f]), [(VOLUME.0, 1.0)]);
+    assert!(handle(&[0xb1, 7]).is_empty());
+}
+
+#[test]
+fn midi_notes() {
+    assert_eq!(
+        note_event(&[0x93, 60, 100]),
+        Some(NoteEvent::On {
+            note: 60,
+            velocity: 100
+        })
+    );
+    assert_eq!(
+        note_event(&[0x90, 60, 0]),
+        Some(NoteEvent::On {
+            note: 60,
+            velocity: 0
+        })
+    );
+    assert_eq!(
+        note_event(&[0x80, 60, 64]),
+        Some(NoteEvent::Off { note: 60 })
+    );
+    assert_eq!(note_event(&[0xbf, 123, 0]), Some(NoteEvent::AllOff));
+    assert_eq!(note_event(&[0xb0, 7, 127]), None);
+    assert_eq!(note_event(&[0x90, 60]), None);
+}
+
+#[test]
+fn port_names_from_metadata() {
+    let (_dsp, state) = DspHandle::<TestDsp<Gain>>::new();
+    assert_eq!(
+        port_names(state.meta(), "input", "in", 2),
+        ["left_in", "in1"]
+    );
+    assert_eq!(
+        port_names(state.meta(), "output", "out", 2),
+        ["out0", "right_out"]
+    );
+    assert!(port_names(&HashMap::new(), "input", "in", 0).is_empty());
+}
+
+#[test]
+#[ignore = "needs a running JACK server"]
+fn processes_and_connects_ports() {
+    let (dsp, mut state) = DspHandle::<TestDsp<Gain>>::new();
+    let client = JackBuilder::new(dsp, &state)
+        .name("faust_jack_test")
+        .connect_inputs(AutoConnect::Physical)
+        .connect_outputs(AutoConnect::Ports(vec!["system:playback_2".into()]))
+        .activate()
+        .expect("failed to activate");
+    assert_eq!(
+        client.input_ports(),
+        ["faust_jack_test:left_in", "faust_jack_test:in1"]
+    );
+    assert_eq!(
+        client.output_ports(),
+        ["faust_jack_test:out0", "faust_jack_test:right_out"]
+    );
+    assert_eq!(client.midi_port(), Some("faust_jack_test:midi_in"));
+
+    let jack = client.client();
+    let port = |name: &str| jack.port_by_name(name).expect("port exists");
+    assert!(port("faust_jack_test:left_in")
+        .is_connected_to("system:capture_1")
+        .expect("port is alive"));
+    assert!(port("faust_jack_test:out0")
+        .is_connected_to("system:playback_2")
+        .expect("port is alive"));
+    assert_eq!(
+        port("faust_jack_test:right_out")
+            .connected_count()
+            .expect("port is alive"),
+        0
+    );
+
+    state.set_param(VOLUME.0, 0.5);
+    state.update();
+    thread::sleep(Duration::from_millis(300));
+    state.update();
+    assert!(state.get_param(CYCLES.0).copied().unwrap_or_default() > 0.0);
+    assert!(!client.is_shut_down());
+    client.deactivate().expect("failed to deactivate");
+}
+
+#[test]
+#[ignore = "needs a running JACK server"]
+fn missing_ports_fail_activation() {
+    let (dsp, state) = DspHandle::<TestDsp<Gain>>::new();
+    let result = JackBuilder::new(dsp, &state)
+        .name("faust_jack_missing")
+        .midi(false)
+        .connect_outputs(AutoConnect::Ports(vec!["nobody:nothing".into()]))
+        .activate();
+    assert!(result.is_err());
+}
+
+#[test]
+#[ignore = "needs a running JACK server"]
+fn backend_maps_channels_to_physical_ports() {
+    let (dsp, state) = DspHandle::<TestDsp<Gain>>::new();
+    let config = BackendConfig {
+        inputs: ChannelMap::Channels(vec![]),
+        outputs: ChannelMap::Channels(vec![None, Some(0)]),
+        ..BackendConfig::default()
+    };
+    let mut backend = JackBackend::new(config)
+        .with_state(&state)
+        .with_name("faust_jack_backend");
+    backend.start(dsp).expect("failed to start");
+    let client = backend.client().expect("backend is running");
+    assert_eq!(backend.sample_rate(), client.client().sample_rate());
+    let port = |name: &str| client.client().port_by_name(name).expect("port exists");
+    assert!(port("faust_jack_backend:right_out")
+        .is_connected_to("system:playback_1")
+        .expect("port is alive"));
+    for unconnected in ["faust_jack_backend:left_in", "faust_jack_backend:out0"] {
+        assert_eq!(
+            port(unconnected).connected_count().expect("port is alive"),
+            0
+        );
+    }
+    backend.stop().expect("failed to stop");
+    assert!(!backend.is_running());
+}
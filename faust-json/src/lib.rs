@@ -4,168 +4,451 @@
     clippy::pedantic,
     clippy::nursery,
     // clippy::cargo
-    unused_crate_dependencies,    
+    unused_crate_dependencies,
     clippy::unwrap_used
 )]
 
 #[cfg(test)]
-use serde_json as _;
+use serde_json_path_to_error as _;
 
-use serde::{Deserialize, Deserializer};
-use std::collections::HashMap;
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+pub use serde_json::{Map, Value};
+use std::{fmt, io::Read};
 
-#[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
+/// How [`FaustJson::parse`] treats fields it does not know.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Unknown fields are kept in the `extra` maps, so descriptions of newer faust versions can be read.
+    #[default]
+    Lenient,
+    /// Unknown fields are an error.
+    Strict,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    /// Paths of the fields rejected in [`ParseMode::Strict`], e.g. `ui[0].items[1].foo`.
+    UnknownFields(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "{err}"),
+            Self::UnknownFields(fields) => write!(f, "unknown fields: {}", fields.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(err) => Some(err),
+            Self::UnknownFields(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// Description of a dsp written by `faust -json`.
+///
+/// Deserializing with serde directly is lenient, see [`ParseMode`]. Fields only written by some faust
+/// versions are optional.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct FaustJson {
     pub name: String,
     pub filename: String,
     pub version: String,
+    #[serde(default)] // missing in old versions
     pub compile_options: String,
     #[serde(default)] //allow empty list
     pub library_list: Vec<String>,
     #[serde(default)] //allow empty list
     pub include_pathnames: Vec<String>,
+    #[serde(default)] // missing in old versions
     pub size: u32,
     pub inputs: usize,
     pub outputs: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sr_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha_key: Option<String>,
+    /// Dsp code, written for the wasm backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub copyright: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub classname: Option<String>,
     #[serde(default)] //allow empty list
     pub meta: Vec<Meta>,
     #[serde(default)] //allow empty list
     pub ui: Vec<LayoutItem>,
+    /// Memory layout of the dsp struct, written by newer faust versions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub memory_layout: Vec<Map<String, Value>>,
+    /// Fields unknown to this crate.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl FaustJson {
+    /// # Errors
+    ///
+    /// If the json is invalid or, in [`ParseMode::Strict`], contains unknown fields.
+    pub fn parse(json: &str, mode: ParseMode) -> Result<Self, Error> {
+        serde_json::from_str::<Self>(json)?.check(mode)
+    }
+
+    /// # Errors
+    ///
+    /// If the json is invalid or, in [`ParseMode::Strict`], contains unknown fields.
+    pub fn from_reader(reader: impl Read, mode: ParseMode) -> Result<Self, Error> {
+        serde_json::from_reader::<_, Self>(reader)?.check(mode)
+    }
+
+    fn check(self, mode: ParseMode) -> Result<Self, Error> {
+        let unknown_fields = self.unknown_fields();
+        if mode == ParseMode::Strict && !unknown_fields.is_empty() {
+            return Err(Error::UnknownFields(unknown_fields));
+        }
+        Ok(self)
+    }
+
+    /// Paths of all fields kept in `extra` maps.
+    #[must_use]
+    pub fn unknown_fields(&self) -> Vec<String> {
+        let mut fields = self.extra.keys().cloned().collect::<Vec<_>>();
+        for (i, item) in self.ui.iter().enumerate() {
+            item.collect_unknown_fields(&format!("ui[{i}]"), &mut fields);
+        }
+        fields
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Meta {
     pub key: String,
     pub value: String,
 }
 
+/// Faust writes metadata as objects with a single entry. A `null` value is read as empty string.
 impl<'de> Deserialize<'de> for Meta {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let map: HashMap<String, Option<String>> = Deserialize::deserialize(deserializer)
-            .unwrap_or_else(|err| panic!("Error during Deserialization: {err}"));
-        let Some((key, Some(value))): Option<(&String, &Option<String>)> = map.iter().next() else {
-            panic!("meta dictionary is unexpectedly empty")
-        };
-
-        Ok(Self {
-            key: key.to_owned(),
-            value: value.to_owned(),
-        })
+        struct MetaVisitor;
+
+        impl<'de> Visitor<'de> for MetaVisitor {
+            type Value = Meta;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object with a single entry")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let Some((key, value)) = map.next_entry::<String, Option<String>>()? else {
+                    return Err(de::Error::invalid_length(0, &self));
+                };
+                if let Some(next_key) = map.next_key::<String>()? {
+                    return Err(de::Error::custom(format!(
+                        "meta entry {key} has more than one key, also found {next_key}"
+                    )));
+                }
+                Ok(Meta {
+                    key,
+                    value: value.unwrap_or_default(),
+                })
+            }
+        }
+
+        deserializer.deserialize_map(MetaVisitor)
+    }
+}
+
+/// Written like faust does, as an object with a single entry.
+impl Serialize for Meta {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(&self.key, &self.value)?;
+        map.end()
+    }
+}
+
+/// Reads the url of a soundfile, either as the string faust writes (`{'a.wav';'b.wav'}`) or as a list.
+fn deserialize_soundfile_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Url {
+        String(String),
+        List(Vec<String>),
     }
+
+    Ok(match Url::deserialize(deserializer)? {
+        Url::String(url) => url,
+        Url::List(urls) => {
+            let urls = urls
+                .iter()
+                .map(|url| format!("'{url}'"))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", urls.join(";"))
+        }
+    })
+}
+
+/// Splits the url of a soundfile into its files, e.g. `{'a.wav';'b.wav'}` into `a.wav` and `b.wav`.
+#[must_use]
+pub fn soundfile_urls(url: &str) -> Vec<String> {
+    let url = url.trim();
+    let list = url
+        .strip_prefix('{')
+        .and_then(|url| url.strip_suffix('}'))
+        .unwrap_or(url);
+    list.split(';')
+        .map(|url| url.trim().trim_matches('\'').to_owned())
+        .filter(|url| !url.is_empty())
+        .collect()
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 pub enum LayoutItem {
     TGroup {
         label: String,
-        items: Vec<LayoutItem>,
-        #[serde(default)]
+        items: Vec<Self>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     VGroup {
         label: String,
-        items: Vec<LayoutItem>,
-        #[serde(default)]
+        items: Vec<Self>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     HGroup {
         label: String,
-        items: Vec<LayoutItem>,
-        #[serde(default)]
+        items: Vec<Self>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     VSlider {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
         init: f32,
         min: f32,
         max: f32,
         step: f32,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     HSlider {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
         init: f32,
         min: f32,
         max: f32,
         step: f32,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     NEntry {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
         init: Option<f32>,
         min: f32,
         max: f32,
         step: f32,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     Button {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
         init: Option<f32>,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     CheckBox {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
         init: Option<f32>,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     VBarGraph {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
         min: f32,
         max: f32,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
     HBarGraph {
         label: String,
+        #[serde(default)] // missing in old versions
         shortname: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
         min: f32,
         max: f32,
-        #[serde(default)]
+        /// Offset of the zone in the dsp struct, written for some backends like wasm.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
 
     Soundfile {
         label: String,
+        #[serde(deserialize_with = "deserialize_soundfile_url")]
         url: String,
         address: String,
+        #[serde(default)] // missing in old versions
         varname: String,
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
         meta: Vec<Meta>,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
     },
 }
+
+impl LayoutItem {
+    /// Fields of the item unknown to this crate.
+    #[must_use]
+    pub const fn extra(&self) -> &Map<String, Value> {
+        match self {
+            Self::TGroup { extra, .. }
+            | Self::VGroup { extra, .. }
+            | Self::HGroup { extra, .. }
+            | Self::VSlider { extra, .. }
+            | Self::HSlider { extra, .. }
+            | Self::NEntry { extra, .. }
+            | Self::Button { extra, .. }
+            | Self::CheckBox { extra, .. }
+            | Self::VBarGraph { extra, .. }
+            | Self::HBarGraph { extra, .. }
+            | Self::Soundfile { extra, .. } => extra,
+        }
+    }
+
+    /// Offset of the zone of a widget in the dsp struct, if faust wrote it.
+    #[must_use]
+    pub const fn index(&self) -> Option<u32> {
+        match self {
+            Self::VSlider { index, .. }
+            | Self::HSlider { index, .. }
+            | Self::NEntry { index, .. }
+            | Self::Button { index, .. }
+            | Self::CheckBox { index, .. }
+            | Self::VBarGraph { index, .. }
+            | Self::HBarGraph { index, .. } => *index,
+            Self::TGroup { .. }
+            | Self::VGroup { .. }
+            | Self::HGroup { .. }
+            | Self::Soundfile { .. } => None,
+        }
+    }
+
+    fn collect_unknown_fields(&self, path: &str, fields: &mut Vec<String>) {
+        fields.extend(self.extra().keys().map(|key| format!("{path}.{key}")));
+        if let Self::TGroup { items, .. }
+        | Self::VGroup { items, .. }
+        | Self::HGroup { items, .. } = self
+        {
+            for (i, item) in items.iter().enumerate() {
+                item.collect_unknown_fields(&format!("{path}.items[{i}]"), fields);
+            }
+        }
+    }
+}
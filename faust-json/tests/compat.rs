@@ -0,0 +1,126 @@
+use faust_json::*;
+
+fn with_ui(ui: &str) -> String {
+    format!(
+        r#"{{
+	"name": "test",
+	"filename": "test.dsp",
+	"version": "2.77.3",
+	"compile_options": "-lang rust",
+	"size": 8,
+	"inputs": 0,
+	"outputs": 1,
+	"ui": [ {ui} ]
+}}"#
+    )
+}
+
+#[test]
+fn meta_errors_instead_of_panics() {
+    let parse = |meta: &str| serde_json::from_str::<Meta>(meta);
+    assert_eq!(
+        parse(r#"{ "unit": null }"#).expect("null is valid"),
+        Meta {
+            key: "unit".to_owned(),
+            value: String::new()
+        }
+    );
+    assert!(parse("{}").is_err());
+    assert!(parse(r#"{ "a": "1", "b": "2" }"#).is_err());
+    assert!(parse(r#"[ "a" ]"#).is_err());
+}
+
+#[test]
+fn strict_mode_lists_unknown_fields() {
+    let json = with_ui(
+        r#"{ "type": "vgroup", "label": "test", "future": 1, "items": [
+            { "type": "button", "label": "gate", "shortname": "gate", "address": "/test/gate", "varname": "fButton0", "color": "red" }
+        ] }"#,
+    )
+    .replace(r#""size": 8,"#, r#""size": 8, "new_field": true,"#);
+    let Err(Error::UnknownFields(fields)) = FaustJson::parse(&json, ParseMode::Strict) else {
+        panic!("strict mode accepted unknown fields")
+    };
+    assert_eq!(
+        fields,
+        ["new_field", "ui[0].future", "ui[0].items[0].color"]
+    );
+}
+
+#[test]
+fn lenient_mode_keeps_unknown_fields() {
+    let json = with_ui(
+        r#"{ "type": "button", "label": "gate", "shortname": "gate", "address": "/gate", "varname": "fButton0", "color": "red" }"#,
+    );
+    let parsed = FaustJson::parse(&json, ParseMode::Lenient).expect("lenient mode failed");
+    assert_eq!(parsed.ui[0].extra().get("color"), Some(&Value::from("red")));
+    let written = serde_json::to_string(&parsed).expect("cannot serialize");
+    assert!(written.contains(r#""color":"red""#));
+    assert_eq!(
+        FaustJson::parse(&written, ParseMode::Lenient).expect("cannot read back"),
+        parsed
+    );
+}
+
+#[test]
+fn old_format() {
+    // no compile options, size, shortnames and varnames, but zone indices
+    let json = r#"{
+	"name": "old",
+	"filename": "old.dsp",
+	"version": "2.20.2",
+	"inputs": 0,
+	"outputs": 1,
+	"meta": [ { "name": "old" } ],
+	"ui": [ { "type": "vgroup", "label": "old", "items": [
+		{ "type": "hslider", "label": "freq", "address": "/old/freq", "index": 8, "init": 440, "min": 20, "max": 2000, "step": 1 }
+	] } ]
+}"#;
+    let parsed = FaustJson::parse(json, ParseMode::Strict).expect("old format rejected");
+    assert_eq!(parsed.size, 0);
+    let LayoutItem::VGroup { items, .. } = &parsed.ui[0] else {
+        panic!("expected a vgroup")
+    };
+    assert!(matches!(
+        &items[0],
+        LayoutItem::HSlider { index: Some(8), varname, .. } if varname.is_empty()
+    ));
+}
+
+#[test]
+fn new_format() {
+    let json = with_ui("").replace(
+        r#""size": 8,"#,
+        r#""size": 8,
+	"sr_index": 0,
+	"sha_key": "4F2A",
+	"code": "cHJvY2VzcyA9IF87",
+	"memory_layout": [ { "name": "fSampleRate", "type": "kInt32", "size": 1, "size_bytes": 4, "read": 1, "write": 1 } ],"#,
+    );
+    let parsed = FaustJson::parse(&json, ParseMode::Strict).expect("new format rejected");
+    assert_eq!(parsed.sr_index, Some(0));
+    assert_eq!(parsed.sha_key.as_deref(), Some("4F2A"));
+    assert_eq!(parsed.memory_layout.len(), 1);
+}
+
+#[test]
+fn soundfile_url_lists() {
+    let soundfile = |url: &str| {
+        let json = with_ui(&format!(
+            r#"{{ "type": "soundfile", "label": "sf", "url": {url}, "address": "/test/sf", "varname": "fSoundfile0" }}"#
+        ));
+        match FaustJson::parse(&json, ParseMode::Strict)
+            .expect("invalid soundfile")
+            .ui
+            .remove(0)
+        {
+            LayoutItem::Soundfile { url, .. } => url,
+            _ => panic!("expected a soundfile"),
+        }
+    };
+    let url = soundfile(r#""{'a.wav';'b.wav'}""#);
+    assert_eq!(url, "{'a.wav';'b.wav'}");
+    assert_eq!(soundfile(r#"[ "a.wav", "b.wav" ]"#), url);
+    assert_eq!(soundfile_urls(&url), ["a.wav", "b.wav"]);
+    assert_eq!(soundfile_urls("c.wav"), ["c.wav"]);
+}
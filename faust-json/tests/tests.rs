@@ -1,4 +1,5 @@
 use faust_json::*;
+use serde_json_path_to_error as serde_json;
 
 use std::{
     env,
@@ -185,8 +186,10 @@ fn hand_written_broken() {
 		}
 	]
 }"##;
-    let result: Result<FaustJson, _> = serde_json::from_str(f);
-    assert!(result.is_err());
+    let result = FaustJson::parse(f, ParseMode::Strict);
+    assert!(
+        matches!(&result, Err(Error::UnknownFields(fields)) if fields == &["ui[0].items[0].Bla"])
+    );
 
     // match one {
     //     Ok(f) => {
@@ -13,11 +13,18 @@ use faust_build::macro_lib::{
     build_dsp_code_from_macro, build_faust_file_from_macro, FileMacroArgs,
 };
 
+/// Compiles the faust code given to the macro.
+///
+/// Flags are read from `declare flags "..."`. `-I <dir>` and `-A <dir>` are resolved relative to the crate root,
+/// which is also searched for libraries.
 #[proc_macro]
 pub fn dsp(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     build_dsp_code_from_macro(&input.into()).into()
 }
 
+/// Compiles a dsp file relative to the crate root, e.g. `include!("dsp/volume.dsp", [Double, ImportDir("dsp/lib")])`.
+///
+/// The array holds `CodeOption` variants as well as `ImportDir(dir)` and `ArchitectureDir(dir)`.
 #[proc_macro]
 pub fn include(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let args = syn::parse_macro_input!(input as FileMacroArgs);
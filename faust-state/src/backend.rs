@@ -0,0 +1,318 @@
+//! Backends that drive a [`DspHandle`] with [`DspHandle::update_and_compute`].
+//!
+//! Applications can choose an [`AudioBackend`] at runtime, e.g. JACK from `faust-jack` on a
+//! workstation, the [`NullBackend`] on a headless server and the [`OfflineBackend`] in tests.
+
+use crate::{DspHandle, DEFAULT_BLOCK_SIZE};
+use faust_types::*;
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Runs a dsp until it is stopped.
+///
+/// [`AudioBackend::stop`] returns the dsp, so it can be started again on another backend.
+pub trait AudioBackend<T> {
+    /// Initializes `dsp` with the sample rate of the backend and starts computing it.
+    fn start(&mut self, dsp: DspHandle<T>) -> Result<(), String>;
+    /// Stops computing and returns the dsp.
+    fn stop(&mut self) -> Result<DspHandle<T>, String>;
+    fn is_running(&self) -> bool;
+    fn sample_rate(&self) -> u32;
+    /// Maximal number of frames per call of [`DspHandle::update_and_compute`].
+    fn block_size(&self) -> usize;
+}
+
+/// The device channel of every dsp channel.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ChannelMap {
+    /// Dsp channel `i` uses device channel `i`.
+    #[default]
+    Identity,
+    /// Dsp channel `i` uses the device channel at index `i`. Channels without an entry or with
+    /// `None` are not connected.
+    Channels(Vec<Option<usize>>),
+}
+
+impl ChannelMap {
+    #[must_use]
+    pub fn device_channel(&self, channel: usize) -> Option<usize> {
+        match self {
+            Self::Identity => Some(channel),
+            Self::Channels(channels) => channels.get(channel).copied().flatten(),
+        }
+    }
+
+    /// Number of device channels needed for `channels` dsp channels.
+    #[must_use]
+    pub fn device_channels(&self, channels: usize) -> usize {
+        (0..channels)
+            .filter_map(|channel| self.device_channel(channel))
+            .map(|device| device + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Settings of a backend. Backends with a server, like JACK, use the sample rate and block size
+/// of the server instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendConfig {
+    pub sample_rate: u32,
+    pub block_size: usize,
+    pub inputs: ChannelMap,
+    pub outputs: ChannelMap,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            block_size: DEFAULT_BLOCK_SIZE,
+            inputs: ChannelMap::Identity,
+            outputs: ChannelMap::Identity,
+        }
+    }
+}
+
+impl BackendConfig {
+    fn validate(&self) -> Result<i32, String> {
+        if self.block_size == 0 || i32::try_from(self.block_size).is_err() {
+            return Err(format!("invalid block size {}", self.block_size));
+        }
+        i32::try_from(self.sample_rate)
+            .ok()
+            .filter(|sample_rate| *sample_rate > 0)
+            .ok_or_else(|| format!("invalid sample rate {}", self.sample_rate))
+    }
+}
+
+/// Computes the dsp on a thread, paced like a sound card, with silent inputs. Outputs are
+/// discarded, so the channel maps are ignored.
+#[derive(Debug)]
+pub struct NullBackend<T> {
+    config: BackendConfig,
+    running: Arc<AtomicBool>,
+    frames: Arc<AtomicU64>,
+    thread: Option<JoinHandle<DspHandle<T>>>,
+}
+
+impl<T> NullBackend<T> {
+    #[must_use]
+    pub fn new(config: BackendConfig) -> Self {
+        Self {
+            config,
+            running: Arc::new(AtomicBool::new(false)),
+            frames: Arc::new(AtomicU64::new(0)),
+            thread: None,
+        }
+    }
+
+    /// Frames computed since the last start.
+    #[must_use]
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> AudioBackend<T> for NullBackend<T>
+where
+    T: FaustDsp<T = f32> + Send + 'static,
+{
+    fn start(&mut self, mut dsp: DspHandle<T>) -> Result<(), String> {
+        if self.thread.is_some() {
+            return Err("backend is already running".into());
+        }
+        let sample_rate = self.config.validate()?;
+        let block_size = self.config.block_size;
+        let period = Duration::from_secs(block_size as u64) / self.config.sample_rate;
+        dsp.init(sample_rate);
+        self.running.store(true, Ordering::Relaxed);
+        self.frames.store(0, Ordering::Relaxed);
+        let running = self.running.clone();
+        let frames = self.frames.clone();
+        let thread = thread::Builder::new()
+            .name("faust-null-backend".into())
+            .spawn(move || {
+                let mut buffers =
+                    AudioBuffers::new(dsp.num_inputs(), dsp.num_outputs(), block_size);
+                let mut deadline = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    buffers.process(block_size, |count, inputs, outputs| {
+                        dsp.update_and_compute(count, inputs, outputs);
+                    });
+                    frames.fetch_add(block_size as u64, Ordering::Relaxed);
+                    deadline += period;
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                }
+                dsp
+            })
+            .map_err(|err| format!("failed to spawn thread: {err}"))?;
+        self.thread = Some(thread);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<DspHandle<T>, String> {
+        let thread = self.thread.take().ok_or("backend is not running")?;
+        self.running.store(false, Ordering::Relaxed);
+        thread.join().map_err(|_| "dsp thread panicked".to_string())
+    }
+
+    fn is_running(&self) -> bool {
+        self.thread.is_some()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate
+    }
+
+    fn block_size(&self) -> usize {
+        self.config.block_size
+    }
+}
+
+impl<T> Drop for NullBackend<T> {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Renders a fixed number of frames to memory as fast as possible when started.
+///
+/// ```
+/// # fn render<D: faust_types::FaustDsp<T = f32> + 'static>() -> Result<(), String> {
+/// use faust_state::{
+///     backend::{AudioBackend, BackendConfig, OfflineBackend},
+///     DspHandle,
+/// };
+///
+/// let (dsp, _state) = DspHandle::<D>::new();
+/// let mut backend = OfflineBackend::new(BackendConfig::default(), 48000);
+/// backend.start(dsp)?;
+/// let left = &backend.output()[0];
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OfflineBackend<T> {
+    config: BackendConfig,
+    frames: usize,
+    input: Vec<Vec<f32>>,
+    output: Vec<Vec<f32>>,
+    dsp: Option<DspHandle<T>>,
+}
+
+impl<T> OfflineBackend<T> {
+    /// Renders `frames` frames with silent inputs.
+    #[must_use]
+    pub fn new(config: BackendConfig, frames: usize) -> Self {
+        Self {
+            config,
+            frames,
+            input: Vec::new(),
+            output: Vec::new(),
+            dsp: None,
+        }
+    }
+
+    /// Device input channels, routed to the dsp by [`BackendConfig::inputs`]. Missing channels
+    /// and frames are silent.
+    #[must_use]
+    pub fn with_input(mut self, input: Vec<Vec<f32>>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Device output channels of the last start. Dsp outputs mapped to the same device channel
+    /// are summed.
+    #[must_use]
+    pub fn output(&self) -> &[Vec<f32>] {
+        &self.output
+    }
+
+    #[must_use]
+    pub fn into_output(self) -> Vec<Vec<f32>> {
+        self.output
+    }
+}
+
+impl<T> AudioBackend<T> for OfflineBackend<T>
+where
+    T: FaustDsp<T = f32> + 'static,
+{
+    fn start(&mut self, mut dsp: DspHandle<T>) -> Result<(), String> {
+        if self.dsp.is_some() {
+            return Err("backend is already running".into());
+        }
+        let sample_rate = self.config.validate()?;
+        dsp.init(sample_rate);
+        let BackendConfig {
+            block_size,
+            inputs: input_map,
+            outputs: output_map,
+            ..
+        } = &self.config;
+        let block_size = *block_size;
+        let mut buffers = AudioBuffers::new(dsp.num_inputs(), dsp.num_outputs(), block_size);
+        self.output = vec![vec![0.0; self.frames]; output_map.device_channels(dsp.num_outputs())];
+        let mut offset = 0;
+        while offset < self.frames {
+            let frames = block_size.min(self.frames - offset);
+            let block = offset..offset + frames;
+            for channel in 0..buffers.inputs() {
+                let input = &mut buffers.input_mut(channel)[..frames];
+                let device = input_map
+                    .device_channel(channel)
+                    .and_then(|device| self.input.get(device));
+                input.fill(0.0);
+                if let Some(samples) =
+                    device.and_then(|device| device.get(offset..device.len().min(block.end)))
+                {
+                    input[..samples.len()].copy_from_slice(samples);
+                }
+            }
+            buffers.process(frames, |count, inputs, outputs| {
+                dsp.update_and_compute(count, inputs, outputs);
+            });
+            for channel in 0..buffers.outputs() {
+                if let Some(device) = output_map.device_channel(channel) {
+                    let output = &buffers.output(channel)[..frames];
+                    for (sample, value) in self.output[device][block.clone()].iter_mut().zip(output)
+                    {
+                        *sample += value;
+                    }
+                }
+            }
+            offset += frames;
+        }
+        self.dsp = Some(dsp);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<DspHandle<T>, String> {
+        self.dsp
+            .take()
+            .ok_or_else(|| "backend is not running".into())
+    }
+
+    fn is_running(&self) -> bool {
+        self.dsp.is_some()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate
+    }
+
+    fn block_size(&self) -> usize {
+        self.config.block_size
+    }
+}
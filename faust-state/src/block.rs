@@ -0,0 +1,219 @@
+//! Computing a dsp with a fixed block size while the host uses arbitrary block sizes.
+
+use crate::DEFAULT_BLOCK_SIZE;
+use faust_types::*;
+use std::convert::TryFrom;
+
+/// Collects host blocks of any length into blocks of exactly `block_size` frames.
+///
+/// The buffers are allocated once, so the output is delayed by [`FixedBlocks::latency`] frames.
+/// [`FixedBlocks::compute`] takes the function that computes a block, e.g. of a
+/// [`DspHandle`](crate::DspHandle):
+///
+/// ```
+/// use faust_state::{block::FixedBlocks, graph::DspGraph, DspHandle};
+///
+/// let (mut handle, _state) = DspHandle::from_dsp(Box::new(DspGraph::wires(1)));
+/// let mut blocks = FixedBlocks::new(handle.num_inputs(), handle.num_outputs(), 4);
+/// let mut output = [0.0; 6];
+/// blocks.compute(6, &[&[1.0; 6]], &mut [&mut output], |count, inputs, outputs| {
+///     handle.update_and_compute(count, inputs, outputs);
+/// });
+/// assert_eq!(output, [0.0, 0.0, 0.0, 0.0, 1.0, 1.0]);
+/// ```
+pub struct FixedBlocks<T> {
+    buffers: AudioBuffers<T>,
+    /// Frames of the current block that were already exchanged with the host.
+    position: usize,
+}
+
+impl<T> FixedBlocks<T>
+where
+    T: Sample + Default,
+{
+    #[must_use]
+    pub fn new(inputs: usize, outputs: usize, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must not be zero");
+        assert!(
+            i32::try_from(block_size).is_ok(),
+            "block size {} is too large",
+            block_size
+        );
+        Self {
+            buffers: AudioBuffers::new(inputs, outputs, block_size),
+            position: 0,
+        }
+    }
+
+    /// Buffers with the channels of `dsp`.
+    #[must_use]
+    pub fn for_dsp(dsp: &(impl FaustDsp<T = T> + ?Sized), block_size: usize) -> Self {
+        Self::new(
+            dsp.get_num_inputs() as usize,
+            dsp.get_num_outputs() as usize,
+            block_size,
+        )
+    }
+
+    #[must_use]
+    pub fn block_size(&self) -> usize {
+        self.buffers.max_frames()
+    }
+
+    /// Delay of the output in frames, which is the block size.
+    #[must_use]
+    pub fn latency(&self) -> usize {
+        self.block_size()
+    }
+
+    /// Drops the pending frames, e.g. after the dsp was initialized again.
+    pub fn clear(&mut self) {
+        for channel in 0..self.buffers.inputs() {
+            self.buffers.input_mut(channel).fill(T::default());
+        }
+        for channel in 0..self.buffers.outputs() {
+            self.buffers.output_mut(channel).fill(T::default());
+        }
+        self.position = 0;
+    }
+
+    /// Exchanges `count` frames with the host and calls `compute` for every block that is full.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[T]],
+        outputs: &mut [&mut [T]],
+        mut compute: impl FnMut(i32, &[&[T]], &mut [&mut [T]]),
+    ) {
+        let count = count as usize;
+        let block_size = self.block_size();
+        let mut done = 0;
+        while done < count {
+            let frames = (block_size - self.position).min(count - done);
+            let block = self.position..self.position + frames;
+            for (channel, input) in inputs.iter().take(self.buffers.inputs()).enumerate() {
+                self.buffers.input_mut(channel)[block.clone()]
+                    .copy_from_slice(&input[done..done + frames]);
+            }
+            for (channel, output) in outputs.iter_mut().take(self.buffers.outputs()).enumerate() {
+                output[done..done + frames]
+                    .copy_from_slice(&self.buffers.output(channel)[block.clone()]);
+            }
+            self.position += frames;
+            done += frames;
+            if self.position == block_size {
+                self.buffers.process(block_size, &mut compute);
+                self.position = 0;
+            }
+        }
+    }
+}
+
+/// Calls [`FaustDsp::compute`] of the wrapped dsp with exactly `block_size` frames.
+///
+/// Inputs are collected until a block is full, so the output is delayed by [`FixedBlockDsp::latency`]
+/// frames. Host blocks of any length work, longer ones are computed in several blocks.
+pub struct FixedBlockDsp<D: FaustDsp> {
+    dsp: Box<D>,
+    blocks: FixedBlocks<D::T>,
+}
+
+impl<D> FixedBlockDsp<D>
+where
+    D: FaustDsp,
+    D::T: Sample + Default,
+{
+    #[must_use]
+    pub fn new(dsp: Box<D>, block_size: usize) -> Self {
+        Self {
+            blocks: FixedBlocks::for_dsp(&*dsp, block_size),
+            dsp,
+        }
+    }
+
+    #[must_use]
+    pub fn block_size(&self) -> usize {
+        self.blocks.block_size()
+    }
+
+    /// Delay of the output in frames, which is the block size.
+    #[must_use]
+    pub fn latency(&self) -> usize {
+        self.blocks.latency()
+    }
+
+    #[must_use]
+    pub fn inner(&self) -> &D {
+        &self.dsp
+    }
+
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.dsp
+    }
+}
+
+impl<D> FaustDsp for FixedBlockDsp<D>
+where
+    D: FaustDsp,
+    D::T: Sample + Default,
+{
+    type T = D::T;
+
+    /// Uses [`DEFAULT_BLOCK_SIZE`].
+    fn new() -> Self {
+        Self::new(Box::new(D::new()), DEFAULT_BLOCK_SIZE)
+    }
+    fn metadata(&self, m: &mut dyn Meta) {
+        self.dsp.metadata(m);
+    }
+    fn get_sample_rate(&self) -> i32 {
+        self.dsp.get_sample_rate()
+    }
+    fn get_num_inputs(&self) -> i32 {
+        self.dsp.get_num_inputs()
+    }
+    fn get_num_outputs(&self) -> i32 {
+        self.dsp.get_num_outputs()
+    }
+    fn class_init(sample_rate: i32) {
+        D::class_init(sample_rate);
+    }
+    fn instance_reset_params(&mut self) {
+        self.dsp.instance_reset_params();
+    }
+    fn instance_clear(&mut self) {
+        self.dsp.instance_clear();
+        self.blocks.clear();
+    }
+    fn instance_constants(&mut self, sample_rate: i32) {
+        self.dsp.instance_constants(sample_rate);
+    }
+    fn instance_init(&mut self, sample_rate: i32) {
+        self.dsp.instance_init(sample_rate);
+        self.blocks.clear();
+    }
+    fn init(&mut self, sample_rate: i32) {
+        self.dsp.init(sample_rate);
+        self.blocks.clear();
+    }
+    fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+        self.dsp.build_user_interface(ui_interface);
+    }
+    fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+        D::build_user_interface_static(ui_interface);
+    }
+    fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+        self.dsp.get_param(param)
+    }
+    fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+        self.dsp.set_param(param, value);
+    }
+    fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]) {
+        let dsp = &mut self.dsp;
+        self.blocks
+            .compute(count, inputs, outputs, |count, inputs, outputs| {
+                dsp.compute(count, inputs, outputs);
+            });
+    }
+}
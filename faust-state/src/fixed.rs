@@ -0,0 +1,219 @@
+//! An allocation-free variant of [`DspHandle`](crate::DspHandle) for `no_std` targets.
+//!
+//! Instead of the ring buffers of [`StateHandle`](crate::StateHandle), parameters are exchanged
+//! through [`AtomicParams`], which has a fixed number of slots and can live in a `static`. The
+//! audio side owns a [`FixedDspHandle`], e.g. in the interrupt handler of the audio peripheral,
+//! while the rest of the firmware reads and writes the parameters.
+//!
+//! ```
+//! use faust_state::fixed::{AtomicParams, FixedDspHandle};
+//! # use faust_types::*;
+//! # struct Gain { gain: f32 }
+//! # impl FaustDsp for Gain {
+//! #     type T = f32;
+//! #     fn new() -> Self { Self { gain: 1.0 } }
+//! #     fn metadata(&self, _m: &mut dyn Meta) {}
+//! #     fn get_sample_rate(&self) -> i32 { 48_000 }
+//! #     fn get_num_inputs(&self) -> i32 { 1 }
+//! #     fn get_num_outputs(&self) -> i32 { 1 }
+//! #     fn class_init(_sample_rate: i32) {}
+//! #     fn instance_reset_params(&mut self) {}
+//! #     fn instance_clear(&mut self) {}
+//! #     fn instance_constants(&mut self, _sample_rate: i32) {}
+//! #     fn instance_init(&mut self, _sample_rate: i32) {}
+//! #     fn init(&mut self, _sample_rate: i32) {}
+//! #     fn build_user_interface(&self, _ui_interface: &mut dyn UI<f32>) {}
+//! #     fn build_user_interface_static(_ui_interface: &mut dyn UI<f32>) {}
+//! #     fn get_param(&self, param: ParamIndex) -> Option<f32> {
+//! #         (param.0 == 0).then(|| self.gain)
+//! #     }
+//! #     fn set_param(&mut self, param: ParamIndex, value: f32) {
+//! #         if param.0 == 0 { self.gain = value }
+//! #     }
+//! #     fn compute(&mut self, count: i32, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+//! #         for i in 0..count as usize { outputs[0][i] = inputs[0][i] * self.gain }
+//! #     }
+//! # }
+//!
+//! static PARAMS: AtomicParams<1> = AtomicParams::new();
+//!
+//! let mut handle = FixedDspHandle::<Gain, 1>::new(&PARAMS);
+//! handle.init(48_000);
+//! assert_eq!(PARAMS.get(ParamIndex(0)), Some(1.0));
+//!
+//! // e.g. from the main loop
+//! PARAMS.set(ParamIndex(0), 0.5);
+//!
+//! // in the audio interrupt
+//! let mut output = [0.0; 2];
+//! handle.update_and_compute(2, &[&[1.0, 2.0]], &mut [&mut output]);
+//! assert_eq!(output, [0.5, 1.0]);
+//! ```
+
+use core::{
+    convert::TryFrom,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+use faust_types::*;
+
+/// Parameter values of a dsp with at most `N` parameters, shared between the audio side and the
+/// rest of the program without locks or allocations.
+///
+/// Slots are indexed by [`ParamIndex`]. The generated code declares `FAUST_ACTIVES` and
+/// `FAUST_PASSIVES`, their sum is the number of slots a dsp needs.
+#[derive(Debug)]
+pub struct AtomicParams<const N: usize> {
+    /// Values set by [`AtomicParams::set`], not yet seen by the dsp if `pending` is set.
+    requested: [AtomicU32; N],
+    pending: [AtomicBool; N],
+    /// Values of the dsp after its last computed block.
+    current: [AtomicU32; N],
+    /// Slots that hold a parameter of the dsp.
+    used: [AtomicBool; N],
+}
+
+impl<const N: usize> AtomicParams<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            requested: [const { AtomicU32::new(0) }; N],
+            pending: [const { AtomicBool::new(false) }; N],
+            current: [const { AtomicU32::new(0) }; N],
+            used: [const { AtomicBool::new(false) }; N],
+        }
+    }
+
+    /// Sets a parameter, the dsp sees it before its next computed block.
+    ///
+    /// Indices without a slot are ignored.
+    pub fn set(&self, idx: ParamIndex, value: f32) {
+        if let Some(slot) = Self::slot(idx) {
+            self.requested[slot].store(value.to_bits(), Ordering::Relaxed);
+            self.pending[slot].store(true, Ordering::Release);
+        }
+    }
+
+    /// Value of a parameter after the last computed block, also of passive ones like bargraphs.
+    ///
+    /// `None` for indices that are not a parameter of the dsp or before [`FixedDspHandle::init`].
+    pub fn get(&self, idx: ParamIndex) -> Option<f32> {
+        let slot = Self::slot(idx)?;
+        self.used[slot]
+            .load(Ordering::Acquire)
+            .then(|| f32::from_bits(self.current[slot].load(Ordering::Relaxed)))
+    }
+
+    fn slot(idx: ParamIndex) -> Option<usize> {
+        usize::try_from(idx.0).ok().filter(|slot| *slot < N)
+    }
+
+    fn apply(&self, dsp: &mut impl FaustDsp<T = f32>) {
+        for (idx, (pending, requested)) in (0..).zip(self.pending.iter().zip(&self.requested)) {
+            if pending.swap(false, Ordering::Acquire) {
+                let value = f32::from_bits(requested.load(Ordering::Relaxed));
+                dsp.set_param(ParamIndex(idx), value);
+            }
+        }
+    }
+
+    fn publish(&self, dsp: &impl FaustDsp<T = f32>) {
+        for (idx, (current, used)) in (0..).zip(self.current.iter().zip(&self.used)) {
+            if let Some(value) = dsp.get_param(ParamIndex(idx)) {
+                current.store(value.to_bits(), Ordering::Relaxed);
+                used.store(true, Ordering::Release);
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for AtomicParams<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Owns a dsp by value and exchanges its parameters through [`AtomicParams`].
+///
+/// Unlike [`DspHandle`](crate::DspHandle) nothing is allocated, neither on creation nor while
+/// computing, and denormals are not flushed.
+#[derive(Debug)]
+pub struct FixedDspHandle<'a, T, const N: usize> {
+    dsp: T,
+    params: &'a AtomicParams<N>,
+    initialized: bool,
+}
+
+impl<'a, T, const N: usize> FixedDspHandle<'a, T, N>
+where
+    T: FaustDsp<T = f32>,
+{
+    #[must_use]
+    pub fn new(params: &'a AtomicParams<N>) -> Self {
+        Self::from_dsp(T::new(), params)
+    }
+
+    pub fn from_dsp(dsp: T, params: &'a AtomicParams<N>) -> Self {
+        Self {
+            dsp,
+            params,
+            initialized: false,
+        }
+    }
+
+    /// Initializes the dsp and publishes the initial values of its parameters.
+    pub fn init(&mut self, sample_rate: i32) {
+        self.dsp.init(sample_rate);
+        self.params.publish(&self.dsp);
+        self.initialized = true;
+    }
+
+    #[must_use]
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Applies the parameters set since the last block, computes and publishes the new values.
+    pub fn update_and_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        self.params.apply(&mut self.dsp);
+        self.dsp.compute(count, inputs, outputs);
+        self.params.publish(&self.dsp);
+    }
+
+    /// Like [`FixedDspHandle::update_and_compute`], but returns an error instead of computing with
+    /// buffers that do not match the dsp.
+    pub fn checked_update_and_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) -> Result<(), ComputeError> {
+        self.dsp.check_compute(count, inputs, outputs)?;
+        self.update_and_compute(count, inputs, outputs);
+        Ok(())
+    }
+
+    pub fn params(&self) -> &'a AtomicParams<N> {
+        self.params
+    }
+
+    pub fn dsp(&self) -> &T {
+        &self.dsp
+    }
+
+    pub fn dsp_mut(&mut self) -> &mut T {
+        &mut self.dsp
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.dsp.get_num_inputs() as usize
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.dsp.get_num_outputs() as usize
+    }
+}
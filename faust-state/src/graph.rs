@@ -0,0 +1,469 @@
+//! Composition of several dsps with the operators of the faust language.
+//!
+//! ```text
+//! eq : compressor        DspGraph::seq
+//! left , right           DspGraph::par
+//! source <: (a , b)      DspGraph::split
+//! (a , b) :> sum         DspGraph::merge
+//! ```
+//!
+//! A [`DspGraph`] implements [`FaustDsp`], so it can be driven by a [`DspHandle`](crate::DspHandle).
+//! The parameters of every dsp are exposed below its name, e.g. `eq/gain`, with the outermost group
+//! of the dsp replaced by the name.
+
+use crate::{
+    params::{OffsetUi, ParamsBuilder},
+    DEFAULT_BLOCK_SIZE,
+};
+use faust_types::{
+    buffers::{with_channels, with_channels_mut},
+    *,
+};
+
+struct Leaf {
+    name: String,
+    dsp: Box<dyn FaustDsp<T = f32>>,
+    /// Index of the first parameter of the dsp in the graph.
+    offset: i32,
+    params: i32,
+}
+
+enum Node {
+    Dsp(Leaf),
+    Wires(usize),
+    Sequential(Box<Self>, Box<Self>, Vec<Vec<f32>>),
+    Parallel(Box<Self>, Box<Self>),
+    Split(Box<Self>, Box<Self>, Vec<Vec<f32>>),
+    /// Buffers for the outputs of the first node and the summed inputs of the second.
+    Merge(Box<Self>, Box<Self>, Vec<Vec<f32>>, Vec<Vec<f32>>),
+}
+
+impl Node {
+    fn inputs(&self) -> usize {
+        match self {
+            Self::Dsp(leaf) => leaf.dsp.get_num_inputs() as usize,
+            Self::Wires(channels) => *channels,
+            Self::Parallel(a, b) => a.inputs() + b.inputs(),
+            Self::Sequential(a, ..) | Self::Split(a, ..) | Self::Merge(a, ..) => a.inputs(),
+        }
+    }
+
+    fn outputs(&self) -> usize {
+        match self {
+            Self::Dsp(leaf) => leaf.dsp.get_num_outputs() as usize,
+            Self::Wires(channels) => *channels,
+            Self::Parallel(a, b) => a.outputs() + b.outputs(),
+            Self::Sequential(_, b, _) | Self::Split(_, b, _) | Self::Merge(_, b, ..) => b.outputs(),
+        }
+    }
+
+    fn children(&self) -> Option<(&Self, &Self)> {
+        match self {
+            Self::Dsp(_) | Self::Wires(_) => None,
+            Self::Sequential(a, b, ..)
+            | Self::Parallel(a, b)
+            | Self::Split(a, b, ..)
+            | Self::Merge(a, b, ..) => Some((a, b)),
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<(&mut Self, &mut Self)> {
+        match self {
+            Self::Dsp(_) | Self::Wires(_) => None,
+            Self::Sequential(a, b, ..)
+            | Self::Parallel(a, b)
+            | Self::Split(a, b, ..)
+            | Self::Merge(a, b, ..) => Some((a, b)),
+        }
+    }
+
+    fn params(&self) -> i32 {
+        let mut params = 0;
+        self.for_each_leaf(&mut |leaf| params += leaf.params);
+        params
+    }
+
+    fn for_each_leaf<'a>(&'a self, f: &mut impl FnMut(&'a Leaf)) {
+        if let Self::Dsp(leaf) = self {
+            f(leaf);
+        } else if let Some((a, b)) = self.children() {
+            a.for_each_leaf(f);
+            b.for_each_leaf(f);
+        }
+    }
+
+    fn for_each_leaf_mut(&mut self, f: &mut impl FnMut(&mut Leaf)) {
+        if let Self::Dsp(leaf) = self {
+            f(leaf);
+        } else if let Some((a, b)) = self.children_mut() {
+            a.for_each_leaf_mut(f);
+            b.for_each_leaf_mut(f);
+        }
+    }
+
+    fn leaf(&self, param: ParamIndex) -> Option<&Leaf> {
+        if let Self::Dsp(leaf) = self {
+            return leaf.contains(param).then_some(leaf);
+        }
+        let (a, b) = self.children()?;
+        a.leaf(param).or_else(|| b.leaf(param))
+    }
+
+    fn leaf_mut(&mut self, param: ParamIndex) -> Option<&mut Leaf> {
+        if let Self::Dsp(leaf) = self {
+            return leaf.contains(param).then_some(leaf);
+        }
+        let (a, b) = self.children_mut()?;
+        a.leaf_mut(param).or_else(move || b.leaf_mut(param))
+    }
+
+    /// Allocates the buffers between the nodes, `block_size` frames per channel.
+    fn allocate(&mut self, block_size: usize) {
+        let buffers = |channels| vec![vec![0.0; block_size]; channels];
+        match self {
+            Self::Dsp(_) | Self::Wires(_) | Self::Parallel(..) => {}
+            Self::Sequential(a, _, outputs) | Self::Split(a, _, outputs) => {
+                *outputs = buffers(a.outputs());
+            }
+            Self::Merge(a, b, outputs, sums) => {
+                *outputs = buffers(a.outputs());
+                *sums = buffers(b.inputs());
+            }
+        }
+        if let Some((a, b)) = self.children_mut() {
+            a.allocate(block_size);
+            b.allocate(block_size);
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Dsp(leaf) => leaf.dsp.instance_clear(),
+            Self::Wires(_) | Self::Parallel(..) => {}
+            Self::Sequential(_, _, outputs) | Self::Split(_, _, outputs) => {
+                for buffer in outputs.iter_mut() {
+                    buffer.fill(0.0);
+                }
+            }
+            Self::Merge(_, _, outputs, sums) => {
+                for buffer in outputs.iter_mut().chain(sums) {
+                    buffer.fill(0.0);
+                }
+            }
+        }
+        if let Some((a, b)) = self.children_mut() {
+            a.clear();
+            b.clear();
+        }
+    }
+
+    /// `count` is at most the length of the buffers.
+    fn compute(&mut self, count: i32, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        let frames = count as usize;
+        match self {
+            Self::Dsp(leaf) => leaf.dsp.compute(count, inputs, outputs),
+            Self::Wires(_) => {
+                for (output, input) in outputs.iter_mut().zip(inputs) {
+                    output[..frames].copy_from_slice(&input[..frames]);
+                }
+            }
+            Self::Sequential(a, b, buffers) => {
+                with_channels_mut(slices_mut(buffers, frames), |buffers| {
+                    a.compute(count, inputs, buffers);
+                });
+                with_channels(slices(buffers, frames), |buffers| {
+                    b.compute(count, buffers, outputs);
+                });
+            }
+            Self::Parallel(a, b) => {
+                let (a_inputs, b_inputs) = inputs.split_at(a.inputs());
+                let (a_outputs, b_outputs) = outputs.split_at_mut(a.outputs());
+                a.compute(count, a_inputs, a_outputs);
+                b.compute(count, b_inputs, b_outputs);
+            }
+            Self::Split(a, b, buffers) => {
+                with_channels_mut(slices_mut(buffers, frames), |buffers| {
+                    a.compute(count, inputs, buffers);
+                });
+                let split = (0..b.inputs()).map(|i| &buffers[i % buffers.len()][..frames]);
+                with_channels(split, |split| b.compute(count, split, outputs));
+            }
+            Self::Merge(a, b, buffers, sums) => {
+                with_channels_mut(slices_mut(buffers, frames), |buffers| {
+                    a.compute(count, inputs, buffers);
+                });
+                for sum in sums.iter_mut() {
+                    sum[..frames].fill(0.0);
+                }
+                let channels = sums.len();
+                for (i, buffer) in buffers.iter().enumerate() {
+                    for (sum, sample) in sums[i % channels][..frames].iter_mut().zip(buffer) {
+                        *sum += sample;
+                    }
+                }
+                with_channels(slices(sums, frames), |sums| b.compute(count, sums, outputs));
+            }
+        }
+    }
+}
+
+impl Leaf {
+    fn contains(&self, param: ParamIndex) -> bool {
+        (self.offset..self.offset + self.params).contains(&param.0)
+    }
+}
+
+fn slices(buffers: &[Vec<f32>], frames: usize) -> impl ExactSizeIterator<Item = &[f32]> {
+    buffers.iter().map(move |buffer| &buffer[..frames])
+}
+
+fn slices_mut(
+    buffers: &mut [Vec<f32>],
+    frames: usize,
+) -> impl ExactSizeIterator<Item = &mut [f32]> {
+    buffers.iter_mut().map(move |buffer| &mut buffer[..frames])
+}
+
+/// Dsps combined with sequential, parallel, split and merge composition.
+///
+/// Channel counts are checked when combining and the buffers between the dsps are allocated up front,
+/// so computing does not allocate for up to [`MAX_CHANNELS`](faust_types::buffers::MAX_CHANNELS)
+/// channels between two dsps.
+pub struct DspGraph {
+    root: Node,
+    block_size: usize,
+    sample_rate: i32,
+}
+
+impl DspGraph {
+    /// A graph of a single dsp, whose parameters are exposed below `name`.
+    pub fn dsp(name: impl Into<String>, dsp: Box<dyn FaustDsp<T = f32>>) -> Self {
+        let mut builder = ParamsBuilder::new();
+        dsp.build_user_interface(&mut builder);
+        let params = builder.inner.keys().max().map_or(0, |max| max + 1);
+        Self::from_node(Node::Dsp(Leaf {
+            name: name.into(),
+            dsp,
+            offset: 0,
+            params,
+        }))
+    }
+
+    /// Passes `channels` channels through unchanged, like `_` in faust.
+    #[must_use]
+    pub fn wires(channels: usize) -> Self {
+        Self::from_node(Node::Wires(channels))
+    }
+
+    fn from_node(root: Node) -> Self {
+        let mut graph = Self {
+            root,
+            block_size: DEFAULT_BLOCK_SIZE,
+            sample_rate: 0,
+        };
+        graph.allocate();
+        graph
+    }
+
+    /// Frames computed at once by the dsps. Longer blocks are computed in several steps.
+    #[must_use]
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must not be zero");
+        self.block_size = block_size;
+        self.allocate();
+        self
+    }
+
+    #[must_use]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    #[must_use]
+    pub fn inputs(&self) -> usize {
+        self.root.inputs()
+    }
+
+    #[must_use]
+    pub fn outputs(&self) -> usize {
+        self.root.outputs()
+    }
+
+    /// Names of the dsps in the order of their parameters.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.root
+            .for_each_leaf(&mut |leaf| names.push(leaf.name.as_str()));
+        names
+    }
+
+    /// `self : next`, the outputs of `self` are the inputs of `next`.
+    pub fn seq(self, next: Self) -> Result<Self, String> {
+        if self.outputs() != next.inputs() {
+            return Err(format!(
+                "sequential composition needs as many inputs ({}) as outputs ({})",
+                next.inputs(),
+                self.outputs()
+            ));
+        }
+        self.combine(next, |a, b| Node::Sequential(a, b, Vec::new()))
+    }
+
+    /// `self , other`, both side by side.
+    pub fn par(self, other: Self) -> Result<Self, String> {
+        self.combine(other, Node::Parallel)
+    }
+
+    /// `self <: next`, the outputs of `self` are repeated over the inputs of `next`.
+    pub fn split(self, next: Self) -> Result<Self, String> {
+        let (outputs, inputs) = (self.outputs(), next.inputs());
+        if outputs == 0 || inputs % outputs != 0 {
+            return Err(format!(
+                "split composition needs a multiple of {outputs} inputs, got {inputs}"
+            ));
+        }
+        self.combine(next, |a, b| Node::Split(a, b, Vec::new()))
+    }
+
+    /// `self :> next`, the outputs of `self` are summed into the inputs of `next`.
+    pub fn merge(self, next: Self) -> Result<Self, String> {
+        let (outputs, inputs) = (self.outputs(), next.inputs());
+        if inputs == 0 || outputs % inputs != 0 {
+            return Err(format!(
+                "merge composition needs a multiple of {inputs} outputs, got {outputs}"
+            ));
+        }
+        self.combine(next, |a, b| Node::Merge(a, b, Vec::new(), Vec::new()))
+    }
+
+    fn combine(
+        self,
+        mut other: Self,
+        node: impl FnOnce(Box<Node>, Box<Node>) -> Node,
+    ) -> Result<Self, String> {
+        let names = self.names();
+        if let Some(name) = other.names().into_iter().find(|name| names.contains(name)) {
+            return Err(format!("dsp name {name} is used twice"));
+        }
+        let offset = self.root.params();
+        other
+            .root
+            .for_each_leaf_mut(&mut |leaf| leaf.offset += offset);
+        let mut graph = Self {
+            root: node(Box::new(self.root), Box::new(other.root)),
+            block_size: self.block_size.max(other.block_size),
+            sample_rate: self.sample_rate,
+        };
+        graph.allocate();
+        Ok(graph)
+    }
+
+    fn allocate(&mut self) {
+        self.root.allocate(self.block_size);
+    }
+}
+
+/// Declares the metadata of a dsp as `name/key`.
+struct LeafMeta<'a> {
+    meta: &'a mut dyn Meta,
+    name: &'a str,
+}
+
+impl Meta for LeafMeta<'_> {
+    fn declare(&mut self, key: &str, value: &str) {
+        self.meta.declare(&format!("{}/{key}", self.name), value);
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+impl FaustDsp for DspGraph {
+    type T = f32;
+
+    /// An empty graph without channels.
+    fn new() -> Self {
+        Self::wires(0)
+    }
+    fn metadata(&self, m: &mut dyn Meta) {
+        self.root.for_each_leaf(&mut |leaf| {
+            leaf.dsp.metadata(&mut LeafMeta {
+                meta: &mut *m,
+                name: &leaf.name,
+            });
+        });
+    }
+    fn get_sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+    fn get_num_inputs(&self) -> i32 {
+        self.inputs() as i32
+    }
+    fn get_num_outputs(&self) -> i32 {
+        self.outputs() as i32
+    }
+    /// The dsps are initialized by [`FaustDsp::init`].
+    fn class_init(_sample_rate: i32) {}
+    fn instance_reset_params(&mut self) {
+        self.root
+            .for_each_leaf_mut(&mut |leaf| leaf.dsp.instance_reset_params());
+    }
+    fn instance_clear(&mut self) {
+        self.root.clear();
+    }
+    fn instance_constants(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate;
+        self.root
+            .for_each_leaf_mut(&mut |leaf| leaf.dsp.instance_constants(sample_rate));
+    }
+    fn instance_init(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate;
+        self.root
+            .for_each_leaf_mut(&mut |leaf| leaf.dsp.instance_init(sample_rate));
+    }
+    fn init(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate;
+        self.root
+            .for_each_leaf_mut(&mut |leaf| leaf.dsp.init(sample_rate));
+    }
+    fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+        ui_interface.open_vertical_box("graph");
+        self.root.for_each_leaf(&mut |leaf| {
+            ui_interface.open_vertical_box(&leaf.name);
+            leaf.dsp
+                .build_user_interface(&mut OffsetUi::without_outer_group(
+                    &mut *ui_interface,
+                    leaf.offset,
+                ));
+            ui_interface.close_box();
+        });
+        ui_interface.close_box();
+    }
+    /// The dsps of a graph are only known to an instance, see [`FaustDsp::build_user_interface`].
+    fn build_user_interface_static(_ui_interface: &mut dyn UI<Self::T>) {}
+    fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+        let leaf = self.root.leaf(param)?;
+        leaf.dsp.get_param(ParamIndex(param.0 - leaf.offset))
+    }
+    fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+        if let Some(leaf) = self.root.leaf_mut(param) {
+            let param = ParamIndex(param.0 - leaf.offset);
+            leaf.dsp.set_param(param, value);
+        }
+    }
+    fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]) {
+        let count = count as usize;
+        let mut start = 0;
+        while start < count {
+            let end = count.min(start + self.block_size);
+            let block_inputs = inputs.iter().map(|input| &input[start..end]);
+            let block_outputs = outputs.iter_mut().map(|output| &mut output[start..end]);
+            let root = &mut self.root;
+            with_channels(block_inputs, |inputs| {
+                with_channels_mut(block_outputs, |outputs| {
+                    root.compute((end - start) as i32, inputs, outputs);
+                });
+            });
+            start = end;
+        }
+    }
+}
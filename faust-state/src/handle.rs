@@ -0,0 +1,387 @@
+use crate::{
+    params::{MetaBuilder, ParamsBuilder},
+    poly::{Instrument, NoteEvent},
+    soundfile::{SoundfileDecl, SoundfileLoader},
+    Node,
+};
+use faust_types::*;
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::collections::{BTreeMap, HashMap};
+
+const DEFAULT_NAME: &str = "rust_faust";
+
+/// Notes the [`StateHandle`] can send before the dsp computes the next block.
+const NOTE_QUEUE_SIZE: usize = 256;
+
+#[derive(Debug)]
+pub struct DspHandle<T> {
+    dsp: Box<T>,
+    dsp_tx: Producer<State>,
+    dsp_rx: Consumer<State>,
+    notes_rx: Consumer<NoteEvent>,
+    /// Set by [`DspHandle::from_instrument`].
+    play_note: Option<fn(&mut T, NoteEvent)>,
+    name: String,
+    initialized: bool,
+}
+
+impl<T> DspHandle<T>
+where
+    T: FaustDsp<T = f32> + 'static,
+{
+    #[must_use]
+    pub fn new() -> (Self, StateHandle) {
+        let dsp = Box::new(T::new());
+        Self::from_dsp(dsp)
+    }
+
+    pub fn from_dsp(dsp: Box<T>) -> (Self, StateHandle) {
+        let meta = MetaBuilder::from_dsp(&*dsp);
+        let params = ParamsBuilder::from_dsp(&*dsp);
+        let name = meta
+            .get("name")
+            .map_or(DEFAULT_NAME, String::as_str)
+            .to_string();
+
+        let (dsp_tx, main_rx) = RingBuffer::new(1).split();
+        let (main_tx, dsp_rx) = RingBuffer::new(1).split();
+        let (notes_tx, notes_rx) = RingBuffer::new(NOTE_QUEUE_SIZE).split();
+
+        let this = {
+            Self {
+                name: name.clone(),
+                dsp,
+                dsp_tx,
+                dsp_rx,
+                notes_rx,
+                play_note: None,
+                initialized: false,
+            }
+        };
+        let mut state = State {
+            updates: HashMap::with_capacity(params.len()),
+            state: HashMap::with_capacity(params.len()),
+        };
+
+        let mut params_by_path = BTreeMap::new();
+        for (idx, node) in &params {
+            params_by_path.insert(node.path(), *idx);
+            state.state.insert(*idx, node.widget_type().init_value());
+        }
+
+        let state_handle = StateHandle {
+            name,
+            state,
+            meta,
+            params,
+            params_by_path,
+            main_rx,
+            main_tx,
+            notes_tx,
+        };
+        (this, state_handle)
+    }
+
+    pub fn update_and_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let mut state = self.dsp_rx.pop().map_or(None, |state| {
+            self.update_params_from_state(&state);
+            Some(state)
+        });
+        while let Ok(event) = self.notes_rx.pop() {
+            self.handle_note(event);
+        }
+
+        // Potentially improves the performance of SIMD floating-point math
+        // by flushing denormals/underflow to zero.
+        // See: https://gist.github.com/GabrielMajeri/545042ee4f956d5b2141105eb6a505a9
+        // See: https://github.com/grame-cncm/faust/blob/master-dev/architecture/faust/dsp/dsp.h#L236
+        let mask = if cfg!(any(target_arch = "arm", target_arch = "aarch64")) {
+            1 << 24 // FZ
+        } else if cfg!(any(target_feature = "sse2")) {
+            0x8040
+        } else if cfg!(any(target_feature = "sse")) {
+            0x8000
+        } else {
+            0x0000
+        };
+        // Set fp status register to masked value
+        let fpsr = self.get_fp_status_register();
+        if let Some(fpsr) = fpsr {
+            self.set_fp_status_register(fpsr | mask);
+        }
+
+        self.compute(count, inputs, outputs);
+
+        // Reset fp status register to old value
+        if let Some(fpsr) = fpsr {
+            self.set_fp_status_register(fpsr);
+        }
+
+        if !self.dsp_tx.is_full() && state.is_some() {
+            let mut state = state.take().expect("cannot fail");
+            self.update_state_from_params(&mut state);
+            let _ = self.dsp_tx.push(state);
+        }
+    }
+
+    /// Like [`DspHandle::update_and_compute`], but returns an error instead of computing with
+    /// buffers that do not match the dsp.
+    pub fn checked_update_and_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) -> Result<(), ComputeError> {
+        self.dsp.check_compute(count, inputs, outputs)?;
+        self.update_and_compute(count, inputs, outputs);
+        Ok(())
+    }
+
+    // Gets the fp status register.
+    // Needed for flushing denormals
+    #[allow(unreachable_code)]
+    fn get_fp_status_register(&self) -> Option<u32> {
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        unsafe {
+            use std::arch::asm;
+            let fspr: u32;
+            asm!("msr fpcr, {0:r}", out(reg) fspr);
+            return Some(fspr);
+        }
+        #[cfg(target_feature = "sse")]
+        unsafe {
+            use std::arch::x86_64::*;
+            return Some(_mm_getcsr());
+        }
+        None
+    }
+
+    // Sets the fp status register.
+    // Needed for flushing denormals
+    #[allow(unreachable_code)]
+    fn set_fp_status_register(&self, fspr: u32) {
+        #[cfg(any(target_arch = "arm", target_arch = "aarch64"))]
+        unsafe {
+            use std::arch::asm;
+            asm!("mrs {0:r}, fpcr", in(reg) fspr);
+            return;
+        }
+        #[cfg(target_feature = "sse")]
+        unsafe {
+            use std::arch::x86_64::*;
+            _mm_setcsr(fspr);
+        }
+    }
+
+    pub fn update_params_from_state(&mut self, state: &State) {
+        for (idx, value) in &state.updates {
+            let idx = ParamIndex(*idx);
+            self.dsp.set_param(idx, *value);
+        }
+    }
+
+    pub fn update_state_from_params(&self, state: &mut State) {
+        for (idx, value) in &mut state.state {
+            let idx = ParamIndex(*idx);
+            if let Some(new_value) = self.dsp.get_param(idx) {
+                *value = new_value;
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sets a parameter directly on the dsp, e.g. from the audio thread. The [`StateHandle`] sees
+    /// the new value after its next [`StateHandle::update`].
+    pub fn set_param(&mut self, idx: ParamIndex, value: f32) {
+        self.dsp.set_param(idx, value);
+    }
+
+    /// Plays a note directly on the dsp, e.g. from the audio thread. Ignored unless the handle was
+    /// created by [`DspHandle::from_instrument`].
+    pub fn handle_note(&mut self, event: NoteEvent) {
+        if let Some(play_note) = self.play_note {
+            play_note(&mut self.dsp, event);
+        }
+    }
+
+    pub fn plays_notes(&self) -> bool {
+        self.play_note.is_some()
+    }
+
+    // fn get_param(&self, param: ParamIndex) -> Option<Self::T>;
+    // fn set_param(&mut self, param: ParamIndex, value: Self::T);
+    pub fn compute(&mut self, count: i32, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        self.dsp.compute(count, inputs, outputs);
+    }
+
+    pub fn checked_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) -> Result<(), ComputeError> {
+        self.dsp.checked_compute(count, inputs, outputs)
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.dsp.get_num_inputs() as usize
+    }
+
+    pub fn num_outputs(&self) -> usize {
+        self.dsp.get_num_outputs() as usize
+    }
+
+    pub fn init(&mut self, sample_rate: i32) {
+        self.dsp.init(sample_rate);
+        self.initialized = true;
+    }
+}
+
+impl<T> DspHandle<T>
+where
+    T: Instrument + FaustDsp<T = f32> + 'static,
+{
+    /// Like [`DspHandle::from_dsp`], and the notes sent with [`StateHandle::send_note`] or
+    /// [`DspHandle::handle_note`] are played by `dsp`, e.g. a [`PolyDsp`](crate::poly::PolyDsp).
+    pub fn from_instrument(dsp: Box<T>) -> (Self, StateHandle) {
+        let (mut this, state) = Self::from_dsp(dsp);
+        this.play_note = Some(T::handle_note);
+        (this, state)
+    }
+}
+
+impl<T> DspHandle<T>
+where
+    T: SoundfileDsp<T = f32> + 'static,
+{
+    /// Soundfiles declared by the dsp, ordered by their index.
+    #[must_use]
+    pub fn soundfiles(&self) -> Vec<SoundfileDecl> {
+        ParamsBuilder::soundfiles_from_dsp::<T>()
+    }
+
+    /// Sets the audio data of a soundfile, which is only possible before [`DspHandle::init`].
+    pub fn set_soundfile(&mut self, index: SoundfileIndex, data: Soundfile) -> Result<(), String> {
+        if self.initialized {
+            return Err(format!(
+                "soundfile {} cannot be set after the dsp was initialized",
+                index.0
+            ));
+        }
+        self.dsp.set_soundfile(index, data);
+        Ok(())
+    }
+
+    /// Loads every soundfile declared by the dsp from the files of its url.
+    pub fn load_soundfiles(&mut self, loader: &SoundfileLoader) -> Result<(), String> {
+        for soundfile in self.soundfiles() {
+            let data = loader.load(&soundfile.url)?;
+            self.set_soundfile(soundfile.index, data)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct State {
+    pub state: HashMap<i32, f32>,
+    pub updates: HashMap<i32, f32>,
+}
+
+impl State {
+    pub fn insert(&mut self, idx: i32, value: f32) {
+        self.updates.insert(idx, value);
+        self.state.insert(idx, value);
+    }
+}
+
+#[derive(Debug)]
+pub struct StateHandle {
+    name: String,
+    pub state: State,
+    meta: HashMap<String, String>,
+    params: HashMap<i32, Node>,
+    params_by_path: BTreeMap<String, i32>,
+    main_rx: Consumer<State>,
+    main_tx: Producer<State>,
+    notes_tx: Producer<NoteEvent>,
+}
+
+impl StateHandle {
+    pub fn set_param(&mut self, idx: i32, value: f32) {
+        self.state.insert(idx, value);
+    }
+
+    pub fn get_param(&self, idx: i32) -> Option<&f32> {
+        self.state.state.get(&idx)
+    }
+
+    pub fn set_by_path(&mut self, path: &str, value: f32) -> Result<(), String> {
+        let idx = if let Some(idx) = self.params_by_path.get(path) {
+            Some(*idx)
+        } else {
+            return Err("No such path".into());
+        };
+        if let Some(idx) = idx {
+            self.set_param(idx, value);
+        }
+        Ok(())
+    }
+
+    pub fn get_by_path(&self, path: &str) -> Option<&f32> {
+        self.params_by_path
+            .get(path)
+            .and_then(|idx| self.get_param(*idx))
+    }
+
+    /// Queues a note that is played before the next block in [`DspHandle::update_and_compute`].
+    pub fn send_note(&mut self, event: NoteEvent) -> Result<(), String> {
+        self.notes_tx
+            .push(event)
+            .map_err(|_| "note queue is full".to_string())
+    }
+
+    pub fn send(&mut self) {
+        self.update();
+    }
+
+    pub fn update(&mut self) {
+        if let Ok(state) = self.main_rx.pop() {
+            self.state.state = state.state;
+        }
+        if !self.main_tx.is_full() {
+            let state = self.state.clone();
+            if let Err(e) = self.main_tx.push(state) {
+                eprintln!("error sending state update: {e}");
+            } else {
+                self.state.updates.clear();
+            }
+        }
+    }
+
+    pub fn params(&self) -> &HashMap<i32, Node> {
+        &self.params
+    }
+
+    pub fn params_by_path(&self) -> impl Iterator<Item = (&String, Option<&f32>)> {
+        self.params_by_path
+            .iter()
+            .map(move |(path, idx)| (path, self.get_param(*idx)))
+    }
+
+    pub fn meta(&self) -> &HashMap<String, String> {
+        &self.meta
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -0,0 +1,413 @@
+use crate::soundfile::SoundfileDecl;
+use faust_types::*;
+use std::{collections::HashMap, ops::RangeInclusive};
+
+pub struct MetaBuilder {
+    inner: HashMap<String, String>,
+}
+
+impl MetaBuilder {
+    pub(crate) fn from_dsp<T>(dsp: &impl FaustDsp<T = T>) -> HashMap<String, String> {
+        let mut metadata = Self {
+            inner: HashMap::new(),
+        };
+        dsp.metadata(&mut metadata);
+        metadata.inner
+    }
+}
+
+impl faust_types::Meta for MetaBuilder {
+    fn declare(&mut self, key: &str, value: &str) {
+        self.inner.insert(key.into(), value.into());
+    }
+}
+
+#[derive(Debug)]
+pub struct ParamsBuilder {
+    pub(crate) inner: HashMap<i32, Node>,
+    soundfiles: Vec<SoundfileDecl>,
+    prefix: Vec<String>,
+    first_group: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    pub(crate) label: String,
+    prefix: String,
+    typ: WidgetType,
+    metadata: Vec<[String; 2]>,
+}
+
+impl Node {
+    #[must_use]
+    pub fn path(&self) -> String {
+        let mut path = self.prefix.clone();
+        if !path.is_empty() {
+            path += "/";
+        }
+        path += &self.label;
+        path
+    }
+
+    #[must_use]
+    pub fn widget_type(&self) -> &WidgetType {
+        &self.typ
+    }
+
+    /// Metadata declared for the widget, e.g. `["midi", "ctrl 7"]`.
+    #[must_use]
+    pub fn metadata(&self) -> &[[String; 2]] {
+        &self.metadata
+    }
+}
+
+/// General types of widgets declared in the DSP
+#[derive(Debug, Clone, Default)]
+pub enum WidgetType {
+    /// Only has metadata
+    /// There should not be any after building the DSP.
+    #[default]
+    Unknown,
+    /// Temporary on button.
+    Button,
+    /// Stable on/off button.
+    Toggle,
+    /// Vertical slider
+    VerticalSlider(RangedInput),
+    /// Horizontal slider
+    HorizontalSlider(RangedInput),
+    /// Numeric entry
+    NumEntry(RangedInput),
+    /// Horizontal bargraph
+    HorizontalBarGraph(RangedOutput),
+    /// Vertical bargraph
+    VerticalBargraph(RangedOutput),
+}
+
+impl WidgetType {
+    /// Retrieve the init value for this widget
+    #[must_use]
+    pub fn init_value(&self) -> f32 {
+        match self {
+            Self::NumEntry(input) | Self::HorizontalSlider(input) | Self::VerticalSlider(input) => {
+                input.init
+            }
+            // Buttons and checkboxes are off by default.
+            // Passive widgets will need an update from the DSP before having a value
+            _ => 0.0,
+        }
+    }
+}
+
+/// A ranged input controlled by the user.
+#[derive(Debug, Clone)]
+pub struct RangedInput {
+    /// Initial value defined in the DSP
+    pub init: f32,
+    /// Available range defined in the DSP
+    /// This range is declared but not enforced
+    pub range: RangeInclusive<f32>,
+    /// Precision of the value
+    /// This value is declared but not enforced
+    pub step: f32,
+}
+
+impl RangedInput {
+    #[must_use]
+    pub fn new(init: f32, min: f32, max: f32, step: f32) -> Self {
+        Self {
+            init,
+            range: min..=max,
+            step,
+        }
+    }
+}
+
+/// A ranged output value controlled by the DSP.
+#[derive(Debug, Clone)]
+pub struct RangedOutput {
+    /// Declared range of the widget
+    /// This value is declared but not enforced
+    pub range: RangeInclusive<f32>,
+}
+
+impl RangedOutput {
+    #[must_use]
+    pub fn new(min: f32, max: f32) -> Self {
+        Self { range: min..=max }
+    }
+}
+
+impl ParamsBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: HashMap::new(),
+            soundfiles: Vec::new(),
+            first_group: true,
+            prefix: Vec::new(),
+            // state: Vec::new(),
+        }
+    }
+    pub(crate) fn from_dsp(dsp: &impl FaustDsp<T = f32>) -> HashMap<i32, Node> {
+        let mut builder = Self::new();
+        dsp.build_user_interface(&mut builder);
+        builder.inner
+    }
+    pub(crate) fn soundfiles_from_dsp<D: SoundfileDsp<T = f32>>() -> Vec<SoundfileDecl> {
+        let mut builder = Self::new();
+        D::build_soundfile_interface(&mut builder);
+        builder
+            .soundfiles
+            .sort_by_key(|soundfile| soundfile.index.0);
+        builder.soundfiles
+    }
+
+    fn open_group(&mut self, label: &str) {
+        if self.first_group {
+            self.first_group = false;
+        } else {
+            self.prefix.push(label.into());
+        }
+    }
+    fn close_group(&mut self) {
+        self.prefix.pop();
+    }
+
+    fn add_or_update_widget(
+        &mut self,
+        label: &str,
+        idx: ParamIndex,
+        typ: WidgetType,
+        metadata: Option<Vec<[String; 2]>>,
+    ) {
+        let prefix = self.prefix[..].join("/");
+        let idx = idx.0;
+        if let std::collections::hash_map::Entry::Vacant(e) = self.inner.entry(idx) {
+            let node = Node {
+                label: label.to_string(),
+                prefix,
+                typ,
+                metadata: metadata.unwrap_or_default(),
+            };
+            e.insert(node);
+        } else {
+            let node = self.inner.get_mut(&idx).expect("ParamIndex not valid");
+            node.label = label.to_string();
+            node.typ = typ;
+            if let Some(mut metadata) = metadata {
+                node.metadata.append(metadata.as_mut());
+            }
+        }
+    }
+}
+
+impl UI<f32> for ParamsBuilder {
+    fn open_tab_box(&mut self, label: &str) {
+        self.open_group(label);
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.open_group(label);
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.open_group(label);
+    }
+    fn close_box(&mut self) {
+        self.close_group();
+    }
+
+    // -- active widgets
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        self.add_or_update_widget(label, param, WidgetType::Button, None);
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        self.add_or_update_widget(label, param, WidgetType::Toggle, None);
+    }
+    fn add_vertical_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        let typ = WidgetType::VerticalSlider(RangedInput::new(init, min, max, step));
+        self.add_or_update_widget(label, param, typ, None);
+    }
+    fn add_horizontal_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        let typ = WidgetType::HorizontalSlider(RangedInput::new(init, min, max, step));
+        self.add_or_update_widget(label, param, typ, None);
+    }
+    fn add_num_entry(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        let typ = WidgetType::NumEntry(RangedInput::new(init, min, max, step));
+        self.add_or_update_widget(label, param, typ, None);
+    }
+
+    // -- passive widgets
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        let typ = WidgetType::HorizontalBarGraph(RangedOutput::new(min, max));
+        self.add_or_update_widget(label, param, typ, None);
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        let typ = WidgetType::VerticalBargraph(RangedOutput::new(min, max));
+        self.add_or_update_widget(label, param, typ, None);
+    }
+
+    // -- soundfiles
+    fn add_soundfile(&mut self, label: &str, url: &str, soundfile: SoundfileIndex) {
+        let mut path = self.prefix.clone();
+        path.push(label.to_string());
+        self.soundfiles.push(SoundfileDecl {
+            path: path.join("/"),
+            url: url.to_string(),
+            index: soundfile,
+        });
+    }
+
+    // -- metadata declarations
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        if let Some(param_index) = param {
+            if !self.inner.contains_key(&param_index.0) {
+                self.add_or_update_widget(
+                    "Unknown",
+                    param_index,
+                    WidgetType::default(),
+                    Some(vec![[key.to_string(), value.to_string()]]),
+                );
+            } else if let Some(node) = self.inner.get_mut(&param_index.0) {
+                node.metadata.push([key.to_string(), value.to_string()]);
+            }
+        }
+    }
+}
+
+/// Exposes the parameters of a dsp that is part of another dsp, with indices shifted by `offset`.
+pub struct OffsetUi<'a> {
+    ui: &'a mut dyn UI<f32>,
+    offset: i32,
+    depth: usize,
+}
+
+impl<'a> OffsetUi<'a> {
+    pub fn new(ui: &'a mut dyn UI<f32>, offset: i32) -> Self {
+        Self {
+            ui,
+            offset,
+            depth: 1,
+        }
+    }
+
+    /// Drops the outermost group of the dsp, e.g. to open one with another name instead.
+    pub fn without_outer_group(ui: &'a mut dyn UI<f32>, offset: i32) -> Self {
+        Self {
+            ui,
+            offset,
+            depth: 0,
+        }
+    }
+
+    fn param(&self, param: ParamIndex) -> ParamIndex {
+        ParamIndex(param.0 + self.offset)
+    }
+
+    fn open(&mut self, label: &str, open: impl FnOnce(&mut dyn UI<f32>, &str)) {
+        if self.depth > 0 {
+            open(self.ui, label);
+        }
+        self.depth += 1;
+    }
+}
+
+impl UI<f32> for OffsetUi<'_> {
+    fn open_tab_box(&mut self, label: &str) {
+        self.open(label, |ui, label| ui.open_tab_box(label));
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.open(label, |ui, label| ui.open_horizontal_box(label));
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.open(label, |ui, label| ui.open_vertical_box(label));
+    }
+    fn close_box(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            self.ui.close_box();
+        }
+    }
+
+    // -- active widgets
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        self.ui.add_button(label, self.param(param));
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        self.ui.add_check_button(label, self.param(param));
+    }
+    fn add_vertical_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        self.ui
+            .add_vertical_slider(label, self.param(param), init, min, max, step);
+    }
+    fn add_horizontal_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        self.ui
+            .add_horizontal_slider(label, self.param(param), init, min, max, step);
+    }
+    fn add_num_entry(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        self.ui
+            .add_num_entry(label, self.param(param), init, min, max, step);
+    }
+
+    // -- passive widgets
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        self.ui
+            .add_horizontal_bargraph(label, self.param(param), min, max);
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        self.ui
+            .add_vertical_bargraph(label, self.param(param), min, max);
+    }
+
+    // -- metadata declarations
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        let param = param.map(|param| self.param(param));
+        self.ui.declare(param, key, value);
+    }
+}
@@ -0,0 +1,689 @@
+//! Polyphonic instruments built from a monophonic dsp.
+//!
+//! Voices are played through the parameters faust instruments conventionally declare: `freq` or `key`
+//! for the pitch, `gate` and `gain`, `vel` or `velocity` for the velocity. All other parameters are
+//! shared, setting them changes every voice.
+
+use crate::{
+    params::{MetaBuilder, OffsetUi, ParamsBuilder},
+    DEFAULT_BLOCK_SIZE,
+};
+use faust_types::{
+    buffers::{with_channels, with_channels_mut},
+    *,
+};
+
+/// Number of voices if the dsp does not declare `options "[nvoices:n]"`.
+pub const DEFAULT_VOICES: usize = 8;
+
+/// Output level below which a released voice counts as silent and is free again.
+const SILENCE: f32 = 1e-5;
+
+/// Which voice is taken for a new note when all voices are busy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VoiceStealing {
+    /// The voice whose note started first.
+    #[default]
+    Oldest,
+    /// The voice with the lowest output level in the last block.
+    Quietest,
+    /// The voice playing the same note, even if other voices are free. Otherwise the oldest voice.
+    SameNote,
+}
+
+/// A note for an instrument, e.g. from a MIDI input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteEvent {
+    /// A midi velocity of `0..=127`, where `0` releases the note like [`NoteEvent::Off`].
+    On {
+        note: u8,
+        velocity: u8,
+    },
+    Off {
+        note: u8,
+    },
+    AllOff,
+}
+
+/// A dsp that plays notes, see [`DspHandle::from_instrument`](crate::DspHandle::from_instrument).
+pub trait Instrument {
+    fn handle_note(&mut self, event: NoteEvent);
+}
+
+/// The conventional voice parameters of a dsp.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VoiceControls {
+    pub freq: Option<ParamIndex>,
+    pub key: Option<ParamIndex>,
+    pub gate: Option<ParamIndex>,
+    pub gain: Option<ParamIndex>,
+    pub velocity: Option<ParamIndex>,
+}
+
+impl VoiceControls {
+    /// Looks up the parameters by the last segment of their path.
+    #[must_use]
+    pub fn from_dsp<T: FaustDsp<T = f32>>() -> Self {
+        let mut builder = ParamsBuilder::new();
+        T::build_user_interface_static(&mut builder);
+        let mut controls = Self::default();
+        for (idx, node) in builder.inner {
+            let param = Some(ParamIndex(idx));
+            match node.label.as_str() {
+                "freq" => controls.freq = param,
+                "key" => controls.key = param,
+                "gate" => controls.gate = param,
+                "gain" => controls.gain = param,
+                "vel" | "velocity" => controls.velocity = param,
+                _ => {}
+            }
+        }
+        controls
+    }
+
+    #[must_use]
+    pub fn contains(&self, param: ParamIndex) -> bool {
+        [self.freq, self.key, self.gate, self.gain, self.velocity]
+            .iter()
+            .flatten()
+            .any(|control| control.0 == param.0)
+    }
+
+    fn set(dsp: &mut impl FaustDsp<T = f32>, param: Option<ParamIndex>, value: f32) {
+        if let Some(param) = param {
+            dsp.set_param(param, value);
+        }
+    }
+
+    fn note_on(&self, dsp: &mut impl FaustDsp<T = f32>, note: u8, velocity: u8) {
+        let freq = 440.0 * ((f32::from(note) - 69.0) / 12.0).exp2();
+        Self::set(dsp, self.freq, freq);
+        Self::set(dsp, self.key, f32::from(note));
+        Self::set(dsp, self.gain, f32::from(velocity) / 127.0);
+        Self::set(dsp, self.velocity, f32::from(velocity));
+        Self::set(dsp, self.gate, 1.0);
+    }
+
+    fn note_off(&self, dsp: &mut impl FaustDsp<T = f32>) {
+        Self::set(dsp, self.gate, 0.0);
+    }
+}
+
+/// Reads `n` from `declare options "[nvoices:n]"`.
+#[must_use]
+pub fn declared_voices(dsp: &impl FaustDsp<T = f32>) -> Option<usize> {
+    let meta = MetaBuilder::from_dsp(dsp);
+    let options = meta.get("options")?;
+    let start = options.find("[nvoices:")? + "[nvoices:".len();
+    let end = start + options[start..].find(']')?;
+    options[start..end].trim().parse().ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VoiceState {
+    Free,
+    Playing(u8),
+    Released(u8),
+}
+
+impl VoiceState {
+    fn note(self) -> Option<u8> {
+        match self {
+            Self::Free => None,
+            Self::Playing(note) | Self::Released(note) => Some(note),
+        }
+    }
+}
+
+struct Voice<T> {
+    dsp: Box<T>,
+    state: VoiceState,
+    started: u64,
+    level: f32,
+    /// Note of a stolen voice, started after one block with the gate closed.
+    next: Option<(u8, u8)>,
+}
+
+/// Plays a dsp with several voices and mixes them, optionally followed by an effect.
+///
+/// `PolyDsp` implements [`FaustDsp`] itself, its user interface contains the shared parameters and
+/// those of the effect, numbered after the parameters of the voices.
+pub struct PolyDsp<T> {
+    voices: Vec<Voice<T>>,
+    controls: VoiceControls,
+    stealing: VoiceStealing,
+    effect: Option<Box<dyn FaustDsp<T = f32>>>,
+    /// Index of the first parameter of the effect.
+    effect_offset: i32,
+    clock: u64,
+    block_size: usize,
+    /// Output of one voice and the mixed voices before the effect, `block_size` frames per channel.
+    voice_buffers: Vec<Vec<f32>>,
+    mix_buffers: Vec<Vec<f32>>,
+}
+
+impl<T> PolyDsp<T>
+where
+    T: FaustDsp<T = f32>,
+{
+    #[must_use]
+    pub fn with_voices(voices: usize) -> Self {
+        Self::from_voices((0..voices).map(|_| Box::new(T::new())).collect())
+    }
+
+    #[must_use]
+    pub fn from_voices(voices: Vec<Box<T>>) -> Self {
+        assert!(
+            !voices.is_empty(),
+            "a polyphonic dsp needs at least one voice"
+        );
+        let channels = voices[0].get_num_outputs() as usize;
+        Self {
+            voices: voices
+                .into_iter()
+                .map(|dsp| Voice {
+                    dsp,
+                    state: VoiceState::Free,
+                    started: 0,
+                    level: 0.0,
+                    next: None,
+                })
+                .collect(),
+            controls: VoiceControls::from_dsp::<T>(),
+            stealing: VoiceStealing::default(),
+            effect: None,
+            effect_offset: 0,
+            clock: 0,
+            block_size: DEFAULT_BLOCK_SIZE,
+            voice_buffers: vec![vec![0.0; DEFAULT_BLOCK_SIZE]; channels],
+            mix_buffers: Vec::new(),
+        }
+    }
+
+    /// Processes the mixed voices with `effect`, which needs an input for every output of the voices.
+    #[must_use]
+    pub fn with_effect(mut self, effect: Box<dyn FaustDsp<T = f32>>) -> Self {
+        assert_eq!(
+            effect.get_num_inputs() as usize,
+            self.voice_buffers.len(),
+            "inputs of the effect do not match the outputs of the voices"
+        );
+        self.mix_buffers = vec![vec![0.0; self.block_size]; self.voice_buffers.len()];
+        self.effect_offset = ParamsBuilder::from_dsp(&*self.voices[0].dsp)
+            .keys()
+            .max()
+            .map_or(0, |idx| idx + 1);
+        self.effect = Some(effect);
+        self
+    }
+
+    /// Frames computed at once by the voices. Longer blocks are computed in several steps.
+    #[must_use]
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must not be zero");
+        self.block_size = block_size;
+        for buffer in self.voice_buffers.iter_mut().chain(&mut self.mix_buffers) {
+            *buffer = vec![0.0; block_size];
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    #[must_use]
+    pub fn with_stealing(mut self, stealing: VoiceStealing) -> Self {
+        self.stealing = stealing;
+        self
+    }
+
+    #[must_use]
+    pub fn controls(&self) -> &VoiceControls {
+        &self.controls
+    }
+
+    #[must_use]
+    pub fn voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Voices that are playing a note or its release.
+    #[must_use]
+    pub fn active_voices(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|voice| voice.state != VoiceState::Free)
+            .count()
+    }
+
+    /// Notes whose key is held.
+    pub fn notes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.voices.iter().filter_map(|voice| match voice.state {
+            VoiceState::Playing(note) => Some(note),
+            _ => None,
+        })
+    }
+
+    #[must_use]
+    pub fn effect(&self) -> Option<&dyn FaustDsp<T = f32>> {
+        self.effect.as_deref()
+    }
+
+    pub fn effect_mut(&mut self) -> Option<&mut (dyn FaustDsp<T = f32> + 'static)> {
+        self.effect.as_deref_mut()
+    }
+
+    /// Starts `note` with a midi velocity of `0..=127`, a velocity of `0` releases it.
+    ///
+    /// A stolen voice is released for one block before it plays the new note.
+    pub fn note_on(&mut self, note: u8, velocity: u8) {
+        if velocity == 0 {
+            self.note_off(note);
+            return;
+        }
+        let velocity = velocity.min(127);
+        self.clock += 1;
+        let index = self.allocate(note);
+        let voice = &mut self.voices[index];
+        if let VoiceState::Playing(_) = voice.state {
+            self.controls.note_off(&mut *voice.dsp);
+            voice.next = Some((note, velocity));
+        } else {
+            self.controls.note_on(&mut *voice.dsp, note, velocity);
+        }
+        voice.state = VoiceState::Playing(note);
+        voice.started = self.clock;
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        for voice in &mut self.voices {
+            if voice.state == VoiceState::Playing(note) {
+                self.controls.note_off(&mut *voice.dsp);
+                voice.next = None;
+                voice.state = VoiceState::Released(note);
+            }
+        }
+    }
+
+    pub fn all_notes_off(&mut self) {
+        for voice in &mut self.voices {
+            if let VoiceState::Playing(note) = voice.state {
+                self.controls.note_off(&mut *voice.dsp);
+                voice.next = None;
+                voice.state = VoiceState::Released(note);
+            }
+        }
+    }
+
+    fn allocate(&self, note: u8) -> usize {
+        if self.stealing == VoiceStealing::SameNote {
+            if let Some(index) = self
+                .voices
+                .iter()
+                .position(|voice| voice.state.note() == Some(note))
+            {
+                return index;
+            }
+        }
+        self.voices
+            .iter()
+            .position(|voice| voice.state == VoiceState::Free)
+            .or_else(|| self.steal(|state| matches!(state, VoiceState::Released(_))))
+            .or_else(|| self.steal(|_| true))
+            .expect("a polyphonic dsp has at least one voice")
+    }
+
+    fn steal(&self, filter: impl Fn(VoiceState) -> bool) -> Option<usize> {
+        let candidates = self
+            .voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| filter(voice.state));
+        let compare = |a: &Voice<T>, b: &Voice<T>| match self.stealing {
+            VoiceStealing::Quietest => a.level.total_cmp(&b.level),
+            VoiceStealing::Oldest | VoiceStealing::SameNote => a.started.cmp(&b.started),
+        };
+        candidates
+            .min_by(|(_, a), (_, b)| compare(a, b))
+            .map(|(index, _)| index)
+    }
+
+    fn reset_voices(&mut self) {
+        for voice in &mut self.voices {
+            voice.state = VoiceState::Free;
+            voice.level = 0.0;
+            voice.next = None;
+        }
+    }
+}
+
+impl<T> Instrument for PolyDsp<T>
+where
+    T: FaustDsp<T = f32>,
+{
+    fn handle_note(&mut self, event: NoteEvent) {
+        match event {
+            NoteEvent::On { note, velocity } => self.note_on(note, velocity),
+            NoteEvent::Off { note } => self.note_off(note),
+            NoteEvent::AllOff => self.all_notes_off(),
+        }
+    }
+}
+
+/// Hides the voice parameters from the user interface of the voices and adds the effect inside
+/// their outermost group.
+struct SharedParams<'a> {
+    ui: &'a mut dyn UI<f32>,
+    controls: VoiceControls,
+    effect: Option<(&'a dyn FaustDsp<T = f32>, i32)>,
+    depth: usize,
+}
+
+impl<'a> SharedParams<'a> {
+    fn new(ui: &'a mut dyn UI<f32>, controls: VoiceControls) -> Self {
+        Self {
+            ui,
+            controls,
+            effect: None,
+            depth: 0,
+        }
+    }
+
+    fn add_effect(&mut self) {
+        if let Some((effect, offset)) = self.effect.take() {
+            effect.build_user_interface(&mut OffsetUi::new(&mut *self.ui, offset));
+        }
+    }
+}
+
+impl UI<f32> for SharedParams<'_> {
+    fn open_tab_box(&mut self, label: &str) {
+        self.depth += 1;
+        self.ui.open_tab_box(label);
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.depth += 1;
+        self.ui.open_horizontal_box(label);
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.depth += 1;
+        self.ui.open_vertical_box(label);
+    }
+    fn close_box(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.add_effect();
+        }
+        self.ui.close_box();
+    }
+
+    // -- active widgets
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        if !self.controls.contains(param) {
+            self.ui.add_button(label, param);
+        }
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        if !self.controls.contains(param) {
+            self.ui.add_check_button(label, param);
+        }
+    }
+    fn add_vertical_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        if !self.controls.contains(param) {
+            self.ui
+                .add_vertical_slider(label, param, init, min, max, step);
+        }
+    }
+    fn add_horizontal_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        if !self.controls.contains(param) {
+            self.ui
+                .add_horizontal_slider(label, param, init, min, max, step);
+        }
+    }
+    fn add_num_entry(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: f32,
+        min: f32,
+        max: f32,
+        step: f32,
+    ) {
+        if !self.controls.contains(param) {
+            self.ui.add_num_entry(label, param, init, min, max, step);
+        }
+    }
+
+    // -- passive widgets
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        if !self.controls.contains(param) {
+            self.ui.add_horizontal_bargraph(label, param, min, max);
+        }
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: f32, max: f32) {
+        if !self.controls.contains(param) {
+            self.ui.add_vertical_bargraph(label, param, min, max);
+        }
+    }
+
+    // -- soundfiles
+    fn add_soundfile(&mut self, label: &str, url: &str, soundfile: SoundfileIndex) {
+        self.ui.add_soundfile(label, url, soundfile);
+    }
+
+    // -- metadata declarations
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        if !param.is_some_and(|param| self.controls.contains(param)) {
+            self.ui.declare(param, key, value);
+        }
+    }
+}
+
+impl<T> FaustDsp for PolyDsp<T>
+where
+    T: FaustDsp<T = f32>,
+{
+    type T = f32;
+
+    /// Uses the number of voices declared by the dsp, or [`DEFAULT_VOICES`].
+    fn new() -> Self {
+        let voices = declared_voices(&T::new()).unwrap_or(DEFAULT_VOICES);
+        Self::with_voices(voices)
+    }
+    fn metadata(&self, m: &mut dyn Meta) {
+        self.voices[0].dsp.metadata(m);
+    }
+    fn get_sample_rate(&self) -> i32 {
+        self.voices[0].dsp.get_sample_rate()
+    }
+    fn get_num_inputs(&self) -> i32 {
+        self.voices[0].dsp.get_num_inputs()
+    }
+    fn get_num_outputs(&self) -> i32 {
+        self.effect.as_ref().map_or_else(
+            || self.voices[0].dsp.get_num_outputs(),
+            |effect| effect.get_num_outputs(),
+        )
+    }
+    fn class_init(sample_rate: i32) {
+        T::class_init(sample_rate);
+    }
+    fn instance_reset_params(&mut self) {
+        for voice in &mut self.voices {
+            voice.dsp.instance_reset_params();
+        }
+        if let Some(effect) = &mut self.effect {
+            effect.instance_reset_params();
+        }
+    }
+    fn instance_clear(&mut self) {
+        for voice in &mut self.voices {
+            voice.dsp.instance_clear();
+        }
+        if let Some(effect) = &mut self.effect {
+            effect.instance_clear();
+        }
+        self.reset_voices();
+    }
+    fn instance_constants(&mut self, sample_rate: i32) {
+        for voice in &mut self.voices {
+            voice.dsp.instance_constants(sample_rate);
+        }
+        if let Some(effect) = &mut self.effect {
+            effect.instance_constants(sample_rate);
+        }
+    }
+    fn instance_init(&mut self, sample_rate: i32) {
+        for voice in &mut self.voices {
+            voice.dsp.instance_init(sample_rate);
+        }
+        if let Some(effect) = &mut self.effect {
+            effect.instance_init(sample_rate);
+        }
+        self.reset_voices();
+    }
+    fn init(&mut self, sample_rate: i32) {
+        for voice in &mut self.voices {
+            voice.dsp.init(sample_rate);
+        }
+        if let Some(effect) = &mut self.effect {
+            effect.init(sample_rate);
+        }
+        self.reset_voices();
+    }
+    fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+        let mut ui = SharedParams::new(ui_interface, self.controls);
+        ui.effect = self
+            .effect
+            .as_deref()
+            .map(|effect| (effect, self.effect_offset));
+        self.voices[0].dsp.build_user_interface(&mut ui);
+        ui.add_effect();
+    }
+    /// Without an instance the effect is unknown, only the shared parameters are built.
+    fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+        T::build_user_interface_static(&mut SharedParams::new(
+            ui_interface,
+            VoiceControls::from_dsp::<T>(),
+        ));
+    }
+    fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+        match &self.effect {
+            Some(effect) if param.0 >= self.effect_offset => {
+                effect.get_param(ParamIndex(param.0 - self.effect_offset))
+            }
+            _ => self.voices[0].dsp.get_param(param),
+        }
+    }
+    /// Sets the parameter of every voice, or of the effect.
+    fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+        match &mut self.effect {
+            Some(effect) if param.0 >= self.effect_offset => {
+                effect.set_param(ParamIndex(param.0 - self.effect_offset), value);
+            }
+            _ => {
+                for voice in &mut self.voices {
+                    voice.dsp.set_param(param, value);
+                }
+            }
+        }
+    }
+    fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]) {
+        let count = count as usize;
+        let mut start = 0;
+        while start < count {
+            let end = count.min(start + self.block_size);
+            let block_inputs = inputs.iter().map(|input| &input[start..end]);
+            let block_outputs = outputs.iter_mut().map(|output| &mut output[start..end]);
+            with_channels(block_inputs, |inputs| {
+                with_channels_mut(block_outputs, |outputs| {
+                    self.compute_block(end - start, inputs, outputs);
+                });
+            });
+            start = end;
+        }
+    }
+}
+
+impl<T> PolyDsp<T>
+where
+    T: FaustDsp<T = f32>,
+{
+    /// `frames` is at most the block size.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn compute_block(&mut self, frames: usize, inputs: &[&[f32]], outputs: &mut [&mut [f32]]) {
+        let Self {
+            voices,
+            controls,
+            effect,
+            voice_buffers,
+            mix_buffers,
+            ..
+        } = self;
+        let count = frames as i32;
+        let voice_outputs = voice_buffers.iter_mut().map(|buffer| &mut buffer[..frames]);
+        with_channels_mut(voice_outputs, |voice_outputs| {
+            if let Some(effect) = effect {
+                let mix = mix_buffers.iter_mut().map(|buffer| &mut buffer[..frames]);
+                with_channels_mut(mix, |mix| {
+                    mix_voices(voices, controls, count, inputs, voice_outputs, mix);
+                });
+                let mix = mix_buffers.iter().map(|buffer| &buffer[..frames]);
+                with_channels(mix, |mix| effect.compute(count, mix, outputs));
+            } else {
+                mix_voices(voices, controls, count, inputs, voice_outputs, outputs);
+            }
+        });
+    }
+}
+
+/// Adds the output of all voices that are not free to `mix`.
+fn mix_voices<T: FaustDsp<T = f32>>(
+    voices: &mut [Voice<T>],
+    controls: &VoiceControls,
+    count: i32,
+    inputs: &[&[f32]],
+    voice_outputs: &mut [&mut [f32]],
+    mix: &mut [&mut [f32]],
+) {
+    let frames = count as usize;
+    for channel in mix.iter_mut() {
+        channel[..frames].fill(0.0);
+    }
+    for voice in voices {
+        if voice.state == VoiceState::Free {
+            continue;
+        }
+        voice.dsp.compute(count, inputs, voice_outputs);
+        voice.level = 0.0;
+        for (channel, voice_output) in mix.iter_mut().zip(voice_outputs.iter()) {
+            for (sample, voice_sample) in channel.iter_mut().zip(voice_output.iter()) {
+                *sample += voice_sample;
+                voice.level = voice.level.max(voice_sample.abs());
+            }
+        }
+        if let Some((note, velocity)) = voice.next.take() {
+            controls.note_on(&mut *voice.dsp, note, velocity);
+        } else if matches!(voice.state, VoiceState::Released(_)) && voice.level < SILENCE {
+            voice.state = VoiceState::Free;
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Loading of the soundfiles declared by a dsp.
+
+use faust_types::{Soundfile, SoundfileIndex};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+/// A soundfile as reported by [`faust_types::UI::add_soundfile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoundfileDecl {
+    pub path: String,
+    pub url: String,
+    pub index: SoundfileIndex,
+}
+
+/// Reads the wav files referenced by soundfile urls. Relative paths are resolved against the base dir.
+#[derive(Debug, Clone)]
+pub struct SoundfileLoader {
+    base_dir: PathBuf,
+}
+
+impl SoundfileLoader {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Loads every file of `url` as one part of the soundfile.
+    ///
+    /// The soundfile gets as many channels as the file with the most channels, missing channels of
+    /// the other parts are silent.
+    pub fn load<T>(&self, url: &str) -> Result<Soundfile<T>, String>
+    where
+        T: From<f32> + Copy + Default,
+    {
+        let files = urls(url);
+        if files.is_empty() {
+            return Err(format!("soundfile url {url} contains no file"));
+        }
+        let parts = files
+            .iter()
+            .map(|file| read_wav(&self.base_dir.join(file)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let channels = parts
+            .iter()
+            .map(|(_, channels)| channels.len())
+            .max()
+            .unwrap_or_default();
+        let mut soundfile = Soundfile::new(channels);
+        for (sample_rate, channels) in parts {
+            let channels = channels
+                .into_iter()
+                .map(|channel| channel.into_iter().map(T::from).collect())
+                .collect::<Vec<Vec<T>>>();
+            soundfile.push_part(sample_rate, &channels);
+        }
+        Ok(soundfile)
+    }
+}
+
+/// Splits a soundfile url into its files, e.g. `{'a.wav';'b.wav'}` into `a.wav` and `b.wav`.
+#[must_use]
+pub fn urls(url: &str) -> Vec<&str> {
+    let url = url.trim();
+    let list = url
+        .strip_prefix('{')
+        .and_then(|url| url.strip_suffix('}'))
+        .unwrap_or(url);
+    list.split(';')
+        .map(|url| url.trim().trim_matches('\''))
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+/// Reads a wav file into its sample rate and one buffer per channel. Integer samples are scaled to `-1.0..1.0`.
+pub fn read_wav(path: &Path) -> Result<(i32, Vec<Vec<f32>>), String> {
+    let error = |err: hound::Error| format!("cannot read soundfile {}: {err}", path.display());
+    let reader = hound::WavReader::open(path).map_err(error)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?,
+        #[allow(clippy::cast_precision_loss)]
+        hound::SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error)?
+        }
+    };
+    let channel_count = usize::from(spec.channels);
+    if channel_count == 0 {
+        return Err(format!("soundfile {} has no channels", path.display()));
+    }
+    let mut channels = vec![Vec::with_capacity(samples.len() / channel_count); channel_count];
+    for frame in samples.chunks(channel_count) {
+        for (channel, sample) in channels.iter_mut().zip(frame) {
+            channel.push(*sample);
+        }
+    }
+    let sample_rate = i32::try_from(spec.sample_rate)
+        .map_err(|_| format!("sample rate of {} is too high", path.display()))?;
+    Ok((sample_rate, channels))
+}
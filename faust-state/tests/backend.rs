@@ -0,0 +1,127 @@
+use faust_state::{
+    backend::{AudioBackend, BackendConfig, ChannelMap, NullBackend, OfflineBackend},
+    DspHandle,
+};
+use faust_types::*;
+use std::{thread, time::Duration};
+
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+const OFFSET: ParamIndex = ParamIndex(0);
+
+/// Outputs its input plus `offset`, and the sample rate it was initialized with.
+struct Offset;
+
+impl Spec for Offset {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 2;
+    const META: &'static [(&'static str, &'static str)] = &[("name", "offset")];
+    const UI: &'static [Ui] = &[
+        Ui::VBox("offset"),
+        Ui::NumEntry("offset", 0.0, -10.0, 10.0, 1.0),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        for (output, input) in outputs[0][..count].iter_mut().zip(inputs[0]) {
+            *output = input + dsp.params[OFFSET.0 as usize];
+        }
+        outputs[1][..count].fill(dsp.sample_rate as f32);
+    }
+}
+
+#[test]
+fn offline_renders_with_channel_maps() {
+    let (dsp, mut state) = DspHandle::<TestDsp<Offset>>::new();
+    state.set_param(OFFSET.0, 1.0);
+    state.update();
+    let config = BackendConfig {
+        sample_rate: 100,
+        block_size: 4,
+        inputs: ChannelMap::Channels(vec![Some(1)]),
+        outputs: ChannelMap::Channels(vec![Some(1), Some(1)]),
+    };
+    let mut backend =
+        OfflineBackend::new(config, 10).with_input(vec![vec![], vec![1.0, 2.0, 3.0, 4.0, 5.0]]);
+    backend.start(dsp).expect("failed to render");
+    assert!(backend.is_running());
+    let output = backend.output();
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], [0.0; 10]);
+    // Both outputs are summed into the second device channel.
+    assert_eq!(
+        output[1],
+        [102.0, 103.0, 104.0, 105.0, 106.0, 101.0, 101.0, 101.0, 101.0, 101.0]
+    );
+    assert!(backend
+        .start(DspHandle::<TestDsp<Offset>>::new().0)
+        .is_err());
+    backend.stop().expect("backend is running");
+    assert!(backend.stop().is_err());
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    for config in [
+        BackendConfig {
+            block_size: 0,
+            ..BackendConfig::default()
+        },
+        BackendConfig {
+            sample_rate: 0,
+            ..BackendConfig::default()
+        },
+    ] {
+        let mut backend = NullBackend::new(config);
+        assert!(backend
+            .start(DspHandle::<TestDsp<Offset>>::new().0)
+            .is_err());
+        assert!(!backend.is_running());
+    }
+}
+
+#[test]
+fn backends_are_interchangeable() {
+    let config = BackendConfig {
+        sample_rate: 48000,
+        block_size: 480,
+        ..BackendConfig::default()
+    };
+    let mut backends: Vec<Box<dyn AudioBackend<TestDsp<Offset>>>> = vec![
+        Box::new(NullBackend::new(config.clone())),
+        Box::new(OfflineBackend::new(config, 480)),
+    ];
+    let (mut dsp, _state) = DspHandle::<TestDsp<Offset>>::new();
+    for backend in &mut backends {
+        backend.start(dsp).expect("failed to start");
+        assert_eq!((backend.sample_rate(), backend.block_size()), (48000, 480));
+        dsp = backend.stop().expect("failed to stop");
+        assert!(!backend.is_running());
+    }
+}
+
+#[test]
+fn null_backend_is_paced_in_real_time() {
+    let mut backend = NullBackend::new(BackendConfig {
+        sample_rate: 48000,
+        block_size: 480,
+        ..BackendConfig::default()
+    });
+    backend
+        .start(DspHandle::<TestDsp<Offset>>::new().0)
+        .expect("failed to start");
+    thread::sleep(Duration::from_millis(100));
+    let frames = backend.frames();
+    backend.stop().expect("failed to stop");
+    // About 4800 frames, far less than a free running loop.
+    assert!((480..48000).contains(&frames), "{}", frames);
+}
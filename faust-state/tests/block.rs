@@ -0,0 +1,118 @@
+use faust_state::{
+    block::{FixedBlockDsp, FixedBlocks},
+    DspHandle,
+};
+use faust_types::*;
+
+#[path = "fixtures/allocations.rs"]
+mod allocations;
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp};
+
+/// Adds the frame counter to its input and records the block sizes it was computed with.
+struct Recorder;
+
+impl Spec for Recorder {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 1;
+    type State = Vec<i32>;
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        dsp.state.push(count as i32);
+        for (frame, (output, input)) in outputs[0][..count].iter_mut().zip(inputs[0]).enumerate() {
+            *output = input + (dsp.frames + frame) as f32;
+        }
+    }
+}
+
+#[test]
+fn computes_fixed_blocks_with_latency() {
+    let mut dsp = FixedBlockDsp::new(Box::new(TestDsp::<Recorder>::new()), 4);
+    assert_eq!(dsp.latency(), 4);
+    let input = [100.0; 11];
+    let mut output = Vec::new();
+    for count in [3, 1, 7] {
+        let mut block = vec![0.0; count];
+        dsp.compute(count as i32, &[&input[..count]], &mut [&mut block]);
+        output.extend(block);
+    }
+    assert_eq!(dsp.inner().state, [4, 4]);
+    assert_eq!(
+        output,
+        [0.0, 0.0, 0.0, 0.0, 100.0, 101.0, 102.0, 103.0, 104.0, 105.0, 106.0]
+    );
+}
+
+#[test]
+fn init_clears_pending_frames() {
+    let mut dsp = FixedBlockDsp::new(Box::new(TestDsp::<Recorder>::new()), 2);
+    let mut output = [0.0; 3];
+    dsp.compute(3, &[&[1.0; 3]], &mut [&mut output]);
+    dsp.init(48000);
+    dsp.compute(3, &[&[1.0; 3]], &mut [&mut output]);
+    assert_eq!(dsp.inner().state, [2, 2]);
+    assert_eq!(output, [0.0, 0.0, 3.0]);
+}
+
+#[test]
+fn compute_does_not_allocate() {
+    let mut dsp = FixedBlockDsp::new(Box::new(TestDsp::<Recorder>::new()), 4);
+    dsp.inner_mut().state.reserve(4);
+    let mut output = [0.0; 10];
+    let allocations = allocations::allocations(|| {
+        dsp.compute(10, &[&[0.0; 10]], &mut [&mut output]);
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(dsp.inner().state, [4, 4]);
+}
+
+#[test]
+fn wide_blocks_do_not_allocate() {
+    let channels = buffers::MAX_CHANNELS + 1;
+    let mut blocks = FixedBlocks::<f32>::new(channels, channels, 4);
+    let input = [1.0; 8];
+    let inputs = vec![&input[..]; channels];
+    let mut outputs = vec![[0.0; 8]; channels];
+    let mut outputs = outputs.iter_mut().map(|o| &mut o[..]).collect::<Vec<_>>();
+    let allocations = allocations::allocations(|| {
+        blocks.compute(8, &inputs, &mut outputs, |_count, inputs, outputs| {
+            assert_eq!((inputs.len(), outputs.len()), (channels, channels));
+            for (output, input) in outputs.iter_mut().zip(inputs) {
+                output.copy_from_slice(input);
+            }
+        });
+    });
+    assert_eq!(allocations, 0);
+    assert!(outputs.iter().all(|output| output[4..] == [1.0; 4]));
+}
+
+#[test]
+fn dsp_handle_in_fixed_blocks() {
+    let (mut handle, _state) = DspHandle::from_dsp(Box::new(TestDsp::<Recorder>::new()));
+    let mut blocks = FixedBlocks::new(handle.num_inputs(), handle.num_outputs(), 4);
+    assert_eq!(blocks.latency(), 4);
+    let mut counts = Vec::new();
+    let mut output = Vec::new();
+    for count in [3, 6] {
+        let mut block = vec![0.0; count];
+        blocks.compute(
+            count as i32,
+            &[&[10.0; 6][..count]],
+            &mut [&mut block],
+            |count, inputs, outputs| {
+                counts.push(count);
+                handle.update_and_compute(count, inputs, outputs);
+            },
+        );
+        output.extend(block);
+    }
+    assert_eq!(counts, [4, 4]);
+    assert_eq!(output, [0.0, 0.0, 0.0, 0.0, 10.0, 11.0, 12.0, 13.0, 14.0]);
+}
@@ -0,0 +1,102 @@
+use faust_state::fixed::{AtomicParams, FixedDspHandle};
+use faust_types::*;
+use std::thread;
+
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+const LEVEL: ParamIndex = ParamIndex(0);
+const GAIN: ParamIndex = ParamIndex(1);
+
+/// Multiplies its input with `gain` and shows the peak of the last block in `level`.
+struct Gain;
+
+impl Spec for Gain {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 1;
+    const UI: &'static [Ui] = &[
+        Ui::VBox("gain"),
+        Ui::VBargraph("level", 0.0, 1.0),
+        Ui::HSlider("gain", 1.0, 0.0, 2.0, 0.01),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let gain = dsp.params[GAIN.0 as usize];
+        let mut level = 0.0_f32;
+        for (output, input) in outputs[0][..count].iter_mut().zip(inputs[0]) {
+            *output = input * gain;
+            level = level.max(output.abs());
+        }
+        dsp.params[LEVEL.0 as usize] = level;
+    }
+}
+
+#[test]
+fn params_are_exchanged_per_block() {
+    let params = AtomicParams::<3>::new();
+    let mut handle = FixedDspHandle::<TestDsp<Gain>, 3>::new(&params);
+    assert_eq!(params.get(GAIN), None);
+
+    handle.init(48_000);
+    assert!(handle.is_initialized());
+    assert_eq!(params.get(GAIN), Some(1.0));
+    assert_eq!(params.get(LEVEL), Some(0.0));
+    // slots without a parameter and indices without a slot
+    assert_eq!(params.get(ParamIndex(2)), None);
+    assert_eq!(params.get(ParamIndex(-1)), None);
+    params.set(ParamIndex(3), 1.0);
+
+    params.set(GAIN, 0.5);
+    assert_eq!(params.get(GAIN), Some(1.0));
+    let mut output = [0.0; 3];
+    handle.update_and_compute(3, &[&[1.0, -4.0, 2.0]], &mut [&mut output]);
+    assert_eq!(output, [0.5, -2.0, 1.0]);
+    assert_eq!(params.get(GAIN), Some(0.5));
+    assert_eq!(params.get(LEVEL), Some(2.0));
+    assert_eq!(handle.dsp().params[GAIN.0 as usize], 0.5);
+}
+
+#[test]
+fn checked_update_and_compute() {
+    let params = AtomicParams::<2>::default();
+    let mut handle = FixedDspHandle::from_dsp(TestDsp::<Gain>::new(), &params);
+    handle.init(48_000);
+    params.set(GAIN, 2.0);
+    let mut output = [0.0; 2];
+    assert_eq!(
+        handle.checked_update_and_compute(2, &[&[1.0]], &mut [&mut output]),
+        Err(ComputeError::ShortInput {
+            channel: 0,
+            len: 1,
+            count: 2
+        })
+    );
+    // nothing was applied or computed
+    assert_eq!(params.get(GAIN), Some(1.0));
+    handle
+        .checked_update_and_compute(2, &[&[1.0, 1.0]], &mut [&mut output])
+        .unwrap();
+    assert_eq!(output, [2.0, 2.0]);
+}
+
+#[test]
+fn params_from_another_thread() {
+    static PARAMS: AtomicParams<2> = AtomicParams::new();
+    let mut handle = FixedDspHandle::<TestDsp<Gain>, 2>::new(&PARAMS);
+    handle.init(48_000);
+    thread::spawn(|| PARAMS.set(GAIN, 0.25)).join().unwrap();
+    let mut output = [0.0];
+    handle.update_and_compute(1, &[&[1.0]], &mut [&mut output]);
+    assert_eq!(output, [0.25]);
+    let level = thread::spawn(|| PARAMS.get(LEVEL)).join().unwrap();
+    assert_eq!(level, Some(0.25));
+}
@@ -0,0 +1,192 @@
+//! A dsp reading a soundfile, written after the rust output of faust 2.77 for:
+//!
+//! ```faust
+//! declare name "player";
+//! index = +(1) ~ _;
+//! process = (int(hslider("part", 0, 0, 3, 1)), index - 1)
+//!     : soundfile("sample[url:{'a.wav';'b.wav'}]", 1) : !, !, _;
+//! ```
+//!
+//! `compute` reads the soundfile through `fLength`, `fOffset` and `fBuffers` the way code
+//! generated by faust does, with the part clamped to `MAX_SOUNDFILE_PARTS - 1` and the frame to
+//! the length of the part. The `SoundfileDsp` impl at the end is the output of `faust-ui`.
+
+#![allow(clippy::all)]
+#![allow(unused_parens)]
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_mut)]
+#![allow(non_upper_case_globals)]
+use faust_types::*;
+pub type FaustFloat = F32;
+pub const FAUST_INPUTS: usize = 0;
+pub const FAUST_OUTPUTS: usize = 1;
+pub const FAUST_ACTIVES: usize = 1;
+pub const FAUST_PASSIVES: usize = 0;
+#[repr(C)]
+pub struct Player {
+    fSampleRate: i32,
+    fHslider0: F32,
+    fSoundfile0: Soundfile<FaustFloat>,
+    iRec0: [i32; 2],
+}
+impl Player {
+    pub fn new() -> Player {
+        Player {
+            fSampleRate: 0,
+            fHslider0: 0.0,
+            fSoundfile0: Soundfile::default(),
+            iRec0: [0; 2],
+        }
+    }
+    pub fn metadata(&self, m: &mut dyn Meta) {
+        m.declare(
+            "compile_options",
+            r"-lang rust -ct 1 -cn Player -es 1 -mcd 16 -mdd 1024 -mdy 33 -single -ftz 0",
+        );
+        m.declare("filename", r"player.dsp");
+        m.declare("name", r"player");
+    }
+    pub fn get_sample_rate(&self) -> i32 {
+        self.fSampleRate as i32
+    }
+    pub fn class_init(sample_rate: i32) {}
+    pub fn instance_reset_params(&mut self) {
+        self.fHslider0 = 0.0;
+    }
+    pub fn instance_clear(&mut self) {
+        for l0 in 0..2 {
+            self.iRec0[l0 as usize] = 0;
+        }
+    }
+    pub fn instance_constants(&mut self, sample_rate: i32) {
+        self.fSampleRate = sample_rate;
+    }
+    pub fn instance_init(&mut self, sample_rate: i32) {
+        self.instance_constants(sample_rate);
+        self.instance_reset_params();
+        self.instance_clear();
+    }
+    pub fn init(&mut self, sample_rate: i32) {
+        Player::class_init(sample_rate);
+        self.instance_init(sample_rate);
+    }
+    pub fn build_user_interface(&self, ui_interface: &mut dyn UI<FaustFloat>) {
+        Self::build_user_interface_static(ui_interface);
+    }
+    pub fn build_user_interface_static(ui_interface: &mut dyn UI<FaustFloat>) {
+        ui_interface.open_vertical_box("player");
+        ui_interface.add_horizontal_slider("part", ParamIndex(0), 0.0, 0.0, 3.0, 1.0);
+        ui_interface.close_box();
+    }
+    pub fn get_param(&self, param: ParamIndex) -> Option<FaustFloat> {
+        match param.0 {
+            0 => Some(self.fHslider0),
+            _ => None,
+        }
+    }
+    pub fn set_param(&mut self, param: ParamIndex, value: FaustFloat) {
+        match param.0 {
+            0 => self.fHslider0 = value,
+            _ => {}
+        }
+    }
+    pub fn compute(
+        &mut self,
+        count: usize,
+        inputs: &[impl AsRef<[FaustFloat]>],
+        outputs: &mut [impl AsMut<[FaustFloat]>],
+    ) {
+        let [outputs0, ..] = outputs.as_mut() else {
+            panic!("wrong number of output buffers");
+        };
+        let outputs0 = outputs0.as_mut()[..count].iter_mut();
+        let fSoundfile0ca: &Soundfile<FaustFloat> = &self.fSoundfile0;
+        let fSoundfile0ca_le0: &[i32] = &fSoundfile0ca.fLength;
+        let mut iSlow0: i32 = i32::max(0, i32::min((self.fHslider0) as i32, 255));
+        let mut iSlow1: i32 = fSoundfile0ca_le0[(iSlow0) as usize] + -1;
+        let fSoundfile0ca_of0: &[i32] = &fSoundfile0ca.fOffset;
+        let mut iSlow2: i32 = fSoundfile0ca_of0[(iSlow0) as usize];
+        let fSoundfile0ca_bu0: &[Vec<FaustFloat>] = &fSoundfile0ca.fBuffers;
+        let zipped_iterators = outputs0;
+        for output0 in zipped_iterators {
+            self.iRec0[0] = self.iRec0[1] + 1;
+            *output0 = fSoundfile0ca_bu0[0]
+                [(iSlow2 + i32::max(0, i32::min(self.iRec0[0] + -1, iSlow1))) as usize];
+            self.iRec0[1] = self.iRec0[0];
+        }
+    }
+}
+impl FaustDsp for Player {
+    type T = FaustFloat;
+    fn new() -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
+    fn metadata(&self, m: &mut dyn Meta) {
+        self.metadata(m)
+    }
+    fn get_sample_rate(&self) -> i32 {
+        self.get_sample_rate()
+    }
+    fn get_num_inputs(&self) -> i32 {
+        FAUST_INPUTS as i32
+    }
+    fn get_num_outputs(&self) -> i32 {
+        FAUST_OUTPUTS as i32
+    }
+    fn class_init(sample_rate: i32)
+    where
+        Self: Sized,
+    {
+        Self::class_init(sample_rate);
+    }
+    fn instance_reset_params(&mut self) {
+        self.instance_reset_params()
+    }
+    fn instance_clear(&mut self) {
+        self.instance_clear()
+    }
+    fn instance_constants(&mut self, sample_rate: i32) {
+        self.instance_constants(sample_rate)
+    }
+    fn instance_init(&mut self, sample_rate: i32) {
+        self.instance_init(sample_rate)
+    }
+    fn init(&mut self, sample_rate: i32) {
+        self.init(sample_rate)
+    }
+    fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+        self.build_user_interface(ui_interface)
+    }
+    fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>)
+    where
+        Self: Sized,
+    {
+        Self::build_user_interface_static(ui_interface);
+    }
+    fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+        self.get_param(param)
+    }
+    fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+        self.set_param(param, value)
+    }
+    fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]) {
+        self.compute(count as usize, inputs, outputs)
+    }
+}
+impl SoundfileDsp for Player {
+    fn build_soundfile_interface(ui_interface: &mut dyn UI<Self::T>) {
+        ui_interface.add_soundfile("sample", "{'a.wav';'b.wav'}", SoundfileIndex(0));
+    }
+    fn set_soundfile(&mut self, soundfile: SoundfileIndex, data: Soundfile<Self::T>) {
+        match soundfile.0 {
+            0 => self.fSoundfile0 = data,
+            index => panic!("no soundfile with index {}", index),
+        }
+    }
+}
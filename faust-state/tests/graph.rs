@@ -0,0 +1,196 @@
+use faust_state::{graph::DspGraph, DspHandle};
+use faust_types::*;
+
+#[path = "fixtures/allocations.rs"]
+mod allocations;
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+/// Multiplies its input with the `gain` parameter.
+struct Gain;
+
+impl Spec for Gain {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 1;
+    const META: &'static [(&'static str, &'static str)] = &[("name", "gain")];
+    const UI: &'static [Ui] = &[
+        Ui::VBox("gain"),
+        Ui::HSlider("gain", 1.0, 0.0, 10.0, 0.1),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        for (output, input) in outputs[0][..count].iter_mut().zip(inputs[0]) {
+            *output = input * dsp.params[0];
+        }
+    }
+}
+
+/// Outputs the number of frames computed so far.
+struct Counter;
+
+impl Spec for Counter {
+    const INPUTS: usize = 0;
+    const OUTPUTS: usize = 1;
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        _inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        for (frame, output) in outputs[0][..count].iter_mut().enumerate() {
+            *output = (dsp.frames + frame) as f32;
+        }
+    }
+}
+
+fn gain(name: &str, gain: f32) -> DspGraph {
+    DspGraph::dsp(name, Box::new(TestDsp::<Gain>::with_params(&[gain])))
+}
+
+fn compute(graph: &mut DspGraph, inputs: &[f32]) -> Vec<f32> {
+    let inputs = inputs
+        .iter()
+        .map(|input| vec![*input; 4])
+        .collect::<Vec<_>>();
+    let inputs = inputs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let mut buffers = vec![vec![0.0; 4]; graph.outputs()];
+    let mut outputs = buffers
+        .iter_mut()
+        .map(Vec::as_mut_slice)
+        .collect::<Vec<_>>();
+    graph.compute(4, &inputs, &mut outputs);
+    buffers.iter().map(|buffer| buffer[3]).collect()
+}
+
+#[test]
+fn sequential() {
+    let mut graph = gain("a", 2.0).seq(gain("b", 3.0)).expect("invalid graph");
+    assert_eq!((graph.inputs(), graph.outputs()), (1, 1));
+    assert_eq!(compute(&mut graph, &[1.0]), [6.0]);
+}
+
+#[test]
+fn parallel() {
+    let mut graph = gain("a", 2.0)
+        .par(DspGraph::wires(1))
+        .and_then(|graph| graph.par(gain("b", 3.0)))
+        .expect("invalid graph");
+    assert_eq!((graph.inputs(), graph.outputs()), (3, 3));
+    assert_eq!(compute(&mut graph, &[1.0, 2.0, 3.0]), [2.0, 2.0, 9.0]);
+}
+
+#[test]
+fn split_and_merge() {
+    let mut graph = gain("a", 2.0)
+        .split(gain("b", 3.0).par(gain("c", 5.0)).expect("invalid graph"))
+        .expect("invalid graph");
+    assert_eq!(compute(&mut graph, &[1.0]), [6.0, 10.0]);
+
+    let mut graph = gain("a", 2.0)
+        .par(gain("b", 3.0))
+        .and_then(|graph| graph.merge(gain("c", 5.0)))
+        .expect("invalid graph");
+    assert_eq!((graph.inputs(), graph.outputs()), (2, 1));
+    assert_eq!(compute(&mut graph, &[1.0, 2.0]), [40.0]);
+}
+
+#[test]
+fn channel_counts_are_checked() {
+    let stereo = || gain("a", 1.0).par(gain("b", 1.0)).expect("invalid graph");
+    assert!(stereo().seq(gain("c", 1.0)).is_err());
+    assert!(DspGraph::wires(2)
+        .split(
+            gain("c", 1.0)
+                .par(DspGraph::wires(2))
+                .expect("invalid graph")
+        )
+        .is_err());
+    assert!(DspGraph::wires(3).merge(stereo()).is_err());
+    assert_eq!(
+        gain("a", 1.0).seq(gain("a", 1.0)).err().as_deref(),
+        Some("dsp name a is used twice")
+    );
+}
+
+#[test]
+fn params_below_dsp_names() {
+    let graph = gain("eq", 2.0)
+        .seq(gain("limiter", 3.0))
+        .expect("invalid graph");
+    assert_eq!(graph.names(), ["eq", "limiter"]);
+    let (mut dsp, mut state) = DspHandle::from_dsp(Box::new(graph));
+    let paths = state
+        .params_by_path()
+        .map(|(path, value)| (path.clone(), value.copied()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            ("eq/gain".to_owned(), Some(1.0)),
+            ("limiter/gain".to_owned(), Some(1.0))
+        ]
+    );
+    state
+        .set_by_path("limiter/gain", 4.0)
+        .expect("unknown path");
+    state.send();
+    let mut output = [0.0; 4];
+    dsp.update_and_compute(4, &[&[1.0; 4]], &mut [&mut output]);
+    assert_eq!(output, [8.0; 4]);
+}
+
+#[test]
+fn long_blocks_are_split() {
+    let mut graph = DspGraph::dsp("counter", Box::new(TestDsp::<Counter>::new()))
+        .seq(gain("gain", 1.0))
+        .expect("invalid graph")
+        .with_block_size(3);
+    let mut output = [0.0; 10];
+    graph.compute(10, &[], &mut [&mut output]);
+    assert_eq!(output, [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+}
+
+#[test]
+fn checked_update_and_compute() {
+    let (mut dsp, _state) = DspHandle::from_dsp(Box::new(gain("a", 2.0)));
+    let mut output = [0.0; 2];
+    assert_eq!(
+        dsp.checked_update_and_compute(4, &[&[1.0; 4]], &mut [&mut output]),
+        Err(ComputeError::ShortOutput {
+            channel: 0,
+            len: 2,
+            count: 4
+        })
+    );
+    dsp.checked_update_and_compute(2, &[&[1.0; 4]], &mut [&mut output])
+        .expect("invalid arguments");
+    assert_eq!(output, [2.0; 2]);
+}
+
+#[test]
+fn compute_does_not_allocate() {
+    let mut graph = gain("a", 2.0)
+        .split(gain("b", 3.0).par(gain("c", 5.0)).expect("invalid graph"))
+        .and_then(|graph| graph.merge(gain("d", 1.0)))
+        .and_then(|graph| graph.seq(gain("e", 1.0)))
+        .expect("invalid graph")
+        .with_block_size(3);
+    let input = [1.0; 10];
+    let mut output = [0.0; 10];
+    let allocations = allocations::allocations(|| {
+        graph.compute(10, &[&input], &mut [&mut output]);
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!(output, [16.0; 10]);
+}
@@ -0,0 +1,252 @@
+use faust_state::{
+    poly::{NoteEvent, PolyDsp, VoiceStealing},
+    DspHandle,
+};
+use faust_types::*;
+
+#[path = "fixtures/allocations.rs"]
+mod allocations;
+
+#[path = "../../faust-types/tests/fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp, Ui};
+
+const FREQ: ParamIndex = ParamIndex(0);
+const GATE: ParamIndex = ParamIndex(1);
+const GAIN: ParamIndex = ParamIndex(2);
+const VOLUME: ParamIndex = ParamIndex(3);
+
+/// Outputs `gate * gain * volume` without any release.
+struct Synth;
+
+impl Spec for Synth {
+    const INPUTS: usize = 0;
+    const OUTPUTS: usize = 1;
+    const META: &'static [(&'static str, &'static str)] =
+        &[("name", "synth"), ("options", "[midi:on][nvoices:4]")];
+    const UI: &'static [Ui] = &[
+        Ui::VBox("synth"),
+        Ui::HSlider("freq", 440.0, 20.0, 20000.0, 1.0),
+        Ui::Button("gate"),
+        Ui::Declare("unit", "dB"),
+        Ui::HSlider("gain", 0.5, 0.0, 1.0, 0.01),
+        Ui::HSlider("volume", 1.0, 0.0, 1.0, 0.01),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        _count: usize,
+        _inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let params = &dsp.params;
+        outputs[0]
+            .fill(params[GATE.0 as usize] * params[GAIN.0 as usize] * params[VOLUME.0 as usize]);
+    }
+}
+
+/// Multiplies its input with `factor`, which doubles it by default.
+struct Double;
+
+impl Spec for Double {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 2;
+    const UI: &'static [Ui] = &[
+        Ui::VBox("double"),
+        Ui::HSlider("factor", 2.0, 0.0, 4.0, 0.1),
+        Ui::Close,
+    ];
+    type State = ();
+
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        let factor = dsp.params[0];
+        for output in outputs.iter_mut() {
+            for (out, input) in output[..count].iter_mut().zip(inputs[0]) {
+                *out = input * factor;
+            }
+        }
+    }
+}
+
+fn compute(dsp: &mut impl FaustDsp<T = f32>, channels: usize) -> Vec<f32> {
+    let mut buffers = vec![vec![0.0; 4]; channels];
+    let mut outputs = buffers
+        .iter_mut()
+        .map(Vec::as_mut_slice)
+        .collect::<Vec<_>>();
+    dsp.compute(4, &[], &mut outputs);
+    buffers.iter().map(|buffer| buffer[0]).collect()
+}
+
+#[test]
+fn voices_from_metadata() {
+    let poly = <PolyDsp<TestDsp<Synth>> as FaustDsp>::new();
+    assert_eq!(poly.voices(), 4);
+    let controls = poly.controls();
+    assert_eq!(controls.freq.map(|param| param.0), Some(FREQ.0));
+    assert_eq!(controls.gate.map(|param| param.0), Some(GATE.0));
+    assert_eq!(controls.gain.map(|param| param.0), Some(GAIN.0));
+    assert!(controls.key.is_none());
+}
+
+#[test]
+fn only_shared_params_are_exposed() {
+    let (_dsp, state) = DspHandle::from_dsp(Box::new(PolyDsp::<TestDsp<Synth>>::with_voices(2)));
+    let paths = state
+        .params_by_path()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["volume"]);
+}
+
+#[test]
+fn mix_voices() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(4);
+    assert_eq!(compute(&mut poly, 1), [0.0]);
+    poly.note_on(60, 127);
+    poly.note_on(64, 127);
+    assert_eq!(compute(&mut poly, 1), [2.0]);
+    poly.set_param(VOLUME, 0.5);
+    assert_eq!(compute(&mut poly, 1), [1.0]);
+    poly.note_off(60);
+    assert_eq!(poly.notes().collect::<Vec<_>>(), [64]);
+    assert_eq!(compute(&mut poly, 1), [0.5]);
+    assert_eq!(poly.active_voices(), 1);
+    poly.all_notes_off();
+    compute(&mut poly, 1);
+    assert_eq!(poly.active_voices(), 0);
+}
+
+#[test]
+fn zero_velocity_releases_note() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(2);
+    poly.note_on(60, 127);
+    poly.note_on(60, 0);
+    assert_eq!(poly.notes().count(), 0);
+    assert_eq!(compute(&mut poly, 1), [0.0]);
+    assert_eq!(poly.active_voices(), 0);
+}
+
+#[test]
+fn steal_oldest() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(2);
+    poly.note_on(60, 127);
+    poly.note_on(62, 127);
+    poly.note_on(64, 127);
+    assert_eq!(poly.notes().collect::<Vec<_>>(), [64, 62]);
+    // the stolen voice is silent for one block
+    assert_eq!(compute(&mut poly, 1), [1.0]);
+    assert_eq!(compute(&mut poly, 1), [2.0]);
+    poly.note_off(60);
+    assert_eq!(poly.notes().count(), 2);
+}
+
+#[test]
+fn steal_quietest() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(2).with_stealing(VoiceStealing::Quietest);
+    poly.note_on(60, 127);
+    poly.note_on(62, 10);
+    compute(&mut poly, 1);
+    poly.note_on(64, 127);
+    assert_eq!(poly.notes().collect::<Vec<_>>(), [60, 64]);
+}
+
+#[test]
+fn retrigger_same_note() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(2).with_stealing(VoiceStealing::SameNote);
+    poly.note_on(60, 127);
+    poly.note_on(60, 127);
+    assert_eq!(poly.notes().collect::<Vec<_>>(), [60]);
+    poly.note_off(60);
+    poly.note_on(60, 127);
+    assert_eq!(poly.active_voices(), 1);
+}
+
+#[test]
+fn effect_in_series() {
+    let mut poly =
+        PolyDsp::<TestDsp<Synth>>::with_voices(2).with_effect(Box::new(TestDsp::<Double>::new()));
+    assert_eq!(poly.get_num_outputs(), 2);
+    poly.note_on(60, 127);
+    assert_eq!(compute(&mut poly, 2), [2.0, 2.0]);
+}
+
+#[test]
+fn effect_params_are_exposed() {
+    let poly =
+        PolyDsp::<TestDsp<Synth>>::with_voices(2).with_effect(Box::new(TestDsp::<Double>::new()));
+    let (mut dsp, mut state) = DspHandle::from_dsp(Box::new(poly));
+    let paths = state
+        .params_by_path()
+        .map(|(path, value)| (path.clone(), value.copied()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            ("double/factor".to_string(), Some(2.0)),
+            ("volume".to_string(), Some(1.0))
+        ]
+    );
+    state
+        .set_by_path("double/factor", 3.0)
+        .expect("path exists");
+    state.send();
+    dsp.update_and_compute(4, &[], &mut [&mut [0.0; 4], &mut [0.0; 4]]);
+    state.update();
+    assert_eq!(state.get_by_path("double/factor"), Some(&3.0));
+    assert_eq!(state.get_by_path("volume"), Some(&1.0));
+}
+
+#[test]
+fn notes_through_handle() {
+    let (mut dsp, mut state) =
+        DspHandle::from_instrument(Box::new(PolyDsp::<TestDsp<Synth>>::with_voices(2)));
+    assert!(dsp.plays_notes());
+    let compute = |dsp: &mut DspHandle<_>| {
+        let mut output = [0.0; 4];
+        dsp.update_and_compute(4, &[], &mut [&mut output]);
+        output[0]
+    };
+    for note in [60, 64] {
+        state
+            .send_note(NoteEvent::On {
+                note,
+                velocity: 127,
+            })
+            .expect("queue has room");
+    }
+    assert_eq!(compute(&mut dsp), 2.0);
+    state
+        .send_note(NoteEvent::On {
+            note: 60,
+            velocity: 0,
+        })
+        .expect("queue has room");
+    assert_eq!(compute(&mut dsp), 1.0);
+    dsp.handle_note(NoteEvent::AllOff);
+    assert_eq!(compute(&mut dsp), 0.0);
+}
+
+#[test]
+fn compute_does_not_allocate() {
+    let mut poly = PolyDsp::<TestDsp<Synth>>::with_voices(2)
+        .with_effect(Box::new(TestDsp::<Double>::new()))
+        .with_block_size(3);
+    assert_eq!(poly.block_size(), 3);
+    poly.note_on(60, 127);
+    let mut left = [0.0; 10];
+    let mut right = [0.0; 10];
+    let allocations = allocations::allocations(|| {
+        poly.compute(10, &[], &mut [&mut left, &mut right]);
+    });
+    assert_eq!(allocations, 0);
+    assert_eq!((left, right), ([2.0; 10], [2.0; 10]));
+}
@@ -0,0 +1,153 @@
+#[path = "fixtures/player.rs"]
+mod player;
+
+use faust_state::{
+    soundfile::{read_wav, urls, SoundfileLoader},
+    DspHandle,
+};
+use faust_types::{
+    ParamIndex, Soundfile, SoundfileIndex, MAX_SOUNDFILE_PARTS, SOUNDFILE_EMPTY_LENGTH,
+};
+use player::Player;
+use std::path::Path;
+
+const MONO: hound::WavSpec = hound::WavSpec {
+    channels: 1,
+    sample_rate: 44100,
+    bits_per_sample: 16,
+    sample_format: hound::SampleFormat::Int,
+};
+
+fn write_wav(path: &Path, spec: hound::WavSpec, samples: &[f32]) {
+    let mut writer = hound::WavWriter::create(path, spec).expect("cannot create wav file");
+    for sample in samples {
+        match spec.sample_format {
+            hound::SampleFormat::Float => writer.write_sample(*sample),
+            #[allow(clippy::cast_possible_truncation)]
+            hound::SampleFormat::Int => writer.write_sample((sample * 32768.0) as i16),
+        }
+        .expect("cannot write sample");
+    }
+    writer.finalize().expect("cannot finalize wav file");
+}
+
+#[test]
+fn split_urls() {
+    assert_eq!(urls("{'a.wav';'b/c.wav'}"), ["a.wav", "b/c.wav"]);
+    assert_eq!(urls("a.wav"), ["a.wav"]);
+    assert!(urls("{}").is_empty());
+}
+
+#[test]
+fn load_parts() {
+    let dir = tempfile::tempdir().expect("cannot create temp dir");
+    write_wav(&dir.path().join("mono.wav"), MONO, &[0.5, -0.25, 0.0]);
+    let stereo = hound::WavSpec {
+        channels: 2,
+        sample_rate: 48000,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    write_wav(
+        &dir.path().join("stereo.wav"),
+        stereo,
+        &[0.1, 0.2, 0.3, 0.4],
+    );
+
+    let soundfile: Soundfile = SoundfileLoader::new(dir.path())
+        .load("{'mono.wav';'stereo.wav'}")
+        .expect("cannot load soundfile");
+    assert_eq!(soundfile.channels(), 2);
+    assert_eq!(soundfile.parts(), 2);
+    assert_eq!(soundfile.length(), [3, 2]);
+    assert_eq!(soundfile.offset(), [0, 3]);
+    assert_eq!(soundfile.sample_rate(), [44100, 48000]);
+    assert_eq!(soundfile.part(0, 0), [0.5, -0.25, 0.0]);
+    assert_eq!(soundfile.part(1, 0), [0.0; 3]);
+    assert_eq!(soundfile.part(0, 1), [0.1, 0.3]);
+    assert_eq!(soundfile.part(1, 1), [0.2, 0.4]);
+}
+
+#[test]
+fn missing_file() {
+    let dir = tempfile::tempdir().expect("cannot create temp dir");
+    let err = SoundfileLoader::new(dir.path())
+        .load::<f32>("missing.wav")
+        .expect_err("missing file loaded");
+    assert!(err.contains("missing.wav"), "{}", err);
+}
+
+/// The layout the code generated by faust reads: every part has an entry, parts after the loaded
+/// ones are silent.
+#[test]
+fn faust_layout() {
+    let mut soundfile = Soundfile::<f32>::new(1);
+    soundfile.push_part(48000, &[vec![1.0, 2.0]]);
+    assert_eq!(soundfile.fChannels, 1);
+    assert_eq!(soundfile.fParts, 1);
+    assert_eq!(soundfile.fLength.len(), MAX_SOUNDFILE_PARTS);
+    assert_eq!(soundfile.fSR[..2], [48000, 44100]);
+    assert_eq!(soundfile.fOffset[..3], [0, 2, 2]);
+    assert_eq!(
+        soundfile.fLength[MAX_SOUNDFILE_PARTS - 1] as usize,
+        SOUNDFILE_EMPTY_LENGTH
+    );
+    assert_eq!(soundfile.fBuffers[0].len(), 2 + SOUNDFILE_EMPTY_LENGTH);
+    assert_eq!(soundfile.buffer(0), [1.0, 2.0]);
+    assert!(soundfile.part(0, 255).iter().all(|sample| *sample == 0.0));
+
+    for _ in 1..MAX_SOUNDFILE_PARTS {
+        soundfile.push_part(48000, &[vec![3.0]]);
+    }
+    assert_eq!(soundfile.parts(), MAX_SOUNDFILE_PARTS);
+    assert_eq!(soundfile.buffer(0).len(), 2 + MAX_SOUNDFILE_PARTS - 1);
+}
+
+fn play(dir: &Path, part: f32, frames: usize) -> Vec<f32> {
+    let (mut dsp, _state) = DspHandle::<Player>::new();
+    assert_eq!(dsp.soundfiles()[0].index, SoundfileIndex(0));
+    dsp.load_soundfiles(&SoundfileLoader::new(dir))
+        .expect("cannot load soundfiles");
+    dsp.init(44100);
+    dsp.set_param(ParamIndex(0), part);
+    let mut output = vec![0.0; frames];
+    dsp.compute(frames as i32, &[], &mut [&mut output]);
+    output
+}
+
+/// A dsp written like faust output plays the parts loaded through `DspHandle`.
+#[test]
+fn generated_dsp_reads_soundfile() {
+    let dir = tempfile::tempdir().expect("cannot create temp dir");
+    write_wav(&dir.path().join("a.wav"), MONO, &[0.5, -0.25, 0.125]);
+    write_wav(&dir.path().join("b.wav"), MONO, &[0.75, 0.25]);
+
+    // frames after the end of a part repeat its last frame
+    assert_eq!(play(dir.path(), 0.0, 4), [0.5, -0.25, 0.125, 0.125]);
+    assert_eq!(play(dir.path(), 1.0, 3), [0.75, 0.25, 0.25]);
+    // parts without a file are silent
+    assert_eq!(play(dir.path(), 3.0, 2), [0.0, 0.0]);
+}
+
+#[test]
+fn wav_without_channels() {
+    let dir = tempfile::tempdir().expect("cannot create temp dir");
+    let path = dir.path().join("empty.wav");
+    // a canonical 44 byte header with a channel count of 0
+    let mut header = Vec::new();
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&36_u32.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16_u32.to_le_bytes());
+    header.extend_from_slice(&1_u16.to_le_bytes());
+    header.extend_from_slice(&0_u16.to_le_bytes());
+    header.extend_from_slice(&44100_u32.to_le_bytes());
+    header.extend_from_slice(&0_u32.to_le_bytes());
+    header.extend_from_slice(&0_u16.to_le_bytes());
+    header.extend_from_slice(&16_u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&0_u32.to_le_bytes());
+    std::fs::write(&path, header).expect("cannot write wav file");
+    let err = read_wav(&path).expect_err("wav without channels read");
+    assert!(err.contains("empty.wav"), "{}", err);
+}
@@ -0,0 +1,341 @@
+//! Owned audio buffers in the planar layout of [`FaustDsp::compute`], with conversion from and to
+//! interleaved and integer host formats.
+
+#[cfg(feature = "alloc")]
+use crate::FaustDsp;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::{fmt, slice};
+
+/// Channels up to which [`with_channels`] and [`with_channels_mut`] do not allocate.
+pub const MAX_CHANNELS: usize = 64;
+
+/// A sample format of a host that can be converted to the sample type of a dsp.
+///
+/// Integer samples use their full range for `-1.0..1.0`.
+pub trait Sample: Copy {
+    fn to_f64(self) -> f64;
+    /// Values outside of the range of integer samples are clipped.
+    fn from_f64(value: f64) -> Self;
+
+    fn convert<S: Sample>(self) -> S {
+        S::from_f64(self.to_f64())
+    }
+}
+
+impl Sample for f32 {
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl Sample for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+impl Sample for i16 {
+    fn to_f64(self) -> f64 {
+        f64::from(self) / 32768.0
+    }
+    fn from_f64(value: f64) -> Self {
+        round((value * 32768.0).clamp(-32768.0, 32767.0)) as i16
+    }
+}
+
+impl Sample for i32 {
+    fn to_f64(self) -> f64 {
+        f64::from(self) / 2_147_483_648.0
+    }
+    fn from_f64(value: f64) -> Self {
+        round((value * 2_147_483_648.0).clamp(-2_147_483_648.0, 2_147_483_647.0)) as i32
+    }
+}
+
+/// Rounds half away from zero like `f64::round`, which is not available in `core`.
+///
+/// Only used for values in the range of `i32`, where the truncating casts are exact.
+fn round(value: f64) -> f64 {
+    let truncated = value as i64 as f64;
+    let fraction = value - truncated;
+    if fraction >= 0.5 {
+        truncated + 1.0
+    } else if fraction <= -0.5 {
+        truncated - 1.0
+    } else {
+        truncated
+    }
+}
+
+/// Input and output buffers of a dsp, allocated once for a maximal block length.
+///
+/// The channel slices passed to [`FaustDsp::compute`] are stored in arrays that are allocated with
+/// the buffers, so [`AudioBuffers::process`] does not allocate for any number of channels.
+///
+/// ```
+/// use faust_types::AudioBuffers;
+///
+/// let mut buffers = AudioBuffers::<f32>::new(1, 2, 256);
+/// // 16 bit mono input from the host
+/// let frames = buffers.read_interleaved(&[0_i16, 16384, -16384]);
+/// buffers.process(frames, |_count, inputs, outputs| {
+///     outputs[0][..frames].copy_from_slice(&inputs[0][..frames]);
+///     outputs[1][..frames].copy_from_slice(&inputs[0][..frames]);
+/// });
+/// let mut interleaved = [0_i16; 6];
+/// buffers.write_interleaved(&mut interleaved, frames);
+/// assert_eq!(interleaved, [0, 0, 16384, 16384, -16384, -16384]);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug)]
+pub struct AudioBuffers<T> {
+    inputs: Vec<Vec<T>>,
+    outputs: Vec<Vec<T>>,
+    max_frames: usize,
+    channels: Channels<T>,
+}
+
+/// Storage for the channel slices of [`AudioBuffers::process`], with a capacity for every channel.
+///
+/// The pointers are only valid during [`AudioBuffers::process`] and are overwritten before every use.
+#[cfg(feature = "alloc")]
+struct Channels<T> {
+    inputs: Vec<*const [T]>,
+    outputs: Vec<*mut [T]>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Channels<T> {
+    fn new(inputs: usize, outputs: usize) -> Self {
+        Self {
+            inputs: Vec::with_capacity(inputs),
+            outputs: Vec::with_capacity(outputs),
+        }
+    }
+}
+
+// SAFETY: the pointers are never dereferenced outside of `AudioBuffers::process`, which borrows the
+// buffers they point to mutably.
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send> Send for Channels<T> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Sync> Sync for Channels<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T> Clone for Channels<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.inputs.capacity(), self.outputs.capacity())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> fmt::Debug for Channels<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channels").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Sample + Default> AudioBuffers<T> {
+    pub fn new(inputs: usize, outputs: usize, max_frames: usize) -> Self {
+        Self {
+            inputs: vec![vec![T::default(); max_frames]; inputs],
+            outputs: vec![vec![T::default(); max_frames]; outputs],
+            max_frames,
+            channels: Channels::new(inputs, outputs),
+        }
+    }
+
+    /// Buffers with the channels of `dsp`.
+    pub fn for_dsp(dsp: &(impl FaustDsp<T = T> + ?Sized), max_frames: usize) -> Self {
+        Self::new(
+            dsp.get_num_inputs() as usize,
+            dsp.get_num_outputs() as usize,
+            max_frames,
+        )
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    pub fn input(&self, channel: usize) -> &[T] {
+        &self.inputs[channel]
+    }
+
+    pub fn input_mut(&mut self, channel: usize) -> &mut [T] {
+        &mut self.inputs[channel]
+    }
+
+    pub fn output(&self, channel: usize) -> &[T] {
+        &self.outputs[channel]
+    }
+
+    pub fn output_mut(&mut self, channel: usize) -> &mut [T] {
+        &mut self.outputs[channel]
+    }
+
+    /// Calls `f` with the first `frames` frames of every channel, as expected by [`FaustDsp::compute`].
+    pub fn process<R>(
+        &mut self,
+        frames: usize,
+        f: impl FnOnce(i32, &[&[T]], &mut [&mut [T]]) -> R,
+    ) -> R {
+        assert!(
+            frames <= self.max_frames,
+            "{} frames exceed the buffer size of {}",
+            frames,
+            self.max_frames
+        );
+        let channels = &mut self.channels;
+        // the capacity is reserved for every channel, so this does not allocate
+        channels.inputs.clear();
+        channels.inputs.extend(
+            self.inputs
+                .iter()
+                .map(|input| &input[..frames] as *const [T]),
+        );
+        channels.outputs.clear();
+        channels.outputs.extend(
+            self.outputs
+                .iter_mut()
+                .map(|output| &mut output[..frames] as *mut [T]),
+        );
+        // SAFETY: `*const [T]` and `*mut [T]` have the layout of `&[T]` and `&mut [T]`. The pointers
+        // were just taken from distinct buffers that stay borrowed by `self` until `f` returns, and
+        // `f` cannot keep the slices because their lifetimes end with the call.
+        let (inputs, outputs) = unsafe {
+            (
+                slice::from_raw_parts(
+                    channels.inputs.as_ptr().cast::<&[T]>(),
+                    channels.inputs.len(),
+                ),
+                slice::from_raw_parts_mut(
+                    channels.outputs.as_mut_ptr().cast::<&mut [T]>(),
+                    channels.outputs.len(),
+                ),
+            )
+        };
+        f(frames as i32, inputs, outputs)
+    }
+
+    /// Computes the first `frames` frames with `dsp`.
+    pub fn compute(&mut self, dsp: &mut (impl FaustDsp<T = T> + ?Sized), frames: usize) {
+        self.process(frames, |count, inputs, outputs| {
+            dsp.compute(count, inputs, outputs);
+        });
+    }
+
+    /// Fills the inputs from interleaved samples and returns the number of frames read.
+    pub fn read_interleaved<S: Sample>(&mut self, interleaved: &[S]) -> usize {
+        let channels = self.inputs.len();
+        if channels == 0 {
+            return 0;
+        }
+        let frames = (interleaved.len() / channels).min(self.max_frames);
+        for (frame, samples) in interleaved.chunks_exact(channels).take(frames).enumerate() {
+            for (input, sample) in self.inputs.iter_mut().zip(samples) {
+                input[frame] = sample.convert();
+            }
+        }
+        frames
+    }
+
+    /// Writes the first `frames` frames of the outputs as interleaved samples.
+    pub fn write_interleaved<S: Sample>(&self, interleaved: &mut [S], frames: usize) {
+        let channels = self.outputs.len();
+        if channels == 0 {
+            return;
+        }
+        for (frame, samples) in interleaved
+            .chunks_exact_mut(channels)
+            .take(frames)
+            .enumerate()
+        {
+            for (sample, output) in samples.iter_mut().zip(&self.outputs) {
+                *sample = output[frame].convert();
+            }
+        }
+    }
+
+    /// Fills the inputs from one slice per channel and returns the number of frames read.
+    pub fn read_planar<S: Sample>(&mut self, channels: &[&[S]]) -> usize {
+        let frames = channels
+            .iter()
+            .map(|channel| channel.len())
+            .min()
+            .unwrap_or(0)
+            .min(self.max_frames);
+        for (input, channel) in self.inputs.iter_mut().zip(channels) {
+            for (sample, host) in input.iter_mut().zip(&channel[..frames]) {
+                *sample = host.convert();
+            }
+        }
+        frames
+    }
+
+    /// Writes the first `frames` frames of the outputs into one slice per channel.
+    pub fn write_planar<S: Sample>(&self, channels: &mut [&mut [S]], frames: usize) {
+        for (channel, output) in channels.iter_mut().zip(&self.outputs) {
+            for (host, sample) in channel.iter_mut().zip(&output[..frames]) {
+                *host = sample.convert();
+            }
+        }
+    }
+}
+
+/// Calls `f` with `channels` collected into a slice, as expected by [`FaustDsp::compute`].
+///
+/// Allocates only for more than [`MAX_CHANNELS`] channels.
+#[cfg(feature = "alloc")]
+pub fn with_channels<'a, T: 'a, R>(
+    channels: impl ExactSizeIterator<Item = &'a [T]>,
+    f: impl FnOnce(&[&'a [T]]) -> R,
+) -> R {
+    if channels.len() > MAX_CHANNELS {
+        return f(&channels.collect::<Vec<_>>());
+    }
+    let mut array: [&[T]; MAX_CHANNELS] = [&[]; MAX_CHANNELS];
+    let mut count = 0;
+    for (slot, channel) in array.iter_mut().zip(channels) {
+        *slot = channel;
+        count += 1;
+    }
+    f(&array[..count])
+}
+
+/// Calls `f` with `channels` collected into a mutable slice, as expected by [`FaustDsp::compute`].
+///
+/// Allocates only for more than [`MAX_CHANNELS`] channels.
+#[cfg(feature = "alloc")]
+pub fn with_channels_mut<'a, T: 'a, R>(
+    channels: impl ExactSizeIterator<Item = &'a mut [T]>,
+    f: impl FnOnce(&mut [&'a mut [T]]) -> R,
+) -> R {
+    if channels.len() > MAX_CHANNELS {
+        return f(&mut channels.collect::<Vec<_>>());
+    }
+    let mut array: [&mut [T]; MAX_CHANNELS] = core::array::from_fn(|_| &mut [][..]);
+    let mut count = 0;
+    for (slot, channel) in array.iter_mut().zip(channels) {
+        *slot = channel;
+        count += 1;
+    }
+    f(&mut array[..count])
+}
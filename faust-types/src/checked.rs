@@ -0,0 +1,149 @@
+//! Validation of the arguments of [`FaustDsp::compute`].
+
+use crate::FaustDsp;
+use core::{convert::TryFrom, fmt};
+
+/// Arguments of [`FaustDsp::compute`] that the dsp cannot process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeError {
+    NegativeCount(i32),
+    InputChannels {
+        expected: usize,
+        actual: usize,
+    },
+    OutputChannels {
+        expected: usize,
+        actual: usize,
+    },
+    /// An input channel has less than `count` samples.
+    ShortInput {
+        channel: usize,
+        len: usize,
+        count: usize,
+    },
+    /// An output channel has less than `count` samples.
+    ShortOutput {
+        channel: usize,
+        len: usize,
+        count: usize,
+    },
+}
+
+impl fmt::Display for ComputeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NegativeCount(count) => write!(f, "negative frame count {}", count),
+            Self::InputChannels { expected, actual } => {
+                write!(f, "dsp has {} inputs, got {}", expected, actual)
+            }
+            Self::OutputChannels { expected, actual } => {
+                write!(f, "dsp has {} outputs, got {}", expected, actual)
+            }
+            Self::ShortInput {
+                channel,
+                len,
+                count,
+            } => write!(
+                f,
+                "input {} has {} samples, {} are computed",
+                channel, len, count
+            ),
+            Self::ShortOutput {
+                channel,
+                len,
+                count,
+            } => write!(
+                f,
+                "output {} has {} samples, {} are computed",
+                channel, len, count
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ComputeError {}
+
+/// Checked variants of [`FaustDsp::compute`], implemented for every dsp.
+///
+/// The generated code indexes the buffers without checks of its own, so wrong arguments otherwise
+/// panic inside of it or, with too many channels, are silently ignored.
+pub trait CheckedCompute: FaustDsp {
+    /// Checks that there are exactly as many channels as the dsp has and that every channel has at
+    /// least `count` samples.
+    fn check_compute(
+        &self,
+        count: i32,
+        inputs: &[&[Self::T]],
+        outputs: &[&mut [Self::T]],
+    ) -> Result<(), ComputeError> {
+        let count = usize::try_from(count).map_err(|_| ComputeError::NegativeCount(count))?;
+        let expected = self.get_num_inputs() as usize;
+        if inputs.len() != expected {
+            return Err(ComputeError::InputChannels {
+                expected,
+                actual: inputs.len(),
+            });
+        }
+        let expected = self.get_num_outputs() as usize;
+        if outputs.len() != expected {
+            return Err(ComputeError::OutputChannels {
+                expected,
+                actual: outputs.len(),
+            });
+        }
+        if let Some((channel, input)) = inputs
+            .iter()
+            .enumerate()
+            .find(|(_, input)| input.len() < count)
+        {
+            return Err(ComputeError::ShortInput {
+                channel,
+                len: input.len(),
+                count,
+            });
+        }
+        if let Some((channel, output)) = outputs
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.len() < count)
+        {
+            return Err(ComputeError::ShortOutput {
+                channel,
+                len: output.len(),
+                count,
+            });
+        }
+        Ok(())
+    }
+
+    /// Computes only if [`CheckedCompute::check_compute`] succeeds.
+    fn checked_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[Self::T]],
+        outputs: &mut [&mut [Self::T]],
+    ) -> Result<(), ComputeError> {
+        self.check_compute(count, inputs, outputs)?;
+        self.compute(count, inputs, outputs);
+        Ok(())
+    }
+
+    /// Checks the arguments in debug builds and panics with the [`ComputeError`]. Release builds
+    /// compute without checks.
+    fn debug_checked_compute(
+        &mut self,
+        count: i32,
+        inputs: &[&[Self::T]],
+        outputs: &mut [&mut [Self::T]],
+    ) {
+        if cfg!(debug_assertions) {
+            if let Err(err) = self.check_compute(count, inputs, outputs) {
+                panic!("invalid arguments for compute: {}", err);
+            }
+        }
+        self.compute(count, inputs, outputs);
+    }
+}
+
+impl<D: FaustDsp + ?Sized> CheckedCompute for D {}
@@ -1,17 +1,170 @@
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::convert::TryFrom;
+
+pub mod buffers;
+pub mod checked;
+#[cfg(feature = "libm")]
+pub mod math;
+#[cfg(feature = "alloc")]
+pub mod widgets;
+
+#[cfg(feature = "alloc")]
+pub use buffers::AudioBuffers;
+pub use buffers::Sample;
+pub use checked::{CheckedCompute, ComputeError};
+#[cfg(feature = "libm")]
+pub use math::FloatMath;
+
+/// Version of this crate, for generators of crates that depend on it.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub type F32 = f32;
 pub type F64 = f64;
 
 #[derive(Copy, Clone, Debug)]
 pub struct ParamIndex(pub i32);
 
-#[allow(dead_code)]
+/// Position of a soundfile in the order the dsp declares them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SoundfileIndex(pub usize);
+
+/// Number of parts of every [`Soundfile`], like `MAX_SOUNDFILE_PARTS` of the faust architecture.
+pub const MAX_SOUNDFILE_PARTS: usize = 256;
+/// Length in frames of the silent parts of a [`Soundfile`].
+pub const SOUNDFILE_EMPTY_LENGTH: usize = 1024;
+/// Sample rate of the silent parts of a [`Soundfile`].
+pub const SOUNDFILE_EMPTY_SAMPLE_RATE: i32 = 44100;
+
+/// Audio data of a soundfile, laid out like the `Soundfile` of the faust C++ architecture.
+///
+/// A soundfile consists of one or more parts, one per file of its url. The samples of all parts
+/// are concatenated per channel, `fOffset` points to the first frame of each part. The fields
+/// have the names and layout the code generated by faust reads, e.g.
+/// `fSoundfile0.fBuffers[chan][(fSoundfile0.fOffset[part] + frame) as usize]`.
+///
+/// Like in faust, `fLength`, `fSR` and `fOffset` always have [`MAX_SOUNDFILE_PARTS`] entries.
+/// Parts after the loaded ones are silent, they share one block of [`SOUNDFILE_EMPTY_LENGTH`] frames
+/// at the end of the buffers. `fBuffers` has one buffer per channel of the loaded files.
+#[cfg(feature = "alloc")]
 #[allow(non_snake_case)]
-pub struct Soundfile<'a> {
-    fBuffers: &'a &'a F32,
-    fLength: &'a i32,
-    fSR: &'a i32,
-    fOffset: &'a i32,
-    fChannels: i32,
+#[derive(Clone, Debug, PartialEq)]
+pub struct Soundfile<T = F32> {
+    pub fBuffers: Vec<Vec<T>>,
+    pub fLength: Vec<i32>,
+    pub fSR: Vec<i32>,
+    pub fOffset: Vec<i32>,
+    pub fChannels: i32,
+    pub fParts: i32,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Copy + Default> Soundfile<T> {
+    /// Silent soundfile without loaded parts.
+    pub fn new(channels: usize) -> Self {
+        Self {
+            fBuffers: vec![vec![T::default(); SOUNDFILE_EMPTY_LENGTH]; channels],
+            fLength: vec![to_i32(SOUNDFILE_EMPTY_LENGTH); MAX_SOUNDFILE_PARTS],
+            fSR: vec![SOUNDFILE_EMPTY_SAMPLE_RATE; MAX_SOUNDFILE_PARTS],
+            fOffset: vec![0; MAX_SOUNDFILE_PARTS],
+            fChannels: to_i32(channels),
+            fParts: 0,
+        }
+    }
+
+    /// Appends a part with one buffer per channel.
+    ///
+    /// Missing channels are filled with silence, additional channels are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If the soundfile already has [`MAX_SOUNDFILE_PARTS`] parts.
+    pub fn push_part(&mut self, sample_rate: i32, channels: &[Vec<T>]) {
+        let part = self.parts();
+        assert!(
+            part < MAX_SOUNDFILE_PARTS,
+            "a soundfile has at most {} parts",
+            MAX_SOUNDFILE_PARTS
+        );
+        let length = channels.iter().map(Vec::len).max().unwrap_or_default();
+        let offset = self.end();
+        for (i, buffer) in self.fBuffers.iter_mut().enumerate() {
+            let channel = channels.get(i).map_or(&[][..], Vec::as_slice);
+            buffer.truncate(offset);
+            buffer.extend_from_slice(channel);
+            buffer.resize(offset + length + SOUNDFILE_EMPTY_LENGTH, T::default());
+        }
+        self.fLength[part] = to_i32(length);
+        self.fSR[part] = sample_rate;
+        self.fOffset[part] = to_i32(offset);
+        for empty in &mut self.fOffset[part + 1..] {
+            *empty = to_i32(offset + length);
+        }
+        self.fParts += 1;
+    }
+
+    pub fn channels(&self) -> usize {
+        self.fBuffers.len()
+    }
+
+    /// Number of loaded parts.
+    pub fn parts(&self) -> usize {
+        self.fParts as usize
+    }
+
+    /// Samples of all loaded parts of a channel.
+    pub fn buffer(&self, channel: usize) -> &[T] {
+        &self.fBuffers[channel][..self.end()]
+    }
+
+    /// Samples of one part of a channel.
+    pub fn part(&self, channel: usize, part: usize) -> &[T] {
+        let offset = self.fOffset[part] as usize;
+        &self.fBuffers[channel][offset..offset + self.fLength[part] as usize]
+    }
+
+    /// Length of each loaded part in frames.
+    pub fn length(&self) -> &[i32] {
+        &self.fLength[..self.parts()]
+    }
+
+    /// Sample rate of each loaded part.
+    pub fn sample_rate(&self) -> &[i32] {
+        &self.fSR[..self.parts()]
+    }
+
+    /// First frame of each loaded part.
+    pub fn offset(&self) -> &[i32] {
+        &self.fOffset[..self.parts()]
+    }
+
+    /// First frame after the loaded parts.
+    fn end(&self) -> usize {
+        self.parts()
+            .checked_sub(1)
+            .map_or(0, |last| (self.fOffset[last] + self.fLength[last]) as usize)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Copy + Default> Default for Soundfile<T> {
+    /// Silent mono soundfile, like the `defaultsound` faust uses before a soundfile is loaded.
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn to_i32(frames: usize) -> i32 {
+    i32::try_from(frames).expect("soundfile is too long")
 }
 
 pub trait FaustDsp {
@@ -80,10 +233,26 @@ pub trait UI<T> {
     fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T);
     fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T);
 
+    // -- soundfiles
+    /// `url` is the url as declared in the dsp, e.g. `{'a.wav';'b.wav'}` for a soundfile with two parts.
+    fn add_soundfile(&mut self, _label: &str, _url: &str, _soundfile: SoundfileIndex) {}
+
     // -- metadata declarations
     fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str);
 }
 
+/// Dsps that declare soundfiles, implemented by the ui code generated by `faust-ui`.
+///
+/// Soundfiles have to be set before the dsp is initialized.
+#[cfg(feature = "alloc")]
+pub trait SoundfileDsp: FaustDsp {
+    /// Calls [`UI::add_soundfile`] for every soundfile of the dsp.
+    fn build_soundfile_interface(ui_interface: &mut dyn UI<Self::T>)
+    where
+        Self: Sized;
+    fn set_soundfile(&mut self, soundfile: SoundfileIndex, data: Soundfile<Self::T>);
+}
+
 // traits for generated code
 pub trait UISet<D, F> {
     fn set(&self, dsp: &mut D, value: F);
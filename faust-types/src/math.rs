@@ -0,0 +1,149 @@
+//! Float functions for generated code on `no_std` targets, backed by [`libm`].
+//!
+//! The code generated by faust calls math functions on the float type, e.g. `F32::sin(x)`. With
+//! `std` these resolve to the inherent methods of `f32` and `f64`. Without `std` only a few of them,
+//! like `abs` and `max`, exist in `core`, and the calls fall back to [`FloatMath`], which is in scope
+//! through `use faust_types::*;`.
+//!
+//! ```
+//! use faust_types::FloatMath;
+//!
+//! assert_eq!(FloatMath::powf(10.0_f32, 2.0), 100.0);
+//! assert_eq!(FloatMath::rem_euclid(-1.0_f64, 4.0), 3.0);
+//! ```
+
+pub use libm;
+
+/// The float methods of `std` that are missing in `core`, with the same names and signatures.
+pub trait FloatMath: Sized {
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn trunc(self) -> Self;
+    fn fract(self) -> Self;
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn cbrt(self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn exp(self) -> Self;
+    fn exp2(self) -> Self;
+    fn exp_m1(self) -> Self;
+    fn ln(self) -> Self;
+    fn ln_1p(self) -> Self;
+    fn log(self, base: Self) -> Self;
+    fn log2(self) -> Self;
+    fn log10(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+    fn asinh(self) -> Self;
+    fn acosh(self) -> Self;
+    fn atanh(self) -> Self;
+}
+
+macro_rules! float_math {
+    ($float:ty, $($name:ident($($arg:ident),*) => $libm:ident;)*) => {
+        impl FloatMath for $float {
+            $(
+                fn $name(self, $($arg: Self),*) -> Self {
+                    libm::$libm(self, $($arg),*)
+                }
+            )*
+
+            fn fract(self) -> Self {
+                self - FloatMath::trunc(self)
+            }
+
+            fn rem_euclid(self, rhs: Self) -> Self {
+                let rem = self % rhs;
+                if rem < 0.0 {
+                    rem + rhs.abs()
+                } else {
+                    rem
+                }
+            }
+
+            fn powi(self, n: i32) -> Self {
+                FloatMath::powf(self, n as Self)
+            }
+
+            fn log(self, base: Self) -> Self {
+                FloatMath::ln(self) / FloatMath::ln(base)
+            }
+        }
+    };
+}
+
+float_math!(f32,
+    floor() => floorf;
+    ceil() => ceilf;
+    round() => roundf;
+    trunc() => truncf;
+    mul_add(a, b) => fmaf;
+    powf(n) => powf;
+    sqrt() => sqrtf;
+    cbrt() => cbrtf;
+    hypot(other) => hypotf;
+    exp() => expf;
+    exp2() => exp2f;
+    exp_m1() => expm1f;
+    ln() => logf;
+    ln_1p() => log1pf;
+    log2() => log2f;
+    log10() => log10f;
+    sin() => sinf;
+    cos() => cosf;
+    tan() => tanf;
+    asin() => asinf;
+    acos() => acosf;
+    atan() => atanf;
+    atan2(other) => atan2f;
+    sinh() => sinhf;
+    cosh() => coshf;
+    tanh() => tanhf;
+    asinh() => asinhf;
+    acosh() => acoshf;
+    atanh() => atanhf;
+);
+
+float_math!(f64,
+    floor() => floor;
+    ceil() => ceil;
+    round() => round;
+    trunc() => trunc;
+    mul_add(a, b) => fma;
+    powf(n) => pow;
+    sqrt() => sqrt;
+    cbrt() => cbrt;
+    hypot(other) => hypot;
+    exp() => exp;
+    exp2() => exp2;
+    exp_m1() => expm1;
+    ln() => log;
+    ln_1p() => log1p;
+    log2() => log2;
+    log10() => log10;
+    sin() => sin;
+    cos() => cos;
+    tan() => tan;
+    asin() => asin;
+    acos() => acos;
+    atan() => atan;
+    atan2(other) => atan2;
+    sinh() => sinh;
+    cosh() => cosh;
+    tanh() => tanh;
+    asinh() => asinh;
+    acosh() => acosh;
+    atanh() => atanh;
+);
@@ -0,0 +1,215 @@
+//! The widgets of a dsp as plain data, collected from its user interface.
+//!
+//! Hosts and plugin wrappers that need the parameters of a dsp at runtime use [`widgets`] instead
+//! of implementing [`UI`] themselves.
+//!
+//! ```
+//! # use faust_types::*;
+//! # struct Volume;
+//! # impl FaustDsp for Volume {
+//! #     type T = f32;
+//! #     fn new() -> Self { Self }
+//! #     fn metadata(&self, _: &mut dyn Meta) {}
+//! #     fn get_sample_rate(&self) -> i32 { 44100 }
+//! #     fn get_num_inputs(&self) -> i32 { 1 }
+//! #     fn get_num_outputs(&self) -> i32 { 1 }
+//! #     fn class_init(_: i32) {}
+//! #     fn instance_reset_params(&mut self) {}
+//! #     fn instance_clear(&mut self) {}
+//! #     fn instance_constants(&mut self, _: i32) {}
+//! #     fn instance_init(&mut self, _: i32) {}
+//! #     fn init(&mut self, _: i32) {}
+//! #     fn build_user_interface(&self, ui: &mut dyn UI<f32>) { Self::build_user_interface_static(ui) }
+//! #     fn build_user_interface_static(ui: &mut dyn UI<f32>) {
+//! #         ui.open_vertical_box("volume");
+//! #         ui.declare(Some(ParamIndex(0)), "unit", "dB");
+//! #         ui.add_horizontal_slider("gain", ParamIndex(0), 0.0, -70.0, 4.0, 0.1);
+//! #         ui.close_box();
+//! #     }
+//! #     fn get_param(&self, _: ParamIndex) -> Option<f32> { None }
+//! #     fn set_param(&mut self, _: ParamIndex, _: f32) {}
+//! #     fn compute(&mut self, _: i32, _: &[&[f32]], _: &mut [&mut [f32]]) {}
+//! # }
+//! use faust_types::widgets::{widgets, WidgetKind};
+//!
+//! let widgets = widgets::<Volume>();
+//! assert_eq!(widgets[0].kind, WidgetKind::HorizontalSlider);
+//! assert_eq!(widgets[0].address(), "/volume/gain");
+//! assert_eq!(widgets[0].meta("unit"), Some("dB"));
+//! assert_eq!((widgets[0].min, widgets[0].max), (-70.0, 4.0));
+//! ```
+
+use crate::{FaustDsp, ParamIndex, Sample, UI};
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetKind {
+    Button,
+    CheckButton,
+    VerticalSlider,
+    HorizontalSlider,
+    NumEntry,
+    HorizontalBargraph,
+    VerticalBargraph,
+}
+
+impl WidgetKind {
+    /// Bargraphs, written by the dsp instead of the user.
+    pub fn is_passive(self) -> bool {
+        matches!(self, Self::HorizontalBargraph | Self::VerticalBargraph)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WidgetInfo {
+    pub index: ParamIndex,
+    pub kind: WidgetKind,
+    pub label: String,
+    /// Labels of the enclosing boxes, outermost first.
+    pub groups: Vec<String>,
+    /// `0` for buttons and check buttons, `min` for bargraphs.
+    pub init: f64,
+    /// `0` for buttons and check buttons.
+    pub min: f64,
+    /// `1` for buttons and check buttons.
+    pub max: f64,
+    /// `1` for buttons and check buttons, `0` for bargraphs.
+    pub step: f64,
+    /// Metadata declared for the widget, e.g. `unit` or `hidden`, in declaration order.
+    pub meta: Vec<(String, String)>,
+}
+
+impl WidgetInfo {
+    /// Address like `/synth/filter/cutoff`, built from the labels of the boxes and the widget.
+    pub fn address(&self) -> String {
+        let mut address = String::new();
+        for label in self.groups.iter().chain([&self.label]) {
+            address.push('/');
+            address.push_str(label);
+        }
+        address
+    }
+
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The widgets of `D` in the order of its user interface.
+pub fn widgets<D>() -> Vec<WidgetInfo>
+where
+    D: FaustDsp,
+    D::T: Sample,
+{
+    let mut collector = WidgetCollector::default();
+    D::build_user_interface_static(&mut collector);
+    collector.widgets
+}
+
+/// [`UI`] implementation behind [`widgets`], also usable with `FaustDsp::build_user_interface`.
+#[derive(Debug, Default)]
+pub struct WidgetCollector {
+    widgets: Vec<WidgetInfo>,
+    groups: Vec<String>,
+    /// Metadata declared before the widget it belongs to.
+    meta: BTreeMap<i32, Vec<(String, String)>>,
+}
+
+impl WidgetCollector {
+    pub fn into_widgets(self) -> Vec<WidgetInfo> {
+        self.widgets
+    }
+
+    fn add(&mut self, label: &str, index: ParamIndex, kind: WidgetKind, values: [f64; 4]) {
+        let [init, min, max, step] = values;
+        self.widgets.push(WidgetInfo {
+            index,
+            kind,
+            label: label.to_owned(),
+            groups: self.groups.clone(),
+            init,
+            min,
+            max,
+            step,
+            meta: self.meta.remove(&index.0).unwrap_or_default(),
+        });
+    }
+
+    fn add_ranged<T: Sample>(
+        &mut self,
+        label: &str,
+        index: ParamIndex,
+        kind: WidgetKind,
+        values: [T; 4],
+    ) {
+        self.add(label, index, kind, values.map(Sample::to_f64));
+    }
+}
+
+impl<T: Sample> UI<T> for WidgetCollector {
+    fn open_tab_box(&mut self, label: &str) {
+        self.groups.push(label.to_owned());
+    }
+    fn open_horizontal_box(&mut self, label: &str) {
+        self.groups.push(label.to_owned());
+    }
+    fn open_vertical_box(&mut self, label: &str) {
+        self.groups.push(label.to_owned());
+    }
+    fn close_box(&mut self) {
+        self.groups.pop();
+    }
+    fn add_button(&mut self, label: &str, param: ParamIndex) {
+        self.add(label, param, WidgetKind::Button, [0.0, 0.0, 1.0, 1.0]);
+    }
+    fn add_check_button(&mut self, label: &str, param: ParamIndex) {
+        self.add(label, param, WidgetKind::CheckButton, [0.0, 0.0, 1.0, 1.0]);
+    }
+    fn add_vertical_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: T,
+        min: T,
+        max: T,
+        step: T,
+    ) {
+        let values = [init, min, max, step];
+        self.add_ranged(label, param, WidgetKind::VerticalSlider, values);
+    }
+    fn add_horizontal_slider(
+        &mut self,
+        label: &str,
+        param: ParamIndex,
+        init: T,
+        min: T,
+        max: T,
+        step: T,
+    ) {
+        let values = [init, min, max, step];
+        self.add_ranged(label, param, WidgetKind::HorizontalSlider, values);
+    }
+    fn add_num_entry(&mut self, label: &str, param: ParamIndex, init: T, min: T, max: T, step: T) {
+        let values = [init, min, max, step];
+        self.add_ranged(label, param, WidgetKind::NumEntry, values);
+    }
+    fn add_horizontal_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T) {
+        let values = [min.to_f64(), min.to_f64(), max.to_f64(), 0.0];
+        self.add(label, param, WidgetKind::HorizontalBargraph, values);
+    }
+    fn add_vertical_bargraph(&mut self, label: &str, param: ParamIndex, min: T, max: T) {
+        let values = [min.to_f64(), min.to_f64(), max.to_f64(), 0.0];
+        self.add(label, param, WidgetKind::VerticalBargraph, values);
+    }
+    fn declare(&mut self, param: Option<ParamIndex>, key: &str, value: &str) {
+        if let Some(param) = param {
+            self.meta
+                .entry(param.0)
+                .or_default()
+                .push((key.to_owned(), value.to_owned()));
+        }
+    }
+}
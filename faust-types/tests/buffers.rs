@@ -0,0 +1,46 @@
+use faust_types::{buffers::MAX_CHANNELS, AudioBuffers, Sample};
+
+#[test]
+fn integer_samples_are_clipped() {
+    assert_eq!(i16::from_f64(1.5), i16::MAX);
+    assert_eq!(i16::from_f64(-1.0), i16::MIN);
+    assert_eq!(i32::from_f64(0.5), 1 << 30);
+    // halves are rounded away from zero
+    assert_eq!(i16::from_f64(1.5 / 32768.0), 2);
+    assert_eq!(i16::from_f64(-2.5 / 32768.0), -3);
+    assert_eq!(i16::from_f64(0.49 / 32768.0), 0);
+    assert_eq!((-16384_i16).convert::<f32>(), -0.5);
+}
+
+#[test]
+fn planar_f64_host() {
+    let mut buffers = AudioBuffers::<f32>::new(2, 1, 4);
+    let frames = buffers.read_planar(&[&[0.25_f64, 0.5, 0.75][..], &[1.0, 1.0, 1.0, 1.0]]);
+    assert_eq!(frames, 3);
+    buffers.process(frames, |count, inputs, outputs| {
+        assert_eq!(count, 3);
+        for (frame, output) in outputs[0].iter_mut().enumerate() {
+            *output = inputs[0][frame] + inputs[1][frame];
+        }
+    });
+    let mut output = [0.0_f64; 3];
+    buffers.write_planar(&mut [&mut output], frames);
+    assert_eq!(output, [1.25, 1.5, 1.75]);
+}
+
+#[test]
+fn many_channels() {
+    let channels = MAX_CHANNELS + 1;
+    let mut buffers = AudioBuffers::<f32>::new(channels, channels, 2);
+    let interleaved = (0..channels * 2).map(|i| i as f32).collect::<Vec<_>>();
+    assert_eq!(buffers.read_interleaved(&interleaved), 2);
+    buffers.process(2, |_count, inputs, outputs| {
+        assert_eq!((inputs.len(), outputs.len()), (channels, channels));
+        for (output, input) in outputs.iter_mut().zip(inputs) {
+            output.copy_from_slice(input);
+        }
+    });
+    let mut output = vec![0.0_f32; channels * 2];
+    buffers.write_interleaved(&mut output, 2);
+    assert_eq!(output, interleaved);
+}
@@ -0,0 +1,86 @@
+use faust_types::*;
+
+#[path = "fixtures/test_dsp.rs"]
+mod test_dsp;
+
+use test_dsp::{Spec, TestDsp};
+
+/// Copies its input to both outputs.
+struct Split;
+
+impl Spec for Split {
+    const INPUTS: usize = 1;
+    const OUTPUTS: usize = 2;
+    type State = ();
+
+    fn compute(
+        _dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    ) {
+        for output in outputs.iter_mut() {
+            output[..count].copy_from_slice(&inputs[0][..count]);
+        }
+    }
+}
+
+#[test]
+fn valid_arguments() {
+    let mut left = [0.0; 4];
+    let mut right = [0.0; 8];
+    TestDsp::<Split>::new()
+        .checked_compute(4, &[&[1.0; 4]], &mut [&mut left, &mut right])
+        .expect("invalid arguments");
+    assert_eq!(left, [1.0; 4]);
+    assert_eq!(right[..4], [1.0; 4]);
+}
+
+#[test]
+fn invalid_arguments() {
+    let mut dsp = TestDsp::<Split>::new();
+    let mut left = [0.0; 4];
+    let mut right = [0.0; 3];
+    assert_eq!(
+        dsp.checked_compute(-1, &[&[0.0; 4]], &mut [&mut left, &mut right]),
+        Err(ComputeError::NegativeCount(-1))
+    );
+    assert_eq!(
+        dsp.checked_compute(4, &[], &mut [&mut left, &mut right]),
+        Err(ComputeError::InputChannels {
+            expected: 1,
+            actual: 0
+        })
+    );
+    assert_eq!(
+        dsp.checked_compute(4, &[&[0.0; 4]], &mut [&mut left]),
+        Err(ComputeError::OutputChannels {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        dsp.checked_compute(4, &[&[0.0; 2]], &mut [&mut left, &mut right]),
+        Err(ComputeError::ShortInput {
+            channel: 0,
+            len: 2,
+            count: 4
+        })
+    );
+    let err = dsp
+        .checked_compute(4, &[&[0.0; 4]], &mut [&mut left, &mut right])
+        .expect_err("short output accepted");
+    assert_eq!(err.to_string(), "output 1 has 3 samples, 4 are computed");
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "invalid arguments for compute: dsp has 1 inputs, got 2")]
+fn debug_checks_panic() {
+    let mut output = [0.0; 4];
+    TestDsp::<Split>::new().debug_checked_compute(
+        4,
+        &[&[0.0; 4], &[0.0; 4]],
+        &mut [&mut output, &mut [0.0; 4]],
+    );
+}
@@ -0,0 +1,158 @@
+//! The dsp of the tests of all crates, included with `#[path]`.
+//!
+//! [`TestDsp`] implements [`FaustDsp`] for a [`Spec`] that only declares the channels, metadata
+//! and widgets and computes the outputs.
+
+#![allow(dead_code)]
+
+use faust_types::*;
+use std::marker::PhantomData;
+
+/// An item of the user interface, the parameters are numbered in order.
+pub enum Ui {
+    VBox(&'static str),
+    HBox(&'static str),
+    Close,
+    /// Declared on the next parameter.
+    Declare(&'static str, &'static str),
+    Button(&'static str),
+    /// Label, init, min, max and step.
+    HSlider(&'static str, f32, f32, f32, f32),
+    /// Label, init, min, max and step.
+    NumEntry(&'static str, f32, f32, f32, f32),
+    /// Label, min and max.
+    VBargraph(&'static str, f32, f32),
+}
+
+impl Ui {
+    fn init(&self) -> Option<f32> {
+        match self {
+            Self::Button(_) | Self::VBargraph(..) => Some(0.0),
+            Self::HSlider(_, init, ..) | Self::NumEntry(_, init, ..) => Some(*init),
+            Self::VBox(_) | Self::HBox(_) | Self::Close | Self::Declare(..) => None,
+        }
+    }
+}
+
+pub trait Spec: Sized + 'static {
+    const INPUTS: usize;
+    const OUTPUTS: usize;
+    const META: &'static [(&'static str, &'static str)] = &[];
+    const UI: &'static [Ui] = &[];
+    /// Kept by the dsp for the test, e.g. to record its calls.
+    type State: Default;
+
+    /// Computes `count` frames, [`TestDsp::frames`] is advanced afterwards.
+    fn compute(
+        dsp: &mut TestDsp<Self>,
+        count: usize,
+        inputs: &[&[f32]],
+        outputs: &mut [&mut [f32]],
+    );
+}
+
+/// Unlike generated code, `init` only sets the sample rate and keeps the parameters.
+pub struct TestDsp<S: Spec> {
+    /// The values of all parameters, passive ones included.
+    pub params: Vec<f32>,
+    pub sample_rate: i32,
+    /// Frames computed since the last clear.
+    pub frames: usize,
+    pub state: S::State,
+    spec: PhantomData<S>,
+}
+
+impl<S: Spec> TestDsp<S> {
+    #[must_use]
+    pub fn with_params(params: &[f32]) -> Self {
+        let mut dsp = Self::new();
+        dsp.params[..params.len()].copy_from_slice(params);
+        dsp
+    }
+}
+
+impl<S: Spec> FaustDsp for TestDsp<S> {
+    type T = f32;
+
+    fn new() -> Self {
+        Self {
+            params: S::UI.iter().filter_map(Ui::init).collect(),
+            sample_rate: 44100,
+            frames: 0,
+            state: S::State::default(),
+            spec: PhantomData,
+        }
+    }
+    fn metadata(&self, m: &mut dyn Meta) {
+        for (key, value) in S::META {
+            m.declare(key, value);
+        }
+    }
+    fn get_sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+    fn get_num_inputs(&self) -> i32 {
+        S::INPUTS as i32
+    }
+    fn get_num_outputs(&self) -> i32 {
+        S::OUTPUTS as i32
+    }
+    fn class_init(_sample_rate: i32) {}
+    fn instance_reset_params(&mut self) {
+        self.params = S::UI.iter().filter_map(Ui::init).collect();
+    }
+    fn instance_clear(&mut self) {
+        self.frames = 0;
+    }
+    fn instance_constants(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate;
+    }
+    fn instance_init(&mut self, sample_rate: i32) {
+        self.instance_constants(sample_rate);
+        self.instance_reset_params();
+        self.instance_clear();
+    }
+    fn init(&mut self, sample_rate: i32) {
+        self.sample_rate = sample_rate;
+    }
+    fn build_user_interface(&self, ui_interface: &mut dyn UI<Self::T>) {
+        Self::build_user_interface_static(ui_interface);
+    }
+    fn build_user_interface_static(ui_interface: &mut dyn UI<Self::T>) {
+        let mut param = ParamIndex(0);
+        for item in S::UI {
+            match *item {
+                Ui::VBox(label) => ui_interface.open_vertical_box(label),
+                Ui::HBox(label) => ui_interface.open_horizontal_box(label),
+                Ui::Close => ui_interface.close_box(),
+                Ui::Declare(key, value) => ui_interface.declare(Some(param), key, value),
+                Ui::Button(label) => ui_interface.add_button(label, param),
+                Ui::HSlider(label, init, min, max, step) => {
+                    ui_interface.add_horizontal_slider(label, param, init, min, max, step);
+                }
+                Ui::NumEntry(label, init, min, max, step) => {
+                    ui_interface.add_num_entry(label, param, init, min, max, step);
+                }
+                Ui::VBargraph(label, min, max) => {
+                    ui_interface.add_vertical_bargraph(label, param, min, max);
+                }
+            }
+            if item.init().is_some() {
+                param.0 += 1;
+            }
+        }
+    }
+    fn get_param(&self, param: ParamIndex) -> Option<Self::T> {
+        self.params.get(param.0 as usize).copied()
+    }
+    fn set_param(&mut self, param: ParamIndex, value: Self::T) {
+        if let Some(param) = self.params.get_mut(param.0 as usize) {
+            *param = value;
+        }
+    }
+    fn compute(&mut self, count: i32, inputs: &[&[Self::T]], outputs: &mut [&mut [Self::T]]) {
+        let count = count as usize;
+        S::compute(self, count, inputs, outputs);
+        self.frames += count;
+    }
+}
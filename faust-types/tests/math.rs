@@ -0,0 +1,69 @@
+use faust_types::FloatMath;
+
+fn assert_close(actual: f64, expected: f64, name: &str) {
+    let tolerance = 1e-6 * expected.abs().max(1.0);
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{}: {} != {}",
+        name,
+        actual,
+        expected
+    );
+}
+
+/// The libm functions agree with the inherent methods of std.
+#[test]
+fn matches_std() {
+    for &x in &[-2.5_f64, -0.75, -0.5, 0.0, 0.3, 0.5, 0.9, 1.5, 7.25] {
+        let cases: [(&str, f64, f64); 16] = [
+            ("floor", FloatMath::floor(x), x.floor()),
+            ("ceil", FloatMath::ceil(x), x.ceil()),
+            ("round", FloatMath::round(x), x.round()),
+            ("trunc", FloatMath::trunc(x), x.trunc()),
+            ("fract", FloatMath::fract(x), x.fract()),
+            (
+                "rem_euclid",
+                FloatMath::rem_euclid(x, 2.0),
+                x.rem_euclid(2.0),
+            ),
+            ("powi", FloatMath::powi(x, 3), x.powi(3)),
+            ("powf", FloatMath::powf(x.abs(), 1.5), x.abs().powf(1.5)),
+            ("sqrt", FloatMath::sqrt(x.abs()), x.abs().sqrt()),
+            ("exp", FloatMath::exp(x), x.exp()),
+            (
+                "log",
+                FloatMath::log(x.abs() + 1.0, 3.0),
+                (x.abs() + 1.0).log(3.0),
+            ),
+            (
+                "log10",
+                FloatMath::log10(x.abs() + 1.0),
+                (x.abs() + 1.0).log10(),
+            ),
+            ("sin", FloatMath::sin(x), x.sin()),
+            ("tan", FloatMath::tan(x), x.tan()),
+            ("atan2", FloatMath::atan2(x, 0.5), x.atan2(0.5)),
+            ("tanh", FloatMath::tanh(x), x.tanh()),
+        ];
+        for (name, actual, expected) in cases {
+            assert_close(actual, expected, name);
+        }
+
+        let x = x as f32;
+        let cases: [(&str, f32, f32); 6] = [
+            ("floor", FloatMath::floor(x), x.floor()),
+            ("round", FloatMath::round(x), x.round()),
+            (
+                "rem_euclid",
+                FloatMath::rem_euclid(x, 2.0),
+                x.rem_euclid(2.0),
+            ),
+            ("powf", FloatMath::powf(x.abs(), 1.5), x.abs().powf(1.5)),
+            ("cos", FloatMath::cos(x), x.cos()),
+            ("ln_1p", FloatMath::ln_1p(x.abs()), x.abs().ln_1p()),
+        ];
+        for (name, actual, expected) in cases {
+            assert_close(f64::from(actual), f64::from(expected), name);
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Conversion of a [`UiModel`] back into the json and xml description, and between both formats.
+//!
+//! A model read from the json description writes it back unchanged. Information that only one
+//! format has is filled in from the metadata where faust also writes it (`filename`,
+//! `compile_options`) and left empty otherwise.
+
+use crate::model::{Group, GroupKind, JsonDetails, Meta, UiItem, UiModel, Widget, WidgetKind};
+use faust_json::FaustJson;
+use faust_xml::FaustXML;
+use std::convert::TryFrom;
+
+/// Label faust gives to the outermost group in the xml description if it is named after the dsp.
+const NO_LABEL: &str = "0x00";
+
+/// Keys written as elements of their own in the xml description instead of `<meta>`.
+const XML_ELEMENT_KEYS: [&str; 5] = ["name", "author", "license", "copyright", "version"];
+
+fn json_meta(meta: &[Meta]) -> Vec<faust_json::Meta> {
+    meta.iter()
+        .map(|meta| faust_json::Meta {
+            key: meta.key.clone(),
+            value: meta.value.clone(),
+        })
+        .collect()
+}
+
+fn json_item(item: &UiItem) -> faust_json::LayoutItem {
+    use faust_json::LayoutItem;
+
+    match item {
+        UiItem::Group(Group {
+            kind,
+            label,
+            items,
+            meta,
+            extra,
+        }) => {
+            let label = label.clone();
+            let items = items.iter().map(json_item).collect();
+            let meta = json_meta(meta);
+            let extra = extra.clone();
+            match kind {
+                GroupKind::Tab => LayoutItem::TGroup {
+                    label,
+                    items,
+                    meta,
+                    extra,
+                },
+                GroupKind::Vertical => LayoutItem::VGroup {
+                    label,
+                    items,
+                    meta,
+                    extra,
+                },
+                GroupKind::Horizontal => LayoutItem::HGroup {
+                    label,
+                    items,
+                    meta,
+                    extra,
+                },
+            }
+        }
+        UiItem::Widget(widget) => json_widget(widget),
+    }
+}
+
+fn json_widget(widget: &Widget) -> faust_json::LayoutItem {
+    use faust_json::LayoutItem;
+
+    let label = widget.label.clone();
+    let shortname = widget.shortname.clone();
+    let address = widget.address.clone();
+    let varname = widget.varname.clone();
+    let meta = json_meta(&widget.meta);
+    let index = widget.index;
+    let extra = widget.extra.clone();
+    match widget.kind.clone() {
+        WidgetKind::VSlider(range) => LayoutItem::VSlider {
+            label,
+            shortname,
+            address,
+            varname,
+            init: range.init,
+            min: range.min,
+            max: range.max,
+            step: range.step,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::HSlider(range) => LayoutItem::HSlider {
+            label,
+            shortname,
+            address,
+            varname,
+            init: range.init,
+            min: range.min,
+            max: range.max,
+            step: range.step,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::NEntry(range) => LayoutItem::NEntry {
+            label,
+            shortname,
+            address,
+            varname,
+            init: Some(range.init),
+            min: range.min,
+            max: range.max,
+            step: range.step,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::Button => LayoutItem::Button {
+            label,
+            shortname,
+            address,
+            varname,
+            init: None,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::CheckBox => LayoutItem::CheckBox {
+            label,
+            shortname,
+            address,
+            varname,
+            init: None,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::VBarGraph { min, max } => LayoutItem::VBarGraph {
+            label,
+            shortname,
+            address,
+            varname,
+            min,
+            max,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::HBarGraph { min, max } => LayoutItem::HBarGraph {
+            label,
+            shortname,
+            address,
+            varname,
+            min,
+            max,
+            index,
+            meta,
+            extra,
+        },
+        WidgetKind::Soundfile { url } => LayoutItem::Soundfile {
+            label,
+            url,
+            address,
+            varname,
+            meta,
+            extra,
+        },
+    }
+}
+
+/// The fields of [`UiModel::json`] are written back unchanged. Models without them, e.g. from the
+/// xml description, get the file name and compile options from the metadata and empty library
+/// lists. The faust version is taken from [`UiModel::compiler_version`].
+impl From<&UiModel> for FaustJson {
+    fn from(model: &UiModel) -> Self {
+        let details = model.json.clone().unwrap_or_else(|| JsonDetails {
+            filename: model
+                .meta("filename")
+                .map_or_else(|| format!("{}.dsp", model.name), str::to_owned),
+            compile_options: model.meta("compile_options").unwrap_or_default().to_owned(),
+            ..JsonDetails::default()
+        });
+        Self {
+            name: model.name.clone(),
+            filename: details.filename,
+            version: model.compiler_version.clone().unwrap_or_default(),
+            compile_options: details.compile_options,
+            library_list: details.library_list,
+            include_pathnames: details.include_pathnames,
+            size: details.size,
+            inputs: model.inputs,
+            outputs: model.outputs,
+            author: details.author,
+            license: details.license,
+            copyright: details.copyright,
+            classname: details.classname,
+            sr_index: details.sr_index,
+            sha_key: details.sha_key,
+            code: details.code,
+            meta: json_meta(&model.meta),
+            ui: model.items.iter().map(json_item).collect(),
+            memory_layout: details.memory_layout,
+            extra: details.extra,
+        }
+    }
+}
+
+/// Empty values are written as empty elements, like faust does.
+fn xml_meta(meta: &[Meta]) -> Vec<faust_xml::Meta> {
+    meta.iter()
+        .map(|meta| faust_xml::Meta {
+            key: meta.key.clone(),
+            value: Some(meta.value.clone()).filter(|value| !value.is_empty()),
+        })
+        .collect()
+}
+
+/// Widgets and layout of the xml description. Widgets are numbered in layout order, starting at 1.
+#[derive(Default)]
+struct XmlUi {
+    active: Vec<faust_xml::ActiveWidget>,
+    passive: Vec<faust_xml::PassiveWidget>,
+    soundfiles: Vec<faust_xml::SoundfileWidget>,
+    next_id: usize,
+}
+
+impl XmlUi {
+    fn layout_item(
+        &mut self,
+        item: &UiItem,
+        is_root: bool,
+        dsp_name: &str,
+    ) -> faust_xml::LayoutItem {
+        match item {
+            UiItem::Group(group) => {
+                let label = if is_root && group.label == dsp_name {
+                    NO_LABEL.to_owned()
+                } else {
+                    group.label.clone()
+                };
+                let items = group
+                    .items
+                    .iter()
+                    .map(|item| self.layout_item(item, false, dsp_name))
+                    .collect();
+                faust_xml::LayoutItem::Group(faust_xml::Group {
+                    r#type: group.kind.name().to_owned(),
+                    label,
+                    meta: xml_meta(&group.meta),
+                    items,
+                })
+            }
+            UiItem::Widget(widget) => {
+                self.next_id += 1;
+                self.widget(self.next_id, widget);
+                faust_xml::LayoutItem::WRef(faust_xml::WRef { id: self.next_id })
+            }
+        }
+    }
+
+    fn widget(&mut self, id: usize, widget: &Widget) {
+        use faust_xml::{ActiveWidget, PassiveWidget};
+
+        let label = widget.label.clone();
+        let varname = widget.varname.clone();
+        let meta = xml_meta(&widget.meta);
+        match widget.kind.clone() {
+            WidgetKind::VSlider(range) => {
+                self.active.push(ActiveWidget::VSlider(faust_xml::VSlider {
+                    id,
+                    label,
+                    varname,
+                    init: range.init,
+                    min: range.min,
+                    max: range.max,
+                    step: range.step,
+                    meta,
+                }));
+            }
+            WidgetKind::HSlider(range) => {
+                self.active.push(ActiveWidget::HSlider(faust_xml::HSlider {
+                    id,
+                    label,
+                    varname,
+                    init: range.init,
+                    min: range.min,
+                    max: range.max,
+                    step: range.step,
+                    meta,
+                }));
+            }
+            WidgetKind::NEntry(range) => {
+                self.active.push(ActiveWidget::NEntry(faust_xml::NEntry {
+                    id,
+                    label,
+                    varname,
+                    init: Some(range.init),
+                    min: range.min,
+                    max: range.max,
+                    step: range.step,
+                    meta,
+                }));
+            }
+            WidgetKind::Button => self.active.push(ActiveWidget::Button(faust_xml::Button {
+                id,
+                label,
+                varname,
+                init: None,
+                meta,
+            })),
+            WidgetKind::CheckBox => self
+                .active
+                .push(ActiveWidget::CheckBox(faust_xml::CheckBox {
+                    id,
+                    label,
+                    varname,
+                    init: None,
+                    meta,
+                })),
+            WidgetKind::VBarGraph { min, max } => {
+                self.passive
+                    .push(PassiveWidget::VBarGraph(faust_xml::VBarGraph {
+                        id,
+                        label,
+                        varname,
+                        min,
+                        max,
+                        meta,
+                    }));
+            }
+            WidgetKind::HBarGraph { min, max } => {
+                self.passive
+                    .push(PassiveWidget::HBarGraph(faust_xml::HBarGraph {
+                        id,
+                        label,
+                        varname,
+                        min,
+                        max,
+                        meta,
+                    }));
+            }
+            WidgetKind::Soundfile { url } => self.soundfiles.push(faust_xml::SoundfileWidget {
+                r#type: widget.kind.name().to_owned(),
+                id,
+                label,
+                url: Some(url),
+                varname,
+                meta,
+            }),
+        }
+    }
+}
+
+impl From<&UiModel> for FaustXML {
+    fn from(model: &UiModel) -> Self {
+        let mut ui = XmlUi::default();
+        let items = model
+            .items
+            .iter()
+            .map(|item| ui.layout_item(item, true, &model.name))
+            .collect();
+        let element = |key: &str| model.meta(key).map(str::to_owned);
+        Self {
+            name: model.name.clone(),
+            author: element("author"),
+            license: element("license"),
+            copyright: element("copyright"),
+            version: element("version"),
+            classname: None,
+            inputs: model.inputs,
+            outputs: model.outputs,
+            meta: xml_meta(
+                &model
+                    .meta
+                    .iter()
+                    .filter(|meta| !XML_ELEMENT_KEYS.contains(&meta.key.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            ),
+            ui: faust_xml::UI {
+                activewidgets: faust_xml::ActiveWidgets {
+                    count: ui.active.len(),
+                    widget: ui.active,
+                },
+                passivewidgets: faust_xml::PassiveWidgets {
+                    count: ui.passive.len(),
+                    widget: ui.passive,
+                },
+                soundfilewidgets: faust_xml::SoundfileWidgets {
+                    count: ui.soundfiles.len(),
+                    widget: ui.soundfiles,
+                },
+                layout: faust_xml::Layout { items },
+            },
+        }
+    }
+}
+
+/// Converts the json description into the xml description faust would have written.
+#[must_use]
+pub fn json_to_xml(json: &FaustJson) -> FaustXML {
+    let mut xml = FaustXML::from(&UiModel::from(json));
+    xml.classname.clone_from(&json.classname);
+    xml
+}
+
+/// Converts the xml description into the json description, with addresses and shortnames derived
+/// from the layout.
+///
+/// # Errors
+///
+/// If the layout references a widget that is not declared.
+pub fn xml_to_json(xml: &FaustXML) -> Result<FaustJson, String> {
+    let mut json = FaustJson::from(&UiModel::try_from(xml)?);
+    json.classname.clone_from(&xml.classname);
+    Ok(json)
+}
@@ -0,0 +1,127 @@
+//! Rustdoc for generated code, built from the description of the dsp.
+
+use crate::model::{UiModel, Widget};
+use std::fmt::Write;
+
+/// Widget properties shown in the docs of a parameter.
+struct ParamDoc<'a> {
+    widget: &'a Widget,
+}
+
+impl<'a> ParamDoc<'a> {
+    const fn new(widget: &'a Widget) -> Self {
+        Self { widget }
+    }
+
+    fn meta(&self, key: &str) -> Option<&'a str> {
+        self.widget.meta(key)
+    }
+
+    fn range(&self) -> Option<String> {
+        self.widget
+            .bounds()
+            .map(|(min, max)| format!("{min} ..= {max}"))
+    }
+
+    fn init(&self) -> Option<f32> {
+        self.widget.range().map(|range| range.init)
+    }
+
+    fn step(&self) -> Option<f32> {
+        self.widget.range().map(|range| range.step)
+    }
+
+    fn summary(&self) -> String {
+        let widget = self.widget;
+        let mut doc = format!(
+            "`{}` ({}) at `{}`",
+            widget.label,
+            widget.kind.name(),
+            widget.address
+        );
+        let details = [
+            self.range().map(|range| format!("range {range}")),
+            self.init().map(|init| format!("init {init}")),
+            self.step().map(|step| format!("step {step}")),
+            self.meta("unit").map(|unit| format!("unit {unit}")),
+        ];
+        let details = details.iter().flatten().cloned().collect::<Vec<_>>();
+        if !details.is_empty() {
+            write!(doc, ": {}", details.join(", ")).expect("cannot fail");
+        }
+        doc.push('.');
+        if let Some(tooltip) = self.meta("tooltip") {
+            write!(doc, "\n\n{tooltip}").expect("cannot fail");
+        }
+        doc
+    }
+
+    fn table_row(&self) -> String {
+        let cells = [
+            escape_cell(&self.widget.label),
+            format!("`{}`", self.widget.address),
+            self.widget.kind.name().to_owned(),
+            self.meta("unit").map(escape_cell).unwrap_or_default(),
+            self.range().unwrap_or_default(),
+            self.init().map(|init| init.to_string()).unwrap_or_default(),
+            self.step().map(|step| step.to_string()).unwrap_or_default(),
+            self.meta("tooltip").map(escape_cell).unwrap_or_default(),
+        ];
+        format!("| {} |", cells.join(" | "))
+    }
+}
+
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Docs of a single widget: label, address, range, init, step, unit and tooltip.
+#[must_use]
+pub fn param_doc(widget: &Widget) -> String {
+    ParamDoc::new(widget).summary()
+}
+
+/// Markdown table with one row per widget.
+#[must_use]
+pub fn param_table(model: &UiModel) -> String {
+    let mut table = String::from(
+        "| Label | Address | Type | Unit | Range | Init | Step | Tooltip |\n\
+         |---|---|---|---|---|---|---|---|\n",
+    );
+    for widget in model.widgets() {
+        table.push_str(&ParamDoc::new(widget).table_row());
+        table.push('\n');
+    }
+    table
+}
+
+/// Docs of the dsp struct: name, description, global metadata, channels and the parameter table.
+#[must_use]
+pub fn dsp_doc(model: &UiModel) -> String {
+    let meta = |key: &str| model.meta(key);
+    let mut doc = format!("Faust dsp `{}`.\n", model.name);
+    if let Some(description) = meta("description") {
+        write!(doc, "\n{description}\n").expect("cannot fail");
+    }
+    doc.push_str("\n| | |\n|---|---|\n");
+    let properties = [
+        ("Author", meta("author")),
+        ("License", meta("license")),
+        ("Copyright", meta("copyright")),
+        ("Version", meta("version")),
+    ];
+    for (name, value) in properties {
+        if let Some(value) = value {
+            writeln!(doc, "| {name} | {} |", escape_cell(value)).expect("cannot fail");
+        }
+    }
+    writeln!(doc, "| Inputs | {} |", model.inputs).expect("cannot fail");
+    writeln!(doc, "| Outputs | {} |", model.outputs).expect("cannot fail");
+    if let Some(version) = &model.compiler_version {
+        writeln!(doc, "| Faust | {} |", escape_cell(version)).expect("cannot fail");
+    }
+    if !model.items.is_empty() {
+        write!(doc, "\n# Parameters\n\n{}", param_table(model)).expect("cannot fail");
+    }
+    doc
+}
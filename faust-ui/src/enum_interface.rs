@@ -1,4 +1,5 @@
-use faust_json::{FaustJson, LayoutItem};
+use crate::docs;
+use crate::model::{UiModel, Widget, WidgetKind};
 use heck::CamelCase;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
@@ -34,69 +35,37 @@ struct ParamInfo {
     is_active: bool,
     shortname: Ident,
     varname: Ident,
+    doc: Option<String>,
 }
 
 impl ParamInfo {
-    fn active(shortname: &str, varname: &str) -> Vec<Self> {
+    fn active(shortname: &str, varname: &str, doc: Option<String>) -> Vec<Self> {
         vec![Self {
             is_active: true,
             shortname: format_ident!("{shortname}"),
             varname: format_ident!("{varname}"),
+            doc,
         }]
     }
-    fn passive(shortname: &str, varname: &str) -> Vec<Self> {
+    fn passive(shortname: &str, varname: &str, doc: Option<String>) -> Vec<Self> {
         vec![Self {
             is_active: false,
             shortname: format_ident!("{shortname}"),
             varname: format_ident!("{varname}"),
+            doc,
         }]
     }
 }
-trait GetParmInfo {
-    fn get_param_info(&self) -> Vec<ParamInfo>;
-}
-
-impl GetParmInfo for FaustJson {
-    fn get_param_info(&self) -> Vec<ParamInfo> {
-        self.ui
-            .iter()
-            .flat_map(GetParmInfo::get_param_info)
-            .collect()
-    }
-}
-
-impl GetParmInfo for LayoutItem {
-    fn get_param_info(&self) -> Vec<ParamInfo> {
-        match self {
-            Self::HGroup { items, .. }
-            | Self::VGroup { items, .. }
-            | Self::TGroup { items, .. } => {
-                items.iter().flat_map(GetParmInfo::get_param_info).collect()
-            }
-            Self::VSlider {
-                shortname, varname, ..
-            }
-            | Self::HSlider {
-                shortname, varname, ..
-            }
-            | Self::NEntry {
-                shortname, varname, ..
-            }
-            | Self::Button {
-                shortname, varname, ..
-            }
-            | Self::CheckBox {
-                shortname, varname, ..
-            } => ParamInfo::active(&shortname.to_camel_case(), varname),
-            Self::VBarGraph {
-                shortname, varname, ..
+impl ParamInfo {
+    fn from_widget(widget: &Widget) -> Vec<Self> {
+        let doc = Some(docs::param_doc(widget));
+        match widget.kind {
+            // soundfiles are not parameters, see `soundfile_interface`
+            WidgetKind::Soundfile { .. } => Vec::new(),
+            _ if widget.is_active() => {
+                Self::active(&widget.shortname.to_camel_case(), &widget.varname, doc)
             }
-            | Self::HBarGraph {
-                shortname, varname, ..
-            } => ParamInfo::passive(&shortname.to_camel_case(), varname),
-            Self::Soundfile {
-                address, varname, ..
-            } => ParamInfo::active(address, varname),
+            _ => Self::passive(&widget.shortname.to_camel_case(), &widget.varname, doc),
         }
     }
 }
@@ -114,12 +83,32 @@ fn create_qualified_enum(infos: &[&ParamInfo], is_active: bool) -> TokenStream {
     };
     let i: Vec<TokenStream> = infos
         .iter()
-        .map(|param_info| format_ident!("{}", param_info.shortname).to_token_stream())
+        .map(|param_info| {
+            let shortname = format_ident!("{}", param_info.shortname).to_token_stream();
+            let doc = param_info.doc.iter();
+            quote! {
+                #(#[doc = #doc])*
+                #shortname
+            }
+        })
         .collect();
+    let (doc, discriminants_doc) = if is_active {
+        (
+            "Values of the parameters that can be set on the dsp.",
+            "Parameters that can be set on the dsp.",
+        )
+    } else {
+        (
+            "Values of the outputs of the dsp, like bargraphs.",
+            "Outputs of the dsp, like bargraphs.",
+        )
+    };
     quote! {
+        #[doc = #doc]
         #[derive(Debug, Clone, Copy, PartialEq, Display, EnumIter, EnumCount,EnumDiscriminants,VariantNames)]
         #[strum_discriminants(derive(Display,EnumIter, EnumCount,IntoStaticStr,VariantArray,VariantNames,Hash))]
         #[strum_discriminants(name(#discriminants_name))]
+        #[strum_discriminants(doc = #discriminants_doc)]
         pub enum #enum_name {
             #(#i(FaustFloat)),*
         }
@@ -274,7 +263,11 @@ fn create_from_paraminfo(v: &[ParamInfo], dsp_name: &Ident) -> TokenStream {
 }
 
 #[must_use]
-pub fn create(dsp_json: &FaustJson, dsp_name: &Ident) -> TokenStream {
-    let param_info = dsp_json.get_param_info();
+pub fn create(model: &UiModel, dsp_name: &Ident) -> TokenStream {
+    let param_info = model
+        .widgets()
+        .into_iter()
+        .flat_map(ParamInfo::from_widget)
+        .collect::<Vec<_>>();
     create_from_paraminfo(&param_info, dsp_name)
 }
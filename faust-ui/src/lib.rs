@@ -9,26 +9,55 @@
 )]
 #![allow(clippy::missing_panics_doc)]
 
+#[cfg(test)]
+use quick_xml as _;
+#[cfg(test)]
+use serde_json as _;
+
 use faust_json::FaustJson;
+use model::UiModel;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 
+pub mod convert;
+pub mod docs;
 pub mod enum_interface;
+pub mod model;
+pub mod nih_params;
+pub mod soundfile_interface;
 pub mod struct_interface;
 
 #[must_use]
 pub fn generate_ui_code(faust_json: &FaustJson, struct_name: impl AsRef<str>) -> TokenStream {
+    generate_ui_code_from_model(&UiModel::from(faust_json), struct_name)
+}
+
+/// Generates the nih-plug parameters of the dsp, see [`nih_params`].
+#[must_use]
+pub fn generate_nih_params(faust_json: &FaustJson, struct_name: impl AsRef<str>) -> TokenStream {
+    nih_params::create(
+        &UiModel::from(faust_json),
+        &format_ident!("{}", struct_name.as_ref()),
+    )
+}
+
+/// Generates the ui code from a description converted from json or xml.
+#[must_use]
+pub fn generate_ui_code_from_model(model: &UiModel, struct_name: impl AsRef<str>) -> TokenStream {
     let struct_name = format_ident!("{}", struct_name.as_ref());
 
     let ui_static_name = format_ident!("DSP_UI");
     let ui_type = format_ident!("DspUi");
 
-    let ui_enum = enum_interface::create(faust_json, &struct_name);
-    let struct_interface = struct_interface::create(faust_json, &ui_static_name, &ui_type);
+    let ui_enum = enum_interface::create(model, &struct_name);
+    let struct_interface = struct_interface::create(model, &ui_static_name, &ui_type);
+
+    let soundfile_interface = soundfile_interface::create(model, &struct_name);
 
     let ui_code = quote! {
         #ui_enum
         #struct_interface
+        #soundfile_interface
     };
     ui_code
 }
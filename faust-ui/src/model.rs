@@ -0,0 +1,961 @@
+//! Description of a dsp user interface, independent of the file format faust wrote it in.
+//!
+//! Both the json (`-json`) and the xml (`-xml`) description convert into a [`UiModel`],
+//! which the code generation of this crate works on.
+
+use faust_json::{FaustJson, Map, Value};
+use faust_xml::FaustXML;
+use std::{collections::HashMap, convert::TryFrom};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Meta {
+    pub key: String,
+    pub value: String,
+}
+
+impl Meta {
+    #[must_use]
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+fn find_meta<'a>(meta: &'a [Meta], key: &str) -> Option<&'a str> {
+    meta.iter()
+        .find(|meta| meta.key == key)
+        .map(|meta| meta.value.as_str())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiModel {
+    pub name: String,
+    pub inputs: usize,
+    pub outputs: usize,
+    /// Version of faust that generated the description. Only the json description contains it.
+    pub compiler_version: Option<String>,
+    /// Global metadata, including `author`, `license`, `copyright` and `version` if declared.
+    pub meta: Vec<Meta>,
+    pub items: Vec<UiItem>,
+    /// Fields of the json description without a counterpart in the model, kept to write it back.
+    /// `None` for models from the xml description or [`UiModelBuilder`].
+    pub json: Option<JsonDetails>,
+}
+
+/// The fields of [`FaustJson`] that describe the compilation rather than the user interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonDetails {
+    pub filename: String,
+    pub compile_options: String,
+    pub library_list: Vec<String>,
+    pub include_pathnames: Vec<String>,
+    pub size: u32,
+    pub sr_index: Option<u32>,
+    pub sha_key: Option<String>,
+    pub code: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub copyright: Option<String>,
+    pub classname: Option<String>,
+    pub memory_layout: Vec<Map<String, Value>>,
+    pub extra: Map<String, Value>,
+}
+
+impl From<&FaustJson> for JsonDetails {
+    fn from(json: &FaustJson) -> Self {
+        Self {
+            filename: json.filename.clone(),
+            compile_options: json.compile_options.clone(),
+            library_list: json.library_list.clone(),
+            include_pathnames: json.include_pathnames.clone(),
+            size: json.size,
+            sr_index: json.sr_index,
+            sha_key: json.sha_key.clone(),
+            code: json.code.clone(),
+            author: json.author.clone(),
+            license: json.license.clone(),
+            copyright: json.copyright.clone(),
+            classname: json.classname.clone(),
+            memory_layout: json.memory_layout.clone(),
+            extra: json.extra.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiItem {
+    Group(Group),
+    Widget(Widget),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKind {
+    Tab,
+    Vertical,
+    Horizontal,
+}
+
+impl GroupKind {
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Tab => "tgroup",
+            Self::Vertical => "vgroup",
+            Self::Horizontal => "hgroup",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tgroup" => Some(Self::Tab),
+            "vgroup" => Some(Self::Vertical),
+            "hgroup" => Some(Self::Horizontal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub kind: GroupKind,
+    pub label: String,
+    pub items: Vec<UiItem>,
+    pub meta: Vec<Meta>,
+    /// Fields of the json description unknown to `faust-json`.
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Widget {
+    pub kind: WidgetKind,
+    pub label: String,
+    /// Shortest unique suffix of the address, joined with `_`.
+    pub shortname: String,
+    pub address: String,
+    /// Field of the dsp struct holding the value.
+    pub varname: String,
+    pub meta: Vec<Meta>,
+    /// Offset of the zone in the dsp struct, only written to the json description by some backends.
+    pub index: Option<u32>,
+    /// Fields of the json description unknown to `faust-json`.
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub init: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetKind {
+    VSlider(Range),
+    HSlider(Range),
+    NEntry(Range),
+    Button,
+    CheckBox,
+    VBarGraph { min: f32, max: f32 },
+    HBarGraph { min: f32, max: f32 },
+    Soundfile { url: String },
+}
+
+impl WidgetKind {
+    /// Widget type as named by faust, e.g. `vslider`.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::VSlider(_) => "vslider",
+            Self::HSlider(_) => "hslider",
+            Self::NEntry(_) => "nentry",
+            Self::Button => "button",
+            Self::CheckBox => "checkbox",
+            Self::VBarGraph { .. } => "vbargraph",
+            Self::HBarGraph { .. } => "hbargraph",
+            Self::Soundfile { .. } => "soundfile",
+        }
+    }
+}
+
+impl Widget {
+    /// Widget without metadata. Address and shortname are set by [`UiModel::update_addresses`].
+    #[must_use]
+    pub fn new(kind: WidgetKind, label: impl Into<String>, varname: impl Into<String>) -> Self {
+        Self {
+            kind,
+            label: label.into(),
+            shortname: String::new(),
+            address: String::new(),
+            varname: varname.into(),
+            meta: Vec::new(),
+            index: None,
+            extra: Map::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.push(Meta::new(key, value));
+        self
+    }
+
+    fn with_meta_list(mut self, meta: Vec<Meta>) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Widgets set from outside of the dsp. Bargraphs are passive, they are written by the dsp.
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        !matches!(
+            self.kind,
+            WidgetKind::VBarGraph { .. } | WidgetKind::HBarGraph { .. }
+        )
+    }
+
+    #[must_use]
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        find_meta(&self.meta, key)
+    }
+
+    /// Labels and values of a `[style:menu{'a':0;'b':1}]` or `[style:radio{...}]` widget.
+    #[must_use]
+    pub fn style_items(&self) -> Option<Vec<(String, f32)>> {
+        let style = self.meta("style")?.trim();
+        let items = style
+            .strip_prefix("menu")
+            .or_else(|| style.strip_prefix("radio"))?
+            .trim()
+            .strip_prefix('{')?
+            .strip_suffix('}')?;
+        items
+            .split(';')
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| {
+                let (label, value) = item.rsplit_once(':')?;
+                let label = label.trim().trim_matches('\'');
+                Some((label.to_owned(), value.trim().parse().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|items| !items.is_empty())
+    }
+
+    #[must_use]
+    pub const fn range(&self) -> Option<Range> {
+        match self.kind {
+            WidgetKind::VSlider(range) | WidgetKind::HSlider(range) | WidgetKind::NEntry(range) => {
+                Some(range)
+            }
+            _ => None,
+        }
+    }
+
+    /// `(min, max)` of sliders, numerical entries and bargraphs.
+    #[must_use]
+    pub const fn bounds(&self) -> Option<(f32, f32)> {
+        match self.kind {
+            WidgetKind::VSlider(range) | WidgetKind::HSlider(range) | WidgetKind::NEntry(range) => {
+                Some((range.min, range.max))
+            }
+            WidgetKind::VBarGraph { min, max } | WidgetKind::HBarGraph { min, max } => {
+                Some((min, max))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl UiItem {
+    fn update_addresses(&mut self, path: &mut Vec<String>) {
+        match self {
+            Self::Group(group) => {
+                // groups without a label are not part of addresses
+                let has_label = group.label != NO_LABEL;
+                if has_label {
+                    path.push(address_part(&group.label));
+                }
+                for item in &mut group.items {
+                    item.update_addresses(path);
+                }
+                if has_label {
+                    path.pop();
+                }
+            }
+            Self::Widget(widget) => {
+                let mut address = String::new();
+                for part in path.iter() {
+                    address.push('/');
+                    address.push_str(part);
+                }
+                address.push('/');
+                address.push_str(&address_part(&widget.label));
+                widget.address = address;
+            }
+        }
+    }
+
+    fn collect_widgets<'a>(&'a self, widgets: &mut Vec<&'a Widget>) {
+        match self {
+            Self::Group(group) => {
+                for item in &group.items {
+                    item.collect_widgets(widgets);
+                }
+            }
+            Self::Widget(widget) => widgets.push(widget),
+        }
+    }
+
+    fn collect_widgets_mut<'a>(&'a mut self, widgets: &mut Vec<&'a mut Widget>) {
+        match self {
+            Self::Group(group) => {
+                for item in &mut group.items {
+                    item.collect_widgets_mut(widgets);
+                }
+            }
+            Self::Widget(widget) => widgets.push(widget),
+        }
+    }
+}
+
+impl UiModel {
+    #[must_use]
+    pub fn meta(&self, key: &str) -> Option<&str> {
+        find_meta(&self.meta, key)
+    }
+
+    /// All widgets in layout order.
+    #[must_use]
+    pub fn widgets(&self) -> Vec<&Widget> {
+        let mut widgets = Vec::new();
+        for item in &self.items {
+            item.collect_widgets(&mut widgets);
+        }
+        widgets
+    }
+
+    fn widgets_mut(&mut self) -> Vec<&mut Widget> {
+        let mut widgets = Vec::new();
+        for item in &mut self.items {
+            item.collect_widgets_mut(&mut widgets);
+        }
+        widgets
+    }
+
+    fn push_meta_if_missing(&mut self, key: &str, value: Option<&String>) {
+        if let Some(value) = value {
+            if self.meta(key).is_none() {
+                self.meta.push(Meta::new(key, value.as_str()));
+            }
+        }
+    }
+
+    /// Recomputes addresses and shortnames of all widgets from the labels of the layout, like faust
+    /// does. Needed after labels or groups were changed.
+    pub fn update_addresses(&mut self) {
+        let mut path = Vec::new();
+        for item in &mut self.items {
+            item.update_addresses(&mut path);
+        }
+        self.compute_shortnames();
+    }
+
+    /// Sets the shortnames of all widgets to the shortest unique suffix of their address.
+    fn compute_shortnames(&mut self) {
+        let mut widgets = self.widgets_mut();
+        let paths = widgets
+            .iter()
+            .map(|widget| {
+                widget
+                    .address
+                    .split('/')
+                    .filter(|part| !part.is_empty())
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut lengths = vec![1; paths.len()];
+        let shortnames = loop {
+            let shortnames = paths
+                .iter()
+                .zip(&lengths)
+                .map(|(path, length)| path[path.len().saturating_sub(*length)..].join("_"))
+                .collect::<Vec<_>>();
+            let mut changed = false;
+            for (i, shortname) in shortnames.iter().enumerate() {
+                let is_duplicate = shortnames.iter().filter(|name| *name == shortname).count() > 1;
+                if is_duplicate && lengths[i] < paths[i].len() {
+                    lengths[i] += 1;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break shortnames;
+            }
+        };
+        for (widget, shortname) in widgets.iter_mut().zip(shortnames) {
+            widget.shortname = shortname;
+        }
+    }
+}
+
+fn json_meta(meta: &[faust_json::Meta]) -> Vec<Meta> {
+    meta.iter()
+        .map(|meta| Meta::new(meta.key.as_str(), meta.value.as_str()))
+        .collect()
+}
+
+#[allow(clippy::too_many_lines)]
+fn json_item(item: &faust_json::LayoutItem) -> UiItem {
+    use faust_json::LayoutItem;
+
+    let group = |kind, label: &String, items: &[LayoutItem], meta: &[faust_json::Meta]| {
+        UiItem::Group(Group {
+            kind,
+            label: label.clone(),
+            items: items.iter().map(json_item).collect(),
+            meta: json_meta(meta),
+            extra: item.extra().clone(),
+        })
+    };
+    let widget = |kind,
+                  label: &String,
+                  shortname: &String,
+                  address: &String,
+                  varname: &String,
+                  meta: &[faust_json::Meta]| {
+        UiItem::Widget(Widget {
+            kind,
+            label: label.clone(),
+            shortname: shortname.clone(),
+            address: address.clone(),
+            varname: varname.clone(),
+            meta: json_meta(meta),
+            index: item.index(),
+            extra: item.extra().clone(),
+        })
+    };
+    match item {
+        LayoutItem::TGroup {
+            label, items, meta, ..
+        } => group(GroupKind::Tab, label, items, meta),
+        LayoutItem::VGroup {
+            label, items, meta, ..
+        } => group(GroupKind::Vertical, label, items, meta),
+        LayoutItem::HGroup {
+            label, items, meta, ..
+        } => group(GroupKind::Horizontal, label, items, meta),
+        LayoutItem::VSlider {
+            label,
+            shortname,
+            address,
+            varname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::VSlider(Range {
+                init: *init,
+                min: *min,
+                max: *max,
+                step: *step,
+            }),
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        LayoutItem::HSlider {
+            label,
+            shortname,
+            address,
+            varname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::HSlider(Range {
+                init: *init,
+                min: *min,
+                max: *max,
+                step: *step,
+            }),
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        LayoutItem::NEntry {
+            label,
+            shortname,
+            address,
+            varname,
+            init,
+            min,
+            max,
+            step,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::NEntry(Range {
+                init: init.unwrap_or(*min),
+                min: *min,
+                max: *max,
+                step: *step,
+            }),
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        LayoutItem::Button {
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+            ..
+        } => widget(WidgetKind::Button, label, shortname, address, varname, meta),
+        LayoutItem::CheckBox {
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::CheckBox,
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        LayoutItem::VBarGraph {
+            label,
+            shortname,
+            address,
+            varname,
+            min,
+            max,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::VBarGraph {
+                min: *min,
+                max: *max,
+            },
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        LayoutItem::HBarGraph {
+            label,
+            shortname,
+            address,
+            varname,
+            min,
+            max,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::HBarGraph {
+                min: *min,
+                max: *max,
+            },
+            label,
+            shortname,
+            address,
+            varname,
+            meta,
+        ),
+        // the address of a soundfile is used as its name
+        LayoutItem::Soundfile {
+            label,
+            url,
+            address,
+            varname,
+            meta,
+            ..
+        } => widget(
+            WidgetKind::Soundfile { url: url.clone() },
+            label,
+            address,
+            address,
+            varname,
+            meta,
+        ),
+    }
+}
+
+impl From<&FaustJson> for UiModel {
+    fn from(json: &FaustJson) -> Self {
+        let mut model = Self {
+            name: json.name.clone(),
+            inputs: json.inputs,
+            outputs: json.outputs,
+            compiler_version: Some(json.version.clone()),
+            meta: json_meta(&json.meta),
+            items: json.ui.iter().map(json_item).collect(),
+            json: Some(JsonDetails::from(json)),
+        };
+        model.push_meta_if_missing("author", json.author.as_ref());
+        model.push_meta_if_missing("license", json.license.as_ref());
+        model.push_meta_if_missing("copyright", json.copyright.as_ref());
+        // old faust versions do not write shortnames
+        if model
+            .widgets()
+            .iter()
+            .any(|widget| widget.shortname.is_empty())
+        {
+            model.compute_shortnames();
+        }
+        model
+    }
+}
+
+/// Label faust gives to groups without a label.
+const NO_LABEL: &str = "0x00";
+
+/// Characters faust replaces with `_` in addresses.
+fn address_part(label: &str) -> String {
+    label.replace(
+        &[' ', '#', '*', ',', '?', '[', ']', '{', '}', '(', ')'][..],
+        "_",
+    )
+}
+
+fn xml_meta(meta: &[faust_xml::Meta]) -> Vec<Meta> {
+    meta.iter()
+        .map(|meta| Meta::new(meta.key.as_str(), meta.value.clone().unwrap_or_default()))
+        .collect()
+}
+
+struct XmlWidget<'a> {
+    kind: WidgetKind,
+    label: &'a str,
+    varname: &'a str,
+    meta: &'a [faust_xml::Meta],
+}
+
+#[allow(clippy::too_many_lines)]
+fn xml_widgets(xml: &FaustXML) -> HashMap<usize, XmlWidget<'_>> {
+    use faust_xml::{ActiveWidget, PassiveWidget};
+
+    let mut widgets = HashMap::new();
+    for widget in &xml.ui.activewidgets.widget {
+        let (id, widget) = match widget {
+            ActiveWidget::VSlider(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::VSlider(Range {
+                        init: w.init,
+                        min: w.min,
+                        max: w.max,
+                        step: w.step,
+                    }),
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+            ActiveWidget::HSlider(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::HSlider(Range {
+                        init: w.init,
+                        min: w.min,
+                        max: w.max,
+                        step: w.step,
+                    }),
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+            ActiveWidget::NEntry(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::NEntry(Range {
+                        init: w.init.unwrap_or(w.min),
+                        min: w.min,
+                        max: w.max,
+                        step: w.step,
+                    }),
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+            ActiveWidget::Button(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::Button,
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+            ActiveWidget::CheckBox(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::CheckBox,
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+        };
+        widgets.insert(id, widget);
+    }
+    for widget in &xml.ui.passivewidgets.widget {
+        let (id, widget) = match widget {
+            PassiveWidget::VBarGraph(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::VBarGraph {
+                        min: w.min,
+                        max: w.max,
+                    },
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+            PassiveWidget::HBarGraph(w) => (
+                w.id,
+                XmlWidget {
+                    kind: WidgetKind::HBarGraph {
+                        min: w.min,
+                        max: w.max,
+                    },
+                    label: &w.label,
+                    varname: &w.varname,
+                    meta: &w.meta,
+                },
+            ),
+        };
+        widgets.insert(id, widget);
+    }
+    for w in &xml.ui.soundfilewidgets.widget {
+        let widget = XmlWidget {
+            kind: WidgetKind::Soundfile {
+                url: w.url.clone().unwrap_or_default(),
+            },
+            label: &w.label,
+            varname: &w.varname,
+            meta: &w.meta,
+        };
+        widgets.insert(w.id, widget);
+    }
+    widgets
+}
+
+fn xml_item(
+    item: &faust_xml::LayoutItem,
+    widgets: &HashMap<usize, XmlWidget>,
+    is_root: bool,
+    dsp_name: &str,
+) -> Result<UiItem, String> {
+    match item {
+        faust_xml::LayoutItem::Group(group) => {
+            let kind = GroupKind::from_name(&group.r#type)
+                .ok_or_else(|| format!("unknown group type {}", group.r#type))?;
+            // the outermost group is named after the dsp
+            let label = if group.label == NO_LABEL && is_root {
+                dsp_name.to_owned()
+            } else {
+                group.label.clone()
+            };
+            let items = group
+                .items
+                .iter()
+                .map(|item| xml_item(item, widgets, false, dsp_name))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(UiItem::Group(Group {
+                kind,
+                label,
+                items,
+                meta: xml_meta(&group.meta),
+                extra: Map::new(),
+            }))
+        }
+        faust_xml::LayoutItem::WRef(wref) => {
+            let widget = widgets
+                .get(&wref.id)
+                .ok_or_else(|| format!("layout references unknown widget {}", wref.id))?;
+            Ok(UiItem::Widget(
+                Widget::new(widget.kind.clone(), widget.label, widget.varname)
+                    .with_meta_list(xml_meta(widget.meta)),
+            ))
+        }
+    }
+}
+
+/// Addresses and shortnames are not part of the xml description, they are derived from the layout
+/// like faust does for the json description.
+impl TryFrom<&FaustXML> for UiModel {
+    type Error = String;
+
+    fn try_from(xml: &FaustXML) -> Result<Self, Self::Error> {
+        let widgets = xml_widgets(xml);
+        let items = xml
+            .ui
+            .layout
+            .items
+            .iter()
+            .map(|item| xml_item(item, &widgets, true, &xml.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut model = Self {
+            name: xml.name.clone(),
+            inputs: xml.inputs,
+            outputs: xml.outputs,
+            compiler_version: None,
+            meta: xml_meta(&xml.meta),
+            items,
+            json: None,
+        };
+        model.push_meta_if_missing("author", xml.author.as_ref());
+        model.push_meta_if_missing("license", xml.license.as_ref());
+        model.push_meta_if_missing("copyright", xml.copyright.as_ref());
+        model.push_meta_if_missing("version", xml.version.as_ref());
+        model.update_addresses();
+        Ok(model)
+    }
+}
+
+/// Builds a [`UiModel`] in the order the `UI` callbacks of a dsp describe it.
+///
+/// ```
+/// use faust_ui::model::{GroupKind, Range, UiModelBuilder, Widget, WidgetKind};
+///
+/// let model = UiModelBuilder::new("volume")
+///     .inputs(1)
+///     .outputs(1)
+///     .meta("author", "me")
+///     .open_group(GroupKind::Vertical, "volume")
+///     .widget(
+///         Widget::new(
+///             WidgetKind::VSlider(Range { init: 0.0, min: -70.0, max: 4.0, step: 0.1 }),
+///             "gain",
+///             "fVslider0",
+///         )
+///         .with_meta("unit", "dB"),
+///     )
+///     .close_group()
+///     .build();
+/// assert_eq!(model.widgets()[0].address, "/volume/gain");
+/// ```
+#[derive(Debug, Clone)]
+pub struct UiModelBuilder {
+    model: UiModel,
+    open_groups: Vec<Group>,
+}
+
+impl UiModelBuilder {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            model: UiModel {
+                name: name.into(),
+                inputs: 0,
+                outputs: 0,
+                compiler_version: None,
+                meta: Vec::new(),
+                items: Vec::new(),
+                json: None,
+            },
+            open_groups: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn inputs(mut self, inputs: usize) -> Self {
+        self.model.inputs = inputs;
+        self
+    }
+
+    #[must_use]
+    pub const fn outputs(mut self, outputs: usize) -> Self {
+        self.model.outputs = outputs;
+        self
+    }
+
+    #[must_use]
+    pub fn compiler_version(mut self, version: impl Into<String>) -> Self {
+        self.model.compiler_version = Some(version.into());
+        self
+    }
+
+    /// Adds global metadata, or metadata of the innermost open group.
+    #[must_use]
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let meta = Meta::new(key, value);
+        match self.open_groups.last_mut() {
+            Some(group) => group.meta.push(meta),
+            None => self.model.meta.push(meta),
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn open_group(mut self, kind: GroupKind, label: impl Into<String>) -> Self {
+        self.open_groups.push(Group {
+            kind,
+            label: label.into(),
+            items: Vec::new(),
+            meta: Vec::new(),
+            extra: Map::new(),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn close_group(mut self) -> Self {
+        let group = self
+            .open_groups
+            .pop()
+            .expect("close_group called without an open group");
+        self.push(UiItem::Group(group));
+        self
+    }
+
+    #[must_use]
+    pub fn widget(mut self, widget: Widget) -> Self {
+        self.push(UiItem::Widget(widget));
+        self
+    }
+
+    fn push(&mut self, item: UiItem) {
+        match self.open_groups.last_mut() {
+            Some(group) => group.items.push(item),
+            None => self.model.items.push(item),
+        }
+    }
+
+    /// Returns the model with addresses and shortnames computed from the labels.
+    #[must_use]
+    pub fn build(self) -> UiModel {
+        assert!(
+            self.open_groups.is_empty(),
+            "{} groups are still open",
+            self.open_groups.len()
+        );
+        let mut model = self.model;
+        model.update_addresses();
+        model
+    }
+}
@@ -0,0 +1,341 @@
+//! [nih-plug](https://github.com/robbert-vdh/nih-plug) parameters generated from the user interface.
+//!
+//! [`create`] generates a module `nih_params` with a `#[derive(Params)]` struct `DspParams` that has
+//! one field per active widget, in layout order:
+//!
+//! - sliders and numerical entries become a `FloatParam` with the range, step and unit of the
+//!   widget. `[scale:log]` and `[scale:exp]` skew the range, sliders are smoothed.
+//! - widgets with a `[style:menu{...}]` or `[style:radio{...}]` become an `EnumParam` of a
+//!   generated enum with one variant per item.
+//! - buttons and checkboxes become a `BoolParam`.
+//!
+//! Bargraphs are outputs of the dsp and have no nih-plug counterpart. `DspParams::apply` sets the
+//! values on the dsp through the `UIActive` enum of [`crate::enum_interface`], so the module has to
+//! be placed next to the ui code. The crate using it depends on `nih_plug`.
+
+use crate::{
+    enum_interface,
+    model::{UiModel, Widget, WidgetKind},
+};
+use heck::{CamelCase, SnakeCase};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::Ident;
+
+/// Smoothing time of sliders in milliseconds.
+pub const SMOOTHING_MS: f32 = 10.0;
+
+/// Name of the generated params struct.
+#[must_use]
+pub fn params_struct_ident() -> Ident {
+    format_ident!("DspParams")
+}
+
+enum ParamKind {
+    Float {
+        smoothed: bool,
+    },
+    Bool,
+    Enum {
+        choice: Ident,
+        items: Vec<(String, f32)>,
+    },
+}
+
+struct ParamInfo<'a> {
+    widget: &'a Widget,
+    field: Ident,
+    variant: Ident,
+    kind: ParamKind,
+}
+
+impl<'a> ParamInfo<'a> {
+    fn from_widget(widget: &'a Widget, fields: &mut HashSet<String>) -> Option<Self> {
+        let kind = match &widget.kind {
+            WidgetKind::Button | WidgetKind::CheckBox => ParamKind::Bool,
+            WidgetKind::VSlider(_) | WidgetKind::HSlider(_) | WidgetKind::NEntry(_) => {
+                widget.style_items().map_or(
+                    ParamKind::Float {
+                        smoothed: !matches!(widget.kind, WidgetKind::NEntry(_)),
+                    },
+                    |items| ParamKind::Enum {
+                        choice: format_ident!("{}Choice", widget.shortname.to_camel_case()),
+                        items,
+                    },
+                )
+            }
+            _ => return None,
+        };
+        Some(Self {
+            widget,
+            field: ident(&unique(&widget.shortname.to_snake_case(), fields)),
+            variant: format_ident!("{}", widget.shortname.to_camel_case()),
+            kind,
+        })
+    }
+
+    fn field(&self) -> TokenStream {
+        let field = &self.field;
+        let id = &self.widget.shortname;
+        let doc = format!("`{}`", self.widget.address);
+        let ty = match &self.kind {
+            ParamKind::Float { .. } => quote!(FloatParam),
+            ParamKind::Bool => quote!(BoolParam),
+            ParamKind::Enum { choice, .. } => quote!(EnumParam<#choice>),
+        };
+        quote! {
+            #[doc = #doc]
+            #[id = #id]
+            pub #field: #ty
+        }
+    }
+
+    fn init(&self) -> TokenStream {
+        let field = &self.field;
+        let label = &self.widget.label;
+        let range = self.widget.range();
+        let init = match &self.kind {
+            ParamKind::Bool => quote!(BoolParam::new(#label, false)),
+            ParamKind::Enum { choice, items } => {
+                let init = range.map_or(0.0, |range| range.init);
+                let default = items
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, (_, a)), (_, (_, b))| {
+                        (a - init).abs().total_cmp(&(b - init).abs())
+                    })
+                    .map_or(0, |(i, _)| i);
+                let variant = &choice_variants(items)[default];
+                quote!(EnumParam::new(#label, #choice::#variant))
+            }
+            ParamKind::Float { smoothed } => {
+                let range = range.expect("numerical widgets have a range");
+                let (init, min, max) = (float(range.init), float(range.min), float(range.max));
+                let skew = match self.widget.meta("scale") {
+                    Some("log") => Some(-2.0),
+                    Some("exp") => Some(2.0),
+                    _ => None,
+                };
+                let float_range = match skew {
+                    Some(skew) if range.min < range.max => {
+                        let skew = float(skew);
+                        quote! {
+                            FloatRange::Skewed { min: #min, max: #max, factor: FloatRange::skew_factor(#skew) }
+                        }
+                    }
+                    _ => quote!(FloatRange::Linear { min: #min, max: #max }),
+                };
+                let mut param = quote!(FloatParam::new(#label, #init, #float_range));
+                if *smoothed {
+                    let style = if skew == Some(-2.0) && range.min > 0.0 {
+                        quote!(Logarithmic)
+                    } else {
+                        quote!(Linear)
+                    };
+                    let ms = float(SMOOTHING_MS);
+                    param.extend(quote!(.with_smoother(SmoothingStyle::#style(#ms))));
+                }
+                if range.step > 0.0 {
+                    let step = range.step;
+                    let digits = digits(step);
+                    param.extend(quote! {
+                        .with_step_size(#step)
+                        .with_value_to_string(formatters::v2s_f32_rounded(#digits))
+                    });
+                }
+                if let Some(unit) = self.widget.meta("unit").filter(|unit| !unit.is_empty()) {
+                    let unit = format!(" {unit}");
+                    param.extend(quote!(.with_unit(#unit)));
+                }
+                param
+            }
+        };
+        quote!(#field: #init)
+    }
+
+    fn apply(&self) -> TokenStream {
+        let field = &self.field;
+        let variant = &self.variant;
+        let active = enum_interface::enum_active_discriminants_ident();
+        let value = match &self.kind {
+            ParamKind::Float { smoothed: true } => {
+                quote!(self.#field.smoothed.next_step(frames) as FaustFloat)
+            }
+            ParamKind::Float { smoothed: false } => quote!(self.#field.value() as FaustFloat),
+            ParamKind::Bool => quote!(if self.#field.value() { 1.0 } else { 0.0 }),
+            ParamKind::Enum { .. } => quote!(self.#field.value().value()),
+        };
+        quote!(#active::#variant.set(dsp, #value);)
+    }
+
+    fn choice_enum(&self) -> TokenStream {
+        let ParamKind::Enum { choice, items } = &self.kind else {
+            return TokenStream::new();
+        };
+        let doc = format!("Items of `{}`.", self.widget.address);
+        let variants = choice_variants(items);
+        let names = items.iter().map(|(label, _)| label);
+        let values = items.iter().map(|(_, value)| float_unsuffixed(*value));
+        let value_arms = variants
+            .iter()
+            .zip(values)
+            .map(|(variant, value)| quote!(Self::#variant => #value));
+        quote! {
+            #[doc = #doc]
+            #[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum #choice {
+                #(#[name = #names] #variants),*
+            }
+
+            impl #choice {
+                /// Value of the item in the dsp.
+                #[must_use]
+                pub fn value(self) -> FaustFloat {
+                    match self {
+                        #(#value_arms),*
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Number of decimals to show for values with the given step.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn digits(step: f32) -> usize {
+    if step < 1.0 {
+        (-f64::from(step).log10() - 1e-6).ceil() as usize
+    } else {
+        0
+    }
+}
+
+/// `f32` literal, negative numbers as negation since proc macros do not accept negative literals.
+fn float(value: f32) -> TokenStream {
+    let literal = Literal::f32_suffixed(value.abs());
+    if value.is_sign_negative() {
+        quote!(-#literal)
+    } else {
+        quote!(#literal)
+    }
+}
+
+/// Like [`float`] for literals of type `FaustFloat`.
+fn float_unsuffixed(value: f32) -> TokenStream {
+    let literal = Literal::f32_unsuffixed(value.abs());
+    if value.is_sign_negative() {
+        quote!(-#literal)
+    } else {
+        quote!(#literal)
+    }
+}
+
+/// Returns `name`, or `name` with a number appended if it is taken.
+fn unique(name: &str, taken: &mut HashSet<String>) -> String {
+    let name = if name.is_empty() { "param" } else { name };
+    let mut unique = name.to_owned();
+    let mut i = 1;
+    while !taken.insert(unique.clone()) {
+        unique = format!("{name}_{i}");
+        i += 1;
+    }
+    unique
+}
+
+/// Identifier for generated names, raw for keywords and prefixed when starting with a digit.
+fn ident(name: &str) -> Ident {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format_ident!("_{name}")
+    } else if syn::parse_str::<Ident>(&name).is_ok() {
+        format_ident!("{name}")
+    } else {
+        format_ident!("r#{name}")
+    }
+}
+
+/// Variants of the choice enum, the camel case labels of the items.
+fn choice_variants(items: &[(String, f32)]) -> Vec<Ident> {
+    let mut taken = HashSet::new();
+    items
+        .iter()
+        .map(|(label, _)| {
+            let name = label
+                .to_camel_case()
+                .chars()
+                .filter(char::is_ascii_alphanumeric)
+                .collect::<String>();
+            let name = if name.is_empty() || name == "Self" {
+                format!("Item{name}")
+            } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("V{name}")
+            } else {
+                name
+            };
+            format_ident!("{}", unique(&name, &mut taken))
+        })
+        .collect()
+}
+
+/// Generates the `nih_params` module for the dsp `dsp_name`, see the [module docs](self).
+#[must_use]
+pub fn create(model: &UiModel, dsp_name: &Ident) -> TokenStream {
+    let mut fields = HashSet::new();
+    let widgets = model.widgets();
+    let infos = widgets
+        .iter()
+        .filter_map(|widget| ParamInfo::from_widget(widget, &mut fields))
+        .collect::<Vec<_>>();
+    let struct_name = params_struct_ident();
+    let field_defs = infos.iter().map(ParamInfo::field);
+    let inits = infos.iter().map(ParamInfo::init);
+    let applies = infos.iter().map(ParamInfo::apply);
+    let choices = infos.iter().map(ParamInfo::choice_enum);
+    let active = enum_interface::enum_active_discriminants_ident();
+    let ui_imports = if infos.is_empty() {
+        TokenStream::new()
+    } else {
+        quote! {
+            use super::{FaustFloat, #active};
+            use faust_types::UISet;
+        }
+    };
+    let doc = format!("nih-plug parameters of [`{dsp_name}`], generated from its user interface.");
+    quote! {
+        /// nih-plug parameters of the dsp, see `DspParams`.
+        pub mod nih_params {
+            use super::#dsp_name;
+            #ui_imports
+            use nih_plug::prelude::*;
+
+            #[doc = #doc]
+            #[derive(Params)]
+            pub struct #struct_name {
+                #(#field_defs),*
+            }
+
+            impl Default for #struct_name {
+                fn default() -> Self {
+                    Self {
+                        #(#inits),*
+                    }
+                }
+            }
+
+            impl #struct_name {
+                /// Sets the parameters of `dsp` to their current values, call it before every
+                /// `compute` of `frames` frames. Smoothed parameters advance by `frames` steps.
+                #[allow(unused_variables)]
+                pub fn apply(&self, dsp: &mut #dsp_name, frames: u32) {
+                    #(#applies)*
+                }
+            }
+
+            #(#choices)*
+        }
+    }
+}
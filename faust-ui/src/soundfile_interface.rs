@@ -0,0 +1,55 @@
+use crate::model::{UiModel, WidgetKind};
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::Ident;
+
+struct SoundfileInfo<'a> {
+    label: &'a str,
+    url: &'a str,
+    varname: Ident,
+}
+
+/// Implements `SoundfileDsp` for dsps that declare soundfiles.
+///
+/// Soundfiles are numbered in layout order, which is the order `build_soundfile_interface` reports them in.
+#[must_use]
+pub fn create(model: &UiModel, dsp_name: &Ident) -> TokenStream {
+    let soundfiles = model
+        .widgets()
+        .into_iter()
+        .filter_map(|widget| match &widget.kind {
+            WidgetKind::Soundfile { url } => Some(SoundfileInfo {
+                label: &widget.label,
+                url,
+                varname: format_ident!("{}", widget.varname),
+            }),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if soundfiles.is_empty() {
+        return TokenStream::new();
+    }
+    let add_soundfiles = soundfiles.iter().enumerate().map(|(i, soundfile)| {
+        let (label, url) = (soundfile.label, soundfile.url);
+        let index = Literal::usize_unsuffixed(i);
+        quote! { ui_interface.add_soundfile(#label, #url, SoundfileIndex(#index)); }
+    });
+    let set_soundfiles = soundfiles.iter().enumerate().map(|(i, soundfile)| {
+        let varname = &soundfile.varname;
+        let index = Literal::usize_unsuffixed(i);
+        quote! { #index => self.#varname = data }
+    });
+    quote! {
+        impl SoundfileDsp for #dsp_name {
+            fn build_soundfile_interface(ui_interface: &mut dyn UI<Self::T>) {
+                #(#add_soundfiles)*
+            }
+            fn set_soundfile(&mut self, soundfile: SoundfileIndex, data: Soundfile<Self::T>) {
+                match soundfile.0 {
+                    #(#set_soundfiles,)*
+                    index => panic!("no soundfile with index {}", index),
+                }
+            }
+        }
+    }
+}
@@ -1,22 +1,25 @@
-use faust_json::{FaustJson, LayoutItem};
 use heck::CamelCase;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::Ident;
 
-use crate::enum_interface;
+use crate::{
+    docs, enum_interface,
+    model::{UiItem, UiModel, WidgetKind},
+};
 
 #[derive(Clone)]
 enum StructInfo {
     GroupInfo {
         label: Ident,
         type_name: Ident,
-        items: Vec<StructInfo>,
+        items: Vec<Self>,
     },
     UIInfo {
         label: Ident,
         type_name: Ident,
         shortname: Ident,
+        doc: Option<String>,
     },
 }
 
@@ -33,90 +36,61 @@ impl StructInfo {
                 label,
                 type_name,
                 shortname,
+                doc,
             } => label.to_string(),
         }
     }
-    pub fn active(label: &str, shortname: &str) -> Self {
+    pub fn active(label: &str, shortname: &str, doc: Option<String>) -> Self {
         Self::UIInfo {
             type_name: enum_interface::enum_active_discriminants_ident(),
             label: format_ident!("{label}"),
             shortname: format_ident!("{shortname}"),
+            doc,
         }
     }
-    pub fn passive(label: &str, shortname: &str) -> Self {
+    pub fn passive(label: &str, shortname: &str, doc: Option<String>) -> Self {
         Self::UIInfo {
             type_name: enum_interface::enum_passive_discriminants_ident(),
             label: format_ident!("{label}"),
             shortname: format_ident!("{shortname}"),
+            doc,
         }
     }
 }
 
-trait GetGroupInfo {
-    fn get_ui_structure(&self, parent_type: &Ident) -> StructInfo;
-}
-
-impl GetGroupInfo for LayoutItem {
-    fn get_ui_structure(&self, parent_type: &Ident) -> StructInfo {
+impl UiItem {
+    /// Soundfiles are not parameters and have no field.
+    fn get_ui_structure(&self, parent_type: &Ident) -> Option<StructInfo> {
         match self {
-            Self::TGroup { label, items, .. } => {
-                let type_name = format_ident!("{parent_type}{}", label.to_camel_case());
-                let label = format_ident!("{label}");
-                StructInfo::GroupInfo {
+            Self::Group(group) => {
+                let type_name = format_ident!("{parent_type}{}", group.label.to_camel_case());
+                let label = format_ident!("{}", group.label);
+                Some(StructInfo::GroupInfo {
                     type_name: type_name.clone(),
                     label,
-                    items: items
+                    items: group
+                        .items
                         .iter()
-                        .map(|items| items.get_ui_structure(&type_name))
+                        .filter_map(|items| items.get_ui_structure(&type_name))
                         .collect(),
-                }
+                })
             }
-            Self::VGroup { label, items, .. } => {
-                let type_name = format_ident!("{parent_type}{}", label.to_camel_case());
-                let label = format_ident!("{label}");
-                StructInfo::GroupInfo {
-                    type_name: type_name.clone(),
-                    label,
-                    items: items
-                        .iter()
-                        .map(|items| items.get_ui_structure(&type_name))
-                        .collect(),
-                }
-            }
-            Self::HGroup { label, items, .. } => {
-                let type_name = format_ident!("{parent_type}{}", label.to_camel_case());
-                let label = format_ident!("{label}");
-                StructInfo::GroupInfo {
-                    type_name: type_name.clone(),
-                    label,
-                    items: items
-                        .iter()
-                        .map(|items| items.get_ui_structure(&type_name))
-                        .collect(),
+            Self::Widget(widget) => {
+                let doc = Some(docs::param_doc(widget));
+                match widget.kind {
+                    WidgetKind::Soundfile { .. } => None,
+                    _ if widget.is_active() => Some(StructInfo::active(
+                        &widget.label,
+                        &widget.shortname.to_camel_case(),
+                        doc,
+                    )),
+                    _ => Some(StructInfo::passive(
+                        &widget.label,
+                        &widget.shortname.to_camel_case(),
+                        doc,
+                    )),
                 }
             }
-            Self::VSlider {
-                label, shortname, ..
-            }
-            | Self::HSlider {
-                label, shortname, ..
-            }
-            | Self::NEntry {
-                label, shortname, ..
-            }
-            | Self::Button {
-                label, shortname, ..
-            }
-            | Self::CheckBox {
-                label, shortname, ..
-            } => StructInfo::active(label, &shortname.to_camel_case()),
-            Self::VBarGraph {
-                label, shortname, ..
-            }
-            | Self::HBarGraph {
-                label, shortname, ..
-            } => StructInfo::passive(label, &shortname.to_camel_case()),
-            Self::Soundfile { address, .. } => StructInfo::active(address, address),
         }
     }
 }
@@ -140,12 +114,23 @@ fn create_struct_fields(items: &[StructInfo]) -> Vec<TokenStream> {
         .iter()
         .map(|i: &StructInfo| match i {
             StructInfo::UIInfo {
-                label, type_name, ..
+                label,
+                type_name,
+                doc,
+                ..
+            } => {
+                let doc = doc.iter();
+                quote! {
+                    #(#[doc = #doc])*
+                    pub #label : #type_name
+                }
             }
-            | StructInfo::GroupInfo {
+            StructInfo::GroupInfo {
                 label, type_name, ..
             } => {
+                let doc = format!("Group `{label}`.");
                 quote! {
+                    #[doc = #doc]
                     pub #label : #type_name
                 }
             }
@@ -168,6 +153,7 @@ fn create_struct_defaults(items: &[StructInfo]) -> Vec<TokenStream> {
                 label,
                 type_name,
                 shortname,
+                ..
             } => {
                 quote! {
                     #label: #type_name::#shortname
@@ -180,11 +166,15 @@ fn create_struct_defaults(items: &[StructInfo]) -> Vec<TokenStream> {
 fn create_structs(si: &StructInfo) -> TokenStream {
     match si {
         StructInfo::GroupInfo {
-            items, type_name, ..
+            items,
+            type_name,
+            label,
         } => {
             let fields: Vec<TokenStream> = create_struct_fields(items);
             let defaults: Vec<TokenStream> = create_struct_defaults(items);
+            let doc = format!("Parameters of the group `{label}`.");
             quote::quote! {
+                #[doc = #doc]
                 #[derive(Debug)]
                 pub struct #type_name { #(#fields),* }
 
@@ -204,11 +194,11 @@ fn create_structs(si: &StructInfo) -> TokenStream {
     }
 }
 
-pub fn create(dsp_json: &FaustJson, ui_static_name: &Ident, ui_type: &Ident) -> TokenStream {
-    let ui_info_tree = dsp_json
-        .ui
+pub fn create(model: &UiModel, ui_static_name: &Ident, ui_type: &Ident) -> TokenStream {
+    let ui_info_tree = model
+        .items
         .iter()
-        .map(|items| items.get_ui_structure(ui_type))
+        .filter_map(|items| items.get_ui_structure(ui_type))
         .collect::<Vec<_>>();
     assert_eq!(ui_info_tree.len(), 1);
     let head_label = ui_info_tree
@@ -222,6 +212,7 @@ pub fn create(dsp_json: &FaustJson, ui_static_name: &Ident, ui_type: &Ident) ->
     let ui_info_list = flat_ui_infos(&ui_info_tree);
     let ui_structs: TokenStream = ui_info_list.iter().map(create_structs).collect();
     let ui_global = quote! {
+        /// Parameters of the dsp, ordered like the groups of its user interface.
         pub static #ui_static_name: #head_type = #head_type::static_ui();
     };
     quote! {
@@ -0,0 +1,190 @@
+use faust_json::FaustJson;
+use faust_ui::{
+    convert,
+    model::{GroupKind, Meta, Range, UiItem, UiModel, UiModelBuilder, Widget, WidgetKind},
+};
+use faust_xml::FaustXML;
+use std::{convert::TryFrom, fs, path::PathBuf};
+
+fn example_file(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../examples/file-f64/dsp")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()))
+}
+
+fn json() -> FaustJson {
+    serde_json::from_str(&example_file("volume.dsp.json")).expect("invalid json")
+}
+
+fn xml() -> FaustXML {
+    quick_xml::de::from_str(&example_file("volume.dsp.xml")).expect("invalid xml")
+}
+
+#[test]
+fn json_round_trip() {
+    let json = json();
+    let written = serde_json::to_string_pretty(&json).expect("cannot serialize json");
+    let read: FaustJson = serde_json::from_str(&written).expect("cannot read written json");
+    assert_eq!(json, read);
+}
+
+/// Json descriptions written by faust for the examples.
+const EXAMPLE_JSON: [&str; 4] = [
+    "file-f64/dsp/volume.dsp.json",
+    "include-macro/dsp/volume.dsp.json",
+    "jack-ui/dsp/volume.dsp.json",
+    "dsp-macro/DEBUG_volume_control.json",
+];
+
+#[test]
+fn json_model_round_trip() {
+    for name in EXAMPLE_JSON {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../examples")
+            .join(name);
+        let json: FaustJson = serde_json::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|err| panic!("invalid json {name}: {err}"));
+        assert_eq!(FaustJson::from(&UiModel::from(&json)), json, "{name}");
+    }
+}
+
+/// Fields written by other backends or newer faust versions survive the model.
+#[test]
+fn json_model_keeps_backend_fields() {
+    let mut value: serde_json::Value =
+        serde_json::from_str(&example_file("volume.dsp.json")).expect("invalid json");
+    value["sr_index"] = 8.into();
+    value["code"] = "AGFzbQE=".into();
+    value["memory_layout"] = serde_json::json!([{ "name": "fVslider0", "type": "kFloat" }]);
+    value["new_field"] = true.into();
+    let slider = &mut value["ui"][0]["items"][0]["items"][1];
+    slider["index"] = 12.into();
+    slider["new_widget_field"] = "x".into();
+    value["ui"][0]["new_group_field"] = 1.into();
+    let json: FaustJson = serde_json::from_value(value).expect("invalid json");
+
+    let model = UiModel::from(&json);
+    assert_eq!(model.widgets()[1].index, Some(12));
+    assert_eq!(FaustJson::from(&model), json);
+}
+
+#[test]
+fn xml_round_trip() {
+    let xml = xml();
+    let written = quick_xml::se::to_string(&xml).expect("cannot serialize xml");
+    assert!(written.starts_with("<faust><name>volume</name>"));
+    assert!(written.contains(r#"<widget type="vbargraph" id="1"><label>level</label>"#));
+    assert!(written.contains(r#"<meta key="2"/>"#));
+    let read: FaustXML = quick_xml::de::from_str(&written).expect("cannot read written xml");
+    assert_eq!(xml, read);
+}
+
+#[test]
+fn json_to_xml() {
+    let xml = xml();
+    let converted = convert::json_to_xml(&json());
+    assert_eq!(converted.ui, xml.ui);
+    assert_eq!(
+        (&converted.name, &converted.author, &converted.license),
+        (&xml.name, &xml.author, &xml.license)
+    );
+}
+
+#[test]
+fn xml_to_json() {
+    let json = json();
+    let converted = convert::xml_to_json(&xml()).expect("cannot convert");
+    assert_eq!(converted.ui, json.ui);
+    assert_eq!(converted.filename, "volume.dsp");
+    assert_eq!(converted.compile_options, json.compile_options);
+}
+
+#[test]
+fn rewrite_description() {
+    let mut model = UiModel::from(&json());
+    let faust_ui::model::UiItem::Group(root) = &mut model.items[0] else {
+        panic!("expected a group")
+    };
+    root.items.truncate(1);
+    model.update_addresses();
+    let json = FaustJson::from(&model);
+    let written = serde_json::to_string(&json).expect("cannot serialize json");
+    assert!(written.contains(r#""shortname":"level","address":"/volume/channel_0/level""#));
+    assert!(!written.contains("channel_1"));
+}
+
+#[test]
+fn builder() {
+    let range = Range {
+        init: 0.0,
+        min: 0.0,
+        max: 1.0,
+        step: 0.01,
+    };
+    let model = UiModelBuilder::new("mixer")
+        .inputs(2)
+        .outputs(1)
+        .meta("author", "me")
+        .open_group(GroupKind::Horizontal, "mixer")
+        .open_group(GroupKind::Vertical, "left")
+        .widget(Widget::new(WidgetKind::HSlider(range), "gain", "fHslider0"))
+        .close_group()
+        .open_group(GroupKind::Vertical, "right")
+        .widget(
+            Widget::new(WidgetKind::HSlider(range), "gain", "fHslider1").with_meta("unit", "dB"),
+        )
+        .close_group()
+        .widget(Widget::new(WidgetKind::Button, "mute all", "fButton0"))
+        .close_group()
+        .build();
+    let names = model
+        .widgets()
+        .iter()
+        .map(|widget| (widget.address.as_str(), widget.shortname.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            ("/mixer/left/gain", "left_gain"),
+            ("/mixer/right/gain", "right_gain"),
+            ("/mixer/mute_all", "mute_all"),
+        ]
+    );
+    let xml = FaustXML::from(&model);
+    assert_eq!(xml.author.as_deref(), Some("me"));
+    assert_eq!(xml.ui.activewidgets.count, 3);
+    let read = UiModel::try_from(&xml).expect("cannot read converted xml");
+    assert_eq!(read.items, model.items);
+}
+
+#[test]
+fn xml_soundfiles_and_group_meta() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/player.dsp.xml");
+    let text = fs::read_to_string(path).expect("cannot read fixture");
+    let xml: FaustXML = quick_xml::de::from_str(&text).expect("invalid xml");
+    let model = UiModel::try_from(&xml).expect("cannot convert");
+
+    let UiItem::Group(root) = &model.items[0] else {
+        panic!("no root group");
+    };
+    let UiItem::Group(transport) = &root.items[0] else {
+        panic!("no transport group");
+    };
+    assert_eq!(transport.meta, [Meta::new("style", "knobs")]);
+    let sound = &model.widgets()[1];
+    assert_eq!(
+        sound.kind,
+        WidgetKind::Soundfile {
+            url: "{'a.wav';'b.wav'}".to_owned()
+        }
+    );
+    assert_eq!(sound.address, "/player/transport/sound");
+    assert_eq!(sound.meta, [Meta::new("tooltip", "samples")]);
+
+    let json = FaustJson::from(&model);
+    assert_eq!(convert::json_to_xml(&json).ui, xml.ui);
+    let written = quick_xml::se::to_string(&FaustXML::from(&model)).expect("cannot serialize xml");
+    let read: FaustXML = quick_xml::de::from_str(&written).expect("cannot read written xml");
+    assert_eq!(read.ui, xml.ui);
+}
@@ -0,0 +1,83 @@
+use faust_json::FaustJson;
+use faust_ui::{docs, model::UiModel};
+
+const JSON: &str = r##"
+{
+	"name": "volumecontrol",
+	"filename": "volume.dsp",
+	"version": "2.76.0",
+	"compile_options": "-lang rust -ct 1 -es 1 -mcd 16 -mdd 1024 -mdy 33 -single -ftz 0",
+	"size": 48,
+	"inputs": 2,
+	"outputs": 1,
+	"meta": [
+		{ "author": "Franz Heinzmann" },
+		{ "description": "Volume with a level meter" }
+	],
+	"ui": [
+		{
+			"type": "vgroup",
+			"label": "volumecontrol",
+			"items": [
+				{
+					"type": "vbargraph",
+					"label": "level",
+					"shortname": "level",
+					"address": "/volumecontrol/level",
+					"varname": "fVbargraph0",
+					"min": -60,
+					"max": 5
+				},
+				{
+					"type": "vslider",
+					"label": "volume",
+					"shortname": "volume",
+					"address": "/volumecontrol/volume",
+					"varname": "fVslider0",
+					"meta": [
+						{ "tooltip": "gain | in dB" },
+						{ "unit": "dB" }
+					],
+					"init": 0,
+					"min": -70,
+					"max": 4,
+					"step": 0.1
+				}
+			]
+		}
+	]
+}"##;
+
+fn json() -> FaustJson {
+    serde_json::from_str(JSON).expect("invalid test json")
+}
+
+#[test]
+fn param_doc() {
+    let model = UiModel::from(&json());
+    assert_eq!(
+        docs::param_doc(model.widgets()[1]),
+        "`volume` (vslider) at `/volumecontrol/volume`: range -70 ..= 4, init 0, step 0.1, unit dB.\n\ngain | in dB"
+    );
+}
+
+#[test]
+fn dsp_doc() {
+    let doc = docs::dsp_doc(&UiModel::from(&json()));
+    assert!(doc.starts_with("Faust dsp `volumecontrol`.\n\nVolume with a level meter\n"));
+    assert!(doc.contains("| Author | Franz Heinzmann |\n"));
+    assert!(doc.contains("| Inputs | 2 |\n| Outputs | 1 |\n"));
+    assert!(doc.contains(
+        "| volume | `/volumecontrol/volume` | vslider | dB | -70 ..= 4 | 0 | 0.1 | gain \\| in dB |\n"
+    ));
+    assert!(
+        doc.contains("| level | `/volumecontrol/level` | vbargraph |  | -60 ..= 5 |  |  |  |\n")
+    );
+}
+
+#[test]
+fn generated_code_is_documented() {
+    let code = faust_ui::generate_ui_code(&json(), "Volume").to_string();
+    assert!(code.contains("range -70 ..= 4"));
+    assert!(code.contains("Parameters of the group `volumecontrol`."));
+}
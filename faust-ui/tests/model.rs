@@ -0,0 +1,98 @@
+use faust_json::FaustJson;
+use faust_ui::model::{GroupKind, UiItem, UiModel, UiModelBuilder, Widget, WidgetKind};
+use faust_xml::FaustXML;
+use std::{convert::TryFrom, fs, path::PathBuf};
+
+fn example_file(name: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../examples/file-f64/dsp")
+        .join(name);
+    fs::read_to_string(&path).unwrap_or_else(|err| panic!("cannot read {}: {err}", path.display()))
+}
+
+fn json_model() -> UiModel {
+    let json: FaustJson =
+        serde_json::from_str(&example_file("volume.dsp.json")).expect("invalid json");
+    UiModel::from(&json)
+}
+
+fn xml_model() -> UiModel {
+    let xml: FaustXML =
+        quick_xml::de::from_str(&example_file("volume.dsp.xml")).expect("invalid xml");
+    UiModel::try_from(&xml).expect("invalid layout")
+}
+
+#[test]
+fn xml_and_json_describe_the_same_ui() {
+    let json = json_model();
+    let xml = xml_model();
+    assert_eq!(json.items, xml.items);
+    assert_eq!((json.inputs, json.outputs), (xml.inputs, xml.outputs));
+    for key in ["author", "license", "version"] {
+        assert_eq!(json.meta(key), xml.meta(key), "meta {key}");
+    }
+    assert_eq!(json.compiler_version.as_deref(), Some("2.77.3"));
+    assert_eq!(xml.compiler_version, None);
+}
+
+#[test]
+fn xml_addresses_and_shortnames() {
+    let xml = xml_model();
+    let UiItem::Group(root) = &xml.items[0] else {
+        panic!("expected a group")
+    };
+    assert_eq!(root.label, "volume");
+    let widgets = xml
+        .widgets()
+        .iter()
+        .map(|widget| (widget.address.as_str(), widget.shortname.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        widgets,
+        [
+            ("/volume/channel_0/level", "channel_0_level"),
+            ("/volume/channel_0/volume", "channel_0_volume"),
+            ("/volume/channel_1/level", "channel_1_level"),
+            ("/volume/channel_1/volume", "channel_1_volume"),
+        ]
+    );
+    assert!(matches!(
+        xml.widgets()[0].kind,
+        WidgetKind::VBarGraph { min, max } if (min, max) == (-60.0, 5.0)
+    ));
+}
+
+#[test]
+fn xml_and_json_generate_the_same_code() {
+    assert_eq!(
+        faust_ui::generate_ui_code_from_model(&json_model(), "Amplifer").to_string(),
+        faust_ui::generate_ui_code_from_model(&xml_model(), "Amplifer").to_string()
+    );
+}
+
+#[test]
+fn soundfiles_implement_soundfile_dsp() {
+    let model = UiModelBuilder::new("player")
+        .open_group(GroupKind::Vertical, "player")
+        .widget(Widget::new(
+            WidgetKind::Soundfile {
+                url: "{'a.wav';'b.wav'}".to_owned(),
+            },
+            "samples",
+            "fSoundfile0",
+        ))
+        .widget(Widget::new(WidgetKind::Button, "play", "fButton0"))
+        .close_group()
+        .build();
+    let code = faust_ui::generate_ui_code_from_model(&model, "Player").to_string();
+    assert!(code.contains("impl SoundfileDsp for Player"), "{}", code);
+    assert!(
+        code.contains(
+            r#"ui_interface . add_soundfile ("samples" , "{'a.wav';'b.wav'}" , SoundfileIndex (0))"#
+        ),
+        "{}",
+        code
+    );
+    assert!(code.contains("0 => self . fSoundfile0 = data"), "{}", code);
+    assert!(!code.contains("Samples"), "{}", code);
+}
@@ -0,0 +1,129 @@
+use faust_ui::{
+    model::{GroupKind, Range, UiModel, UiModelBuilder, Widget, WidgetKind},
+    nih_params,
+};
+use quote::format_ident;
+
+fn range(init: f32, min: f32, max: f32, step: f32) -> Range {
+    Range {
+        init,
+        min,
+        max,
+        step,
+    }
+}
+
+fn model() -> UiModel {
+    UiModelBuilder::new("synth")
+        .open_group(GroupKind::Vertical, "synth")
+        .widget(
+            Widget::new(
+                WidgetKind::HSlider(range(1000.0, 20.0, 20000.0, 1.0)),
+                "cutoff",
+                "fHslider0",
+            )
+            .with_meta("scale", "log")
+            .with_meta("unit", "Hz"),
+        )
+        .widget(
+            Widget::new(
+                WidgetKind::VSlider(range(0.0, -70.0, 4.0, 0.1)),
+                "volume",
+                "fVslider0",
+            )
+            .with_meta("unit", "dB"),
+        )
+        .widget(
+            Widget::new(
+                WidgetKind::NEntry(range(1.0, 0.0, 2.0, 1.0)),
+                "wave",
+                "fEntry0",
+            )
+            .with_meta("style", "menu{'Saw':0;'Square':1;'2 Sines':2}"),
+        )
+        .widget(Widget::new(
+            WidgetKind::NEntry(range(3.0, 1.0, 8.0, 1.0)),
+            "voices",
+            "fEntry1",
+        ))
+        .widget(Widget::new(WidgetKind::CheckBox, "type", "fCheckbox0"))
+        .widget(Widget::new(WidgetKind::Button, "gate", "fButton0"))
+        .widget(Widget::new(
+            WidgetKind::VBarGraph {
+                min: -60.0,
+                max: 5.0,
+            },
+            "level",
+            "fVbargraph0",
+        ))
+        .close_group()
+        .build()
+}
+
+#[test]
+fn style_items() {
+    let model = model();
+    let widgets = model.widgets();
+    assert_eq!(
+        widgets[2].style_items(),
+        Some(vec![
+            ("Saw".to_owned(), 0.0),
+            ("Square".to_owned(), 1.0),
+            ("2 Sines".to_owned(), 2.0)
+        ])
+    );
+    let radio = Widget::new(WidgetKind::Button, "mode", "fButton1")
+        .with_meta("style", "radio{ 'a' : -1 ; 'b':0.5 }");
+    assert_eq!(
+        radio.style_items(),
+        Some(vec![("a".to_owned(), -1.0), ("b".to_owned(), 0.5)])
+    );
+    for style in ["knob", "menu{}", "menu{'a'}", "radio{'a':x}"] {
+        let widget = Widget::new(WidgetKind::Button, "mode", "fButton1").with_meta("style", style);
+        assert_eq!(widget.style_items(), None, "{}", style);
+    }
+}
+
+#[test]
+fn params_struct() {
+    let code = nih_params::create(&model(), &format_ident!("Synth")).to_string();
+    let code = code.as_str();
+    let contains = |expected: &str| assert!(code.contains(expected), "{}\n\n{}", expected, code);
+    contains("pub mod nih_params");
+    contains("use super :: { FaustFloat , UIActive }");
+    contains("# [derive (Params)] pub struct DspParams");
+
+    contains("# [id = \"cutoff\"] pub cutoff : FloatParam");
+    contains("FloatRange :: Skewed { min : 20f32 , max : 20000f32 , factor : FloatRange :: skew_factor (- 2f32) }");
+    contains("with_smoother (SmoothingStyle :: Logarithmic (10f32))");
+    contains("with_unit (\" Hz\")");
+
+    contains("FloatParam :: new (\"volume\" , 0f32 , FloatRange :: Linear { min : - 70f32 , max : 4f32 })");
+    contains("with_smoother (SmoothingStyle :: Linear (10f32))");
+    contains(
+        ". with_step_size (0.1f32) . with_value_to_string (formatters :: v2s_f32_rounded (1usize))",
+    );
+
+    contains("pub wave : EnumParam < WaveChoice >");
+    contains("EnumParam :: new (\"wave\" , WaveChoice :: Square)");
+    contains("pub enum WaveChoice { # [name = \"Saw\"] Saw , # [name = \"Square\"] Square , # [name = \"2 Sines\"] V2Sines }");
+    contains("Self :: V2Sines => 2");
+
+    // numerical entries are not smoothed
+    contains("voices : FloatParam :: new (\"voices\" , 3f32 , FloatRange :: Linear { min : 1f32 , max : 8f32 }) . with_step_size");
+    contains("UIActive :: Voices . set (dsp , self . voices . value () as FaustFloat)");
+
+    contains("pub r#type : BoolParam");
+    contains("UIActive :: Gate . set (dsp , if self . gate . value () { 1.0 } else { 0.0 })");
+    contains("UIActive :: Cutoff . set (dsp , self . cutoff . smoothed . next_step (frames) as FaustFloat)");
+    contains("UIActive :: Wave . set (dsp , self . wave . value () . value ())");
+    assert!(!code.contains("level"));
+}
+
+#[test]
+fn params_without_widgets() {
+    let model = UiModelBuilder::new("empty").build();
+    let code = nih_params::create(&model, &format_ident!("Empty")).to_string();
+    assert!(code.contains("pub struct DspParams { }"), "{}", code);
+    assert!(!code.contains("UIActive"), "{}", code);
+}
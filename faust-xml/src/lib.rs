@@ -9,37 +9,41 @@
 )]
 
 use quick_xml::impl_deserialize_for_internally_tagged_enum;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 #[cfg(test)]
 use serde_path_to_error as _;
 
-#[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename = "faust")]
 pub struct FaustXML {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub copyright: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub classname: Option<String>,
     pub inputs: usize,
     pub outputs: usize,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
     pub ui: UI,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
-#[derive(PartialEq, Eq)]
 pub struct Meta {
     #[serde(rename = "@key")]
     pub key: String,
-    #[serde(rename = "$value")]
+    #[serde(rename = "$value", skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct UI {
     pub activewidgets: ActiveWidgets,
@@ -48,7 +52,7 @@ pub struct UI {
     pub layout: Layout,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ActiveWidgets {
     pub count: usize,
@@ -56,7 +60,7 @@ pub struct ActiveWidgets {
     pub widget: Vec<ActiveWidget>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ActiveWidget {
     VSlider(VSlider),
     HSlider(HSlider),
@@ -74,7 +78,19 @@ impl_deserialize_for_internally_tagged_enum! {
     ("checkbox"  => CheckBox(CheckBox)),
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+impl Serialize for ActiveWidget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::VSlider(w) => Tagged::new("vslider", w).serialize(serializer),
+            Self::HSlider(w) => Tagged::new("hslider", w).serialize(serializer),
+            Self::NEntry(w) => Tagged::new("nentry", w).serialize(serializer),
+            Self::Button(w) => Tagged::new("button", w).serialize(serializer),
+            Self::CheckBox(w) => Tagged::new("checkbox", w).serialize(serializer),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct VSlider {
     #[serde(rename = "@id")]
@@ -85,10 +101,10 @@ pub struct VSlider {
     pub min: f32,
     pub max: f32,
     pub step: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct HSlider {
     #[serde(rename = "@id")]
@@ -99,47 +115,50 @@ pub struct HSlider {
     pub min: f32,
     pub max: f32,
     pub step: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct NEntry {
     #[serde(rename = "@id")]
     pub id: usize,
     pub label: String,
     pub varname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init: Option<f32>,
     pub min: f32,
     pub max: f32,
     pub step: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Button {
     #[serde(rename = "@id")]
     pub id: usize,
     pub label: String,
     pub varname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init: Option<f32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct CheckBox {
     #[serde(rename = "@id")]
     pub id: usize,
     pub label: String,
     pub varname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub init: Option<f32>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct PassiveWidgets {
     pub count: usize,
@@ -147,7 +166,7 @@ pub struct PassiveWidgets {
     pub widget: Vec<PassiveWidget>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum PassiveWidget {
     VBarGraph(VBarGraph),
     HBarGraph(HBarGraph),
@@ -159,7 +178,31 @@ impl_deserialize_for_internally_tagged_enum! {
     ("hbargraph"    => HBarGraph(HBarGraph)),
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+impl Serialize for PassiveWidget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::VBarGraph(w) => Tagged::new("vbargraph", w).serialize(serializer),
+            Self::HBarGraph(w) => Tagged::new("hbargraph", w).serialize(serializer),
+        }
+    }
+}
+
+/// Widget with its type written as attribute, the counterpart of `impl_deserialize_for_internally_tagged_enum`.
+#[derive(Serialize)]
+struct Tagged<'a, T> {
+    #[serde(rename = "@type")]
+    r#type: &'static str,
+    #[serde(flatten)]
+    widget: &'a T,
+}
+
+impl<'a, T> Tagged<'a, T> {
+    const fn new(r#type: &'static str, widget: &'a T) -> Self {
+        Self { r#type, widget }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct VBarGraph {
     #[serde(rename = "@id")]
@@ -168,10 +211,10 @@ pub struct VBarGraph {
     pub varname: String,
     pub min: f32,
     pub max: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct HBarGraph {
     #[serde(rename = "@id")]
@@ -180,30 +223,44 @@ pub struct HBarGraph {
     pub varname: String,
     pub min: f32,
     pub max: f32,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub meta: Vec<Meta>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct SoundfileWidgets {
     pub count: usize,
-    #[serde(rename = "$soundfiles", default)]
-    pub soundfiles: Vec<SoundfileWidgets>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub widget: Vec<SoundfileWidget>,
 }
 
-#[derive(Debug, Deserialize)]
+/// A `soundfile("label[url:{'a.wav'}]", n)` primitive, with the same fields as in the json description.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
-pub struct SoundfileWidget {}
+pub struct SoundfileWidget {
+    /// Always `soundfile`.
+    #[serde(rename = "@type")]
+    pub r#type: String,
+    #[serde(rename = "@id")]
+    pub id: usize,
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub varname: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta: Vec<Meta>,
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Layout {
     #[serde(rename = "$value")]
     pub items: Vec<LayoutItem>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub enum LayoutItem {
     #[serde(rename = "group")]
@@ -212,17 +269,19 @@ pub enum LayoutItem {
     WRef(WRef),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Group {
     #[serde(rename = "@type")]
     pub r#type: String,
     pub label: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub meta: Vec<Meta>,
     #[serde(rename = "$value", default)]
     pub items: Vec<LayoutItem>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct WRef {
     #[serde(rename = "@id")]